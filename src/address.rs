@@ -0,0 +1,343 @@
+use crate::{
+    Address,
+    cardano::StakeCredential,
+    error::{APIError, APIErrorCode},
+};
+use pallas_addresses::{ShelleyDelegationPart, ShelleyPaymentPart, StakePayload};
+use std::fmt;
+
+/// flag set on the hardened indices of a [`DerivationPath`]
+pub const HARDENED: u32 = 0x8000_0000;
+
+/// BIP32 derivation path of an address, as reported by the wallet
+///
+/// Hardened indices have the [`HARDENED`] bit set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct DerivationPath(Vec<u32>);
+
+/// The role of an address in the CIP-1852 derivation scheme
+/// (`m / 1852' / 1815' / account' / role / index`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AddressPurpose {
+    /// addresses handed out to receive funds
+    External,
+    /// change addresses
+    Internal,
+    /// staking key
+    Stake,
+    /// DRep key (CIP-105)
+    DRep,
+    /// constitutional committee cold key (CIP-105)
+    CommitteeCold,
+    /// constitutional committee hot key (CIP-105)
+    CommitteeHot,
+    /// the path does not follow the CIP-1852 scheme
+    Unknown,
+}
+
+/// Derivation metadata of an address owned by the wallet.
+///
+/// This is not part of CIP-30 and only some wallets are exposing it
+/// through their `experimental` namespace. It is mostly useful for
+/// hardware wallet aware dApps that need to display or audit which keys
+/// are going to be used.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AddressInfo {
+    pub path: DerivationPath,
+    pub purpose: AddressPurpose,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid derivation path `{0}'")]
+pub struct DerivationPathError(String);
+
+impl DerivationPath {
+    pub fn new(indices: Vec<u32>) -> Self {
+        Self(indices)
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.0
+    }
+
+    /// the account index if the path follows the CIP-1852 scheme
+    pub fn account(&self) -> Option<u32> {
+        match self.0.as_slice() {
+            [purpose, _coin, account, ..] if *purpose == 1852 | HARDENED => {
+                Some(account & !HARDENED)
+            }
+            _ => None,
+        }
+    }
+
+    /// derive the [`AddressPurpose`] from the role index of the path
+    pub fn purpose(&self) -> AddressPurpose {
+        match self.0.as_slice() {
+            [purpose, coin, _account, role, ..]
+                if *purpose == 1852 | HARDENED && *coin == 1815 | HARDENED =>
+            {
+                match role {
+                    0 => AddressPurpose::External,
+                    1 => AddressPurpose::Internal,
+                    2 => AddressPurpose::Stake,
+                    3 => AddressPurpose::DRep,
+                    4 => AddressPurpose::CommitteeCold,
+                    5 => AddressPurpose::CommitteeHot,
+                    _ => AddressPurpose::Unknown,
+                }
+            }
+            _ => AddressPurpose::Unknown,
+        }
+    }
+}
+
+impl std::str::FromStr for DerivationPath {
+    type Err = DerivationPathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = s.split('/');
+        if segments.next() != Some("m") {
+            return Err(DerivationPathError(s.to_owned()));
+        }
+
+        segments
+            .map(|segment| {
+                let (index, hardened) = match segment.strip_suffix(['\'', 'H', 'h']) {
+                    Some(index) => (index, true),
+                    None => (segment, false),
+                };
+                let index = index
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|index| index & HARDENED == 0)
+                    .ok_or_else(|| DerivationPathError(s.to_owned()))?;
+                Ok(if hardened { index | HARDENED } else { index })
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            if index & HARDENED == 0 {
+                write!(f, "/{index}")?;
+            } else {
+                write!(f, "/{}'", index & !HARDENED)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for DerivationPath {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Text(String),
+            Indices(Vec<u32>),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Text(text) => text.parse().map_err(serde::de::Error::custom),
+            Raw::Indices(indices) => Ok(Self(indices)),
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for AddressInfo {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(serde::Deserialize)]
+        struct Raw {
+            path: DerivationPath,
+        }
+
+        let Raw { path } = Raw::deserialize(deserializer)?;
+        let purpose = path.purpose();
+        Ok(Self { path, purpose })
+    }
+}
+
+/// The type of an [`Address`], see CIP-19.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AddressKind {
+    /// payment and stake credentials
+    Base,
+    /// payment credential and a pointer to a stake registration
+    Pointer,
+    /// payment credential only
+    Enterprise,
+    /// stake credential only, for the rewards
+    Reward,
+    Byron,
+}
+
+/// Inspection of the [`Address`]es.
+///
+/// `Address::to_bech32` and `Address::network` are already provided by
+/// `pallas_addresses`.
+pub trait AddressExt {
+    fn kind(&self) -> AddressKind;
+
+    /// the credential controlling the funds at the address, `None` for
+    /// the reward and Byron addresses
+    fn payment_credential(&self) -> Option<StakeCredential>;
+
+    /// the credential the address delegates with, `None` for the
+    /// enterprise, pointer and Byron addresses
+    fn stake_credential(&self) -> Option<StakeCredential>;
+
+    /// bech32 (`addr1...`, `stake1...`), or base58 for the Byron
+    /// addresses
+    fn to_human_readable(&self) -> String;
+}
+
+impl AddressExt for Address {
+    fn kind(&self) -> AddressKind {
+        match self {
+            Address::Shelley(address) => match address.delegation() {
+                ShelleyDelegationPart::Key(_) | ShelleyDelegationPart::Script(_) => {
+                    AddressKind::Base
+                }
+                ShelleyDelegationPart::Pointer(_) => AddressKind::Pointer,
+                ShelleyDelegationPart::Null => AddressKind::Enterprise,
+            },
+            Address::Stake(_) => AddressKind::Reward,
+            Address::Byron(_) => AddressKind::Byron,
+        }
+    }
+
+    fn payment_credential(&self) -> Option<StakeCredential> {
+        match self {
+            Address::Shelley(address) => Some(match address.payment() {
+                ShelleyPaymentPart::Key(hash) => StakeCredential::AddrKeyhash(*hash),
+                ShelleyPaymentPart::Script(hash) => StakeCredential::ScriptHash(*hash),
+            }),
+            Address::Stake(_) | Address::Byron(_) => None,
+        }
+    }
+
+    fn stake_credential(&self) -> Option<StakeCredential> {
+        match self {
+            Address::Shelley(address) => match address.delegation() {
+                ShelleyDelegationPart::Key(hash) => Some(StakeCredential::AddrKeyhash(*hash)),
+                ShelleyDelegationPart::Script(hash) => Some(StakeCredential::ScriptHash(*hash)),
+                ShelleyDelegationPart::Pointer(_) | ShelleyDelegationPart::Null => None,
+            },
+            Address::Stake(address) => Some(match address.payload() {
+                StakePayload::Stake(hash) => StakeCredential::AddrKeyhash(*hash),
+                StakePayload::Script(hash) => StakeCredential::ScriptHash(*hash),
+            }),
+            Address::Byron(_) => None,
+        }
+    }
+
+    fn to_human_readable(&self) -> String {
+        match self {
+            Address::Byron(address) => address.to_base58(),
+            address => address.to_bech32().unwrap_or_else(|_| address.to_hex()),
+        }
+    }
+}
+
+/// decode the `getAddressInfo` response of the experimental API
+///
+/// wallets without derivation data for the given address are expected
+/// to return `null` or `undefined`.
+pub(crate) fn decode_address_info(
+    address: &Address,
+    value: wasm_bindgen::JsValue,
+) -> Result<Option<AddressInfo>, APIError> {
+    if value.is_null() || value.is_undefined() {
+        return Ok(None);
+    }
+
+    serde_wasm_bindgen::from_value(value)
+        .map(Some)
+        .map_err(|error| APIError {
+            code: APIErrorCode::InternalError,
+            info: format!("Invalid address info for {address}: {error}"),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derivation_path_parse() {
+        let path: DerivationPath = "m/1852'/1815'/0'/1/3".parse().unwrap();
+
+        assert_eq!(
+            path.indices(),
+            &[1852 | HARDENED, 1815 | HARDENED, HARDENED, 1, 3]
+        );
+        assert_eq!(path.to_string(), "m/1852'/1815'/0'/1/3");
+        assert_eq!(path.account(), Some(0));
+        assert_eq!(path.purpose(), AddressPurpose::Internal);
+    }
+
+    #[test]
+    fn derivation_path_invalid() {
+        assert!("1852'/1815'".parse::<DerivationPath>().is_err());
+        assert!("m/1852'/abc".parse::<DerivationPath>().is_err());
+        assert!("m/2147483648".parse::<DerivationPath>().is_err());
+    }
+
+    #[test]
+    fn address_kinds() {
+        let payment = "9493315cd92eb5d8c4304e67b7e16ae36d61d34502694657811a2c8e";
+        let stake = "337b62cfff6403a06a3acbc34f8c46003c69fe79a3628cefa9c47251";
+        let credential = |hash: &str| Some(StakeCredential::AddrKeyhash(hash.parse().unwrap()));
+        let parse = |address: &str| address.parse::<Address>().unwrap();
+
+        let base = "addr1qx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer3n0d3vllmyqwsx5wktcd8cc3sq835lu7drv2xwl2wywfgse35a3x";
+        let address = parse(base);
+        assert_eq!(address.kind(), AddressKind::Base);
+        assert_eq!(address.payment_credential(), credential(payment));
+        assert_eq!(address.stake_credential(), credential(stake));
+        assert_eq!(address.to_human_readable(), base);
+
+        let address = parse("addr1vx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzers66hrl8");
+        assert_eq!(address.kind(), AddressKind::Enterprise);
+        assert_eq!(address.stake_credential(), None);
+
+        let address = parse("addr1gx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer5pnz75xxcrzqf96k");
+        assert_eq!(address.kind(), AddressKind::Pointer);
+
+        let address = parse("stake1uyehkck0lajq8gr28t9uxnuvgcqrc6070x3k9r8048z8y5gh6ffgw");
+        assert_eq!(address.kind(), AddressKind::Reward);
+        assert_eq!(address.payment_credential(), None);
+        assert_eq!(address.stake_credential(), credential(stake));
+
+        let byron = "Ae2tdPwUPEZFRbyhz3cpfC2CumGzNkFBN2L42rcUc2yjQpEkxDbkPodpMAi";
+        let address = parse(byron);
+        assert_eq!(address.kind(), AddressKind::Byron);
+        assert_eq!(address.to_human_readable(), byron);
+    }
+
+    #[test]
+    fn address_info_json() {
+        let info: AddressInfo = serde_json::from_value(serde_json::json! { {
+            "path": "m/1852'/1815'/2'/2/0",
+        }})
+        .unwrap();
+        assert_eq!(info.purpose, AddressPurpose::Stake);
+        assert_eq!(info.path.account(), Some(2));
+
+        let info: AddressInfo = serde_json::from_value(serde_json::json! { {
+            "path": [2147483692u32, 2147485463u32, 2147483648u32, 0, 0],
+        }})
+        .unwrap();
+        assert_eq!(info.purpose, AddressPurpose::Unknown);
+    }
+}
@@ -0,0 +1,226 @@
+/*!
+Audit trail of what the users signed.
+
+Compliance minded dApps may need to keep, for every signature request,
+what was presented to the user and what the wallet returned. Once a
+[`ConnectedWallet`] is given an [`AuditLog`] (see
+[`ConnectedWallet::with_audit_log`]) every `signTx` and `signData`
+request is recorded in the log's [`Storage`]: the hash of the body or
+payload, the hash of the returned witness set or signature (or the kind
+of the error), the wallet and the time.
+
+Only hashes are kept, never the payloads: the log does not leak what it
+is meant to attest. The log is exported with [`AuditLog::export_json`].
+
+```
+# use cardano_connector::{audit::{AuditLog, AuditAction, AuditOutcome, AuditRecord}, storage::MemoryStorage};
+# use pallas_crypto::hash::Hash;
+let log = AuditLog::new(MemoryStorage::new());
+log.record(AuditRecord {
+    wallet: "lace".to_owned(),
+    action: AuditAction::SignData,
+    at: 1_700_000_000_000,
+    payload_hash: Hash::from([1; 32]),
+    address: None,
+    outcome: AuditOutcome::Signed { signature_hash: Hash::from([2; 32]), signers: Vec::new() },
+})?;
+assert_eq!(log.records()?.len(), 1);
+let json = log.export_json()?;
+# Ok::<_, cardano_connector::storage::StorageError>(())
+```
+
+[`ConnectedWallet`]: crate::ConnectedWallet
+[`ConnectedWallet::with_audit_log`]: crate::ConnectedWallet::with_audit_log
+*/
+
+use crate::{
+    cardano::{AddrKeyhash, Hash},
+    error::ErrorKind,
+    storage::{Storage, StorageError, read_json},
+};
+use std::rc::Rc;
+
+const AUDIT_KEY: &str = "cardano-connector.audit";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditAction {
+    SignTx,
+    SignData,
+}
+
+/// What the wallet returned.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "outcome", rename_all = "camelCase")]
+pub enum AuditOutcome {
+    #[serde(rename_all = "camelCase")]
+    Signed {
+        /// Blake2b-256 of the returned witness set or `COSE_Sign1`
+        signature_hash: Hash<32>,
+        /// the keys of the vkey witnesses, for the transactions
+        #[serde(default)]
+        signers: Vec<AddrKeyhash>,
+    },
+    /// the request failed, e.g. the user declined
+    Failed {
+        /// see [`ErrorKind::key`]
+        error: String,
+    },
+}
+
+impl AuditOutcome {
+    pub fn failed(kind: ErrorKind) -> Self {
+        Self::Failed {
+            error: kind.key().to_owned(),
+        }
+    }
+}
+
+/// One signature request.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRecord {
+    /// the name of the wallet
+    pub wallet: String,
+    pub action: AuditAction,
+    /// POSIX time of the request, in milliseconds
+    pub at: u64,
+    /// the transaction id, or the Blake2b-256 of the signed payload
+    pub payload_hash: Hash<32>,
+    /// the address the data was signed with, in hexadecimal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(flatten)]
+    pub outcome: AuditOutcome,
+}
+
+/// see the [module documentation](self)
+///
+/// Cloning gives another handle to the same log.
+#[derive(Clone)]
+pub struct AuditLog {
+    storage: Rc<dyn Storage>,
+    key: String,
+}
+
+impl AuditLog {
+    pub fn new(storage: impl Storage + 'static) -> Self {
+        Self {
+            storage: Rc::new(storage),
+            key: AUDIT_KEY.to_owned(),
+        }
+    }
+
+    /// the storage key of the log, to keep several logs
+    pub fn with_key(mut self, key: impl Into<String>) -> Self {
+        self.key = key.into();
+        self
+    }
+
+    /// the recorded requests, oldest first
+    ///
+    /// Fails with [`StorageError::Corrupt`] if the stored trail can't be
+    /// parsed.
+    pub fn records(&self) -> Result<Vec<AuditRecord>, StorageError> {
+        Ok(read_json(&*self.storage, &self.key)?.unwrap_or_default())
+    }
+
+    /// append `record` to the log
+    ///
+    /// Nothing is written if the stored trail can't be parsed, it is
+    /// never replaced.
+    pub fn record(&self, record: AuditRecord) -> Result<(), StorageError> {
+        let mut records = self.records()?;
+        records.push(record);
+        let records = serde_json::to_string(&records)
+            .map_err(|error| StorageError::Write(error.to_string()))?;
+        self.storage.set(&self.key, &records)
+    }
+
+    /// the records as a JSON array
+    pub fn export_json(&self) -> Result<String, StorageError> {
+        serde_json::to_string_pretty(&self.records()?)
+            .map_err(|error| StorageError::Write(error.to_string()))
+    }
+
+    pub fn clear(&self) -> Result<(), StorageError> {
+        self.storage.remove(&self.key)
+    }
+}
+
+impl PartialEq for AuditLog {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.storage, &other.storage) && self.key == other.key
+    }
+}
+
+impl std::fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLog").field("key", &self.key).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    #[test]
+    fn records_are_appended_and_exported() {
+        let log = AuditLog::new(MemoryStorage::new());
+        assert!(log.records().unwrap().is_empty());
+
+        let signed = AuditRecord {
+            wallet: "eternl".to_owned(),
+            action: AuditAction::SignTx,
+            at: 10,
+            payload_hash: Hash::from([1; 32]),
+            address: None,
+            outcome: AuditOutcome::Signed {
+                signature_hash: Hash::from([2; 32]),
+                signers: vec![Hash::from([3; 28])],
+            },
+        };
+        let declined = AuditRecord {
+            action: AuditAction::SignData,
+            at: 20,
+            address: Some("61aa".to_owned()),
+            outcome: AuditOutcome::failed(ErrorKind::UserDeclined),
+            ..signed.clone()
+        };
+        log.record(signed.clone()).unwrap();
+        log.clone().record(declined.clone()).unwrap();
+        assert_eq!(log.records().unwrap(), [signed, declined]);
+
+        let exported: serde_json::Value =
+            serde_json::from_str(&log.export_json().unwrap()).unwrap();
+        assert_eq!(exported[1]["outcome"], "failed");
+        assert_eq!(exported[1]["error"], "user_declined");
+        assert_eq!(exported[0]["action"], "signTx");
+
+        log.clear().unwrap();
+        assert!(log.records().unwrap().is_empty());
+    }
+
+    #[test]
+    fn corrupt_trail_is_kept() {
+        let storage = Rc::new(MemoryStorage::new());
+        storage.set(AUDIT_KEY, "[{\"wallet\"").unwrap();
+        let log = AuditLog::new(Rc::clone(&storage));
+
+        assert!(matches!(log.records(), Err(StorageError::Corrupt { .. })));
+        let record = AuditRecord {
+            wallet: "eternl".to_owned(),
+            action: AuditAction::SignData,
+            at: 10,
+            payload_hash: Hash::from([1; 32]),
+            address: None,
+            outcome: AuditOutcome::failed(ErrorKind::UserDeclined),
+        };
+        assert!(matches!(
+            log.record(record),
+            Err(StorageError::Corrupt { .. })
+        ));
+        assert_eq!(storage.get(AUDIT_KEY).as_deref(), Some("[{\"wallet\""));
+    }
+}
@@ -0,0 +1,404 @@
+/*!
+Data signatures that cannot be replayed elsewhere.
+
+A bare `signData` signature proves the user signed some bytes, not where
+nor for what: a signature given to one dApp to log in could be presented
+to another one, or again later. An [`Envelope`] binds the signed body to
+the dApp's origin, a purpose, a nonce and an expiry. The payload the user
+signs is the envelope in a readable form:
+
+```text
+cardano-connector envelope v1
+origin: https://my-dapp.io
+purpose: login
+nonce: 5f1c9a2e
+expires: 1700000300000
+body: 0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8
+```
+
+The [`EnvelopeVerifier`] checks the signature, each field of the envelope
+and that the nonce is seen only once.
+
+```no_run
+# use cardano_connector::{ConnectedWallet, Address, auth::{Envelope, EnvelopeVerifier}, storage::MemoryStorage};
+# async fn test(wallet: &ConnectedWallet, address: &Address, nonce: String, now: u64) -> anyhow::Result<()> {
+let body = b"log in as alice";
+let envelope = Envelope::new("https://my-dapp.io", "login", nonce, now + 5 * 60_000, body)?;
+let signed = wallet.sign_envelope(address, &envelope).await?;
+
+let verifier = EnvelopeVerifier::new("https://my-dapp.io", MemoryStorage::new());
+verifier.verify(&signed, address, body, now)?;
+# Ok(()) }
+```
+*/
+
+use crate::{
+    Address, SignedData,
+    cardano::Hash,
+    error::VerifyDataError,
+    storage::{Storage, StorageError, read_json},
+};
+use pallas_crypto::hash::Hasher;
+use std::collections::BTreeMap;
+
+/// the version of the envelopes created by this crate
+pub const ENVELOPE_VERSION: u8 = 1;
+
+const HEADER: &str = "cardano-connector envelope v";
+/// the used nonces, with the expiry of their envelope
+const NONCES_KEY: &str = "cardano-connector.nonces";
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum EnvelopeError {
+    #[error(transparent)]
+    Signature(#[from] VerifyDataError),
+    #[error("The signed payload is not an envelope: {0}")]
+    Malformed(String),
+    #[error("Unsupported envelope version {0}")]
+    UnsupportedVersion(u8),
+    #[error("The envelope was signed for {found}, expected {expected}")]
+    OriginMismatch { expected: String, found: String },
+    #[error("The envelope was signed to {found}, expected {expected}")]
+    PurposeMismatch { expected: String, found: String },
+    #[error("The envelope expired at {expires_at}")]
+    Expired { expires_at: u64 },
+    #[error("The envelope was signed for another body")]
+    BodyMismatch,
+    #[error("The nonce `{0}' was already used")]
+    Replayed(String),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+/// What the user signs, see the [module documentation](self).
+///
+/// The origin, purpose and nonce must not contain line breaks, they
+/// would be read as other fields of the payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    pub version: u8,
+    /// the origin of the dApp, e.g. `https://my-dapp.io`
+    pub origin: String,
+    /// what the signature is for, e.g. `login`
+    pub purpose: String,
+    /// unique per envelope, ideally issued by the dApp's backend
+    pub nonce: String,
+    /// POSIX time in milliseconds after which the signature is refused
+    pub expires_at: u64,
+    /// Blake2b-256 of the body
+    pub body_hash: Hash<32>,
+}
+
+impl Envelope {
+    /// Fails with [`EnvelopeError::Malformed`] if the origin, purpose or
+    /// nonce contains a line break.
+    pub fn new(
+        origin: impl Into<String>,
+        purpose: impl Into<String>,
+        nonce: impl Into<String>,
+        expires_at: u64,
+        body: &[u8],
+    ) -> Result<Self, EnvelopeError> {
+        let envelope = Self {
+            version: ENVELOPE_VERSION,
+            origin: origin.into(),
+            purpose: purpose.into(),
+            nonce: nonce.into(),
+            expires_at,
+            body_hash: Hasher::<256>::hash(body),
+        };
+        for (name, field) in [
+            ("origin", &envelope.origin),
+            ("purpose", &envelope.purpose),
+            ("nonce", &envelope.nonce),
+        ] {
+            if field.contains(['\n', '\r']) {
+                return Err(EnvelopeError::Malformed(format!(
+                    "line break in the {name}"
+                )));
+            }
+        }
+        Ok(envelope)
+    }
+
+    /// the bytes the user signs
+    pub fn to_payload(&self) -> Vec<u8> {
+        format!(
+            "{HEADER}{}\norigin: {}\npurpose: {}\nnonce: {}\nexpires: {}\nbody: {}",
+            self.version, self.origin, self.purpose, self.nonce, self.expires_at, self.body_hash
+        )
+        .into_bytes()
+    }
+
+    pub fn parse(payload: &[u8]) -> Result<Self, EnvelopeError> {
+        let malformed = |reason: &str| EnvelopeError::Malformed(reason.to_owned());
+        let payload = std::str::from_utf8(payload).map_err(|_| malformed("not UTF-8"))?;
+        let mut lines = payload.split('\n');
+
+        let version = lines
+            .next()
+            .and_then(|line| line.strip_prefix(HEADER))
+            .ok_or_else(|| malformed("missing header"))?
+            .parse()
+            .map_err(|_| malformed("invalid version"))?;
+        let mut field = |name: &str| {
+            lines
+                .next()
+                .and_then(|line| line.strip_prefix(name))
+                .and_then(|line| line.strip_prefix(": "))
+                .ok_or_else(|| EnvelopeError::Malformed(format!("missing {name}")))
+        };
+        let origin = field("origin")?.to_owned();
+        let purpose = field("purpose")?.to_owned();
+        let nonce = field("nonce")?.to_owned();
+        let expires_at = field("expires")?
+            .parse()
+            .map_err(|_| malformed("invalid expiry"))?;
+        let body_hash = field("body")?
+            .parse()
+            .map_err(|_| malformed("invalid body hash"))?;
+        if lines.next().is_some() {
+            return Err(malformed("trailing content"));
+        }
+
+        Ok(Self {
+            version,
+            origin,
+            purpose,
+            nonce,
+            expires_at,
+            body_hash,
+        })
+    }
+}
+
+/// Checks the signed envelopes, see the [module documentation](self).
+///
+/// The used nonces are kept in the storage until their envelope expires,
+/// the expired ones are purged by the next [`check`](Self::check).
+#[derive(Debug)]
+pub struct EnvelopeVerifier<S> {
+    origin: String,
+    purpose: Option<String>,
+    storage: S,
+}
+
+impl<S: Storage> EnvelopeVerifier<S> {
+    /// accept the envelopes signed for `origin`, whatever their purpose
+    pub fn new(origin: impl Into<String>, storage: S) -> Self {
+        Self {
+            origin: origin.into(),
+            purpose: None,
+            storage,
+        }
+    }
+
+    /// only accept the envelopes signed for `purpose`
+    pub fn with_purpose(mut self, purpose: impl Into<String>) -> Self {
+        self.purpose = Some(purpose.into());
+        self
+    }
+
+    /// check `signed` is a signature by `address` of an envelope of
+    /// `body` for this origin, not expired at `now` (POSIX time in
+    /// milliseconds) and never verified before
+    pub fn verify(
+        &self,
+        signed: &SignedData,
+        address: &Address,
+        body: &[u8],
+        now: u64,
+    ) -> Result<Envelope, EnvelopeError> {
+        signed.verify_address(address)?;
+        self.check(&signed.payload, body, now)
+    }
+
+    /// [`verify`](Self::verify) without the signature, for a payload
+    /// whose signature was checked otherwise
+    pub fn check(&self, payload: &[u8], body: &[u8], now: u64) -> Result<Envelope, EnvelopeError> {
+        let envelope = Envelope::parse(payload)?;
+
+        if envelope.version != ENVELOPE_VERSION {
+            return Err(EnvelopeError::UnsupportedVersion(envelope.version));
+        }
+        if envelope.origin != self.origin {
+            return Err(EnvelopeError::OriginMismatch {
+                expected: self.origin.clone(),
+                found: envelope.origin,
+            });
+        }
+        if let Some(purpose) = self.purpose.as_ref().filter(|p| **p != envelope.purpose) {
+            return Err(EnvelopeError::PurposeMismatch {
+                expected: purpose.clone(),
+                found: envelope.purpose,
+            });
+        }
+        if now > envelope.expires_at {
+            return Err(EnvelopeError::Expired {
+                expires_at: envelope.expires_at,
+            });
+        }
+        if envelope.body_hash != Hasher::<256>::hash(body) {
+            return Err(EnvelopeError::BodyMismatch);
+        }
+
+        let mut nonces = self.nonces()?;
+        nonces.retain(|_, expires_at| now <= *expires_at);
+        if nonces.contains_key(&envelope.nonce) {
+            return Err(EnvelopeError::Replayed(envelope.nonce));
+        }
+        nonces.insert(envelope.nonce.clone(), envelope.expires_at);
+        let nonces = serde_json::to_string(&nonces)
+            .map_err(|error| StorageError::Write(error.to_string()))?;
+        self.storage.set(NONCES_KEY, &nonces)?;
+        Ok(envelope)
+    }
+
+    /// the used nonces, expired or not
+    fn nonces(&self) -> Result<BTreeMap<String, u64>, StorageError> {
+        Ok(read_json(&self.storage, NONCES_KEY)?.unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    fn envelope() -> Envelope {
+        Envelope::new("https://my-dapp.io", "login", "5f1c9a2e", 1_000, b"alice").unwrap()
+    }
+
+    #[test]
+    fn payload_round_trip() {
+        let envelope = envelope();
+        let payload = envelope.to_payload();
+        assert!(
+            String::from_utf8(payload.clone())
+                .unwrap()
+                .starts_with("cardano-connector envelope v1\norigin: https://my-dapp.io\n")
+        );
+        assert_eq!(Envelope::parse(&payload).unwrap(), envelope);
+
+        assert!(matches!(
+            Envelope::parse(b"hello"),
+            Err(EnvelopeError::Malformed(_))
+        ));
+        let mut trailing = payload;
+        trailing.extend_from_slice(b"\nextra: 1");
+        assert!(matches!(
+            Envelope::parse(&trailing),
+            Err(EnvelopeError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn line_breaks_are_refused() {
+        assert!(matches!(
+            Envelope::new(
+                "https://my-dapp.io\npurpose: transfer",
+                "login",
+                "1",
+                1_000,
+                b""
+            ),
+            Err(EnvelopeError::Malformed(_))
+        ));
+        assert!(matches!(
+            Envelope::new("https://my-dapp.io", "login", "1\r", 1_000, b""),
+            Err(EnvelopeError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn expired_nonces_are_purged() {
+        let verifier = EnvelopeVerifier::new("https://my-dapp.io", MemoryStorage::new());
+        verifier
+            .check(&envelope().to_payload(), b"alice", 500)
+            .unwrap();
+        assert_eq!(verifier.nonces().unwrap().len(), 1);
+
+        let later =
+            Envelope::new("https://my-dapp.io", "login", "7d02b4c1", 3_000, b"alice").unwrap();
+        verifier
+            .check(&later.to_payload(), b"alice", 2_000)
+            .unwrap();
+        assert_eq!(
+            verifier.nonces().unwrap(),
+            BTreeMap::from([("7d02b4c1".to_owned(), 3_000)])
+        );
+    }
+
+    #[test]
+    fn envelope_checks() {
+        let verifier =
+            EnvelopeVerifier::new("https://my-dapp.io", MemoryStorage::new()).with_purpose("login");
+        let payload = envelope().to_payload();
+
+        assert_eq!(
+            verifier.check(&payload, b"bob", 500),
+            Err(EnvelopeError::BodyMismatch)
+        );
+        assert_eq!(
+            verifier.check(&payload, b"alice", 1_001),
+            Err(EnvelopeError::Expired { expires_at: 1_000 })
+        );
+        assert_eq!(verifier.check(&payload, b"alice", 500), Ok(envelope()));
+        assert_eq!(
+            verifier.check(&payload, b"alice", 600),
+            Err(EnvelopeError::Replayed("5f1c9a2e".to_owned()))
+        );
+
+        let other = Envelope {
+            origin: "https://evil.io".to_owned(),
+            ..envelope()
+        };
+        assert!(matches!(
+            verifier.check(&other.to_payload(), b"alice", 500),
+            Err(EnvelopeError::OriginMismatch { .. })
+        ));
+        let other = Envelope {
+            purpose: "transfer".to_owned(),
+            ..envelope()
+        };
+        assert!(matches!(
+            verifier.check(&other.to_payload(), b"alice", 500),
+            Err(EnvelopeError::PurposeMismatch { .. })
+        ));
+    }
+
+    #[cfg(feature = "mock")]
+    #[test]
+    fn signed_by_the_wallet() {
+        use crate::{NetworkId, mock::MockWallet, wallet_api::WalletApi as _};
+        use futures::executor::block_on;
+
+        let wallet = MockWallet::new("mock", [7; 32], NetworkId::Preview);
+        let connected = block_on(wallet.enable()).unwrap();
+        let signed = block_on(connected.sign_envelope(&wallet.address(), &envelope())).unwrap();
+
+        let verifier = EnvelopeVerifier::new("https://my-dapp.io", MemoryStorage::new());
+        assert_eq!(
+            verifier.verify(&signed, &wallet.address(), b"alice", 0),
+            Ok(envelope())
+        );
+    }
+
+    #[test]
+    fn invalid_signature_is_refused() {
+        let address = Address::from_bytes(&[0x61; 29]).unwrap();
+        let signed = SignedData {
+            key: [0; 32],
+            signature: [0; 64],
+            signed_data: Vec::new(),
+            address: address.to_vec(),
+            payload: envelope().to_payload(),
+            hashed: false,
+        };
+        let verifier = EnvelopeVerifier::new("https://my-dapp.io", MemoryStorage::new());
+        assert_eq!(
+            verifier.verify(&signed, &address, b"alice", 0),
+            Err(EnvelopeError::Signature(VerifyDataError::InvalidSignature))
+        );
+    }
+}
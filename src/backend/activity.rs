@@ -0,0 +1,151 @@
+use super::{BackendError, ChainProvider};
+use crate::{Address, cardano::TxHash};
+
+/// How long and how much an address has been used on chain.
+///
+/// Trust and reputation scores start from this: a counterparty whose
+/// address was first seen minutes ago deserves more caution than one
+/// active for years.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AddressActivity {
+    /// the number of transactions involving the address
+    pub transactions: usize,
+    /// the oldest transaction involving the address
+    pub first_transaction: Option<TxHash>,
+    /// the most recent transaction involving the address
+    pub last_transaction: Option<TxHash>,
+    /// the POSIX time, in milliseconds, of the block of the first
+    /// transaction
+    pub first_seen: Option<u64>,
+    /// the POSIX time, in milliseconds, of the block of the last
+    /// transaction
+    pub last_used: Option<u64>,
+}
+
+impl AddressActivity {
+    /// whether the address never appeared on chain
+    pub fn is_unused(&self) -> bool {
+        self.transactions == 0
+    }
+
+    /// the time elapsed since the address was first seen, at `now` (POSIX
+    /// time in milliseconds)
+    pub fn age(&self, now: u64) -> Option<u64> {
+        self.first_seen
+            .map(|first_seen| now.saturating_sub(first_seen))
+    }
+
+    /// the time elapsed since the address was last used, at `now` (POSIX
+    /// time in milliseconds)
+    pub fn idle(&self, now: u64) -> Option<u64> {
+        self.last_used
+            .map(|last_used| now.saturating_sub(last_used))
+    }
+}
+
+/// compute the activity from the history of the address and the time of
+/// its first and last transactions
+pub async fn address_activity<P: ChainProvider + ?Sized>(
+    provider: &P,
+    address: &Address,
+) -> Result<AddressActivity, BackendError> {
+    // most recent first
+    let history = provider.address_transactions(address).await?;
+    let (Some(last), Some(first)) = (history.first(), history.last()) else {
+        return Ok(AddressActivity::default());
+    };
+
+    let last_used = provider.transaction_time(last).await?;
+    let first_seen = if first == last {
+        last_used
+    } else {
+        provider.transaction_time(first).await?
+    };
+
+    Ok(AddressActivity {
+        transactions: history.len(),
+        first_transaction: Some(*first),
+        last_transaction: Some(*last),
+        first_seen,
+        last_used,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{Hash, TransactionInput, Utxo};
+    use pallas_addresses::{Network, ShelleyAddress, ShelleyDelegationPart, ShelleyPaymentPart};
+    use std::cell::Cell;
+
+    struct History {
+        transactions: Vec<TxHash>,
+        time_queries: Cell<usize>,
+    }
+
+    impl ChainProvider for History {
+        async fn resolve_input(
+            &self,
+            _input: &TransactionInput,
+        ) -> Result<Option<Utxo>, BackendError> {
+            Ok(None)
+        }
+
+        async fn address_transactions(
+            &self,
+            _address: &Address,
+        ) -> Result<Vec<TxHash>, BackendError> {
+            Ok(self.transactions.clone())
+        }
+
+        async fn transaction_time(&self, hash: &TxHash) -> Result<Option<u64>, BackendError> {
+            self.time_queries.set(self.time_queries.get() + 1);
+            Ok(Some(u64::from(hash[0]) * 1_000))
+        }
+    }
+
+    fn address() -> Address {
+        ShelleyAddress::new(
+            Network::Testnet,
+            ShelleyPaymentPart::key_hash(Hash::from([1; 28])),
+            ShelleyDelegationPart::Null,
+        )
+        .into()
+    }
+
+    #[test]
+    fn first_and_last_transactions() {
+        let provider = History {
+            transactions: vec![
+                Hash::from([9; 32]),
+                Hash::from([5; 32]),
+                Hash::from([2; 32]),
+            ],
+            time_queries: Cell::new(0),
+        };
+
+        let activity = futures::executor::block_on(provider.address_activity(&address())).unwrap();
+
+        assert_eq!(activity.transactions, 3);
+        assert_eq!(activity.first_transaction, Some(Hash::from([2; 32])));
+        assert_eq!(activity.first_seen, Some(2_000));
+        assert_eq!(activity.last_used, Some(9_000));
+        assert_eq!(activity.age(10_000), Some(8_000));
+        assert_eq!(activity.idle(10_000), Some(1_000));
+        assert_eq!(provider.time_queries.get(), 2);
+    }
+
+    #[test]
+    fn unused_address() {
+        let provider = History {
+            transactions: Vec::new(),
+            time_queries: Cell::new(0),
+        };
+
+        let activity = futures::executor::block_on(provider.address_activity(&address())).unwrap();
+
+        assert!(activity.is_unused());
+        assert_eq!(activity.age(10_000), None);
+        assert_eq!(provider.time_queries.get(), 0);
+    }
+}
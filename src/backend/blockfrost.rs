@@ -0,0 +1,773 @@
+/*!
+[Blockfrost](https://blockfrost.io) client: the protocol parameters, the
+chain data and the confirmation status CIP-30 gives no access to.
+
+```no_run
+# use cardano_connector::{NetworkId, backend::{ChainQuery, TxStatus, TxSubmitter, blockfrost::Blockfrost}, cardano::{BuildParams, TxIntent, UtxoSet}, Address};
+# async fn test(utxos: UtxoSet, change: Address, tx_cbor: Vec<u8>) -> anyhow::Result<()> {
+let blockfrost = Blockfrost::for_network(NetworkId::Preview, "previewXXXXXXXX").unwrap();
+let params = blockfrost.protocol_params().await?.build_params(change);
+// ... build and sign the transaction with the params
+let hash = blockfrost.submit_tx(&tx_cbor).await?;
+if let TxStatus::Confirmed { confirmations, .. } = blockfrost.tx_status(&hash).await? {
+    assert!(confirmations > 0);
+}
+# Ok(()) }
+```
+
+The requests go through the [`HttpClient`], with its rate limit and
+retries. This needs the `blockfrost` feature.
+*/
+
+use super::{
+    BackendConfig, BackendError, ChainProvider, ChainQuery, ChainTip, ParamsProvider, StakeInfo,
+    TxStatus, TxSubmitter, UtxoQuery,
+    config::Auth,
+    decode_datum, decode_script,
+    http::HttpClient,
+    rest::{self, bech32, decode_error, quantity, rational, script_type},
+    scripts::ScriptType,
+};
+use crate::{
+    Address, NetworkId,
+    cardano::{
+        AssetId, DepositParams, Hash, Metadata, PoolId, TransactionInput, TransactionOutput,
+        TxHash, Utxo, UtxoSet, Value, WalletValue, fees::ProtocolParams,
+    },
+};
+use pallas_codec::{minicbor, utils::CborWrap};
+use pallas_primitives::{
+    PlutusData, ProtocolVersion,
+    conway::{CostModels, DatumOption, ScriptRef},
+};
+use std::{collections::BTreeMap, str::FromStr};
+
+pub const MAINNET_URL: &str = "https://cardano-mainnet.blockfrost.io/api/v0";
+pub const PREPROD_URL: &str = "https://cardano-preprod.blockfrost.io/api/v0";
+pub const PREVIEW_URL: &str = "https://cardano-preview.blockfrost.io/api/v0";
+
+/// the number of items of the paginated responses
+const PAGE_SIZE: usize = 100;
+
+/// Client of the Blockfrost API, see the [module documentation](self).
+pub struct Blockfrost {
+    client: HttpClient,
+}
+
+impl Blockfrost {
+    /// the API at the `base_url` of the config, which holds the
+    /// [`Auth::ProjectId`]
+    pub fn new(config: BackendConfig) -> Self {
+        Self {
+            client: HttpClient::new(config),
+        }
+    }
+
+    /// the public Blockfrost API of the network, `None` for an unknown
+    /// network
+    pub fn for_network(network: NetworkId, project_id: impl Into<String>) -> Option<Self> {
+        let base_url = match network {
+            NetworkId::Mainnet => MAINNET_URL,
+            NetworkId::PreProduction => PREPROD_URL,
+            NetworkId::Preview => PREVIEW_URL,
+            NetworkId::Unknown(_) => return None,
+        };
+        Some(Self::new(
+            BackendConfig::new(base_url).with_auth(Auth::ProjectId(project_id.into())),
+        ))
+    }
+
+    pub fn client(&self) -> &HttpClient {
+        &self.client
+    }
+
+    /// the datum of the UTxO, inline or looked up by its hash
+    ///
+    /// `None` if the UTxO has no datum, or if Blockfrost has never seen
+    /// the datum of its hash.
+    pub async fn utxo_datum(&self, utxo: &Utxo) -> Result<Option<PlutusData>, BackendError> {
+        let TransactionOutput::PostAlonzo(output) = &utxo.output else {
+            return Ok(None);
+        };
+        match &output.datum_option {
+            None => Ok(None),
+            Some(DatumOption::Data(datum)) => Ok(Some(datum.0.clone())),
+            Some(DatumOption::Hash(hash)) => self.datum_by_hash(hash).await,
+        }
+    }
+
+    /// the UTxO of the output, with its reference script
+    async fn utxo(&self, input: TransactionInput, output: &Output) -> Result<Utxo, BackendError> {
+        let utxo = decode_utxo(input, output)?;
+        let mut utxos = self.with_scripts(vec![utxo]).await?;
+        Ok(utxos.remove(0))
+    }
+
+    /// the UTxOs with their reference scripts, fetched once per script
+    /// however many UTxOs hold it
+    async fn with_scripts(
+        &self,
+        utxos: Vec<(Utxo, Option<Hash<28>>)>,
+    ) -> Result<Vec<Utxo>, BackendError> {
+        let mut scripts: BTreeMap<Hash<28>, Option<ScriptRef>> = BTreeMap::new();
+        let mut with_scripts = Vec::with_capacity(utxos.len());
+        for (mut utxo, script_hash) in utxos {
+            if let (TransactionOutput::PostAlonzo(output), Some(hash)) =
+                (&mut utxo.output, script_hash)
+            {
+                let script = match scripts.get(&hash) {
+                    Some(script) => script.clone(),
+                    None => {
+                        let script = self.script_by_hash(&hash).await?;
+                        scripts.insert(hash, script.clone());
+                        script
+                    }
+                };
+                output.script_ref = script.map(CborWrap);
+            }
+            with_scripts.push(utxo);
+        }
+        Ok(with_scripts)
+    }
+
+    /// the outputs of the `path` (`/addresses/{address}/utxos`...), all
+    /// the pages or only the one of `page`
+    async fn outputs(
+        &self,
+        path: &str,
+        page: Option<(usize, usize)>,
+    ) -> Result<Vec<Output>, BackendError> {
+        if let Some((page, count)) = page {
+            let path = format!("{path}?page={}&count={count}&order=asc", page + 1);
+            return Ok(found(self.client.get(&path).await)?.unwrap_or_default());
+        }
+
+        let mut outputs = Vec::new();
+        for page in 1.. {
+            let path = format!("{path}?page={page}&order=asc");
+            let page: Vec<Output> = found(self.client.get(&path).await)?.unwrap_or_default();
+            let last = page.len() < PAGE_SIZE;
+            outputs.extend(page);
+            if last {
+                break;
+            }
+        }
+        Ok(outputs)
+    }
+}
+
+impl ChainQuery for Blockfrost {
+    async fn protocol_params(&self) -> Result<ProtocolParams, BackendError> {
+        protocol_params(&self.client.get("/epochs/latest/parameters").await?)
+    }
+
+    async fn stake_info(&self, address: &Address) -> Result<Option<StakeInfo>, BackendError> {
+        let path = format!("/accounts/{}", bech32(address)?);
+        match found(self.client.get::<Account>(&path).await)? {
+            Some(account) => stake_info(&account).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn tx_status(&self, hash: &TxHash) -> Result<TxStatus, BackendError> {
+        let Some(tx) = found(self.client.get::<TxInfo>(&format!("/txs/{hash}")).await)? else {
+            return Ok(TxStatus::NotFound);
+        };
+        let tip = self.chain_tip().await?;
+        Ok(tx_status(&tx, &tip))
+    }
+
+    async fn mint_metadata(&self, asset: &AssetId) -> Result<Metadata, BackendError> {
+        let path = format!("/assets/{asset}/history?order=desc");
+        let history: Vec<AssetAction> = found(self.client.get(&path).await)?.unwrap_or_default();
+        let Some(mint) = history.iter().find(|action| action.action == "minted") else {
+            return Ok(Metadata::new());
+        };
+        let Cbor { cbor } = self
+            .client
+            .get(&format!("/txs/{}/cbor", mint.tx_hash))
+            .await?;
+        let cbor = cbor.ok_or_else(|| BackendError::Decode("missing transaction".to_owned()))?;
+        rest::tx_metadata(&cbor)
+    }
+
+    async fn asset_utxo(&self, asset: &AssetId) -> Result<Option<Utxo>, BackendError> {
+        let path = format!("/assets/{asset}/addresses");
+        let holders: Vec<AssetHolder> = found(self.client.get(&path).await)?.unwrap_or_default();
+        let Some(holder) = holders.first() else {
+            return Ok(None);
+        };
+        let path = format!("/addresses/{}/utxos/{asset}", holder.address);
+        let outputs: Vec<Output> = found(self.client.get(&path).await)?.unwrap_or_default();
+        match outputs.first() {
+            Some(output) => self.utxo(input(output)?, output).await.map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+impl ChainProvider for Blockfrost {
+    async fn resolve_input(&self, input: &TransactionInput) -> Result<Option<Utxo>, BackendError> {
+        let path = format!("/txs/{}/utxos", input.transaction_id);
+        let Some(utxos) = found(self.client.get::<TxUtxos>(&path).await)? else {
+            return Ok(None);
+        };
+        let output = utxos.outputs.iter().find(|output| {
+            output.output_index == input.index
+                && !output.collateral
+                && output.consumed_by_tx.is_none()
+        });
+        match output {
+            Some(output) => self.utxo(input.clone(), output).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn address_utxos(&self, address: &Address) -> Result<UtxoSet, BackendError> {
+        self.address_utxos_page(address, &UtxoQuery::new()).await
+    }
+
+    /// The asset filter and the pagination are done by Blockfrost when
+    /// it can, the other filters on the received UTxOs.
+    async fn address_utxos_page(
+        &self,
+        address: &Address,
+        query: &UtxoQuery,
+    ) -> Result<UtxoSet, BackendError> {
+        let request = UtxoRequest::new(address, query)?;
+        let outputs = self.outputs(&request.path, request.page).await?;
+        let utxos = outputs
+            .iter()
+            .map(|output| decode_utxo(input(output)?, output))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let matching = utxos
+            .into_iter()
+            .filter(|(utxo, _)| query.matches(utxo))
+            .skip(request.skip)
+            .take(request.take)
+            .collect();
+        // the scripts of the returned UTxOs only
+        let utxos = self.with_scripts(matching).await?;
+        Ok(UtxoSet::from(utxos))
+    }
+
+    async fn address_transactions(&self, address: &Address) -> Result<Vec<TxHash>, BackendError> {
+        let path = match address {
+            Address::Stake(_) => {
+                return Err(BackendError::Unsupported(
+                    "the history of stake addresses".to_owned(),
+                ));
+            }
+            address => format!("/addresses/{}/transactions", bech32(address)?),
+        };
+
+        let mut hashes = Vec::new();
+        for page in 1.. {
+            let transactions: Vec<AddressTransaction> = found(
+                self.client
+                    .get(&format!("{path}?page={page}&order=desc"))
+                    .await,
+            )?
+            .unwrap_or_default();
+            for transaction in &transactions {
+                hashes.push(Hash::from_str(&transaction.tx_hash).map_err(decode_error)?);
+            }
+            if transactions.len() < PAGE_SIZE {
+                break;
+            }
+        }
+        Ok(hashes)
+    }
+
+    async fn transaction_time(&self, hash: &TxHash) -> Result<Option<u64>, BackendError> {
+        let tx = found(self.client.get::<TxInfo>(&format!("/txs/{hash}")).await)?;
+        Ok(tx.map(|tx| tx.block_time * 1_000))
+    }
+
+    async fn chain_tip(&self) -> Result<ChainTip, BackendError> {
+        let block: Block = self.client.get("/blocks/latest").await?;
+        Ok(ChainTip {
+            slot: block.slot.unwrap_or_default(),
+            hash: Hash::from_str(&block.hash).map_err(decode_error)?,
+            height: block.height.unwrap_or_default(),
+        })
+    }
+
+    async fn datum_by_hash(&self, hash: &Hash<32>) -> Result<Option<PlutusData>, BackendError> {
+        let path = format!("/scripts/datum/{hash}/cbor");
+        let Some(Cbor { cbor }) = found(self.client.get(&path).await)? else {
+            return Ok(None);
+        };
+        let cbor = cbor.ok_or_else(|| BackendError::Decode("missing datum".to_owned()))?;
+        decode_datum(hash, &hex::decode(cbor).map_err(decode_error)?).map(Some)
+    }
+
+    async fn script_by_hash(&self, hash: &Hash<28>) -> Result<Option<ScriptRef>, BackendError> {
+        let Some(script) = found(self.client.get::<Script>(&format!("/scripts/{hash}")).await)?
+        else {
+            return Ok(None);
+        };
+        let script_type = script_type(&script.r#type)?;
+        if script_type == ScriptType::Native {
+            return Err(BackendError::Unsupported(
+                "native scripts from Blockfrost".to_owned(),
+            ));
+        }
+
+        let Cbor { cbor } = self.client.get(&format!("/scripts/{hash}/cbor")).await?;
+        let cbor = cbor.ok_or_else(|| BackendError::Decode("missing script".to_owned()))?;
+        decode_script(hash, script_type, &hex::decode(cbor).map_err(decode_error)?).map(Some)
+    }
+}
+
+impl ParamsProvider for Blockfrost {
+    async fn cost_models(&self) -> Result<(ProtocolVersion, CostModels), BackendError> {
+        cost_models(&self.client.get("/epochs/latest/parameters").await?)
+    }
+}
+
+impl TxSubmitter for Blockfrost {
+    async fn submit_tx(&self, tx: &[u8]) -> Result<TxHash, BackendError> {
+        let hash: String = self.client.post_cbor("/tx/submit", tx).await?;
+        Hash::from_str(&hash).map_err(decode_error)
+    }
+}
+
+/// How [`Blockfrost::address_utxos_page`] requests the UTxOs of a
+/// [`UtxoQuery`].
+#[derive(Debug, PartialEq, Eq)]
+struct UtxoRequest {
+    path: String,
+    /// the page requested to Blockfrost, when it does the pagination
+    page: Option<(usize, usize)>,
+    /// the pagination left to do on the matching UTxOs
+    skip: usize,
+    take: usize,
+}
+
+impl UtxoRequest {
+    fn new(address: &Address, query: &UtxoQuery) -> Result<Self, BackendError> {
+        let (path, by_asset) = match (address, &query.asset) {
+            (Address::Stake(_), _) => (format!("/accounts/{}/utxos", bech32(address)?), false),
+            (address, Some(asset)) => (
+                format!("/addresses/{}/utxos/{asset}", bech32(address)?),
+                true,
+            ),
+            (address, None) => (format!("/addresses/{}/utxos", bech32(address)?), false),
+        };
+        // Blockfrost can only paginate what it filtered itself
+        let filtered = query.policy.is_some()
+            || query.inline_datum_only
+            || (query.asset.is_some() && !by_asset);
+
+        Ok(match query.page {
+            Some((page, count)) if !filtered && (1..=PAGE_SIZE).contains(&count) => Self {
+                path,
+                page: Some((page, count)),
+                skip: 0,
+                take: usize::MAX,
+            },
+            Some((page, count)) => Self {
+                path,
+                page: None,
+                skip: page.saturating_mul(count),
+                take: count,
+            },
+            None => Self {
+                path,
+                page: None,
+                skip: 0,
+                take: usize::MAX,
+            },
+        })
+    }
+}
+
+/// the UTxO of the output and the hash of its reference script, not
+/// fetched yet
+fn decode_utxo(
+    input: TransactionInput,
+    output: &Output,
+) -> Result<(Utxo, Option<Hash<28>>), BackendError> {
+    let script_hash = output
+        .reference_script_hash
+        .as_deref()
+        .map(Hash::from_str)
+        .transpose()
+        .map_err(decode_error)?;
+    let utxo = Utxo {
+        input,
+        output: decode_output(output)?,
+    };
+    Ok((utxo, script_hash))
+}
+
+/// `None` for a 404, Blockfrost's answer for what it doesn't know about
+fn found<T>(result: Result<T, BackendError>) -> Result<Option<T>, BackendError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(BackendError::Provider(message)) if message.starts_with("HTTP 404") => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+/// the input of an output of `/addresses/{address}/utxos`
+fn input(output: &Output) -> Result<TransactionInput, BackendError> {
+    let transaction_id = output
+        .tx_hash
+        .as_deref()
+        .ok_or_else(|| BackendError::Decode("missing tx_hash".to_owned()))?;
+    Ok(TransactionInput {
+        transaction_id: Hash::from_str(transaction_id).map_err(decode_error)?,
+        index: output.output_index,
+    })
+}
+
+/// `/epochs/latest/parameters`
+#[derive(Debug, serde::Deserialize)]
+struct EpochParams {
+    min_fee_a: u64,
+    min_fee_b: u64,
+    max_tx_size: u64,
+    key_deposit: String,
+    pool_deposit: String,
+    price_mem: Option<f64>,
+    price_step: Option<f64>,
+    coins_per_utxo_size: Option<String>,
+    collateral_percent: Option<u64>,
+    protocol_major_ver: u64,
+    protocol_minor_ver: u64,
+    #[serde(default)]
+    cost_models_raw: Option<BTreeMap<String, Vec<i64>>>,
+}
+
+/// `/accounts/{stake_address}`
+#[derive(Debug, serde::Deserialize)]
+struct Account {
+    active: bool,
+    controlled_amount: String,
+    withdrawable_amount: String,
+    pool_id: Option<String>,
+}
+
+/// `/txs/{hash}`
+#[derive(Debug, serde::Deserialize)]
+struct TxInfo {
+    block_height: u64,
+    block_time: u64,
+    slot: u64,
+}
+
+/// `/assets/{unit}/history`
+#[derive(Debug, serde::Deserialize)]
+struct AssetAction {
+    tx_hash: String,
+    action: String,
+}
+
+/// `/assets/{unit}/addresses`
+#[derive(Debug, serde::Deserialize)]
+struct AssetHolder {
+    address: String,
+}
+
+/// `/blocks/latest`, the genesis block has no slot nor height
+#[derive(Debug, serde::Deserialize)]
+struct Block {
+    hash: String,
+    height: Option<u64>,
+    slot: Option<u64>,
+}
+
+/// `/txs/{hash}/utxos`
+#[derive(Debug, serde::Deserialize)]
+struct TxUtxos {
+    outputs: Vec<Output>,
+}
+
+/// an output of `/txs/{hash}/utxos` or `/addresses/{address}/utxos`
+#[derive(Debug, serde::Deserialize)]
+struct Output {
+    address: String,
+    amount: Vec<Amount>,
+    output_index: u64,
+    /// only in the address UTxOs
+    #[serde(default)]
+    tx_hash: Option<String>,
+    #[serde(default)]
+    data_hash: Option<String>,
+    #[serde(default)]
+    inline_datum: Option<String>,
+    #[serde(default)]
+    reference_script_hash: Option<String>,
+    /// only in the transaction UTxOs
+    #[serde(default)]
+    collateral: bool,
+    #[serde(default)]
+    consumed_by_tx: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Amount {
+    unit: String,
+    quantity: String,
+}
+
+/// `/addresses/{address}/transactions`
+#[derive(Debug, serde::Deserialize)]
+struct AddressTransaction {
+    tx_hash: String,
+}
+
+/// `/scripts/{hash}`
+#[derive(Debug, serde::Deserialize)]
+struct Script {
+    r#type: String,
+}
+
+/// `/scripts/{hash}/cbor` and `/scripts/datum/{hash}/cbor`
+#[derive(Debug, serde::Deserialize)]
+struct Cbor {
+    cbor: Option<String>,
+}
+
+fn protocol_params(params: &EpochParams) -> Result<ProtocolParams, BackendError> {
+    let defaults = ProtocolParams::MAINNET;
+    Ok(ProtocolParams {
+        min_fee_a: params.min_fee_a,
+        min_fee_b: params.min_fee_b,
+        price_mem: params.price_mem.map_or(defaults.price_mem, rational),
+        price_step: params.price_step.map_or(defaults.price_step, rational),
+        coins_per_utxo_byte: match &params.coins_per_utxo_size {
+            Some(coins) => quantity(coins)?,
+            None => defaults.coins_per_utxo_byte,
+        },
+        max_tx_size: params.max_tx_size,
+        deposits: DepositParams {
+            key_deposit: quantity(&params.key_deposit)?,
+            pool_deposit: quantity(&params.pool_deposit)?,
+        },
+        collateral_percentage: params
+            .collateral_percent
+            .unwrap_or(defaults.collateral_percentage),
+    })
+}
+
+fn cost_models(params: &EpochParams) -> Result<(ProtocolVersion, CostModels), BackendError> {
+    let raw = params
+        .cost_models_raw
+        .as_ref()
+        .ok_or_else(|| BackendError::Decode("missing cost_models_raw".to_owned()))?;
+    Ok((
+        (params.protocol_major_ver, params.protocol_minor_ver),
+        CostModels {
+            plutus_v1: raw.get("PlutusV1").cloned(),
+            plutus_v2: raw.get("PlutusV2").cloned(),
+            plutus_v3: raw.get("PlutusV3").cloned(),
+        },
+    ))
+}
+
+fn stake_info(account: &Account) -> Result<StakeInfo, BackendError> {
+    Ok(StakeInfo {
+        registered: account.active,
+        pool: account
+            .pool_id
+            .as_deref()
+            .map(PoolId::from_str)
+            .transpose()
+            .map_err(decode_error)?,
+        controlled: quantity(&account.controlled_amount)?,
+        rewards: quantity(&account.withdrawable_amount)?,
+    })
+}
+
+fn tx_status(tx: &TxInfo, tip: &ChainTip) -> TxStatus {
+    TxStatus::Confirmed {
+        slot: tx.slot,
+        height: tx.block_height,
+        confirmations: (tip.height + 1).saturating_sub(tx.block_height),
+    }
+}
+
+/// the output, without its reference script
+fn decode_output(output: &Output) -> Result<TransactionOutput, BackendError> {
+    let mut value = WalletValue::zero();
+    for amount in &output.amount {
+        let quantity = quantity(&amount.quantity)?;
+        match amount.unit.as_str() {
+            "lovelace" => value.add_lovelace(quantity),
+            unit => value.add_asset(AssetId::from_str(unit).map_err(decode_error)?, quantity),
+        }
+    }
+
+    let datum_option = match (&output.inline_datum, &output.data_hash) {
+        (Some(datum), _) => {
+            let datum: PlutusData = minicbor::decode(&hex::decode(datum).map_err(decode_error)?)
+                .map_err(decode_error)?;
+            Some(DatumOption::Data(CborWrap(datum)))
+        }
+        (None, Some(hash)) => Some(DatumOption::Hash(
+            Hash::from_str(hash).map_err(decode_error)?,
+        )),
+        (None, None) => None,
+    };
+
+    rest::output(&output.address, Value::from(&value), datum_option, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{assets_of, lovelace_of};
+    use pallas_addresses::{Network, ShelleyAddress, ShelleyDelegationPart, ShelleyPaymentPart};
+
+    const PARAMS: &str = r#"{
+        "epoch": 500, "min_fee_a": 44, "min_fee_b": 155381, "max_tx_size": 16384,
+        "key_deposit": "2000000", "pool_deposit": "500000000",
+        "protocol_major_ver": 10, "protocol_minor_ver": 0,
+        "price_mem": 0.0577, "price_step": 0.0000721,
+        "coins_per_utxo_size": "4310", "collateral_percent": 150,
+        "cost_models_raw": { "PlutusV1": [100788, 420], "PlutusV3": [100788, 16000, -1] }
+    }"#;
+
+    #[test]
+    fn decode_protocol_params() {
+        let params: EpochParams = serde_json::from_str(PARAMS).unwrap();
+
+        assert_eq!(protocol_params(&params), Ok(ProtocolParams::MAINNET));
+        let (version, cost_models) = cost_models(&params).unwrap();
+        assert_eq!(version, (10, 0));
+        assert_eq!(cost_models.plutus_v1, Some(vec![100788, 420]));
+        assert_eq!(cost_models.plutus_v2, None);
+        assert_eq!(cost_models.plutus_v3, Some(vec![100788, 16000, -1]));
+    }
+
+    #[test]
+    fn decode_utxo_output() {
+        let address: Address = ShelleyAddress::new(
+            Network::Testnet,
+            ShelleyPaymentPart::key_hash(Hash::from([1; 28])),
+            ShelleyDelegationPart::Null,
+        )
+        .into();
+        let json = r#"{
+                "address": "{address}",
+                "tx_hash": "39a7a284c2a0948189dc45dec670211cd4d72f7b66c5726c08d9b3df11e44d58",
+                "output_index": 0,
+                "amount": [
+                    { "unit": "lovelace", "quantity": "42000000" },
+                    { "unit": "0101010101010101010101010101010101010101010101010101010174657374", "quantity": "12" }
+                ],
+                "data_hash": "9e478573ab81ea7a8e31891ce0648b81229f408d596a3483e6f4f9b92d3cf710",
+                "inline_datum": null,
+                "reference_script_hash": null
+            }"#
+        .replace("{address}", &address.to_bech32().unwrap());
+        let output: Output = serde_json::from_str(&json).unwrap();
+
+        let decoded = decode_output(&output).unwrap();
+        let TransactionOutput::PostAlonzo(decoded) = &decoded else {
+            panic!("expected a post alonzo output");
+        };
+        assert_eq!(lovelace_of(&decoded.value), 42_000_000);
+        assert_eq!(
+            assets_of(&decoded.value)
+                .values()
+                .copied()
+                .collect::<Vec<_>>(),
+            [12]
+        );
+        assert!(matches!(decoded.datum_option, Some(DatumOption::Hash(_))));
+
+        // an inline datum: the integer 42
+        let output = Output {
+            inline_datum: Some("182a".to_owned()),
+            ..output
+        };
+        let TransactionOutput::PostAlonzo(decoded) = decode_output(&output).unwrap() else {
+            panic!("expected a post alonzo output");
+        };
+        assert!(matches!(decoded.datum_option, Some(DatumOption::Data(_))));
+    }
+
+    #[test]
+    fn utxo_queries() {
+        let address: Address = ShelleyAddress::new(
+            Network::Testnet,
+            ShelleyPaymentPart::key_hash(Hash::from([1; 28])),
+            ShelleyDelegationPart::Null,
+        )
+        .into();
+        let path = format!("/addresses/{}/utxos", address.to_bech32().unwrap());
+        let asset = AssetId::new(Hash::from([2; 28]), b"token".to_vec().into());
+
+        let request = UtxoRequest::new(&address, &UtxoQuery::new().with_page(2, 20)).unwrap();
+        assert_eq!(request.path, path);
+        assert_eq!((request.page, request.skip), (Some((2, 20)), 0));
+
+        let query = UtxoQuery::new().with_asset(asset.clone()).with_page(1, 10);
+        let request = UtxoRequest::new(&address, &query).unwrap();
+        assert_eq!(request.path, format!("{path}/{asset}"));
+        assert_eq!(request.page, Some((1, 10)));
+
+        // filtered after the fact, so paginated after the fact
+        let query = UtxoQuery::new().inline_datum_only().with_page(1, 10);
+        let request = UtxoRequest::new(&address, &query).unwrap();
+        assert_eq!(
+            request,
+            UtxoRequest {
+                path,
+                page: None,
+                skip: 10,
+                take: 10,
+            }
+        );
+    }
+
+    #[test]
+    fn confirmations_from_the_tip() {
+        let tx = TxInfo {
+            block_height: 100,
+            block_time: 1_700_000_000,
+            slot: 5_000,
+        };
+        let tip = ChainTip {
+            slot: 5_100,
+            hash: Hash::from([0; 32]),
+            height: 104,
+        };
+        assert_eq!(
+            tx_status(&tx, &tip),
+            TxStatus::Confirmed {
+                slot: 5_000,
+                height: 100,
+                confirmations: 5,
+            }
+        );
+    }
+
+    #[test]
+    fn decode_account() {
+        let account: Account = serde_json::from_str(
+            r#"{
+                "stake_address": "stake1ux3g2c9dx2nhhehyrezyxpkstartcqmu9hk63qgfkccw5rqttygt7",
+                "active": true, "active_epoch": 412,
+                "controlled_amount": "619154618165", "withdrawable_amount": "319154618165",
+                "pool_id": "02020202020202020202020202020202020202020202020202020202"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            stake_info(&account),
+            Ok(StakeInfo {
+                registered: true,
+                pool: Some(PoolId(Hash::from([2; 28]))),
+                controlled: 619_154_618_165,
+                rewards: 319_154_618_165,
+            })
+        );
+    }
+}
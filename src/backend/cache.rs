@@ -0,0 +1,213 @@
+/*!
+Cache of the backend responses, with stale-while-revalidate semantics.
+
+The data rendered on every page (protocol parameters, pool metadata,
+token registry entries...) changes rarely. The [`ResponseCache`] keeps
+the responses in a [`Storage`] so they survive page reloads:
+
+* a response younger than [`fresh_for`](ResponseCache::fresh_for) is
+  returned as is;
+* an older one, up to [`keep_for`](ResponseCache::keep_for), is still
+  returned right away but flagged [`stale`](Cached::stale): the caller
+  renders it and [`revalidate`](ResponseCache::revalidate)s it in the
+  background;
+* otherwise the response is fetched and stored.
+
+```no_run
+# use cardano_connector::{backend::{BackendError, ParamsProvider, ResponseCache}, storage::LocalStorage};
+# async fn test(provider: impl ParamsProvider) -> Result<(), BackendError> {
+let cache = ResponseCache::new(LocalStorage);
+let now = js_sys::Date::now() as u64;
+let cost_models = cache.get("cost_models", now, || provider.cost_models()).await?;
+if cost_models.stale {
+    // e.g. with `wasm_bindgen_futures::spawn_local`
+    cache.revalidate("cost_models", now, || provider.cost_models()).await?;
+}
+# Ok(()) }
+```
+*/
+
+use super::BackendError;
+use crate::storage::{Storage, StorageError};
+use serde::{Serialize, de::DeserializeOwned};
+use std::time::Duration;
+
+/// A response from the cache.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cached<T> {
+    pub value: T,
+    /// when the response was fetched, POSIX time in milliseconds
+    pub fetched_at: u64,
+    /// the response is older than [`ResponseCache::fresh_for`], it
+    /// should be revalidated
+    pub stale: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Entry<T> {
+    at: u64,
+    value: T,
+}
+
+/// see the [module documentation](self)
+#[derive(Debug)]
+pub struct ResponseCache<S> {
+    storage: S,
+    prefix: String,
+    fresh_for: Duration,
+    keep_for: Duration,
+}
+
+impl<S: Storage> ResponseCache<S> {
+    /// the responses are fresh for 5 minutes and kept for a day
+    pub fn new(storage: S) -> Self {
+        Self {
+            storage,
+            prefix: "cardano-connector.cache.".to_owned(),
+            fresh_for: Duration::from_secs(5 * 60),
+            keep_for: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+
+    /// prefix of the storage keys, to share the storage with other caches
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// how long a response is returned without revalidation
+    pub fn fresh_for(mut self, duration: Duration) -> Self {
+        self.fresh_for = duration;
+        self
+    }
+
+    /// how long a response is kept at all
+    pub fn keep_for(mut self, duration: Duration) -> Self {
+        self.keep_for = duration;
+        self
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}{key}", self.prefix)
+    }
+
+    /// the stored response, `None` if missing, expired or not readable
+    pub fn lookup<T: DeserializeOwned>(&self, key: &str, now: u64) -> Option<Cached<T>> {
+        let stored = self.storage.get(&self.key(key))?;
+        let Ok(entry) = serde_json::from_str::<Entry<T>>(&stored) else {
+            let _ = self.storage.remove(&self.key(key));
+            return None;
+        };
+
+        let age = now.saturating_sub(entry.at);
+        if u128::from(age) > self.keep_for.as_millis() {
+            let _ = self.storage.remove(&self.key(key));
+            return None;
+        }
+        Some(Cached {
+            value: entry.value,
+            fetched_at: entry.at,
+            stale: u128::from(age) > self.fresh_for.as_millis(),
+        })
+    }
+
+    pub fn store<T: Serialize>(&self, key: &str, value: &T, now: u64) -> Result<(), StorageError> {
+        let entry = serde_json::to_string(&Entry { at: now, value })
+            .map_err(|error| StorageError::Write(error.to_string()))?;
+        self.storage.set(&self.key(key), &entry)
+    }
+
+    pub fn invalidate(&self, key: &str) -> Result<(), StorageError> {
+        self.storage.remove(&self.key(key))
+    }
+
+    /// the stored response, possibly stale, or the response of `fetch`
+    /// if there is none
+    ///
+    /// The cache is best effort: the response is returned even if it
+    /// couldn't be stored.
+    pub async fn get<T, F>(
+        &self,
+        key: &str,
+        now: u64,
+        fetch: impl FnOnce() -> F,
+    ) -> Result<Cached<T>, BackendError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = Result<T, BackendError>>,
+    {
+        if let Some(cached) = self.lookup(key, now) {
+            return Ok(cached);
+        }
+        let value = self.revalidate(key, now, fetch).await?;
+        Ok(Cached {
+            value,
+            fetched_at: now,
+            stale: false,
+        })
+    }
+
+    /// fetch the response and store it
+    ///
+    /// On error the stored response, if any, is kept.
+    pub async fn revalidate<T, F>(
+        &self,
+        key: &str,
+        now: u64,
+        fetch: impl FnOnce() -> F,
+    ) -> Result<T, BackendError>
+    where
+        T: Serialize,
+        F: Future<Output = Result<T, BackendError>>,
+    {
+        let value = fetch().await?;
+        let _ = self.store(key, &value, now);
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+    use futures::executor::block_on;
+    use std::cell::Cell;
+
+    const MINUTE: u64 = 60_000;
+
+    #[test]
+    fn stale_while_revalidate() {
+        let cache = ResponseCache::new(MemoryStorage::new());
+        let fetches = Cell::new(0);
+        let fetch = || {
+            fetches.set(fetches.get() + 1);
+            async { Ok(fetches.get()) }
+        };
+
+        let cached = block_on(cache.get("params", 0, fetch)).unwrap();
+        assert_eq!((cached.value, cached.stale), (1, false));
+
+        let cached = block_on(cache.get("params", 4 * MINUTE, fetch)).unwrap();
+        assert_eq!((cached.value, cached.stale), (1, false));
+
+        let cached = block_on(cache.get("params", 10 * MINUTE, fetch)).unwrap();
+        assert_eq!((cached.value, cached.stale), (1, true));
+        assert_eq!(fetches.get(), 1);
+
+        block_on(cache.revalidate("params", 10 * MINUTE, fetch)).unwrap();
+        let cached = block_on(cache.get("params", 11 * MINUTE, fetch)).unwrap();
+        assert_eq!((cached.value, cached.stale), (2, false));
+    }
+
+    #[test]
+    fn expired_responses() {
+        let cache = ResponseCache::new(MemoryStorage::new()).keep_for(Duration::from_secs(3_600));
+        cache.store("pool", &"metadata", 0).unwrap();
+
+        assert!(cache.lookup::<String>("pool", 60 * MINUTE).is_some());
+        assert_eq!(cache.lookup::<String>("pool", 61 * MINUTE), None);
+
+        let failing = || async { Err::<String, _>(BackendError::Unavailable("down".to_owned())) };
+        assert!(block_on(cache.get("pool", 61 * MINUTE, failing)).is_err());
+    }
+}
@@ -0,0 +1,268 @@
+use super::BackendError;
+use std::time::Duration;
+
+/// How to authenticate against the chain indexer.
+#[derive(Clone, PartialEq, Eq, Default)]
+pub enum Auth {
+    #[default]
+    None,
+    /// Blockfrost's `project_id` header
+    ProjectId(String),
+    /// `Authorization: Bearer <token>` header, as used by Koios
+    Bearer(String),
+}
+
+impl Auth {
+    /// the HTTP header to add to every request, if any
+    pub fn header(&self) -> Option<(&'static str, String)> {
+        match self {
+            Self::None => None,
+            Self::ProjectId(project_id) => Some(("project_id", project_id.clone())),
+            Self::Bearer(token) => Some(("Authorization", format!("Bearer {token}"))),
+        }
+    }
+}
+
+// don't leak the credentials in the logs
+impl std::fmt::Debug for Auth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => f.write_str("None"),
+            Self::ProjectId(_) => f.write_str("ProjectId(***)"),
+            Self::Bearer(_) => f.write_str("Bearer(***)"),
+        }
+    }
+}
+
+/// Configuration of a backend client.
+#[derive(Debug, Clone)]
+pub struct BackendConfig {
+    pub base_url: String,
+    pub auth: Auth,
+    /// maximum number of requests per second, with bursts of up to
+    /// `burst` requests
+    pub requests_per_second: u32,
+    pub burst: u32,
+    /// how many times a rate limited request is retried before giving up
+    pub max_retries: u32,
+    /// the [`Quota`] is low below this percentage of requests remaining
+    pub low_quota_percent: u8,
+}
+
+impl BackendConfig {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            auth: Auth::None,
+            requests_per_second: 10,
+            burst: 500,
+            max_retries: 3,
+            low_quota_percent: 10,
+        }
+    }
+
+    pub fn with_auth(mut self, auth: Auth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    pub fn with_rate_limit(mut self, requests_per_second: u32, burst: u32) -> Self {
+        self.requests_per_second = requests_per_second;
+        self.burst = burst;
+        self
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn with_low_quota_percent(mut self, percent: u8) -> Self {
+        self.low_quota_percent = percent;
+        self
+    }
+
+    pub fn rate_limiter(&self) -> RateLimiter {
+        RateLimiter::new(self.requests_per_second, self.burst)
+    }
+
+    /// how long to wait before retrying the request that failed with
+    /// `error` on its `attempt`-th try (starting at 0)
+    ///
+    /// `None` if the request shouldn't be retried.
+    pub fn retry_delay(&self, attempt: u32, error: &BackendError) -> Option<Duration> {
+        if attempt >= self.max_retries {
+            return None;
+        }
+
+        match error {
+            BackendError::RateLimited {
+                retry_after: Some(retry_after),
+            } => Some(*retry_after),
+            // exponential back off: 1s, 2s, 4s...
+            BackendError::RateLimited { retry_after: None } => {
+                Some(Duration::from_secs(1 << attempt.min(6)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Token bucket limiting the rate of the requests sent to the backend.
+///
+/// The time is given by the caller (in milliseconds, e.g. from
+/// `Date::now()`) so it can be used both in the browser and natively.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    per_second: u32,
+    burst: u32,
+    tokens: f64,
+    last_ms: Option<u64>,
+}
+
+impl RateLimiter {
+    pub fn new(per_second: u32, burst: u32) -> Self {
+        Self {
+            per_second,
+            burst,
+            tokens: burst as f64,
+            last_ms: None,
+        }
+    }
+
+    /// take a token to send a request at `now_ms`
+    ///
+    /// If no token is available, returns how long to wait before trying
+    /// again.
+    pub fn acquire(&mut self, now_ms: u64) -> Result<(), Duration> {
+        if let Some(last_ms) = self.last_ms {
+            let elapsed = now_ms.saturating_sub(last_ms) as f64 / 1_000.0;
+            self.tokens = (self.tokens + elapsed * self.per_second as f64).min(self.burst as f64);
+        }
+        self.last_ms = Some(now_ms);
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else if self.per_second == 0 {
+            Err(Duration::MAX)
+        } else {
+            let missing = 1.0 - self.tokens;
+            Err(Duration::from_secs_f64(missing / self.per_second as f64))
+        }
+    }
+}
+
+/// The quota of requests left, as reported by the backend in the
+/// `RateLimit-*` (or `X-RateLimit-*`) response headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Quota {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    /// until the quota is reset
+    pub reset: Option<Duration>,
+}
+
+impl Quota {
+    /// read the quota from the response headers, `None` if the backend
+    /// doesn't report it
+    ///
+    /// `header` gives the value of the header of the (lower case) name.
+    pub fn from_headers(header: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let number = |name: &str| {
+            header(&format!("ratelimit-{name}"))
+                .or_else(|| header(&format!("x-ratelimit-{name}")))
+                .and_then(|value| value.trim().parse::<u64>().ok())
+        };
+        let quota = Self {
+            limit: number("limit"),
+            remaining: number("remaining"),
+            reset: number("reset").map(Duration::from_secs),
+        };
+        (quota != Self::default()).then_some(quota)
+    }
+
+    /// fewer than `percent` % of the requests remain (or none, when the
+    /// limit is not known)
+    pub fn is_low(&self, percent: u8) -> bool {
+        match (self.remaining, self.limit) {
+            (Some(remaining), Some(limit)) => remaining * 100 < limit * u64::from(percent),
+            (Some(remaining), None) => remaining == 0,
+            (None, _) => false,
+        }
+    }
+}
+
+/// parse the value of a `Retry-After` header
+///
+/// Only the delay in seconds form is supported, HTTP dates are ignored.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse().ok().map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limiter_refills() {
+        let mut limiter = RateLimiter::new(2, 2);
+
+        assert_eq!(limiter.acquire(0), Ok(()));
+        assert_eq!(limiter.acquire(0), Ok(()));
+        assert_eq!(limiter.acquire(0), Err(Duration::from_millis(500)));
+        assert_eq!(limiter.acquire(500), Ok(()));
+        // never more than the burst
+        assert_eq!(limiter.acquire(10_000), Ok(()));
+        assert_eq!(limiter.acquire(10_000), Ok(()));
+        assert!(limiter.acquire(10_000).is_err());
+    }
+
+    #[test]
+    fn retry_rate_limited() {
+        let config = BackendConfig::new("https://example.com").with_max_retries(2);
+        let limited = BackendError::RateLimited {
+            retry_after: parse_retry_after("3"),
+        };
+
+        assert_eq!(
+            config.retry_delay(0, &limited),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(config.retry_delay(2, &limited), None);
+        assert_eq!(
+            config.retry_delay(1, &BackendError::RateLimited { retry_after: None }),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(config.retry_delay(0, &BackendError::Unauthorized), None);
+    }
+
+    #[test]
+    fn quota_headers() {
+        let headers = |name: &str| match name {
+            "x-ratelimit-limit" => Some("500".to_owned()),
+            "x-ratelimit-remaining" => Some(" 42".to_owned()),
+            "ratelimit-reset" => Some("60".to_owned()),
+            _ => None,
+        };
+
+        let quota = Quota::from_headers(headers).unwrap();
+        assert_eq!(quota.limit, Some(500));
+        assert_eq!(quota.remaining, Some(42));
+        assert_eq!(quota.reset, Some(Duration::from_secs(60)));
+        assert!(quota.is_low(10));
+        assert!(!quota.is_low(5));
+        assert_eq!(Quota::from_headers(|_| None), None);
+    }
+
+    #[test]
+    fn auth_is_not_leaked() {
+        let auth = Auth::ProjectId("mainnetSecret".to_owned());
+
+        assert_eq!(format!("{auth:?}"), "ProjectId(***)");
+        assert_eq!(
+            auth.header(),
+            Some(("project_id", "mainnetSecret".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,304 @@
+/*!
+Several providers of the same kind, for resilience against outages.
+
+[`Failover`] queries its providers in order: when one fails, the next one
+is tried and only the error of the last one is returned. Providers of
+different kinds (e.g. two indexers) can be combined with an `enum`
+implementing the provider traits.
+
+The critical reads, the protocol parameters and their cost models, can
+also require several providers to agree with [`Failover::with_quorum`]: a single compromised
+or lagging provider can't change the parameters the transactions are
+built with.
+
+```no_run
+# use cardano_connector::backend::{Failover, ParamsProvider};
+# async fn test<P: ParamsProvider>(a: P, b: P, c: P) -> anyhow::Result<()> {
+// 2 of the 3 providers must return the same cost models
+let providers = Failover::new(vec![a, b, c]).with_quorum(2);
+let (version, cost_models) = providers.cost_models().await?;
+# Ok(()) }
+```
+*/
+
+use super::{
+    AddressActivity, BackendError, BlockHash, BlockProvider, BlockRef, ChainProvider, ChainQuery,
+    ChainTip, ParamsProvider, StakeInfo, TxStatus, TxSubmitter, UtxoQuery,
+};
+use crate::{
+    Address,
+    cardano::{
+        AssetId, EpochInfo, Hash, Metadata, SlotConfig, TransactionInput, TxHash, Utxo, UtxoSet,
+        fees::ProtocolParams,
+    },
+};
+use pallas_primitives::{
+    PlutusData, ProtocolVersion,
+    conway::{CostModels, ScriptRef},
+};
+
+/// see the [module documentation](self)
+#[derive(Debug, Clone)]
+pub struct Failover<P> {
+    providers: Vec<P>,
+    quorum: usize,
+}
+
+impl<P> Failover<P> {
+    /// the providers, in the order they are queried
+    pub fn new(providers: Vec<P>) -> Self {
+        Self {
+            providers,
+            quorum: 1,
+        }
+    }
+
+    /// the number of providers that must return the same protocol
+    /// parameters (and cost models)
+    pub fn with_quorum(mut self, quorum: usize) -> Self {
+        self.quorum = quorum.max(1);
+        self
+    }
+
+    pub fn providers(&self) -> &[P] {
+        &self.providers
+    }
+
+    pub fn quorum(&self) -> usize {
+        self.quorum
+    }
+
+    /// the result of the first provider answering
+    async fn first<'a, T, F>(&'a self, query: impl Fn(&'a P) -> F) -> Result<T, BackendError>
+    where
+        F: Future<Output = Result<T, BackendError>>,
+    {
+        let mut last_error = no_provider();
+        for provider in &self.providers {
+            match query(provider).await {
+                Ok(value) => return Ok(value),
+                Err(error) => last_error = error,
+            }
+        }
+        Err(last_error)
+    }
+
+    /// the first result returned by `quorum` providers
+    ///
+    /// The providers are queried in order, only as many as needed.
+    async fn agreed<'a, T, F>(&'a self, query: impl Fn(&'a P) -> F) -> Result<T, BackendError>
+    where
+        T: PartialEq,
+        F: Future<Output = Result<T, BackendError>>,
+    {
+        if self.quorum <= 1 {
+            return self.first(query).await;
+        }
+
+        let mut answers: Vec<(T, usize)> = Vec::new();
+        let mut last_error = None;
+        for provider in &self.providers {
+            let value = match query(provider).await {
+                Ok(value) => value,
+                Err(error) => {
+                    last_error = Some(error);
+                    continue;
+                }
+            };
+            let index = match answers.iter().position(|(answer, _)| *answer == value) {
+                Some(index) => index,
+                None => {
+                    answers.push((value, 0));
+                    answers.len() - 1
+                }
+            };
+            answers[index].1 += 1;
+            if answers[index].1 >= self.quorum {
+                return Ok(answers.swap_remove(index).0);
+            }
+        }
+
+        let agreeing = answers.iter().map(|(_, count)| *count).max();
+        match (agreeing, last_error) {
+            (None, Some(error)) => Err(error),
+            (None, None) => Err(no_provider()),
+            (Some(agreeing), _) => Err(BackendError::Unverified(format!(
+                "only {agreeing} of the {} required providers agree",
+                self.quorum
+            ))),
+        }
+    }
+}
+
+fn no_provider() -> BackendError {
+    BackendError::Unavailable("no provider configured".to_owned())
+}
+
+impl<P: ChainProvider> ChainProvider for Failover<P> {
+    async fn resolve_input(&self, input: &TransactionInput) -> Result<Option<Utxo>, BackendError> {
+        self.first(|provider| provider.resolve_input(input)).await
+    }
+
+    async fn address_utxos(&self, address: &Address) -> Result<UtxoSet, BackendError> {
+        self.first(|provider| provider.address_utxos(address)).await
+    }
+
+    async fn address_utxos_page(
+        &self,
+        address: &Address,
+        query: &UtxoQuery,
+    ) -> Result<UtxoSet, BackendError> {
+        self.first(|provider| provider.address_utxos_page(address, query))
+            .await
+    }
+
+    async fn address_transactions(&self, address: &Address) -> Result<Vec<TxHash>, BackendError> {
+        self.first(|provider| provider.address_transactions(address))
+            .await
+    }
+
+    async fn transaction_time(&self, hash: &TxHash) -> Result<Option<u64>, BackendError> {
+        self.first(|provider| provider.transaction_time(hash)).await
+    }
+
+    async fn address_activity(&self, address: &Address) -> Result<AddressActivity, BackendError> {
+        self.first(|provider| provider.address_activity(address))
+            .await
+    }
+
+    async fn chain_tip(&self) -> Result<ChainTip, BackendError> {
+        self.first(|provider| provider.chain_tip()).await
+    }
+
+    async fn epoch_info(&self, config: &SlotConfig) -> Result<EpochInfo, BackendError> {
+        self.first(|provider| provider.epoch_info(config)).await
+    }
+
+    async fn datum_by_hash(&self, hash: &Hash<32>) -> Result<Option<PlutusData>, BackendError> {
+        self.first(|provider| provider.datum_by_hash(hash)).await
+    }
+
+    async fn script_by_hash(&self, hash: &Hash<28>) -> Result<Option<ScriptRef>, BackendError> {
+        self.first(|provider| provider.script_by_hash(hash)).await
+    }
+}
+
+impl<P: ChainQuery> ChainQuery for Failover<P> {
+    async fn protocol_params(&self) -> Result<ProtocolParams, BackendError> {
+        self.agreed(|provider| provider.protocol_params()).await
+    }
+
+    async fn stake_info(&self, address: &Address) -> Result<Option<StakeInfo>, BackendError> {
+        self.first(|provider| provider.stake_info(address)).await
+    }
+
+    async fn tx_status(&self, hash: &TxHash) -> Result<TxStatus, BackendError> {
+        self.first(|provider| provider.tx_status(hash)).await
+    }
+
+    async fn mint_metadata(&self, asset: &AssetId) -> Result<Metadata, BackendError> {
+        self.first(|provider| provider.mint_metadata(asset)).await
+    }
+
+    async fn asset_utxo(&self, asset: &AssetId) -> Result<Option<Utxo>, BackendError> {
+        self.first(|provider| provider.asset_utxo(asset)).await
+    }
+}
+
+impl<P: ParamsProvider> ParamsProvider for Failover<P> {
+    async fn cost_models(&self) -> Result<(ProtocolVersion, CostModels), BackendError> {
+        self.agreed(|provider| provider.cost_models()).await
+    }
+}
+
+impl<P: BlockProvider> BlockProvider for Failover<P> {
+    async fn transaction_block(&self, hash: &TxHash) -> Result<Option<BlockRef>, BackendError> {
+        self.first(|provider| provider.transaction_block(hash))
+            .await
+    }
+
+    async fn block_cbor(&self, hash: &BlockHash) -> Result<Vec<u8>, BackendError> {
+        self.first(|provider| provider.block_cbor(hash)).await
+    }
+
+    async fn tip_height(&self) -> Result<u64, BackendError> {
+        self.first(|provider| provider.tip_height()).await
+    }
+}
+
+impl<P: TxSubmitter> TxSubmitter for Failover<P> {
+    async fn submit_tx(&self, tx: &[u8]) -> Result<TxHash, BackendError> {
+        self.first(|provider| provider.submit_tx(tx)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// answers with the given protocol version, or fails
+    struct Params {
+        version: Option<u64>,
+        calls: Cell<usize>,
+    }
+
+    fn params(version: Option<u64>) -> Params {
+        Params {
+            version,
+            calls: Cell::new(0),
+        }
+    }
+
+    impl ParamsProvider for Params {
+        async fn cost_models(&self) -> Result<(ProtocolVersion, CostModels), BackendError> {
+            self.calls.set(self.calls.get() + 1);
+            let version = self
+                .version
+                .ok_or_else(|| BackendError::Unavailable("down".to_owned()))?;
+            let cost_models = CostModels {
+                plutus_v1: None,
+                plutus_v2: None,
+                plutus_v3: None,
+            };
+            Ok(((version, 0), cost_models))
+        }
+    }
+
+    fn version(providers: &Failover<Params>) -> Result<u64, BackendError> {
+        futures::executor::block_on(providers.cost_models()).map(|((version, _), _)| version)
+    }
+
+    #[test]
+    fn failover_to_next_provider() {
+        let providers = Failover::new(vec![params(None), params(Some(10)), params(Some(9))]);
+
+        assert_eq!(version(&providers), Ok(10));
+        assert_eq!(providers.providers()[2].calls.get(), 0);
+
+        let providers = Failover::new(vec![params(None), params(None)]);
+        assert_eq!(
+            version(&providers),
+            Err(BackendError::Unavailable("down".to_owned()))
+        );
+    }
+
+    #[test]
+    fn quorum_reads() {
+        let providers = Failover::new(vec![
+            params(Some(9)),
+            params(None),
+            params(Some(10)),
+            params(Some(10)),
+        ])
+        .with_quorum(2);
+        assert_eq!(version(&providers), Ok(10));
+
+        let providers =
+            Failover::new(vec![params(Some(9)), params(Some(10)), params(None)]).with_quorum(2);
+        assert!(matches!(
+            version(&providers),
+            Err(BackendError::Unverified(_))
+        ));
+    }
+}
@@ -0,0 +1,153 @@
+use super::{BackendConfig, BackendError, Quota, RateLimiter};
+use crate::{
+    events::{Event, EventBus},
+    ffi::{self, fetch::HttpResponse},
+};
+use std::cell::RefCell;
+use wasm_bindgen::JsValue;
+
+/// HTTP client of the backends, applying the [`BackendConfig`]: the
+/// authentication header, the rate limit and the retries.
+///
+/// The [`Quota`] reported by the backend is kept, and [`Event::QuotaLow`]
+/// emitted when it goes below the
+/// [`low_quota_percent`](BackendConfig::low_quota_percent).
+pub struct HttpClient {
+    config: BackendConfig,
+    limiter: RefCell<RateLimiter>,
+    quota: RefCell<Option<Quota>>,
+    events: Option<EventBus>,
+}
+
+impl HttpClient {
+    pub fn new(config: BackendConfig) -> Self {
+        let limiter = RefCell::new(config.rate_limiter());
+        Self {
+            config,
+            limiter,
+            quota: RefCell::new(None),
+            events: None,
+        }
+    }
+
+    /// emit [`Event::QuotaLow`] on the bus
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    pub fn config(&self) -> &BackendConfig {
+        &self.config
+    }
+
+    /// the quota reported with the last response, `None` if the backend
+    /// doesn't report it
+    pub fn quota(&self) -> Option<Quota> {
+        *self.quota.borrow()
+    }
+
+    fn update_quota(&self, response: &HttpResponse) {
+        let Some(quota) = Quota::from_headers(|name| response.header(name)) else {
+            return;
+        };
+        let percent = self.config.low_quota_percent;
+        let was_low = self
+            .quota
+            .replace(Some(quota))
+            .is_some_and(|previous| previous.is_low(percent));
+        if let (Some(events), false, true) = (&self.events, was_low, quota.is_low(percent)) {
+            events.emit(Event::QuotaLow {
+                base_url: self.config.base_url.clone(),
+                quota,
+            });
+        }
+    }
+
+    /// `GET` the path (relative to the `base_url`) and decode the JSON
+    /// response
+    pub async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T, BackendError> {
+        decode(&self.send("GET", path, None, None).await?)
+    }
+
+    /// `POST` the JSON body and decode the JSON response
+    pub async fn post_json<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &serde_json::Value,
+    ) -> Result<T, BackendError> {
+        let body = JsValue::from_str(&body.to_string());
+        decode(
+            &self
+                .send("POST", path, Some("application/json"), Some(body))
+                .await?,
+        )
+    }
+
+    /// `POST` the raw CBOR bytes (e.g. a transaction to submit) and
+    /// decode the JSON response
+    pub async fn post_cbor<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        cbor: &[u8],
+    ) -> Result<T, BackendError> {
+        let body = js_sys::Uint8Array::from(cbor).into();
+        decode(
+            &self
+                .send("POST", path, Some("application/cbor"), Some(body))
+                .await?,
+        )
+    }
+
+    async fn send(
+        &self,
+        method: &str,
+        path: &str,
+        content_type: Option<&str>,
+        body: Option<JsValue>,
+    ) -> Result<String, BackendError> {
+        let url = format!("{}{path}", self.config.base_url.trim_end_matches('/'));
+        let mut headers: Vec<_> = self.config.auth.header().into_iter().collect();
+        if let Some(content_type) = content_type {
+            headers.push(("Content-Type", content_type.to_owned()));
+        }
+
+        let mut attempt = 0;
+        loop {
+            let acquired = self
+                .limiter
+                .borrow_mut()
+                .acquire(js_sys::Date::now() as u64);
+            if let Err(wait) = acquired {
+                ffi::timer::sleep(wait).await;
+                continue;
+            }
+
+            let response = ffi::fetch::fetch(method, &url, &headers, body.clone()).await;
+            if let Ok(response) = &response {
+                self.update_quota(response);
+            }
+            let error = match response {
+                Ok(HttpResponse { status, body, .. }) if (200..300).contains(&status) => {
+                    return Ok(body);
+                }
+                Ok(HttpResponse {
+                    status,
+                    retry_after,
+                    body,
+                    ..
+                }) => BackendError::from_status(status, retry_after.as_deref(), body),
+                Err(error) => BackendError::Unavailable(format!("{error:?}")),
+            };
+
+            let Some(delay) = self.config.retry_delay(attempt, &error) else {
+                return Err(error);
+            };
+            ffi::timer::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+fn decode<T: serde::de::DeserializeOwned>(body: &str) -> Result<T, BackendError> {
+    serde_json::from_str(body).map_err(|error| BackendError::Decode(error.to_string()))
+}
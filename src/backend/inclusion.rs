@@ -0,0 +1,371 @@
+/*!
+Proofs that a transaction is included in a block, at a given depth.
+
+A backend saying a transaction is confirmed is usually good enough. For
+high value flows the [`verify_inclusion`] function checks it from the
+raw block instead: the block hashes to the expected block hash, the
+header commits (through its block body hash) to the transaction bodies
+and one of them hashes to the transaction hash. The depth of the block
+is then compared with the tip of the chain.
+
+The header itself is not validated: its VRF and KES proofs and its
+operational certificate are not checked, nor is the block chained to
+its predecessor. The block is only as trustworthy as the block hash the
+backend gave for the transaction.
+
+Once verified, the transaction can be confirmed in the
+[`PendingUtxos`](crate::cardano::PendingUtxos) (or a
+[`SigningSession`](crate::signing_session::SigningSession)).
+
+```no_run
+# use cardano_connector::{TxHash, backend::{BlockProvider, verify_inclusion}};
+# async fn test(provider: impl BlockProvider, hash: TxHash) -> anyhow::Result<()> {
+// at least 15 blocks deep
+let proof = verify_inclusion(&provider, &hash, 15).await?;
+# Ok(()) }
+```
+*/
+
+use super::BackendError;
+use crate::cardano::TxHash;
+use pallas_codec::minicbor::{Decoder, data::Type, decode};
+use pallas_crypto::hash::{Hash, Hasher};
+
+pub type BlockHash = Hash<32>;
+
+/// index of the block body hash in the (Babbage and later) header body
+const BLOCK_BODY_HASH_INDEX: u64 = 7;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum InclusionError {
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    #[error("The transaction {0} is not on chain")]
+    NotOnChain(TxHash),
+    #[error("Couldn't decode the block: {0}")]
+    Decode(String),
+    #[error("The block hashes to {found}, expected {expected}")]
+    BlockHashMismatch {
+        expected: BlockHash,
+        found: BlockHash,
+    },
+    #[error("The block body doesn't match the block body hash of its header")]
+    BodyHashMismatch,
+    #[error("The transaction {0} is not part of the block")]
+    NotInBlock(TxHash),
+    #[error("The transaction is {depth} blocks deep, {required} required")]
+    NotDeepEnough { depth: u64, required: u64 },
+}
+
+impl From<decode::Error> for InclusionError {
+    fn from(error: decode::Error) -> Self {
+        Self::Decode(error.to_string())
+    }
+}
+
+/// The position of a transaction on chain, as given by the backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockRef {
+    pub hash: BlockHash,
+    pub height: u64,
+}
+
+/// Source of blocks, for the backends exposing them.
+#[allow(async_fn_in_trait)]
+pub trait BlockProvider {
+    /// the block including the transaction, `None` if the transaction is
+    /// not on chain
+    async fn transaction_block(&self, hash: &TxHash) -> Result<Option<BlockRef>, BackendError>;
+
+    /// the raw CBOR of the block
+    async fn block_cbor(&self, hash: &BlockHash) -> Result<Vec<u8>, BackendError>;
+
+    /// the height of the tip of the chain
+    async fn tip_height(&self) -> Result<u64, BackendError>;
+}
+
+impl<P: BlockProvider> BlockProvider for &P {
+    async fn transaction_block(&self, hash: &TxHash) -> Result<Option<BlockRef>, BackendError> {
+        (*self).transaction_block(hash).await
+    }
+
+    async fn block_cbor(&self, hash: &BlockHash) -> Result<Vec<u8>, BackendError> {
+        (*self).block_cbor(hash).await
+    }
+
+    async fn tip_height(&self) -> Result<u64, BackendError> {
+        (*self).tip_height().await
+    }
+}
+
+/// A transaction verified to be part of a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InclusionProof {
+    pub block: BlockHash,
+    pub height: u64,
+    pub slot: u64,
+    /// the index of the transaction in the block
+    pub index: usize,
+    /// `false` if the transaction failed its Plutus scripts: only its
+    /// collateral was spent
+    pub valid: bool,
+}
+
+impl InclusionProof {
+    /// the number of blocks on top of (and including) the block of the
+    /// transaction
+    pub fn depth(&self, tip_height: u64) -> u64 {
+        (tip_height + 1).saturating_sub(self.height)
+    }
+}
+
+/// fetch the block of the transaction and check the transaction is part
+/// of it, at least `min_depth` blocks deep
+pub async fn verify_inclusion<P: BlockProvider>(
+    provider: &P,
+    hash: &TxHash,
+    min_depth: u64,
+) -> Result<InclusionProof, InclusionError> {
+    let block = provider
+        .transaction_block(hash)
+        .await?
+        .ok_or(InclusionError::NotOnChain(*hash))?;
+    let cbor = provider.block_cbor(&block.hash).await?;
+    let proof = verify_block(&cbor, &block.hash, hash)?;
+
+    let depth = proof.depth(provider.tip_height().await?);
+    if depth < min_depth {
+        return Err(InclusionError::NotDeepEnough {
+            depth,
+            required: min_depth,
+        });
+    }
+    Ok(proof)
+}
+
+/// check the raw block hashes to `block_hash` and includes the transaction
+///
+/// The block may be wrapped with its era (`[era, block]`), as returned by
+/// most backends. Its header is not validated, see the
+/// [module documentation](self).
+pub fn verify_block(
+    cbor: &[u8],
+    block_hash: &BlockHash,
+    hash: &TxHash,
+) -> Result<InclusionProof, InclusionError> {
+    let mut decoder = Decoder::new(cbor);
+    let mut length = decoder.array()?;
+    if decoder.datatype()? != Type::Array {
+        // [era, block]
+        decoder.skip()?;
+        length = decoder.array()?;
+    }
+    if length.is_some_and(|length| length < 5) {
+        return Err(InclusionError::Decode("missing block parts".to_owned()));
+    }
+
+    let header = raw(&mut decoder)?;
+    let found = Hasher::<256>::hash(header);
+    if found != *block_hash {
+        return Err(InclusionError::BlockHashMismatch {
+            expected: *block_hash,
+            found,
+        });
+    }
+    let (height, slot, body_hash) = header_body(header)?;
+
+    // transaction bodies, witness sets, auxiliary data, invalid transactions
+    let parts = [
+        raw(&mut decoder)?,
+        raw(&mut decoder)?,
+        raw(&mut decoder)?,
+        raw(&mut decoder)?,
+    ];
+    let mut hashes = Vec::with_capacity(parts.len() * 32);
+    for part in parts {
+        hashes.extend_from_slice(Hasher::<256>::hash(part).as_ref());
+    }
+    if Hasher::<256>::hash(&hashes) != body_hash {
+        return Err(InclusionError::BodyHashMismatch);
+    }
+
+    let index = elements(parts[0])?
+        .into_iter()
+        .position(|body| Hasher::<256>::hash(body) == *hash)
+        .ok_or(InclusionError::NotInBlock(*hash))?;
+    let mut invalid = Decoder::new(parts[3]);
+    let invalid: Vec<u64> = invalid.array_iter()?.collect::<Result<_, _>>()?;
+
+    Ok(InclusionProof {
+        block: *block_hash,
+        height,
+        slot,
+        index,
+        valid: !invalid.contains(&(index as u64)),
+    })
+}
+
+/// the block number, slot and block body hash of the header
+fn header_body(header: &[u8]) -> Result<(u64, u64, Hash<32>), InclusionError> {
+    let mut decoder = Decoder::new(header);
+    decoder.array()?;
+    decoder.array()?;
+    let height = decoder.u64()?;
+    let slot = decoder.u64()?;
+    for _ in 2..BLOCK_BODY_HASH_INDEX {
+        decoder.skip()?;
+    }
+    let body_hash = <[u8; 32]>::try_from(decoder.bytes()?)
+        .map_err(|_| InclusionError::Decode("invalid block body hash".to_owned()))?;
+    Ok((height, slot, Hash::from(body_hash)))
+}
+
+/// the raw bytes of the next CBOR item
+fn raw<'b>(decoder: &mut Decoder<'b>) -> Result<&'b [u8], InclusionError> {
+    let start = decoder.position();
+    decoder.skip()?;
+    Ok(&decoder.input()[start..decoder.position()])
+}
+
+/// the raw bytes of the items of the CBOR array
+fn elements(array: &[u8]) -> Result<Vec<&[u8]>, InclusionError> {
+    let mut decoder = Decoder::new(array);
+    let mut elements = Vec::new();
+    match decoder.array()? {
+        Some(length) => {
+            for _ in 0..length {
+                elements.push(raw(&mut decoder)?);
+            }
+        }
+        None => {
+            while decoder.datatype()? != Type::Break {
+                elements.push(raw(&mut decoder)?);
+            }
+        }
+    }
+    Ok(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::testing::{empty_body, empty_witness_set};
+    use pallas_codec::minicbor::{self, Encoder};
+
+    /// a block with the given transaction bodies, the last one invalid,
+    /// wrapped with its era
+    fn block(fees: &[u64]) -> (Vec<u8>, BlockHash, Vec<TxHash>) {
+        block_with(fees, minicbor::to_vec(vec![fees.len() as u64 - 1]).unwrap())
+    }
+
+    /// a block with the given transaction bodies and the given raw list
+    /// of invalid transactions, wrapped with its era
+    fn block_with(fees: &[u64], invalid: Vec<u8>) -> (Vec<u8>, BlockHash, Vec<TxHash>) {
+        let mut bodies = Vec::new();
+        let mut encoder = Encoder::new(&mut bodies);
+        encoder.array(fees.len() as u64).unwrap();
+        let mut hashes = Vec::new();
+        for fee in fees {
+            let mut body = empty_body();
+            body.fee = *fee;
+            let body = minicbor::to_vec(body).unwrap();
+            hashes.push(Hasher::<256>::hash(&body));
+            encoder.writer_mut().extend_from_slice(&body);
+        }
+        let witnesses = minicbor::to_vec(vec![empty_witness_set(); fees.len()]).unwrap();
+        let auxiliary_data =
+            minicbor::to_vec(std::collections::BTreeMap::<u64, u64>::new()).unwrap();
+
+        let mut body_hashes = Vec::new();
+        for part in [&bodies, &witnesses, &auxiliary_data, &invalid] {
+            body_hashes.extend_from_slice(Hasher::<256>::hash(part).as_ref());
+        }
+        let body_hash = Hasher::<256>::hash(&body_hashes);
+
+        let mut header = Vec::new();
+        let mut encoder = Encoder::new(&mut header);
+        encoder.array(2).unwrap().array(10).unwrap();
+        encoder.u64(42).unwrap().u64(1_000).unwrap();
+        encoder.bytes(&[0; 32]).unwrap().bytes(&[1; 32]).unwrap();
+        encoder.bytes(&[2; 32]).unwrap();
+        encoder
+            .array(2)
+            .unwrap()
+            .bytes(&[3; 64])
+            .unwrap()
+            .bytes(&[4; 80])
+            .unwrap();
+        encoder
+            .u64(1_024)
+            .unwrap()
+            .bytes(body_hash.as_ref())
+            .unwrap();
+        encoder
+            .array(4)
+            .unwrap()
+            .bytes(&[5; 32])
+            .unwrap()
+            .u64(0)
+            .unwrap();
+        encoder.u64(0).unwrap().bytes(&[6; 64]).unwrap();
+        encoder.array(2).unwrap().u64(10).unwrap().u64(0).unwrap();
+        encoder.bytes(&[7; 448]).unwrap();
+        let block_hash = Hasher::<256>::hash(&header);
+
+        let mut cbor = Vec::new();
+        let mut encoder = Encoder::new(&mut cbor);
+        encoder.array(2).unwrap().u64(7).unwrap().array(5).unwrap();
+        for part in [&header, &bodies, &witnesses, &auxiliary_data, &invalid] {
+            encoder.writer_mut().extend_from_slice(part);
+        }
+        (cbor, block_hash, hashes)
+    }
+
+    #[test]
+    fn transaction_in_block() {
+        let (cbor, block_hash, hashes) = block(&[200_000, 300_000]);
+
+        let proof = verify_block(&cbor, &block_hash, &hashes[0]).unwrap();
+        assert_eq!(proof.height, 42);
+        assert_eq!(proof.slot, 1_000);
+        assert_eq!(proof.index, 0);
+        assert!(proof.valid);
+        assert_eq!(proof.depth(51), 10);
+
+        let proof = verify_block(&cbor, &block_hash, &hashes[1]).unwrap();
+        assert!(!proof.valid);
+    }
+
+    #[test]
+    fn tampered_block() {
+        let (mut cbor, block_hash, hashes) = block(&[200_000]);
+
+        assert_eq!(
+            verify_block(&cbor, &block_hash, &TxHash::from([0; 32])),
+            Err(InclusionError::NotInBlock(TxHash::from([0; 32])))
+        );
+        assert!(matches!(
+            verify_block(&cbor, &TxHash::from([0; 32]), &hashes[0]),
+            Err(InclusionError::BlockHashMismatch { .. })
+        ));
+
+        // change the invalid transactions, at the end of the block
+        let last = cbor.len() - 1;
+        cbor[last] = 1;
+        assert_eq!(
+            verify_block(&cbor, &block_hash, &hashes[0]),
+            Err(InclusionError::BodyHashMismatch)
+        );
+    }
+
+    #[test]
+    fn undecodable_invalid_transactions() {
+        let invalid = minicbor::to_vec(vec!["0"]).unwrap();
+        let (cbor, block_hash, hashes) = block_with(&[200_000], invalid);
+
+        assert!(matches!(
+            verify_block(&cbor, &block_hash, &hashes[0]),
+            Err(InclusionError::Decode(_))
+        ));
+    }
+}
@@ -0,0 +1,600 @@
+/*!
+[Koios](https://koios.rest) client, the chain data from public endpoints.
+
+The public endpoints of Koios need no API key (a token raises the rate
+limit, see [`Auth::Bearer`]): a dApp can resolve the protocol
+parameters, the stake of its users and the confirmation of their
+transactions without an account with a provider.
+
+```no_run
+# use cardano_connector::{NetworkId, backend::{ChainQuery, TxStatus, koios::Koios}, TxHash};
+# async fn test(hash: TxHash) -> anyhow::Result<()> {
+let koios = Koios::for_network(NetworkId::Mainnet).unwrap();
+let params = koios.protocol_params().await?;
+if koios.tx_status(&hash).await? == TxStatus::NotFound {
+    // not on chain yet
+}
+# Ok(()) }
+```
+
+The requests go through the [`HttpClient`], with its rate limit and
+retries. This needs the `koios` feature.
+*/
+
+use super::{
+    BackendConfig, BackendError, ChainProvider, ChainQuery, ChainTip, ParamsProvider, StakeInfo,
+    TxStatus, TxSubmitter,
+    config::Auth,
+    decode_datum, decode_script,
+    http::HttpClient,
+    rest::{self, bech32, decode_error, quantity, rational, script_type},
+};
+use crate::{
+    Address, NetworkId,
+    cardano::{
+        AssetId, DepositParams, Hash, Metadata, PoolId, TransactionInput, TxHash, Utxo, UtxoSet,
+        Value, WalletValue, fees::ProtocolParams,
+    },
+};
+use pallas_codec::{minicbor, utils::CborWrap};
+use pallas_primitives::{
+    PlutusData, ProtocolVersion,
+    conway::{CostModels, DatumOption},
+};
+use serde_json::json;
+use std::{collections::BTreeMap, str::FromStr};
+
+pub const MAINNET_URL: &str = "https://api.koios.rest/api/v1";
+pub const PREPROD_URL: &str = "https://preprod.koios.rest/api/v1";
+pub const PREVIEW_URL: &str = "https://preview.koios.rest/api/v1";
+
+/// the maximum number of rows of a response
+const PAGE_SIZE: usize = 1_000;
+
+/// Client of the Koios API, see the [module documentation](self).
+pub struct Koios {
+    client: HttpClient,
+}
+
+impl Koios {
+    /// the API at the `base_url` of the config
+    pub fn new(config: BackendConfig) -> Self {
+        Self {
+            client: HttpClient::new(config),
+        }
+    }
+
+    /// the public Koios API of the network, without API key, `None` for
+    /// an unknown network
+    pub fn for_network(network: NetworkId) -> Option<Self> {
+        let base_url = match network {
+            NetworkId::Mainnet => MAINNET_URL,
+            NetworkId::PreProduction => PREPROD_URL,
+            NetworkId::Preview => PREVIEW_URL,
+            NetworkId::Unknown(_) => return None,
+        };
+        Some(Self::new(BackendConfig::new(base_url)))
+    }
+
+    /// authenticate the requests with a Koios token, for a higher rate
+    /// limit
+    pub fn with_token(self, token: impl Into<String>) -> Self {
+        let config = self.client.config().clone();
+        Self::new(config.with_auth(Auth::Bearer(token.into())))
+    }
+
+    pub fn client(&self) -> &HttpClient {
+        &self.client
+    }
+
+    async fn params(&self) -> Result<CliParams, BackendError> {
+        self.client.get("/cli_protocol_params").await
+    }
+
+    async fn tx_info(&self, hash: &TxHash) -> Result<Option<TxInfo>, BackendError> {
+        let body = json!({
+            "_tx_hashes": [hash.to_string()],
+            "_inputs": false,
+            "_metadata": false,
+            "_assets": false,
+            "_withdrawals": false,
+            "_certs": false,
+            "_scripts": false,
+            "_bytecode": false,
+        });
+        let txs: Vec<TxInfo> = self.client.post_json("/tx_info", &body).await?;
+        Ok(txs.into_iter().next())
+    }
+
+    /// the rows of all the pages of the `endpoint`, Koios caps the
+    /// responses to [`PAGE_SIZE`] rows
+    async fn pages<T: serde::de::DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        body: &serde_json::Value,
+    ) -> Result<Vec<T>, BackendError> {
+        let mut rows = Vec::new();
+        for page in 0.. {
+            let path = format!("{endpoint}?offset={}&limit={PAGE_SIZE}", page * PAGE_SIZE);
+            let page: Vec<T> = self.client.post_json(&path, body).await?;
+            let last = page.len() < PAGE_SIZE;
+            rows.extend(page);
+            if last {
+                break;
+            }
+        }
+        Ok(rows)
+    }
+
+    /// all the UTxOs of the `endpoint` (`/address_utxos` or
+    /// `/account_utxos`)
+    async fn utxos(
+        &self,
+        endpoint: &str,
+        body: serde_json::Value,
+    ) -> Result<UtxoSet, BackendError> {
+        let rows: Vec<UtxoRow> = self.pages(endpoint, &body).await?;
+        rows.iter().map(decode_utxo).collect()
+    }
+}
+
+impl ChainQuery for Koios {
+    async fn protocol_params(&self) -> Result<ProtocolParams, BackendError> {
+        Ok(protocol_params(&self.params().await?))
+    }
+
+    async fn stake_info(&self, address: &Address) -> Result<Option<StakeInfo>, BackendError> {
+        let body = json!({ "_stake_addresses": [bech32(address)?] });
+        let accounts: Vec<Account> = self.client.post_json("/account_info", &body).await?;
+        accounts.first().map(stake_info).transpose()
+    }
+
+    async fn tx_status(&self, hash: &TxHash) -> Result<TxStatus, BackendError> {
+        let Some(tx) = self.tx_info(hash).await? else {
+            return Ok(TxStatus::NotFound);
+        };
+        let tip = self.chain_tip().await?;
+        Ok(TxStatus::Confirmed {
+            slot: tx.absolute_slot,
+            height: tx.block_height,
+            confirmations: (tip.height + 1).saturating_sub(tx.block_height),
+        })
+    }
+
+    async fn mint_metadata(&self, asset: &AssetId) -> Result<Metadata, BackendError> {
+        let body = json!({ "_asset_list": [asset_ref(asset)] });
+        let assets: Vec<AssetInfo> = self.client.post_json("/asset_info", &body).await?;
+        let Some(AssetInfo {
+            minting_tx_hash: Some(hash),
+        }) = assets.first()
+        else {
+            return Ok(Metadata::new());
+        };
+        let body = json!({ "_tx_hashes": [hash] });
+        let txs: Vec<TxCbor> = self.client.post_json("/tx_cbor", &body).await?;
+        match txs.first() {
+            Some(tx) => rest::tx_metadata(&tx.cbor),
+            None => Ok(Metadata::new()),
+        }
+    }
+
+    async fn asset_utxo(&self, asset: &AssetId) -> Result<Option<Utxo>, BackendError> {
+        let body = json!({ "_asset_list": [asset_ref(asset)], "_extended": true });
+        let rows: Vec<UtxoRow> = self.client.post_json("/asset_utxos", &body).await?;
+        rows.iter()
+            .find(|row| !row.is_spent)
+            .map(decode_utxo)
+            .transpose()
+    }
+}
+
+impl ChainProvider for Koios {
+    async fn resolve_input(&self, input: &TransactionInput) -> Result<Option<Utxo>, BackendError> {
+        let body = json!({
+            "_utxo_refs": [format!("{}#{}", input.transaction_id, input.index)],
+            "_extended": true,
+        });
+        let rows: Vec<UtxoRow> = self.client.post_json("/utxo_info", &body).await?;
+        rows.iter()
+            .find(|row| !row.is_spent)
+            .map(decode_utxo)
+            .transpose()
+    }
+
+    async fn address_utxos(&self, address: &Address) -> Result<UtxoSet, BackendError> {
+        match address {
+            Address::Stake(_) => {
+                let body = json!({ "_stake_addresses": [bech32(address)?], "_extended": true });
+                self.utxos("/account_utxos", body).await
+            }
+            address => {
+                let body = json!({ "_addresses": [bech32(address)?], "_extended": true });
+                self.utxos("/address_utxos", body).await
+            }
+        }
+    }
+
+    async fn address_transactions(&self, address: &Address) -> Result<Vec<TxHash>, BackendError> {
+        let (path, body) = match address {
+            Address::Stake(_) => (
+                "/account_txs",
+                json!({ "_stake_address": bech32(address)? }),
+            ),
+            address => ("/address_txs", json!({ "_addresses": [bech32(address)?] })),
+        };
+        let mut txs: Vec<AddressTx> = self.pages(path, &body).await?;
+        txs.sort_by_key(|tx| std::cmp::Reverse(tx.block_height));
+        txs.iter()
+            .map(|tx| Hash::from_str(&tx.tx_hash).map_err(decode_error))
+            .collect()
+    }
+
+    async fn transaction_time(&self, hash: &TxHash) -> Result<Option<u64>, BackendError> {
+        Ok(self.tx_info(hash).await?.map(|tx| tx.tx_timestamp * 1_000))
+    }
+
+    async fn chain_tip(&self) -> Result<ChainTip, BackendError> {
+        let tips: Vec<Tip> = self.client.get("/tip").await?;
+        let tip = tips
+            .first()
+            .ok_or_else(|| BackendError::Decode("empty tip".to_owned()))?;
+        Ok(ChainTip {
+            slot: tip.abs_slot,
+            hash: Hash::from_str(&tip.hash).map_err(decode_error)?,
+            height: tip.block_height,
+        })
+    }
+
+    async fn datum_by_hash(&self, hash: &Hash<32>) -> Result<Option<PlutusData>, BackendError> {
+        let body = json!({ "_datum_hashes": [hash.to_string()] });
+        let datums: Vec<Bytes> = self.client.post_json("/datum_info", &body).await?;
+        let Some(Bytes { bytes: Some(bytes) }) = datums.first() else {
+            return Ok(None);
+        };
+        decode_datum(hash, &hex::decode(bytes).map_err(decode_error)?).map(Some)
+    }
+
+    async fn script_by_hash(
+        &self,
+        hash: &Hash<28>,
+    ) -> Result<Option<pallas_primitives::conway::ScriptRef>, BackendError> {
+        let body = json!({ "_script_hashes": [hash.to_string()] });
+        let scripts: Vec<Script> = self.client.post_json("/script_info", &body).await?;
+        let Some(Script {
+            r#type,
+            bytes: Some(bytes),
+        }) = scripts.first()
+        else {
+            return Ok(None);
+        };
+        decode_script(
+            hash,
+            script_type(r#type)?,
+            &hex::decode(bytes).map_err(decode_error)?,
+        )
+        .map(Some)
+    }
+}
+
+impl ParamsProvider for Koios {
+    async fn cost_models(&self) -> Result<(ProtocolVersion, CostModels), BackendError> {
+        Ok(cost_models(&self.params().await?))
+    }
+}
+
+impl TxSubmitter for Koios {
+    async fn submit_tx(&self, tx: &[u8]) -> Result<TxHash, BackendError> {
+        let hash: String = self.client.post_cbor("/submittx", tx).await?;
+        Hash::from_str(&hash).map_err(decode_error)
+    }
+}
+
+/// the `[policy_id, asset_name]` pair of the asset queries
+fn asset_ref(asset: &AssetId) -> [String; 2] {
+    [asset.policy.to_string(), hex::encode(asset.name.as_slice())]
+}
+
+/// `/cli_protocol_params`, in the format of `cardano-cli`
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CliParams {
+    tx_fee_per_byte: u64,
+    tx_fee_fixed: u64,
+    max_tx_size: u64,
+    stake_address_deposit: u64,
+    stake_pool_deposit: u64,
+    execution_unit_prices: Option<Prices>,
+    utxo_cost_per_byte: Option<u64>,
+    collateral_percentage: Option<u64>,
+    protocol_version: Version,
+    #[serde(default)]
+    cost_models: BTreeMap<String, Vec<i64>>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Prices {
+    price_memory: f64,
+    price_steps: f64,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Version {
+    major: u64,
+    minor: u64,
+}
+
+/// `/tip`
+#[derive(Debug, serde::Deserialize)]
+struct Tip {
+    hash: String,
+    abs_slot: u64,
+    #[serde(alias = "block_no")]
+    block_height: u64,
+}
+
+/// `/tx_info`
+#[derive(Debug, serde::Deserialize)]
+struct TxInfo {
+    absolute_slot: u64,
+    block_height: u64,
+    tx_timestamp: u64,
+}
+
+/// `/asset_info`, `minting_tx_hash` is the latest mint
+#[derive(Debug, serde::Deserialize)]
+struct AssetInfo {
+    minting_tx_hash: Option<String>,
+}
+
+/// `/tx_cbor`
+#[derive(Debug, serde::Deserialize)]
+struct TxCbor {
+    cbor: String,
+}
+
+/// `/address_txs` and `/account_txs`
+#[derive(Debug, serde::Deserialize)]
+struct AddressTx {
+    tx_hash: String,
+    block_height: u64,
+}
+
+/// `/account_info`
+#[derive(Debug, serde::Deserialize)]
+struct Account {
+    status: String,
+    delegated_pool: Option<String>,
+    total_balance: String,
+    rewards_available: String,
+}
+
+/// an extended row of `/utxo_info`, `/address_utxos` or `/account_utxos`
+#[derive(Debug, serde::Deserialize)]
+struct UtxoRow {
+    tx_hash: String,
+    tx_index: u64,
+    address: String,
+    value: String,
+    datum_hash: Option<String>,
+    inline_datum: Option<Bytes>,
+    reference_script: Option<Script>,
+    #[serde(default)]
+    asset_list: Option<Vec<Asset>>,
+    #[serde(default)]
+    is_spent: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Asset {
+    policy_id: String,
+    asset_name: Option<String>,
+    quantity: String,
+}
+
+/// the datums of `/datum_info` and the inline datums
+#[derive(Debug, serde::Deserialize)]
+struct Bytes {
+    bytes: Option<String>,
+}
+
+/// the scripts of `/script_info` and the reference scripts
+#[derive(Debug, serde::Deserialize)]
+struct Script {
+    r#type: String,
+    bytes: Option<String>,
+}
+
+fn protocol_params(params: &CliParams) -> ProtocolParams {
+    let defaults = ProtocolParams::MAINNET;
+    ProtocolParams {
+        min_fee_a: params.tx_fee_per_byte,
+        min_fee_b: params.tx_fee_fixed,
+        price_mem: params
+            .execution_unit_prices
+            .as_ref()
+            .map_or(defaults.price_mem, |prices| rational(prices.price_memory)),
+        price_step: params
+            .execution_unit_prices
+            .as_ref()
+            .map_or(defaults.price_step, |prices| rational(prices.price_steps)),
+        coins_per_utxo_byte: params
+            .utxo_cost_per_byte
+            .unwrap_or(defaults.coins_per_utxo_byte),
+        max_tx_size: params.max_tx_size,
+        deposits: DepositParams {
+            key_deposit: params.stake_address_deposit,
+            pool_deposit: params.stake_pool_deposit,
+        },
+        collateral_percentage: params
+            .collateral_percentage
+            .unwrap_or(defaults.collateral_percentage),
+    }
+}
+
+fn cost_models(params: &CliParams) -> (ProtocolVersion, CostModels) {
+    (
+        (params.protocol_version.major, params.protocol_version.minor),
+        CostModels {
+            plutus_v1: params.cost_models.get("PlutusV1").cloned(),
+            plutus_v2: params.cost_models.get("PlutusV2").cloned(),
+            plutus_v3: params.cost_models.get("PlutusV3").cloned(),
+        },
+    )
+}
+
+fn stake_info(account: &Account) -> Result<StakeInfo, BackendError> {
+    Ok(StakeInfo {
+        registered: account.status == "registered",
+        pool: account
+            .delegated_pool
+            .as_deref()
+            .map(PoolId::from_str)
+            .transpose()
+            .map_err(decode_error)?,
+        controlled: quantity(&account.total_balance)?,
+        rewards: quantity(&account.rewards_available)?,
+    })
+}
+
+fn decode_utxo(row: &UtxoRow) -> Result<Utxo, BackendError> {
+    let mut value = WalletValue::from_lovelace(quantity(&row.value)?);
+    for asset in row.asset_list.iter().flatten() {
+        let unit = format!(
+            "{}{}",
+            asset.policy_id,
+            asset.asset_name.as_deref().unwrap_or_default()
+        );
+        value.add_asset(
+            AssetId::from_str(&unit).map_err(decode_error)?,
+            quantity(&asset.quantity)?,
+        );
+    }
+
+    let datum_option = match (&row.inline_datum, &row.datum_hash) {
+        (Some(Bytes { bytes: Some(bytes) }), _) => {
+            let datum: PlutusData = minicbor::decode(&hex::decode(bytes).map_err(decode_error)?)
+                .map_err(decode_error)?;
+            Some(DatumOption::Data(CborWrap(datum)))
+        }
+        (_, Some(hash)) => Some(DatumOption::Hash(
+            Hash::from_str(hash).map_err(decode_error)?,
+        )),
+        _ => None,
+    };
+
+    let script_ref = match &row.reference_script {
+        Some(Script {
+            r#type,
+            bytes: Some(bytes),
+        }) => {
+            let bytes = hex::decode(bytes).map_err(decode_error)?;
+            let script_type = script_type(r#type)?;
+            let hash = pallas_crypto::hash::Hasher::<224>::hash_tagged(&bytes, script_type.tag());
+            Some(decode_script(&hash, script_type, &bytes)?)
+        }
+        _ => None,
+    };
+
+    Ok(Utxo {
+        input: TransactionInput {
+            transaction_id: Hash::from_str(&row.tx_hash).map_err(decode_error)?,
+            index: row.tx_index,
+        },
+        output: rest::output(&row.address, Value::from(&value), datum_option, script_ref)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{TransactionOutput, assets_of, lovelace_of};
+    use pallas_addresses::{Network, ShelleyAddress, ShelleyDelegationPart, ShelleyPaymentPart};
+
+    #[test]
+    fn decode_cli_params() {
+        let params: CliParams = serde_json::from_str(
+            r#"{
+                "txFeePerByte": 44, "txFeeFixed": 155381, "maxTxSize": 16384,
+                "stakeAddressDeposit": 2000000, "stakePoolDeposit": 500000000,
+                "executionUnitPrices": { "priceMemory": 0.0577, "priceSteps": 0.0000721 },
+                "utxoCostPerByte": 4310, "collateralPercentage": 150,
+                "protocolVersion": { "major": 10, "minor": 0 },
+                "costModels": { "PlutusV2": [205665, 812] }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(protocol_params(&params), ProtocolParams::MAINNET);
+        let (version, cost_models) = cost_models(&params);
+        assert_eq!(version, (10, 0));
+        assert_eq!(cost_models.plutus_v1, None);
+        assert_eq!(cost_models.plutus_v2, Some(vec![205665, 812]));
+    }
+
+    #[test]
+    fn decode_account() {
+        let account: Account = serde_json::from_str(
+            r#"{
+                "stake_address": "stake1u8yxtugdv63wxafy9d00nuz6hjyyp4qnggvc9a3vxh8yl0ckml2uz",
+                "status": "registered", "delegated_drep": null,
+                "delegated_pool": "02020202020202020202020202020202020202020202020202020202",
+                "total_balance": "1000000", "utxo": "900000", "rewards_available": "100000"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            stake_info(&account),
+            Ok(StakeInfo {
+                registered: true,
+                pool: Some(PoolId(Hash::from([2; 28]))),
+                controlled: 1_000_000,
+                rewards: 100_000,
+            })
+        );
+    }
+
+    #[test]
+    fn decode_extended_utxo() {
+        let address: Address = ShelleyAddress::new(
+            Network::Testnet,
+            ShelleyPaymentPart::key_hash(Hash::from([1; 28])),
+            ShelleyDelegationPart::Null,
+        )
+        .into();
+        let json = r#"{
+                "tx_hash": "39a7a284c2a0948189dc45dec670211cd4d72f7b66c5726c08d9b3df11e44d58",
+                "tx_index": 1,
+                "address": "{address}",
+                "value": "2000000",
+                "datum_hash": "818ee3db3bbbd04f9f2ce21778cac3ac605802a4fcb00c8b3a58ee2dafc17d46",
+                "inline_datum": { "bytes": "182a", "value": { "int": 42 } },
+                "reference_script": null,
+                "asset_list": [
+                    { "policy_id": "01010101010101010101010101010101010101010101010101010101",
+                      "asset_name": "74657374", "quantity": "5" }
+                ],
+                "is_spent": false
+            }"#
+        .replace("{address}", &address.to_bech32().unwrap());
+        let row: UtxoRow = serde_json::from_str(&json).unwrap();
+
+        let utxo = decode_utxo(&row).unwrap();
+        assert_eq!(utxo.input.index, 1);
+        assert_eq!(utxo.address().unwrap(), address);
+        let TransactionOutput::PostAlonzo(output) = &utxo.output else {
+            panic!("expected a post alonzo output");
+        };
+        assert_eq!(lovelace_of(&output.value), 2_000_000);
+        assert_eq!(
+            assets_of(&output.value)
+                .values()
+                .copied()
+                .collect::<Vec<_>>(),
+            [5]
+        );
+        // the inline datum wins over its hash
+        assert!(matches!(output.datum_option, Some(DatumOption::Data(_))));
+    }
+}
@@ -0,0 +1,293 @@
+/*!
+Light client mode: the data of the [`ChainProvider`] is checked against
+the certificates of a Mithril aggregator.
+
+Mithril signers (a large share of the stake pools) periodically certify
+the set of transactions on chain. The [`MithrilVerifier`] asks the
+aggregator for the Merkle proofs that transactions are part of the latest
+certified set, verifies the chain of certificates up to the genesis key
+and checks the proofs against the certificate. The dApp no longer has to
+trust Blockfrost or Koios about the existence of a transaction, only the
+Mithril genesis key.
+
+The certified set lags behind the tip of the chain (by a few dozens of
+blocks): the most recent transactions are not certified yet.
+
+```no_run
+# use cardano_connector::backend::{ChainProvider, mithril::{CertifiedProvider, MithrilVerifier}};
+# use cardano_connector::cardano::TransactionInput;
+# async fn test(
+#     provider: impl ChainProvider,
+#     genesis_verification_key: &str,
+#     input: TransactionInput,
+# ) -> anyhow::Result<()> {
+let verifier = MithrilVerifier::new(
+    "https://aggregator.release-mainnet.api.mithril.network/aggregator",
+    genesis_verification_key,
+);
+let provider = CertifiedProvider::new(provider, verifier);
+// fails if the transaction producing the output is not certified
+let utxo = provider.resolve_input(&input).await?;
+# Ok(()) }
+```
+
+This needs the `mithril` feature and the `@mithril-dev/mithril-client-wasm`
+package.
+*/
+
+use super::{BackendError, ChainProvider, ChainTip};
+use crate::{
+    Address,
+    cardano::{Hash, HashExt as _, TransactionInput, TxHash, Utxo, UtxoSet},
+    ffi::mithril::MithrilClient,
+};
+use pallas_primitives::{PlutusData, conway::ScriptRef};
+use std::collections::BTreeSet;
+use wasm_bindgen::JsValue;
+
+/// the transactions proof returned by the aggregator
+#[derive(Debug, serde::Deserialize)]
+struct ProofsMessage {
+    certificate_hash: String,
+    certified_transactions: Vec<CertifiedSet>,
+    non_certified_transactions: Vec<String>,
+    latest_block_number: u64,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct CertifiedSet {
+    transactions_hashes: Vec<String>,
+}
+
+/// The outcome of [`MithrilVerifier::certify_transactions`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertifiedTransactions {
+    /// the hash of the certificate the proofs were checked against
+    pub certificate_hash: String,
+    /// the transactions proven to be on chain
+    pub certified: BTreeSet<TxHash>,
+    /// the transactions not part of the certified set: too recent or
+    /// not on chain
+    pub non_certified: BTreeSet<TxHash>,
+    /// the last block covered by the certificate
+    pub latest_block_number: u64,
+}
+
+impl CertifiedTransactions {
+    fn from_message(message: ProofsMessage) -> Result<Self, BackendError> {
+        let hash = |hash: &String| {
+            TxHash::from_hex(hash).map_err(|error| BackendError::Decode(error.to_string()))
+        };
+
+        Ok(Self {
+            certified: message
+                .certified_transactions
+                .iter()
+                .flat_map(|set| set.transactions_hashes.iter())
+                .map(hash)
+                .collect::<Result<_, _>>()?,
+            non_certified: message
+                .non_certified_transactions
+                .iter()
+                .map(hash)
+                .collect::<Result<_, _>>()?,
+            certificate_hash: message.certificate_hash,
+            latest_block_number: message.latest_block_number,
+        })
+    }
+
+    pub fn is_certified(&self, hash: &TxHash) -> bool {
+        self.certified.contains(hash)
+    }
+}
+
+/// Client of a Mithril aggregator, see the [module documentation](self).
+#[derive(Clone)]
+pub struct MithrilVerifier {
+    client: MithrilClient,
+}
+
+impl MithrilVerifier {
+    /// `genesis_verification_key` is the (hex encoded) genesis key of the
+    /// Mithril network of the aggregator
+    pub fn new(aggregator_endpoint: &str, genesis_verification_key: &str) -> Self {
+        Self {
+            client: MithrilClient::new(aggregator_endpoint, genesis_verification_key),
+        }
+    }
+
+    /// fetch and verify the proofs that the transactions are on chain
+    ///
+    /// Fails with [`BackendError::Unverified`] if the proofs or the
+    /// certificates are not valid: the aggregator can't be trusted.
+    pub async fn certify_transactions(
+        &self,
+        hashes: &[TxHash],
+    ) -> Result<CertifiedTransactions, BackendError> {
+        let hashes: Vec<JsValue> = hashes
+            .iter()
+            .map(|hash| JsValue::from_str(&hash.to_string()))
+            .collect();
+        let proofs = self
+            .client
+            .get_cardano_transaction_proofs(hashes.into_boxed_slice())
+            .await
+            .map_err(unavailable)?;
+        let message: ProofsMessage = serde_wasm_bindgen::from_value(proofs.clone())
+            .map_err(|error| BackendError::Decode(error.to_string()))?;
+
+        let certificate = self
+            .client
+            .verify_certificate_chain(&message.certificate_hash)
+            .await
+            .map_err(unverified)?;
+        let computed = self
+            .client
+            .verify_cardano_transaction_proof_then_compute_message(&proofs, &certificate)
+            .await
+            .map_err(unverified)?;
+        let valid = self
+            .client
+            .verify_message_match_certificate(&computed, &certificate)
+            .await
+            .map_err(unverified)?;
+        if valid.as_bool() != Some(true) {
+            return Err(BackendError::Unverified(format!(
+                "the transactions proof doesn't match the certificate {}",
+                message.certificate_hash
+            )));
+        }
+
+        CertifiedTransactions::from_message(message)
+    }
+}
+
+fn unavailable(error: JsValue) -> BackendError {
+    BackendError::Unavailable(format!("{error:?}"))
+}
+
+fn unverified(error: JsValue) -> BackendError {
+    BackendError::Unverified(format!("{error:?}"))
+}
+
+/// A [`ChainProvider`] whose answers are checked with a
+/// [`MithrilVerifier`].
+///
+/// * the resolved inputs must have been created by a certified
+///   transaction;
+/// * the history of the addresses only lists the certified transactions.
+///
+/// The content of the outputs and whether they are still unspent can't be
+/// certified: Mithril only certifies the transactions.
+pub struct CertifiedProvider<P> {
+    provider: P,
+    verifier: MithrilVerifier,
+}
+
+impl<P: ChainProvider> CertifiedProvider<P> {
+    pub fn new(provider: P, verifier: MithrilVerifier) -> Self {
+        Self { provider, verifier }
+    }
+
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    pub fn verifier(&self) -> &MithrilVerifier {
+        &self.verifier
+    }
+}
+
+impl<P: ChainProvider> ChainProvider for CertifiedProvider<P> {
+    async fn resolve_input(&self, input: &TransactionInput) -> Result<Option<Utxo>, BackendError> {
+        let Some(utxo) = self.provider.resolve_input(input).await? else {
+            return Ok(None);
+        };
+
+        let certified = self
+            .verifier
+            .certify_transactions(&[input.transaction_id])
+            .await?;
+        if !certified.is_certified(&input.transaction_id) {
+            return Err(BackendError::Unverified(format!(
+                "the transaction {} is not certified",
+                input.transaction_id
+            )));
+        }
+        Ok(Some(utxo))
+    }
+
+    async fn address_utxos(&self, address: &Address) -> Result<UtxoSet, BackendError> {
+        let utxos = self.provider.address_utxos(address).await?;
+        let hashes: Vec<TxHash> = utxos
+            .iter()
+            .map(Utxo::transaction_id)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let certified = self.verifier.certify_transactions(&hashes).await?;
+
+        Ok(utxos
+            .into_iter()
+            .filter(|utxo| certified.is_certified(&utxo.transaction_id()))
+            .collect())
+    }
+
+    async fn address_transactions(&self, address: &Address) -> Result<Vec<TxHash>, BackendError> {
+        let history = self.provider.address_transactions(address).await?;
+        let certified = self.verifier.certify_transactions(&history).await?;
+
+        Ok(history
+            .into_iter()
+            .filter(|hash| certified.is_certified(hash))
+            .collect())
+    }
+
+    async fn transaction_time(&self, hash: &TxHash) -> Result<Option<u64>, BackendError> {
+        self.provider.transaction_time(hash).await
+    }
+
+    async fn chain_tip(&self) -> Result<ChainTip, BackendError> {
+        self.provider.chain_tip().await
+    }
+
+    // the datums and scripts are checked against their hash
+    async fn datum_by_hash(&self, hash: &Hash<32>) -> Result<Option<PlutusData>, BackendError> {
+        self.provider.datum_by_hash(hash).await
+    }
+
+    async fn script_by_hash(&self, hash: &Hash<28>) -> Result<Option<ScriptRef>, BackendError> {
+        self.provider.script_by_hash(hash).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_proofs_message() {
+        let certified = "a".repeat(64);
+        let pending = "b".repeat(64);
+        let message: ProofsMessage = serde_json::from_value(serde_json::json!({
+            "certificate_hash": "cert",
+            "certified_transactions": [
+                { "transactions_hashes": [certified], "proof": "00" },
+            ],
+            "non_certified_transactions": [pending],
+            "latest_block_number": 42,
+        }))
+        .unwrap();
+
+        let transactions = CertifiedTransactions::from_message(message).unwrap();
+
+        assert!(transactions.is_certified(&TxHash::from([0xaa; 32])));
+        assert!(!transactions.is_certified(&TxHash::from([0xbb; 32])));
+        assert!(
+            transactions
+                .non_certified
+                .contains(&TxHash::from([0xbb; 32]))
+        );
+        assert_eq!(transactions.latest_block_number, 42);
+    }
+}
@@ -0,0 +1,370 @@
+/*!
+Access to chain data that CIP-30 does not give access to.
+
+The wallet only knows about its own UTxOs. Whenever a transaction spends
+foreign inputs (e.g. script UTxOs) we need to ask a chain indexer. This is
+abstracted by the [`ChainProvider`] trait.
+*/
+
+mod activity;
+#[cfg(feature = "blockfrost")]
+pub mod blockfrost;
+mod cache;
+mod config;
+mod failover;
+mod http;
+mod inclusion;
+#[cfg(feature = "koios")]
+pub mod koios;
+#[cfg(feature = "mithril")]
+pub mod mithril;
+mod price;
+mod push;
+mod query;
+mod resolver;
+#[cfg(any(feature = "blockfrost", feature = "koios"))]
+mod rest;
+mod scripts;
+
+pub use self::{
+    activity::{AddressActivity, address_activity},
+    cache::{Cached, ResponseCache},
+    config::{Auth, BackendConfig, Quota, RateLimiter, parse_retry_after},
+    failover::Failover,
+    http::HttpClient,
+    inclusion::{
+        BlockHash, BlockProvider, BlockRef, InclusionError, InclusionProof, verify_block,
+        verify_inclusion,
+    },
+    price::{FiatEstimate, HttpPriceProvider, PriceProvider, fiat_estimate},
+    push::{OgmiosChainSync, PushReply, PushSource, PushWatcher, Watched},
+    query::UtxoQuery,
+    resolver::InputResolver,
+    scripts::{ScriptType, decode_datum, decode_script},
+};
+use crate::{
+    Address,
+    cardano::{
+        AssetId, Coin, EpochInfo, Hash, Metadata, PoolId, SlotConfig, TransactionInput, TxHash,
+        Utxo, UtxoSet, cost_models::CostModelStore, fees::ProtocolParams,
+    },
+};
+use pallas_primitives::{
+    PlutusData, ProtocolVersion,
+    conway::{CostModels, ScriptRef},
+};
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BackendError {
+    #[error("The provider returned an error: {0}")]
+    Provider(String),
+    #[error("The provider is not reachable: {0}")]
+    Unavailable(String),
+    #[error("Couldn't decode the provider's response: {0}")]
+    Decode(String),
+    #[error("The provider rejected the credentials")]
+    Unauthorized,
+    #[error("Too many requests sent to the provider")]
+    RateLimited { retry_after: Option<Duration> },
+    #[error("The provider does not support {0}")]
+    Unsupported(String),
+    #[error("The provider's data couldn't be verified: {0}")]
+    Unverified(String),
+}
+
+impl BackendError {
+    /// map an unsuccessful HTTP response to the matching error
+    pub fn from_status(status: u16, retry_after: Option<&str>, body: String) -> Self {
+        match status {
+            401 | 403 => Self::Unauthorized,
+            429 => Self::RateLimited {
+                retry_after: retry_after.and_then(parse_retry_after),
+            },
+            500..=599 => Self::Unavailable(format!("HTTP {status}: {body}")),
+            _ => Self::Provider(format!("HTTP {status}: {body}")),
+        }
+    }
+
+    /// the request may succeed if tried again later
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Unavailable(_) | Self::RateLimited { .. })
+    }
+}
+
+/// The most recent block of the chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainTip {
+    pub slot: u64,
+    pub hash: BlockHash,
+    pub height: u64,
+}
+
+/// Where a transaction is, as known by a provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxStatus {
+    /// not on chain: still in the mempool, dropped or never submitted
+    NotFound,
+    /// included in the block at `height`, `confirmations` blocks deep
+    /// (1 when the block is the tip)
+    Confirmed {
+        slot: u64,
+        height: u64,
+        confirmations: u64,
+    },
+}
+
+/// The state of a stake credential.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StakeInfo {
+    /// the stake key is registered
+    pub registered: bool,
+    /// the pool the stake is delegated to
+    pub pool: Option<PoolId>,
+    /// the lovelace of the UTxOs delegating to the credential, with its
+    /// rewards
+    pub controlled: Coin,
+    /// the rewards that can be withdrawn
+    pub rewards: Coin,
+}
+
+/// Queries of the chain state beyond the UTxOs: the protocol
+/// parameters, the stake of the accounts, the confirmation of the
+/// transactions and the metadata of the assets.
+#[allow(async_fn_in_trait)]
+pub trait ChainQuery {
+    /// the protocol parameters of the current epoch
+    async fn protocol_params(&self) -> Result<ProtocolParams, BackendError>;
+
+    /// the state of the stake credential of the stake `address`
+    ///
+    /// Returns `None` if the provider has never seen the credential.
+    async fn stake_info(&self, address: &Address) -> Result<Option<StakeInfo>, BackendError>;
+
+    /// whether the transaction is on chain, and how deep
+    async fn tx_status(&self, hash: &TxHash) -> Result<TxStatus, BackendError>;
+
+    /// the metadata of the latest transaction minting the asset, e.g.
+    /// its CIP-25 metadata
+    ///
+    /// Empty if the provider has never seen the asset.
+    async fn mint_metadata(&self, asset: &AssetId) -> Result<Metadata, BackendError> {
+        let _ = asset;
+        Err(BackendError::Unsupported(
+            "asset metadata queries".to_owned(),
+        ))
+    }
+
+    /// a UTxO holding the asset, meant for the assets held by a single
+    /// UTxO such as the CIP-68 reference NFTs
+    ///
+    /// Returns `None` if the asset is not held by any UTxO.
+    async fn asset_utxo(&self, asset: &AssetId) -> Result<Option<Utxo>, BackendError> {
+        let _ = asset;
+        Err(BackendError::Unsupported("asset UTxO queries".to_owned()))
+    }
+}
+
+impl<P: ChainQuery> ChainQuery for &P {
+    async fn protocol_params(&self) -> Result<ProtocolParams, BackendError> {
+        (*self).protocol_params().await
+    }
+
+    async fn stake_info(&self, address: &Address) -> Result<Option<StakeInfo>, BackendError> {
+        (*self).stake_info(address).await
+    }
+
+    async fn tx_status(&self, hash: &TxHash) -> Result<TxStatus, BackendError> {
+        (*self).tx_status(hash).await
+    }
+
+    async fn mint_metadata(&self, asset: &AssetId) -> Result<Metadata, BackendError> {
+        (*self).mint_metadata(asset).await
+    }
+
+    async fn asset_utxo(&self, asset: &AssetId) -> Result<Option<Utxo>, BackendError> {
+        (*self).asset_utxo(asset).await
+    }
+}
+
+/// Source of chain data.
+///
+/// The futures are not required to be `Send` as this crate is meant to
+/// run in the browser's single threaded environment.
+#[allow(async_fn_in_trait)]
+pub trait ChainProvider {
+    /// Resolve the output referenced by the given input.
+    ///
+    /// Returns `None` if the provider doesn't know about this output
+    /// (it may not exist or it may have already been spent).
+    async fn resolve_input(&self, input: &TransactionInput) -> Result<Option<Utxo>, BackendError>;
+
+    /// The UTxOs currently sitting at the address.
+    ///
+    /// For a stake (reward) address, these are all the UTxOs of the
+    /// addresses delegating to this stake credential.
+    async fn address_utxos(&self, address: &Address) -> Result<UtxoSet, BackendError> {
+        let _ = address;
+        Err(BackendError::Unsupported(
+            "address UTxOs queries".to_owned(),
+        ))
+    }
+
+    /// The UTxOs at the address matching the `query`, see
+    /// [`UtxoQuery`].
+    ///
+    /// The default implementation filters and paginates the
+    /// [`address_utxos`](Self::address_utxos), providers able to filter
+    /// or paginate on their side should override it.
+    async fn address_utxos_page(
+        &self,
+        address: &Address,
+        query: &UtxoQuery,
+    ) -> Result<UtxoSet, BackendError> {
+        Ok(query.apply(self.address_utxos(address).await?))
+    }
+
+    /// The transactions involving the address, most recent first.
+    ///
+    /// As for [`address_utxos`](Self::address_utxos), a stake address
+    /// covers all the addresses delegating to it.
+    async fn address_transactions(&self, address: &Address) -> Result<Vec<TxHash>, BackendError> {
+        let _ = address;
+        Err(BackendError::Unsupported(
+            "address history queries".to_owned(),
+        ))
+    }
+
+    /// The POSIX time, in milliseconds, of the block including the
+    /// transaction.
+    ///
+    /// Returns `None` if the transaction is not on chain.
+    async fn transaction_time(&self, hash: &TxHash) -> Result<Option<u64>, BackendError> {
+        let _ = hash;
+        Err(BackendError::Unsupported(
+            "transaction time queries".to_owned(),
+        ))
+    }
+
+    /// How long and how much the address has been used, see
+    /// [`AddressActivity`].
+    ///
+    /// The default implementation looks up the history of the address
+    /// and the time of its first and last transactions, providers with a
+    /// dedicated query should override it.
+    async fn address_activity(&self, address: &Address) -> Result<AddressActivity, BackendError> {
+        address_activity(self, address).await
+    }
+
+    /// The tip of the chain, as known by the provider.
+    async fn chain_tip(&self) -> Result<ChainTip, BackendError> {
+        Err(BackendError::Unsupported("chain tip queries".to_owned()))
+    }
+
+    /// The current epoch, computed from the slot of the
+    /// [`chain_tip`](Self::chain_tip) and the slot configuration of the
+    /// network.
+    async fn epoch_info(&self, config: &SlotConfig) -> Result<EpochInfo, BackendError> {
+        Ok(config.epoch_info(self.chain_tip().await?.slot))
+    }
+
+    /// The datum of the given hash, for the outputs holding only the
+    /// hash of their datum.
+    ///
+    /// Returns `None` if the provider has never seen the datum.
+    /// Implementations should decode it with [`decode_datum`].
+    async fn datum_by_hash(&self, hash: &Hash<32>) -> Result<Option<PlutusData>, BackendError> {
+        let _ = hash;
+        Err(BackendError::Unsupported("datum queries".to_owned()))
+    }
+
+    /// The script of the given hash.
+    ///
+    /// Returns `None` if the provider has never seen the script.
+    /// Implementations should decode it with [`decode_script`].
+    async fn script_by_hash(&self, hash: &Hash<28>) -> Result<Option<ScriptRef>, BackendError> {
+        let _ = hash;
+        Err(BackendError::Unsupported("script queries".to_owned()))
+    }
+}
+
+impl<P: ChainProvider> ChainProvider for &P {
+    async fn resolve_input(&self, input: &TransactionInput) -> Result<Option<Utxo>, BackendError> {
+        (*self).resolve_input(input).await
+    }
+
+    async fn address_utxos(&self, address: &Address) -> Result<UtxoSet, BackendError> {
+        (*self).address_utxos(address).await
+    }
+
+    async fn address_utxos_page(
+        &self,
+        address: &Address,
+        query: &UtxoQuery,
+    ) -> Result<UtxoSet, BackendError> {
+        (*self).address_utxos_page(address, query).await
+    }
+
+    async fn address_transactions(&self, address: &Address) -> Result<Vec<TxHash>, BackendError> {
+        (*self).address_transactions(address).await
+    }
+
+    async fn transaction_time(&self, hash: &TxHash) -> Result<Option<u64>, BackendError> {
+        (*self).transaction_time(hash).await
+    }
+
+    async fn address_activity(&self, address: &Address) -> Result<AddressActivity, BackendError> {
+        (*self).address_activity(address).await
+    }
+
+    async fn chain_tip(&self) -> Result<ChainTip, BackendError> {
+        (*self).chain_tip().await
+    }
+
+    async fn epoch_info(&self, config: &SlotConfig) -> Result<EpochInfo, BackendError> {
+        (*self).epoch_info(config).await
+    }
+
+    async fn datum_by_hash(&self, hash: &Hash<32>) -> Result<Option<PlutusData>, BackendError> {
+        (*self).datum_by_hash(hash).await
+    }
+
+    async fn script_by_hash(&self, hash: &Hash<28>) -> Result<Option<ScriptRef>, BackendError> {
+        (*self).script_by_hash(hash).await
+    }
+}
+
+/// Source of the protocol parameters.
+#[allow(async_fn_in_trait)]
+pub trait ParamsProvider {
+    /// the current protocol version and its Plutus cost models
+    async fn cost_models(&self) -> Result<(ProtocolVersion, CostModels), BackendError>;
+}
+
+impl<P: ParamsProvider> ParamsProvider for &P {
+    async fn cost_models(&self) -> Result<(ProtocolVersion, CostModels), BackendError> {
+        (*self).cost_models().await
+    }
+}
+
+impl CostModelStore {
+    /// fetch the current cost models from the provider and store them
+    pub async fn refresh<P: ParamsProvider>(&mut self, provider: &P) -> Result<(), BackendError> {
+        let (version, cost_models) = provider.cost_models().await?;
+        self.update(version, cost_models);
+        Ok(())
+    }
+}
+
+/// Submission of signed transactions to the network.
+#[allow(async_fn_in_trait)]
+pub trait TxSubmitter {
+    /// submit the CBOR encoded transaction, returning its hash
+    async fn submit_tx(&self, tx: &[u8]) -> Result<TxHash, BackendError>;
+}
+
+impl<P: TxSubmitter> TxSubmitter for &P {
+    async fn submit_tx(&self, tx: &[u8]) -> Result<TxHash, BackendError> {
+        (*self).submit_tx(tx).await
+    }
+}
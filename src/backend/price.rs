@@ -0,0 +1,192 @@
+use super::{BackendConfig, BackendError, http::HttpClient};
+use crate::cardano::{AssetId, Value, ValueExt as _};
+
+/// Source of the fiat prices, to display approximate fiat values next to
+/// the balances.
+#[allow(async_fn_in_trait)]
+pub trait PriceProvider {
+    /// The fiat price, in `currency` (e.g. `"usd"`), of one ADA if `asset`
+    /// is `None`, or of one unit (without decimals) of the native asset.
+    ///
+    /// `None` if the provider doesn't know the price of the asset.
+    async fn price(
+        &self,
+        asset: Option<&AssetId>,
+        currency: &str,
+    ) -> Result<Option<f64>, BackendError>;
+}
+
+impl<P: PriceProvider> PriceProvider for &P {
+    async fn price(
+        &self,
+        asset: Option<&AssetId>,
+        currency: &str,
+    ) -> Result<Option<f64>, BackendError> {
+        (*self).price(asset, currency).await
+    }
+}
+
+/// An approximate fiat value, see [`fiat_estimate`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FiatEstimate {
+    pub currency: String,
+    /// the value of the ADA and of the priced assets
+    pub amount: f64,
+    /// the assets without known price, not part of the `amount`
+    pub unpriced: Vec<AssetId>,
+}
+
+/// estimate the fiat value of `value` with the prices of the provider
+pub async fn fiat_estimate<P: PriceProvider>(
+    value: &Value,
+    provider: &P,
+    currency: &str,
+) -> Result<FiatEstimate, BackendError> {
+    let mut estimate = FiatEstimate {
+        currency: currency.to_owned(),
+        amount: 0.0,
+        unpriced: Vec::new(),
+    };
+
+    for (asset, quantity) in value.dimensions() {
+        match (provider.price(asset.as_ref(), currency).await?, asset) {
+            (Some(price), None) => estimate.amount += price * quantity as f64 / 1_000_000.0,
+            (Some(price), Some(_)) => estimate.amount += price * quantity as f64,
+            (None, Some(asset)) => estimate.unpriced.push(asset),
+            (None, None) => {}
+        }
+    }
+
+    Ok(estimate)
+}
+
+/// [`PriceProvider`] querying an HTTP price API returning JSON.
+///
+/// The `path` and the `pointer` (a JSON pointer to the price in the
+/// response) are templates: `{currency}` is replaced with the currency
+/// and `{unit}` with `ada` or the asset's unit (policy id and asset name
+/// in hex). For example, for the ADA price only with CoinGecko:
+///
+/// ```
+/// # use cardano_connector::backend::{BackendConfig, HttpPriceProvider};
+/// let provider = HttpPriceProvider::new(
+///     BackendConfig::new("https://api.coingecko.com/api/v3"),
+///     "/simple/price?ids=cardano&vs_currencies={currency}",
+///     "/cardano/{currency}",
+/// )
+/// .ada_only();
+/// ```
+pub struct HttpPriceProvider {
+    client: HttpClient,
+    path: String,
+    pointer: String,
+    ada_only: bool,
+}
+
+impl HttpPriceProvider {
+    pub fn new(config: BackendConfig, path: impl Into<String>, pointer: impl Into<String>) -> Self {
+        Self {
+            client: HttpClient::new(config),
+            path: path.into(),
+            pointer: pointer.into(),
+            ada_only: false,
+        }
+    }
+
+    /// don't query the prices of the native assets
+    pub fn ada_only(mut self) -> Self {
+        self.ada_only = true;
+        self
+    }
+}
+
+fn expand(template: &str, unit: &str, currency: &str) -> String {
+    template
+        .replace("{unit}", unit)
+        .replace("{currency}", currency)
+}
+
+impl PriceProvider for HttpPriceProvider {
+    async fn price(
+        &self,
+        asset: Option<&AssetId>,
+        currency: &str,
+    ) -> Result<Option<f64>, BackendError> {
+        if asset.is_some() && self.ada_only {
+            return Ok(None);
+        }
+
+        let unit = asset.map_or_else(|| "ada".to_owned(), AssetId::to_string);
+        let response: serde_json::Value =
+            match self.client.get(&expand(&self.path, &unit, currency)).await {
+                Ok(response) => response,
+                Err(BackendError::Provider(_)) => return Ok(None),
+                Err(error) => return Err(error),
+            };
+
+        Ok(response
+            .pointer(&expand(&self.pointer, &unit, currency))
+            .and_then(|price| {
+                price
+                    .as_f64()
+                    .or_else(|| price.as_str().and_then(|price| price.parse().ok()))
+            }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{Hash, Multiasset, NonEmptyKeyValuePairs};
+    use futures::executor::block_on;
+
+    struct Prices;
+
+    impl PriceProvider for Prices {
+        async fn price(
+            &self,
+            asset: Option<&AssetId>,
+            _currency: &str,
+        ) -> Result<Option<f64>, BackendError> {
+            Ok(match asset {
+                None => Some(0.5),
+                Some(asset) if asset.policy == Hash::from([1; 28]) => Some(0.01),
+                Some(_) => None,
+            })
+        }
+    }
+
+    #[test]
+    fn estimate() {
+        let assets = |policy| {
+            (
+                Hash::from([policy; 28]),
+                NonEmptyKeyValuePairs::from_vec(vec![(
+                    b"token".to_vec().into(),
+                    100.try_into().unwrap(),
+                )])
+                .unwrap(),
+            )
+        };
+        let value = Value::Multiasset(
+            4_000_000,
+            Multiasset::from_vec(vec![assets(1), assets(2)]).unwrap(),
+        );
+
+        let estimate = block_on(fiat_estimate(&value, &Prices, "usd")).unwrap();
+
+        assert_eq!(estimate.amount, 3.0);
+        assert_eq!(
+            estimate.unpriced,
+            vec![AssetId::new(Hash::from([2; 28]), b"token".to_vec().into())]
+        );
+    }
+
+    #[test]
+    fn expand_templates() {
+        assert_eq!(
+            expand("/price/{unit}?vs={currency}", "ada", "eur"),
+            "/price/ada?vs=eur"
+        );
+    }
+}
@@ -0,0 +1,502 @@
+/*!
+Push notifications of the chain activity, with a polling fallback.
+
+A [`PushWatcher`] follows addresses and transactions and emits
+[`Event::AddressTransaction`] and [`Event::TxOnChain`] on the
+[`EventBus`]. The notifications come from a WebSocket when the backend
+has one (e.g. [`OgmiosChainSync`]), the [`PushSource`] translating the
+messages of the server. When the socket drops the watcher downgrades to
+polling the [`ChainProvider`].
+
+```no_run
+# use cardano_connector::{Address, backend::{ChainProvider, OgmiosChainSync, PushWatcher}, events::EventBus};
+# async fn test(provider: impl ChainProvider, address: Address, events: EventBus) {
+let watcher = PushWatcher::new(OgmiosChainSync::new("wss://ogmios.example.com"), events);
+watcher.watch_address(address);
+// e.g. with `wasm_bindgen_futures::spawn_local`
+watcher.run(&provider, std::time::Duration::from_secs(20)).await;
+# }
+```
+*/
+
+use super::{BackendError, ChainProvider};
+use crate::{
+    Address, TxHash,
+    events::{Event, EventBus},
+    ffi::{
+        self,
+        websocket::{MessageEvent, WebSocket},
+    },
+};
+use serde_json::{Value as Json, json};
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeSet,
+    rc::Rc,
+    time::Duration,
+};
+use wasm_bindgen::{JsCast as _, JsValue, closure::Closure};
+
+/// A watched address and the transactions already notified.
+#[derive(Debug, Clone)]
+struct WatchedAddress {
+    address: Address,
+    seen: BTreeSet<TxHash>,
+    /// the history of the address has been fetched once: the older
+    /// transactions are not notified
+    synced: bool,
+}
+
+/// What a [`PushWatcher`] follows.
+#[derive(Debug, Clone, Default)]
+pub struct Watched {
+    addresses: Vec<WatchedAddress>,
+    transactions: BTreeSet<TxHash>,
+}
+
+impl Watched {
+    pub fn addresses(&self) -> impl Iterator<Item = &Address> {
+        self.addresses.iter().map(|watched| &watched.address)
+    }
+
+    /// the transactions not yet seen on chain
+    pub fn transactions(&self) -> &BTreeSet<TxHash> {
+        &self.transactions
+    }
+
+    pub fn is_watched(&self, address: &Address) -> bool {
+        self.addresses().any(|watched| watched == address)
+    }
+
+    fn watch_address(&mut self, address: Address) {
+        if !self.is_watched(&address) {
+            self.addresses.push(WatchedAddress {
+                address,
+                seen: BTreeSet::new(),
+                synced: false,
+            });
+        }
+    }
+
+    fn entry(&mut self, address: &Address) -> Option<&mut WatchedAddress> {
+        self.addresses
+            .iter_mut()
+            .find(|watched| &watched.address == address)
+    }
+
+    /// `true` if the event is news
+    fn record(&mut self, event: &Event) -> bool {
+        match event {
+            Event::TxOnChain { hash } => self.transactions.remove(hash),
+            Event::AddressTransaction { address, hash } => self
+                .entry(address)
+                .is_some_and(|watched| watched.seen.insert(*hash)),
+            _ => false,
+        }
+    }
+
+    /// the transactions of the history (most recent first) not seen yet,
+    /// nothing on the first call
+    fn sync(&mut self, address: &Address, history: Vec<TxHash>) -> Vec<Event> {
+        let Some(watched) = self.entry(address) else {
+            return Vec::new();
+        };
+        if !watched.synced {
+            watched.synced = true;
+            watched.seen.extend(history);
+            return Vec::new();
+        }
+        history
+            .into_iter()
+            .rev()
+            .filter(|hash| watched.seen.insert(*hash))
+            .map(|hash| Event::AddressTransaction {
+                address: address.clone(),
+                hash,
+            })
+            .collect()
+    }
+}
+
+/// What to do with a message of the server.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PushReply {
+    /// the events the message notifies
+    pub events: Vec<Event>,
+    /// the messages to send back
+    pub send: Vec<String>,
+}
+
+/// The protocol of a backend's WebSocket.
+pub trait PushSource {
+    fn url(&self) -> String;
+
+    /// the messages to send once connected
+    fn on_open(&mut self) -> Vec<String>;
+
+    /// handle a text message of the server
+    fn on_message(&mut self, message: &str, watched: &Watched) -> PushReply;
+}
+
+/// Ogmios' (v6) chain synchronization, from the tip of the chain.
+///
+/// Only the outputs of the transactions are matched against the watched
+/// addresses: a transaction spending from an address without sending
+/// anything back is only noticed when polling.
+#[derive(Debug, Clone)]
+pub struct OgmiosChainSync {
+    url: String,
+}
+
+impl OgmiosChainSync {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+
+    fn request(method: &str, params: Option<Json>) -> String {
+        let mut request = json!({ "jsonrpc": "2.0", "method": method });
+        if let Some(params) = params {
+            request["params"] = params;
+        }
+        request.to_string()
+    }
+
+    fn block_events(block: &Json, watched: &Watched) -> Vec<Event> {
+        let mut events = Vec::new();
+        let transactions = block["transactions"].as_array().into_iter().flatten();
+        for transaction in transactions {
+            let Some(hash) = transaction["id"]
+                .as_str()
+                .and_then(|id| id.parse::<TxHash>().ok())
+            else {
+                continue;
+            };
+            if watched.transactions().contains(&hash) {
+                events.push(Event::TxOnChain { hash });
+            }
+
+            let outputs = transaction["outputs"].as_array().into_iter().flatten();
+            let mut addresses: Vec<Address> = Vec::new();
+            for output in outputs {
+                let Some(address) = output["address"]
+                    .as_str()
+                    .and_then(|address| address.parse::<Address>().ok())
+                else {
+                    continue;
+                };
+                if watched.is_watched(&address) && !addresses.contains(&address) {
+                    addresses.push(address);
+                }
+            }
+            events.extend(
+                addresses
+                    .into_iter()
+                    .map(|address| Event::AddressTransaction { address, hash }),
+            );
+        }
+        events
+    }
+}
+
+impl PushSource for OgmiosChainSync {
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    fn on_open(&mut self) -> Vec<String> {
+        vec![Self::request("queryNetwork/tip", None)]
+    }
+
+    fn on_message(&mut self, message: &str, watched: &Watched) -> PushReply {
+        let Ok(message) = serde_json::from_str::<Json>(message) else {
+            return PushReply::default();
+        };
+        let result = &message["result"];
+        match message["method"].as_str() {
+            // start following from the tip
+            Some("queryNetwork/tip") => PushReply {
+                events: Vec::new(),
+                send: vec![Self::request(
+                    "findIntersection",
+                    Some(json!({ "points": [result] })),
+                )],
+            },
+            Some("findIntersection") => PushReply {
+                events: Vec::new(),
+                send: vec![Self::request("nextBlock", None)],
+            },
+            Some("nextBlock") => PushReply {
+                events: match result["direction"].as_str() {
+                    Some("forward") => Self::block_events(&result["block"], watched),
+                    _ => Vec::new(),
+                },
+                send: vec![Self::request("nextBlock", None)],
+            },
+            _ => PushReply::default(),
+        }
+    }
+}
+
+/// see the [module documentation](self)
+pub struct PushWatcher<S> {
+    inner: Rc<Inner<S>>,
+}
+
+struct Inner<S> {
+    source: RefCell<S>,
+    watched: RefCell<Watched>,
+    events: EventBus,
+    socket: RefCell<Option<WebSocket>>,
+    stopped: Cell<bool>,
+}
+
+impl<S> Inner<S> {
+    fn record(&self, events: Vec<Event>) {
+        for event in events {
+            // release the borrow before emitting, the subscribers may
+            // watch more
+            let news = self.watched.borrow_mut().record(&event);
+            if news {
+                self.events.emit(event);
+            }
+        }
+    }
+}
+
+fn send_all(socket: &WebSocket, messages: Vec<String>) {
+    for message in messages {
+        let _ = socket.send(&message);
+    }
+}
+
+impl<S: PushSource + 'static> PushWatcher<S> {
+    pub fn new(source: S, events: EventBus) -> Self {
+        Self {
+            inner: Rc::new(Inner {
+                source: RefCell::new(source),
+                watched: RefCell::new(Watched::default()),
+                events,
+                socket: RefCell::new(None),
+                stopped: Cell::new(false),
+            }),
+        }
+    }
+
+    /// notify the new transactions of the address, its history is
+    /// fetched on the next poll
+    pub fn watch_address(&self, address: Address) {
+        self.inner.watched.borrow_mut().watch_address(address);
+    }
+
+    /// notify once the transaction is on chain
+    pub fn watch_transaction(&self, hash: TxHash) {
+        self.inner.watched.borrow_mut().transactions.insert(hash);
+    }
+
+    pub fn watched(&self) -> Watched {
+        self.inner.watched.borrow().clone()
+    }
+
+    /// the notifications are pushed by the backend, not polled
+    pub fn is_connected(&self) -> bool {
+        self.inner.socket.borrow().is_some()
+    }
+
+    /// query the provider for the watched addresses and transactions
+    pub async fn poll<P: ChainProvider>(&self, provider: &P) -> Result<(), BackendError> {
+        let watched = self.watched();
+
+        for address in watched.addresses() {
+            let history = provider.address_transactions(address).await?;
+            let events = self.inner.watched.borrow_mut().sync(address, history);
+            for event in events {
+                self.inner.events.emit(event);
+            }
+        }
+        for hash in watched.transactions() {
+            if provider.transaction_time(hash).await?.is_some() {
+                self.inner.record(vec![Event::TxOnChain { hash: *hash }]);
+            }
+        }
+        Ok(())
+    }
+
+    /// Follow the WebSocket of the source until it drops, then poll the
+    /// provider every `interval`. Returns once [`stop`](Self::stop)ped.
+    ///
+    /// The provider is polled once first to know the history of the
+    /// addresses. The polling errors are ignored, the next poll retries.
+    pub async fn run<P: ChainProvider>(&self, provider: &P, interval: Duration) {
+        let _ = self.poll(provider).await;
+        if !self.inner.stopped.get() {
+            let _ = self.listen().await;
+        }
+        while !self.inner.stopped.get() {
+            ffi::timer::sleep(interval).await;
+            if !self.inner.stopped.get() {
+                let _ = self.poll(provider).await;
+            }
+        }
+    }
+
+    pub fn stop(&self) {
+        self.inner.stopped.set(true);
+        if let Some(socket) = self.inner.socket.borrow().as_ref() {
+            socket.close();
+        }
+    }
+
+    /// follow the socket until it is closed
+    async fn listen(&self) -> Result<(), JsValue> {
+        let socket = WebSocket::new(&self.inner.source.borrow().url())?;
+
+        let onopen = {
+            let inner = Rc::clone(&self.inner);
+            let socket = socket.clone();
+            Closure::<dyn FnMut()>::new(move || {
+                let messages = inner.source.borrow_mut().on_open();
+                send_all(&socket, messages);
+            })
+        };
+        let onmessage = {
+            let inner = Rc::clone(&self.inner);
+            let socket = socket.clone();
+            Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+                let Some(message) = event.data().as_string() else {
+                    return;
+                };
+                let reply = {
+                    let watched = inner.watched.borrow();
+                    inner.source.borrow_mut().on_message(&message, &watched)
+                };
+                inner.record(reply.events);
+                send_all(&socket, reply.send);
+            })
+        };
+        socket.set_onopen(Some(onopen.as_ref().unchecked_ref()));
+        socket.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+        let closed = js_sys::Promise::new(&mut |resolve, _reject| {
+            socket.set_onclose(Some(&resolve));
+        });
+
+        self.inner.socket.replace(Some(socket.clone()));
+        let _ = wasm_bindgen_futures::JsFuture::from(closed).await;
+        self.inner.socket.replace(None);
+
+        socket.set_onopen(None);
+        socket.set_onmessage(None);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Hash,
+        cardano::{TransactionInput, Utxo, testing::address},
+    };
+    use futures::executor::block_on;
+
+    fn collect(events: &EventBus) -> Rc<RefCell<Vec<Event>>> {
+        let collected = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&collected);
+        events.subscribe(move |event| sink.borrow_mut().push(event.clone()));
+        collected
+    }
+
+    #[test]
+    fn ogmios_chain_sync() {
+        let mut source = OgmiosChainSync::new("wss://ogmios.example.com");
+        let mut watched = Watched::default();
+        watched.watch_address(address(1));
+        watched.transactions.insert(Hash::from([2; 32]));
+
+        assert!(source.on_open()[0].contains("queryNetwork/tip"));
+        let reply = source.on_message(
+            r#"{"jsonrpc":"2.0","method":"queryNetwork/tip","result":{"slot":10,"id":"00"}}"#,
+            &watched,
+        );
+        assert!(reply.send[0].contains(r#""points":[{"id":"00","slot":10}]"#));
+
+        let block = json!({
+            "jsonrpc": "2.0",
+            "method": "nextBlock",
+            "result": { "direction": "forward", "block": { "transactions": [
+                { "id": Hash::<32>::from([1; 32]).to_string(), "outputs": [
+                    { "address": address(1).to_bech32().unwrap() },
+                    { "address": address(1).to_bech32().unwrap() },
+                    { "address": address(3).to_bech32().unwrap() },
+                ] },
+                { "id": Hash::<32>::from([2; 32]).to_string(), "outputs": [] },
+            ] } },
+        });
+        let reply = source.on_message(&block.to_string(), &watched);
+        assert_eq!(
+            reply.events,
+            vec![
+                Event::AddressTransaction {
+                    address: address(1),
+                    hash: Hash::from([1; 32])
+                },
+                Event::TxOnChain {
+                    hash: Hash::from([2; 32])
+                },
+            ]
+        );
+        assert!(reply.send[0].contains("nextBlock"));
+    }
+
+    struct History(RefCell<Vec<TxHash>>);
+
+    impl ChainProvider for History {
+        async fn resolve_input(
+            &self,
+            _input: &TransactionInput,
+        ) -> Result<Option<Utxo>, BackendError> {
+            Ok(None)
+        }
+
+        async fn address_transactions(
+            &self,
+            _address: &Address,
+        ) -> Result<Vec<TxHash>, BackendError> {
+            Ok(self.0.borrow().clone())
+        }
+
+        async fn transaction_time(&self, hash: &TxHash) -> Result<Option<u64>, BackendError> {
+            Ok(self.0.borrow().contains(hash).then_some(1_000))
+        }
+    }
+
+    #[test]
+    fn polling_fallback() {
+        let events = EventBus::new();
+        let collected = collect(&events);
+        let watcher = PushWatcher::new(OgmiosChainSync::new("wss://ogmios.example.com"), events);
+        let provider = History(RefCell::new(vec![Hash::from([1; 32])]));
+        watcher.watch_address(address(1));
+        watcher.watch_transaction(Hash::from([2; 32]));
+
+        // the history is not notified
+        block_on(watcher.poll(&provider)).unwrap();
+        assert!(collected.borrow().is_empty());
+
+        provider.0.borrow_mut().insert(0, Hash::from([2; 32]));
+        block_on(watcher.poll(&provider)).unwrap();
+        block_on(watcher.poll(&provider)).unwrap();
+        assert_eq!(
+            *collected.borrow(),
+            vec![
+                Event::AddressTransaction {
+                    address: address(1),
+                    hash: Hash::from([2; 32])
+                },
+                Event::TxOnChain {
+                    hash: Hash::from([2; 32])
+                },
+            ]
+        );
+        assert!(watcher.watched().transactions().is_empty());
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{
+    AssetId, PolicyId, Utxo, UtxoSet,
+    cardano::{TransactionOutput, assets_of},
+};
+use pallas_primitives::conway::DatumOption;
+
+/// Filters and pagination of the UTxOs of an address, see
+/// [`ChainProvider::address_utxos_page`](super::ChainProvider::address_utxos_page).
+///
+/// The filters are applied before the pagination: the pages only contain
+/// matching UTxOs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UtxoQuery {
+    pub policy: Option<PolicyId>,
+    pub asset: Option<AssetId>,
+    pub inline_datum_only: bool,
+    /// the page index and the number of UTxOs per page
+    pub page: Option<(usize, usize)>,
+}
+
+impl UtxoQuery {
+    /// all the UTxOs of the address
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// only the UTxOs holding an asset of the policy
+    pub fn with_policy(mut self, policy: PolicyId) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// only the UTxOs holding the asset
+    pub fn with_asset(mut self, asset: AssetId) -> Self {
+        self.asset = Some(asset);
+        self
+    }
+
+    /// only the UTxOs with an inline datum, e.g. the ones a script can
+    /// read as reference inputs
+    pub fn inline_datum_only(mut self) -> Self {
+        self.inline_datum_only = true;
+        self
+    }
+
+    /// the `page`-th page (from 0) of `count` UTxOs
+    pub fn with_page(mut self, page: usize, count: usize) -> Self {
+        self.page = Some((page, count));
+        self
+    }
+
+    pub fn matches(&self, utxo: &Utxo) -> bool {
+        if self.inline_datum_only && !has_inline_datum(utxo) {
+            return false;
+        }
+        if self.policy.is_none() && self.asset.is_none() {
+            return true;
+        }
+
+        let assets = assets_of(&utxo.value());
+        let policy = self
+            .policy
+            .is_none_or(|policy| assets.keys().any(|asset| asset.policy == policy));
+        let asset = self
+            .asset
+            .as_ref()
+            .is_none_or(|asset| assets.contains_key(asset));
+        policy && asset
+    }
+
+    /// filter and paginate UTxOs, for the providers without dedicated
+    /// queries
+    pub fn apply(&self, utxos: UtxoSet) -> UtxoSet {
+        let matching = utxos.into_iter().filter(|utxo| self.matches(utxo));
+        match self.page {
+            Some((page, count)) => matching
+                .skip(page.saturating_mul(count))
+                .take(count)
+                .collect(),
+            None => matching.collect(),
+        }
+    }
+}
+
+fn has_inline_datum(utxo: &Utxo) -> bool {
+    match &utxo.output {
+        TransactionOutput::Legacy(_) => false,
+        TransactionOutput::PostAlonzo(output) => {
+            matches!(output.datum_option, Some(DatumOption::Data(_)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{Hash, Multiasset, Value};
+    use pallas_codec::utils::{CborWrap, MaybeIndefArray, NonEmptyKeyValuePairs};
+    use pallas_primitives::{PlutusData, babbage::PseudoPostAlonzoTransactionOutput};
+
+    fn utxo(index: u64, policy: Option<u8>, inline_datum: bool) -> Utxo {
+        let value = match policy {
+            Some(policy) => Value::Multiasset(
+                2_000_000,
+                Multiasset::from_vec(vec![(
+                    Hash::from([policy; 28]),
+                    NonEmptyKeyValuePairs::from_vec(vec![(
+                        b"token".to_vec().into(),
+                        1.try_into().unwrap(),
+                    )])
+                    .unwrap(),
+                )])
+                .unwrap(),
+            ),
+            None => Value::Coin(2_000_000),
+        };
+        Utxo {
+            input: crate::cardano::TransactionInput {
+                transaction_id: Hash::from([0; 32]),
+                index,
+            },
+            output: TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+                address: vec![0x60; 29].into(),
+                value,
+                datum_option: inline_datum.then(|| {
+                    DatumOption::Data(CborWrap(PlutusData::Array(
+                        MaybeIndefArray::Def(Vec::new()),
+                    )))
+                }),
+                script_ref: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn filters_then_pages() {
+        let utxos = UtxoSet::from(vec![
+            utxo(0, Some(1), false),
+            utxo(1, None, true),
+            utxo(2, Some(1), true),
+            utxo(3, Some(2), true),
+            utxo(4, Some(1), true),
+        ]);
+        let indices = |set: UtxoSet| set.iter().map(Utxo::index).collect::<Vec<_>>();
+
+        let query = UtxoQuery::new()
+            .with_policy(Hash::from([1; 28]))
+            .inline_datum_only();
+        assert_eq!(indices(query.apply(utxos.clone())), [2, 4]);
+        assert_eq!(indices(query.with_page(1, 1).apply(utxos.clone())), [4]);
+
+        let asset = AssetId::new(Hash::from([2; 28]), b"token".to_vec().into());
+        assert_eq!(
+            indices(UtxoQuery::new().with_asset(asset).apply(utxos.clone())),
+            [3]
+        );
+        assert_eq!(indices(UtxoQuery::new().with_page(2, 2).apply(utxos)), [4]);
+    }
+}
@@ -0,0 +1,183 @@
+use super::{AddressActivity, BackendError, ChainProvider, ChainTip, UtxoQuery};
+use crate::{
+    Address,
+    cardano::{
+        EpochInfo, Hash, SlotConfig, TransactionBody, TransactionInput, TxHash, Utxo, UtxoSet,
+    },
+};
+use pallas_primitives::{PlutusData, conway::ScriptRef};
+use std::{cell::RefCell, collections::BTreeMap};
+
+/// Resolve the inputs of transactions with a [`ChainProvider`], caching
+/// the resolutions.
+///
+/// Only the outputs found are cached: an output's content never changes
+/// once created while an unknown output may appear later on.
+pub struct InputResolver<P> {
+    provider: P,
+    cache: RefCell<BTreeMap<TransactionInput, Utxo>>,
+}
+
+impl<P: ChainProvider> InputResolver<P> {
+    pub fn new(provider: P) -> Self {
+        Self {
+            provider,
+            cache: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    /// seed the cache with already known UTxOs (e.g. the wallet's UTxOs)
+    pub fn insert(&self, utxos: impl IntoIterator<Item = Utxo>) {
+        self.cache
+            .borrow_mut()
+            .extend(utxos.into_iter().map(|utxo| (utxo.input.clone(), utxo)));
+    }
+
+    /// Resolve all the inputs, collateral inputs and reference inputs of
+    /// the given body.
+    ///
+    /// Inputs that could not be resolved are not part of the returned set.
+    pub async fn resolve_body(&self, body: &TransactionBody) -> Result<UtxoSet, BackendError> {
+        let inputs = body
+            .inputs
+            .iter()
+            .chain(body.collateral.iter().flat_map(|inputs| inputs.iter()))
+            .chain(
+                body.reference_inputs
+                    .iter()
+                    .flat_map(|inputs| inputs.iter()),
+            );
+
+        let mut resolved = UtxoSet::new();
+        for input in inputs {
+            if let Some(utxo) = self.resolve_input(input).await? {
+                resolved.push(utxo);
+            }
+        }
+        Ok(resolved)
+    }
+}
+
+impl<P: ChainProvider> ChainProvider for InputResolver<P> {
+    async fn resolve_input(&self, input: &TransactionInput) -> Result<Option<Utxo>, BackendError> {
+        if let Some(utxo) = self.cache.borrow().get(input) {
+            return Ok(Some(utxo.clone()));
+        }
+
+        let utxo = self.provider.resolve_input(input).await?;
+        if let Some(utxo) = &utxo {
+            self.insert([utxo.clone()]);
+        }
+        Ok(utxo)
+    }
+
+    async fn address_utxos(&self, address: &Address) -> Result<UtxoSet, BackendError> {
+        let utxos = self.provider.address_utxos(address).await?;
+        self.insert(utxos.iter().cloned());
+        Ok(utxos)
+    }
+
+    async fn address_utxos_page(
+        &self,
+        address: &Address,
+        query: &UtxoQuery,
+    ) -> Result<UtxoSet, BackendError> {
+        let utxos = self.provider.address_utxos_page(address, query).await?;
+        self.insert(utxos.iter().cloned());
+        Ok(utxos)
+    }
+
+    async fn address_transactions(&self, address: &Address) -> Result<Vec<TxHash>, BackendError> {
+        self.provider.address_transactions(address).await
+    }
+
+    async fn transaction_time(&self, hash: &TxHash) -> Result<Option<u64>, BackendError> {
+        self.provider.transaction_time(hash).await
+    }
+
+    async fn address_activity(&self, address: &Address) -> Result<AddressActivity, BackendError> {
+        self.provider.address_activity(address).await
+    }
+
+    async fn chain_tip(&self) -> Result<ChainTip, BackendError> {
+        self.provider.chain_tip().await
+    }
+
+    async fn epoch_info(&self, config: &SlotConfig) -> Result<EpochInfo, BackendError> {
+        self.provider.epoch_info(config).await
+    }
+
+    async fn datum_by_hash(&self, hash: &Hash<32>) -> Result<Option<PlutusData>, BackendError> {
+        self.provider.datum_by_hash(hash).await
+    }
+
+    async fn script_by_hash(&self, hash: &Hash<28>) -> Result<Option<ScriptRef>, BackendError> {
+        self.provider.script_by_hash(hash).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{Hash, TransactionOutput, Value};
+    use pallas_codec::utils::Set;
+    use pallas_primitives::babbage::PseudoPostAlonzoTransactionOutput;
+    use std::cell::Cell;
+
+    struct CountingProvider {
+        calls: Cell<usize>,
+    }
+
+    fn input(index: u64) -> TransactionInput {
+        TransactionInput {
+            transaction_id: Hash::from([0; 32]),
+            index,
+        }
+    }
+
+    fn utxo(index: u64) -> Utxo {
+        Utxo {
+            input: input(index),
+            output: TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+                address: vec![0x61; 29].into(),
+                value: Value::Coin(index),
+                datum_option: None,
+                script_ref: None,
+            }),
+        }
+    }
+
+    impl ChainProvider for CountingProvider {
+        async fn resolve_input(
+            &self,
+            input: &TransactionInput,
+        ) -> Result<Option<Utxo>, BackendError> {
+            self.calls.set(self.calls.get() + 1);
+            // pretend the odd outputs don't exist
+            Ok(input.index.is_multiple_of(2).then(|| utxo(input.index)))
+        }
+    }
+
+    #[test]
+    fn resolve_and_cache() {
+        let resolver = InputResolver::new(CountingProvider {
+            calls: Cell::new(0),
+        });
+        resolver.insert([utxo(0)]);
+
+        let mut body = crate::cardano::testing::empty_body();
+        body.inputs = Set::from(vec![input(0), input(1), input(2)]);
+
+        let resolved = futures::executor::block_on(resolver.resolve_body(&body)).unwrap();
+        assert_eq!(resolved.into_vec(), vec![utxo(0), utxo(2)]);
+        assert_eq!(resolver.provider().calls.get(), 2);
+
+        futures::executor::block_on(resolver.resolve_body(&body)).unwrap();
+        // input 2 is cached now, only the missing input is looked up again
+        assert_eq!(resolver.provider().calls.get(), 3);
+    }
+}
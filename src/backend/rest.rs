@@ -0,0 +1,105 @@
+//! What the clients of the REST indexers have in common.
+
+use super::{BackendError, scripts::ScriptType};
+use crate::{
+    Address,
+    cardano::{Metadata, TransactionOutput, Value, inspect::decode_tx},
+};
+use pallas_codec::utils::CborWrap;
+use pallas_primitives::{
+    RationalNumber,
+    babbage::PseudoPostAlonzoTransactionOutput,
+    conway::{DatumOption, ScriptRef},
+};
+
+pub(crate) fn decode_error(error: impl ToString) -> BackendError {
+    BackendError::Decode(error.to_string())
+}
+
+pub(crate) fn bech32(address: &Address) -> Result<String, BackendError> {
+    address.to_bech32().map_err(decode_error)
+}
+
+/// the quantities are given as strings, they may not fit in a JSON
+/// number
+pub(crate) fn quantity(quantity: &str) -> Result<u64, BackendError> {
+    quantity.parse().map_err(decode_error)
+}
+
+/// the decimal price as a rational number, e.g. `0.0577` as `577/10000`
+pub(crate) fn rational(price: f64) -> RationalNumber {
+    let decimals = price
+        .to_string()
+        .split_once('.')
+        .map_or(0, |(_, decimals)| decimals.len() as u32)
+        .min(18);
+    let denominator = 10u64.pow(decimals);
+    RationalNumber {
+        numerator: (price * denominator as f64).round() as u64,
+        denominator,
+    }
+}
+
+/// the language of a script, as the indexers name it
+pub(crate) fn script_type(script_type: &str) -> Result<ScriptType, BackendError> {
+    match script_type {
+        "timelock" | "multisig" => Ok(ScriptType::Native),
+        "plutusV1" => Ok(ScriptType::PlutusV1),
+        "plutusV2" => Ok(ScriptType::PlutusV2),
+        "plutusV3" => Ok(ScriptType::PlutusV3),
+        other => Err(BackendError::Decode(format!("unknown script type {other}"))),
+    }
+}
+
+/// the metadata of the hex encoded transaction, of any era
+pub(crate) fn tx_metadata(cbor: &str) -> Result<Metadata, BackendError> {
+    let tx = decode_tx(&hex::decode(cbor).map_err(decode_error)?).map_err(decode_error)?;
+    Ok(Metadata::of_tx(&tx.tx))
+}
+
+pub(crate) fn output(
+    address: &str,
+    value: Value,
+    datum_option: Option<DatumOption>,
+    script_ref: Option<ScriptRef>,
+) -> Result<TransactionOutput, BackendError> {
+    let address = Address::from_bech32(address).map_err(decode_error)?;
+    Ok(TransactionOutput::PostAlonzo(
+        PseudoPostAlonzoTransactionOutput {
+            address: address.to_vec().into(),
+            value,
+            datum_option,
+            script_ref: script_ref.map(CborWrap),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decimal_prices() {
+        assert_eq!(
+            rational(0.0577),
+            RationalNumber {
+                numerator: 577,
+                denominator: 10_000,
+            }
+        );
+        assert_eq!(
+            rational(0.0000721),
+            RationalNumber {
+                numerator: 721,
+                denominator: 10_000_000,
+            }
+        );
+        assert_eq!(
+            rational(1.0),
+            RationalNumber {
+                numerator: 1,
+                denominator: 1,
+            }
+        );
+    }
+}
@@ -0,0 +1,123 @@
+/*!
+Datums and scripts looked up by their hash.
+
+Outputs may only carry the hash of their datum, or reference a script
+by its hash: spending them requires the datum (or script) itself, as
+known by the provider. The providers return the CBOR they stored, the
+functions here decode it and check it does hash to what was asked, so
+the provider does not need to be trusted.
+*/
+
+use super::BackendError;
+use crate::cardano::Hash;
+use pallas_codec::minicbor;
+use pallas_crypto::hash::Hasher;
+use pallas_primitives::{
+    PlutusData, PlutusScript,
+    conway::{NativeScript, ScriptRef},
+};
+
+/// The language of a script, as given by the providers along the
+/// script's bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ScriptType {
+    Native,
+    PlutusV1,
+    PlutusV2,
+    PlutusV3,
+}
+
+impl ScriptType {
+    /// tag prepended to the script's bytes to compute its hash
+    pub fn tag(&self) -> u8 {
+        match self {
+            Self::Native => 0,
+            Self::PlutusV1 => 1,
+            Self::PlutusV2 => 2,
+            Self::PlutusV3 => 3,
+        }
+    }
+}
+
+/// decode the CBOR of the datum, checking it hashes to `hash`
+pub fn decode_datum(hash: &Hash<32>, cbor: &[u8]) -> Result<PlutusData, BackendError> {
+    let computed = Hasher::<256>::hash(cbor);
+    if &computed != hash {
+        return Err(BackendError::Unverified(format!(
+            "the datum hashes to {computed}, not {hash}"
+        )));
+    }
+    minicbor::decode(cbor).map_err(|error| BackendError::Decode(error.to_string()))
+}
+
+/// decode the script, checking it hashes to `hash`
+///
+/// `bytes` are the bytes of the script as found in the witness set: the
+/// CBOR of a native script, or the (CBOR wrapped flat) bytes of a Plutus
+/// script.
+pub fn decode_script(
+    hash: &Hash<28>,
+    script_type: ScriptType,
+    bytes: &[u8],
+) -> Result<ScriptRef, BackendError> {
+    let computed = Hasher::<224>::hash_tagged(bytes, script_type.tag());
+    if &computed != hash {
+        return Err(BackendError::Unverified(format!(
+            "the script hashes to {computed}, not {hash}"
+        )));
+    }
+
+    Ok(match script_type {
+        ScriptType::Native => {
+            let script: NativeScript =
+                minicbor::decode(bytes).map_err(|error| BackendError::Decode(error.to_string()))?;
+            ScriptRef::NativeScript(script)
+        }
+        ScriptType::PlutusV1 => ScriptRef::PlutusV1Script(PlutusScript(bytes.to_vec().into())),
+        ScriptType::PlutusV2 => ScriptRef::PlutusV2Script(PlutusScript(bytes.to_vec().into())),
+        ScriptType::PlutusV3 => ScriptRef::PlutusV3Script(PlutusScript(bytes.to_vec().into())),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::native_script::script_hash;
+    use pallas_primitives::{BigInt, Constr, MaybeIndefArray};
+
+    #[test]
+    fn datum_checked_against_its_hash() {
+        let datum = PlutusData::Constr(Constr {
+            tag: 121,
+            any_constructor: None,
+            fields: MaybeIndefArray::Def(vec![PlutusData::BigInt(BigInt::Int(42.into()))]),
+        });
+        let cbor = minicbor::to_vec(&datum).unwrap();
+        let hash = Hasher::<256>::hash(&cbor);
+
+        assert_eq!(decode_datum(&hash, &cbor).unwrap(), datum);
+        assert!(matches!(
+            decode_datum(&Hash::from([0; 32]), &cbor),
+            Err(BackendError::Unverified(_))
+        ));
+    }
+
+    #[test]
+    fn script_checked_against_its_hash() {
+        let native = NativeScript::InvalidBefore(42);
+        let cbor = minicbor::to_vec(&native).unwrap();
+        let script = decode_script(&script_hash(&native), ScriptType::Native, &cbor).unwrap();
+        assert!(matches!(script, ScriptRef::NativeScript(decoded) if decoded == native));
+
+        let plutus = [0x4e, 0x4d, 0x01, 0x00, 0x00, 0x33, 0x22, 0x22];
+        let hash = Hasher::<224>::hash_tagged(&plutus, 2);
+        assert!(matches!(
+            decode_script(&hash, ScriptType::PlutusV2, &plutus),
+            Ok(ScriptRef::PlutusV2Script(_))
+        ));
+        assert!(matches!(
+            decode_script(&hash, ScriptType::PlutusV3, &plutus),
+            Err(BackendError::Unverified(_))
+        ));
+    }
+}
@@ -0,0 +1,8 @@
+/*!
+What the native assets of a wallet stand for, beyond their quantity.
+
+* [`nft`]: the CIP-25 and CIP-68 metadata of the NFTs, for the token
+  galleries.
+*/
+
+pub mod nft;
@@ -0,0 +1,477 @@
+/*!
+NFT metadata of the wallet's assets, as defined by CIP-25 and CIP-68.
+
+* CIP-25: the metadata is in the [`CIP25_LABEL`] metadata of the
+  transaction minting the token, under its policy id and asset name.
+* CIP-68: the user token (labelled [`NFT_LABEL`] or [`RFT_LABEL`]) comes
+  with a reference NFT ([`REFERENCE_LABEL`]) whose inline datum holds the
+  metadata, see [`mint`](super::super::mint).
+
+Both are parsed into the same [`NftMetadata`]: the `name`, `image`,
+`mediaType`, `description` and `files` properties of the standards and
+the other properties as [`Attribute`]s. The metadata is looked up with
+a [`ChainQuery`] backend:
+
+```no_run
+# use cardano_connector::{backend::ChainQuery, cardano::{WalletValue, assets::nft}};
+# async fn test(balance: WalletValue, backend: impl ChainQuery) -> anyhow::Result<()> {
+for nft in nft::resolve_all(&balance, &backend).await? {
+    println!("{}: {:?}", nft.metadata.name, nft.metadata.image);
+}
+# Ok(()) }
+```
+*/
+
+use super::super::{
+    AssetId, Metadata, Metadatum, MetadatumLabel, TransactionOutput, WalletValue,
+    mint::{FT_LABEL, NFT_LABEL, REFERENCE_LABEL, RFT_LABEL, cip68_asset_name, parse_asset_name},
+};
+use crate::backend::{BackendError, ChainQuery};
+use pallas_primitives::{BigInt, PlutusData, conway::DatumOption};
+use std::collections::BTreeMap;
+
+/// Metadata label of the CIP-25 NFT metadata.
+pub const CIP25_LABEL: MetadatumLabel = 721;
+
+/// Where the metadata of the token comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NftStandard {
+    /// the metadata of the minting transaction
+    Cip25,
+    /// the datum of the reference NFT
+    Cip68,
+}
+
+/// A value of the metadata.
+///
+/// The CIP-68 datums hold the strings as bytes: the valid UTF-8 bytes
+/// are given as [`Text`](Self::Text).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Attribute {
+    Int(i128),
+    Text(String),
+    Bytes(Vec<u8>),
+    List(Vec<Attribute>),
+    Map(Vec<(Attribute, Attribute)>),
+}
+
+impl Attribute {
+    /// the string, or the concatenation of a list of strings (the strings
+    /// longer than 64 bytes are split in the metadata)
+    pub fn as_text(&self) -> Option<String> {
+        match self {
+            Self::Text(text) => Some(text.clone()),
+            Self::List(chunks) => chunks
+                .iter()
+                .map(|chunk| match chunk {
+                    Self::Text(text) => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        }
+    }
+
+    fn from_metadatum(metadatum: &Metadatum) -> Self {
+        match metadatum {
+            Metadatum::Int(int) => Self::Int(i128::from(*int)),
+            Metadatum::Text(text) => Self::Text(text.clone()),
+            Metadatum::Bytes(bytes) => Self::Bytes(bytes.to_vec()),
+            Metadatum::Array(items) => Self::List(items.iter().map(Self::from_metadatum).collect()),
+            Metadatum::Map(entries) => Self::Map(
+                entries
+                    .iter()
+                    .map(|(key, value)| (Self::from_metadatum(key), Self::from_metadatum(value)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// `None` for the constructors and the integers not fitting in an
+    /// `i128`
+    fn from_plutus_data(data: &PlutusData) -> Option<Self> {
+        match data {
+            PlutusData::BigInt(BigInt::Int(int)) => Some(Self::Int(i128::from(*int))),
+            PlutusData::BigInt(_) | PlutusData::Constr(_) => None,
+            PlutusData::BoundedBytes(bytes) => Some(match String::from_utf8(bytes.to_vec()) {
+                Ok(text) => Self::Text(text),
+                Err(_) => Self::Bytes(bytes.to_vec()),
+            }),
+            PlutusData::Array(items) => Some(Self::List(
+                items.iter().filter_map(Self::from_plutus_data).collect(),
+            )),
+            PlutusData::Map(entries) => Some(Self::Map(
+                entries
+                    .iter()
+                    .filter_map(|(key, value)| {
+                        Some((Self::from_plutus_data(key)?, Self::from_plutus_data(value)?))
+                    })
+                    .collect(),
+            )),
+        }
+    }
+}
+
+/// A file of the token, e.g. the full resolution of its image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NftFile {
+    pub name: Option<String>,
+    pub media_type: Option<String>,
+    /// the URI of the file, e.g. `ipfs://...`
+    pub src: String,
+}
+
+/// The metadata of a token, see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NftMetadata {
+    pub standard: NftStandard,
+    pub name: String,
+    /// the URI of the thumbnail, e.g. `ipfs://...`
+    pub image: Option<String>,
+    pub media_type: Option<String>,
+    pub description: Option<String>,
+    pub files: Vec<NftFile>,
+    /// the other properties, e.g. the traits of the token in its
+    /// collection
+    pub attributes: BTreeMap<String, Attribute>,
+}
+
+impl NftMetadata {
+    /// the CIP-25 metadata of the `asset` in the metadata of its minting
+    /// transaction
+    ///
+    /// Both versions of CIP-25 are accepted: the policy id and the asset
+    /// name as text (version 1, the asset name in UTF-8 or in hex) or as
+    /// bytes (version 2). `None` if the metadata has no valid entry for
+    /// the asset.
+    pub fn from_cip25(metadata: &Metadata, asset: &AssetId) -> Option<Self> {
+        let Metadatum::Map(policies) = metadata.get(CIP25_LABEL)? else {
+            return None;
+        };
+        let policy = hex::encode(asset.policy.as_slice());
+        let Metadatum::Map(assets) = find(policies, &policy, asset.policy.as_slice())? else {
+            return None;
+        };
+        let name = hex::encode(asset.name.as_slice());
+        let properties = find(assets, &name, asset.name.as_slice()).or_else(|| {
+            let name = std::str::from_utf8(asset.name.as_slice()).ok()?;
+            find(assets, name, asset.name.as_slice())
+        })?;
+        Self::from_properties(NftStandard::Cip25, Attribute::from_metadatum(properties))
+    }
+
+    /// the metadata of the inline datum of a CIP-68 reference NFT:
+    /// `Constr 0 [properties, version, extra]`
+    pub fn from_cip68(datum: &PlutusData) -> Option<Self> {
+        let PlutusData::Constr(constr) = datum else {
+            return None;
+        };
+        if constr.tag != 121 {
+            return None;
+        }
+        let properties = Attribute::from_plutus_data(constr.fields.first()?)?;
+        Self::from_properties(NftStandard::Cip68, properties)
+    }
+
+    /// `None` without a `name`
+    fn from_properties(standard: NftStandard, properties: Attribute) -> Option<Self> {
+        let Attribute::Map(entries) = properties else {
+            return None;
+        };
+        let mut attributes: BTreeMap<String, Attribute> = entries
+            .into_iter()
+            .filter_map(|(key, value)| Some((key.as_text()?, value)))
+            .collect();
+        let mut text = |key: &str| attributes.remove(key).as_ref().and_then(Attribute::as_text);
+
+        let name = text("name")?;
+        let image = text("image");
+        let media_type = text("mediaType");
+        let description = text("description");
+        let files = match attributes.remove("files") {
+            Some(Attribute::List(files)) => files.iter().filter_map(file).collect(),
+            _ => Vec::new(),
+        };
+        Some(Self {
+            standard,
+            name,
+            image,
+            media_type,
+            description,
+            files,
+            attributes,
+        })
+    }
+}
+
+/// the value of the key given as text or as bytes
+fn find<'a>(
+    entries: &'a [(Metadatum, Metadatum)],
+    text: &str,
+    bytes: &[u8],
+) -> Option<&'a Metadatum> {
+    entries
+        .iter()
+        .find(|(key, _)| match key {
+            Metadatum::Text(key) => key == text,
+            Metadatum::Bytes(key) => key.as_slice() == bytes,
+            _ => false,
+        })
+        .map(|(_, value)| value)
+}
+
+fn file(file: &Attribute) -> Option<NftFile> {
+    let Attribute::Map(entries) = file else {
+        return None;
+    };
+    let text = |name: &str| {
+        entries
+            .iter()
+            .find(|(key, _)| key.as_text().as_deref() == Some(name))
+            .and_then(|(_, value)| value.as_text())
+    };
+    Some(NftFile {
+        name: text("name"),
+        media_type: text("mediaType"),
+        src: text("src")?,
+    })
+}
+
+/// An NFT of the wallet with its metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nft {
+    pub asset: AssetId,
+    pub quantity: u64,
+    pub metadata: NftMetadata,
+}
+
+/// the metadata of the `asset`: from the datum of its reference NFT for
+/// a CIP-68 user token, from its minting transaction otherwise
+///
+/// `None` if the asset has no (valid) NFT metadata.
+pub async fn resolve<Q: ChainQuery>(
+    asset: &AssetId,
+    backend: &Q,
+) -> Result<Option<NftMetadata>, BackendError> {
+    match parse_asset_name(asset.name.as_slice()) {
+        Some((NFT_LABEL | FT_LABEL | RFT_LABEL, name)) => {
+            let reference = AssetId::new(asset.policy, cip68_asset_name(REFERENCE_LABEL, name));
+            let Some(utxo) = backend.asset_utxo(&reference).await? else {
+                return Ok(None);
+            };
+            let TransactionOutput::PostAlonzo(output) = &utxo.output else {
+                return Ok(None);
+            };
+            Ok(match &output.datum_option {
+                Some(DatumOption::Data(datum)) => NftMetadata::from_cip68(&datum.0),
+                _ => None,
+            })
+        }
+        _ => {
+            let metadata = backend.mint_metadata(asset).await?;
+            Ok(NftMetadata::from_cip25(&metadata, asset))
+        }
+    }
+}
+
+/// the NFTs of the wallet's balance, with their metadata
+///
+/// The candidates are the CIP-68 user NFTs ([`NFT_LABEL`] and
+/// [`RFT_LABEL`]) and the other assets held once, except the reference
+/// NFTs. The assets without NFT metadata are left out. This queries the
+/// backend once per candidate, one after the other.
+pub async fn resolve_all<Q: ChainQuery>(
+    value: &WalletValue,
+    backend: &Q,
+) -> Result<Vec<Nft>, BackendError> {
+    let mut nfts = Vec::new();
+    for (asset, &quantity) in value.assets() {
+        let candidate = match parse_asset_name(asset.name.as_slice()) {
+            Some((NFT_LABEL | RFT_LABEL, _)) => true,
+            Some((REFERENCE_LABEL | FT_LABEL, _)) => false,
+            _ => quantity == 1,
+        };
+        if !candidate {
+            continue;
+        }
+        if let Some(metadata) = resolve(asset, backend).await? {
+            nfts.push(Nft {
+                asset: asset.clone(),
+                quantity,
+                metadata,
+            });
+        }
+    }
+    Ok(nfts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Address,
+        backend::{StakeInfo, TxStatus},
+        cardano::{
+            Hash, TransactionInput, TxHash, Utxo,
+            fees::ProtocolParams,
+            metadata::{bytes, int, list, map, text},
+            mint::cip68_datum,
+        },
+    };
+    use pallas_codec::utils::CborWrap;
+    use pallas_primitives::babbage::PseudoPostAlonzoTransactionOutput;
+
+    fn asset(name: &[u8]) -> AssetId {
+        AssetId::new(Hash::from([1; 28]), name.to_vec().into())
+    }
+
+    fn cip25(policy: Metadatum, name: Metadatum) -> Metadata {
+        let properties = map([
+            (text("name"), text("Artwork #1")),
+            (text("image"), list([text("ipfs://"), text("QmArtwork")])),
+            (text("mediaType"), text("image/png")),
+            (
+                text("files"),
+                list([map([
+                    (text("mediaType"), text("image/png")),
+                    (text("src"), text("ipfs://QmFull")),
+                ])]),
+            ),
+            (text("rarity"), int(3)),
+        ]);
+        Metadata::new().with(
+            CIP25_LABEL,
+            map([
+                (policy, map([(name, properties)])),
+                (text("version"), text("1.0")),
+            ]),
+        )
+    }
+
+    #[test]
+    fn cip25_versions() {
+        let asset = asset(b"Artwork1");
+        let policy = hex::encode([1; 28]);
+
+        for metadata in [
+            cip25(text(&policy), text("Artwork1")),
+            cip25(text(&policy), text(&hex::encode(b"Artwork1"))),
+            cip25(bytes(&[1; 28]), bytes(b"Artwork1")),
+        ] {
+            let nft = NftMetadata::from_cip25(&metadata, &asset).unwrap();
+            assert_eq!(nft.standard, NftStandard::Cip25);
+            assert_eq!(nft.name, "Artwork #1");
+            assert_eq!(nft.image.as_deref(), Some("ipfs://QmArtwork"));
+            assert_eq!(nft.media_type.as_deref(), Some("image/png"));
+            assert_eq!(
+                nft.files,
+                [NftFile {
+                    name: None,
+                    media_type: Some("image/png".to_owned()),
+                    src: "ipfs://QmFull".to_owned(),
+                }]
+            );
+            assert_eq!(
+                nft.attributes,
+                BTreeMap::from([("rarity".to_owned(), Attribute::Int(3))])
+            );
+        }
+
+        let other = self::asset(b"Artwork2");
+        assert_eq!(
+            NftMetadata::from_cip25(&cip25(text(&policy), text("Artwork1")), &other),
+            None
+        );
+    }
+
+    fn datum() -> PlutusData {
+        let bytes = |bytes: &[u8]| PlutusData::BoundedBytes(bytes.to_vec().into());
+        cip68_datum(
+            vec![
+                (bytes(b"name"), bytes(b"Artwork #1")),
+                (bytes(b"image"), bytes(b"ipfs://QmArtwork")),
+                (bytes(b"hash"), bytes(&[0xff, 0xfe])),
+            ],
+            1,
+        )
+    }
+
+    #[test]
+    fn cip68_datum_metadata() {
+        let nft = NftMetadata::from_cip68(&datum()).unwrap();
+        assert_eq!(nft.standard, NftStandard::Cip68);
+        assert_eq!(nft.name, "Artwork #1");
+        assert_eq!(nft.image.as_deref(), Some("ipfs://QmArtwork"));
+        assert_eq!(
+            nft.attributes,
+            BTreeMap::from([("hash".to_owned(), Attribute::Bytes(vec![0xff, 0xfe]))])
+        );
+    }
+
+    /// a backend knowing the CIP-25 metadata of `Artwork1` and the
+    /// reference NFT of `Artwork2`
+    struct Assets;
+
+    impl ChainQuery for Assets {
+        async fn protocol_params(&self) -> Result<ProtocolParams, BackendError> {
+            Ok(ProtocolParams::MAINNET)
+        }
+
+        async fn stake_info(&self, _address: &Address) -> Result<Option<StakeInfo>, BackendError> {
+            Ok(None)
+        }
+
+        async fn tx_status(&self, _hash: &TxHash) -> Result<TxStatus, BackendError> {
+            Ok(TxStatus::NotFound)
+        }
+
+        async fn mint_metadata(&self, asset: &AssetId) -> Result<Metadata, BackendError> {
+            Ok(match asset.name.as_slice() {
+                b"Artwork1" => cip25(text(&hex::encode([1; 28])), text("Artwork1")),
+                _ => Metadata::new(),
+            })
+        }
+
+        async fn asset_utxo(&self, asset: &AssetId) -> Result<Option<Utxo>, BackendError> {
+            if *asset != self::asset(&cip68_asset_name(REFERENCE_LABEL, b"Artwork2")) {
+                return Ok(None);
+            }
+            Ok(Some(Utxo {
+                input: TransactionInput {
+                    transaction_id: Hash::from([2; 32]),
+                    index: 0,
+                },
+                output: TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+                    address: vec![0x61; 29].into(),
+                    value: pallas_primitives::conway::Value::Coin(2_000_000),
+                    datum_option: Some(DatumOption::Data(CborWrap(datum()))),
+                    script_ref: None,
+                }),
+            }))
+        }
+    }
+
+    #[test]
+    fn resolve_wallet_nfts() {
+        let balance = WalletValue::from_lovelace(10_000_000)
+            .with_asset(asset(b"Artwork1"), 1)
+            .with_asset(asset(&cip68_asset_name(NFT_LABEL, b"Artwork2")), 1)
+            // a fungible token, not looked up
+            .with_asset(asset(b"Token"), 1_000)
+            // held once but without metadata
+            .with_asset(asset(b"Unknown"), 1);
+
+        let nfts = futures::executor::block_on(resolve_all(&balance, &Assets)).unwrap();
+
+        // the CIP-68 labels come first in the order of the asset names
+        assert_eq!(nfts.len(), 2);
+        assert_eq!(
+            nfts[0].asset,
+            asset(&cip68_asset_name(NFT_LABEL, b"Artwork2"))
+        );
+        assert_eq!(nfts[0].metadata.standard, NftStandard::Cip68);
+        assert_eq!(nfts[1].asset, asset(b"Artwork1"));
+        assert_eq!(nfts[1].metadata.standard, NftStandard::Cip25);
+        assert_eq!(nfts[1].metadata.name, "Artwork #1");
+    }
+}
@@ -0,0 +1,242 @@
+/*!
+Payments of lovelace and native assets to one or more recipients.
+
+The [`TransactionBuilder`] collects what each recipient receives, one
+output per recipient address, and takes care of the lovelace the outputs
+holding native assets need to carry (the min-ADA). The assets that are
+not sent go back to the change address of the [`BuildParams`] along with
+the remaining lovelace.
+
+```
+# use cardano_connector::{Address, UtxoSet, cardano::{AssetName, BuildParams, PolicyId, TransactionBuilder}};
+# fn test(
+#     alice: &Address,
+#     bob: &Address,
+#     policy: PolicyId,
+#     utxos: &UtxoSet,
+#     params: BuildParams,
+# ) -> anyhow::Result<()> {
+let tx = TransactionBuilder::new(params)
+    .send_asset(alice, policy, AssetName::from(b"token".to_vec()), 100)
+    .send_asset(bob, policy, AssetName::from(b"token".to_vec()), 50)
+    .send_lovelace(bob, 5_000_000)
+    .build(utxos)?;
+# Ok(()) }
+```
+*/
+
+use super::{
+    AssetId, AssetName, BuildError, BuildParams, Coin, Metadatum, MetadatumLabel, PolicyId,
+    Recipient, Tx, TxIntent, UtxoSet, Value, WalletValue,
+};
+use crate::Address;
+use std::collections::BTreeMap;
+
+/// what a recipient receives
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Payment {
+    address: Address,
+    lovelace: Coin,
+    assets: BTreeMap<(PolicyId, AssetName), u64>,
+}
+
+impl Payment {
+    fn value(&self) -> Value {
+        self.assets
+            .iter()
+            .fold(
+                WalletValue::from_lovelace(self.lovelace),
+                |value, ((policy, name), amount)| {
+                    value.with_asset(AssetId::new(*policy, name.clone()), *amount)
+                },
+            )
+            .into()
+    }
+}
+
+/// see the [module documentation](self)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionBuilder {
+    params: BuildParams,
+    payments: Vec<Payment>,
+    metadata: Vec<(MetadatumLabel, Metadatum)>,
+}
+
+impl TransactionBuilder {
+    pub fn new(params: BuildParams) -> Self {
+        Self {
+            params,
+            payments: Vec::new(),
+            metadata: Vec::new(),
+        }
+    }
+
+    pub fn params(&self) -> &BuildParams {
+        &self.params
+    }
+
+    /// send `amount` of the asset to `address`, adding to what the
+    /// address already receives
+    pub fn send_asset(
+        mut self,
+        address: &Address,
+        policy: PolicyId,
+        name: AssetName,
+        amount: u64,
+    ) -> Self {
+        *self
+            .payment(address)
+            .assets
+            .entry((policy, name))
+            .or_default() += amount;
+        self
+    }
+
+    /// send `lovelace` to `address`, adding to what the address already
+    /// receives
+    ///
+    /// The outputs holding assets carry at least their min-ADA, the
+    /// lovelace sent with assets only count if above it.
+    pub fn send_lovelace(mut self, address: &Address, lovelace: Coin) -> Self {
+        self.payment(address).lovelace += lovelace;
+        self
+    }
+
+    pub fn with_metadata(mut self, label: MetadatumLabel, metadatum: Metadatum) -> Self {
+        self.metadata.retain(|(existing, _)| *existing != label);
+        self.metadata.push((label, metadatum));
+        self
+    }
+
+    fn payment(&mut self, address: &Address) -> &mut Payment {
+        let index = match self
+            .payments
+            .iter()
+            .position(|payment| &payment.address == address)
+        {
+            Some(index) => index,
+            None => {
+                self.payments.push(Payment {
+                    address: address.clone(),
+                    lovelace: 0,
+                    assets: BTreeMap::new(),
+                });
+                self.payments.len() - 1
+            }
+        };
+        &mut self.payments[index]
+    }
+
+    /// the intent of the payments, the outputs hold at least their
+    /// min-ADA
+    pub fn intent(&self) -> TxIntent {
+        let mut intent = TxIntent::new();
+        for payment in &self.payments {
+            intent.recipients.push(
+                Recipient {
+                    address: payment.address.to_vec().into(),
+                    value: payment.value(),
+                    datum: None,
+                }
+                .with_min_ada(self.params.coins_per_utxo_byte),
+            );
+        }
+        for (label, metadatum) in &self.metadata {
+            intent = intent.with_metadata(*label, metadatum.clone());
+        }
+        intent
+    }
+
+    /// build the transaction spending from `utxos`, see
+    /// [`build`](super::intent::build)
+    pub fn build(&self, utxos: &UtxoSet) -> Result<Tx, BuildError> {
+        self.intent().build(utxos, &self.params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{
+        AssetId, Hash, TransactionInput, TransactionOutput, Utxo, assets_of, lovelace_of,
+        min_ada_for_output,
+        testing::{address, utxo},
+    };
+
+    fn token(amount: u64) -> Value {
+        Payment {
+            address: address(0),
+            lovelace: 2_000_000,
+            assets: BTreeMap::from([((Hash::from([9; 28]), b"token".to_vec().into()), amount)]),
+        }
+        .value()
+    }
+
+    fn utxo_of(output: &TransactionOutput) -> Utxo {
+        Utxo {
+            input: TransactionInput {
+                transaction_id: Hash::from([0; 32]),
+                index: 0,
+            },
+            output: output.clone(),
+        }
+    }
+
+    #[test]
+    fn send_tokens_with_change() {
+        let policy = Hash::from([9; 28]);
+        let name = AssetName::from(b"token".to_vec());
+        let utxos = UtxoSet::from(vec![
+            utxo(1, &address(1), token(100)),
+            utxo(2, &address(1), Value::Coin(10_000_000)),
+        ]);
+
+        let tx = TransactionBuilder::new(BuildParams::defaults(address(1)))
+            .send_asset(&address(2), policy, name.clone(), 30)
+            .send_asset(&address(3), policy, name.clone(), 20)
+            .send_asset(&address(2), policy, name.clone(), 10)
+            .build(&utxos)
+            .unwrap();
+        let outputs = &tx.transaction_body.outputs;
+
+        assert_eq!(outputs.len(), 3);
+        for output in &outputs[..2] {
+            assert_eq!(
+                lovelace_of(&utxo_of(output).value()),
+                min_ada_for_output(output, 4_310)
+            );
+        }
+        let received = |index: usize| assets_of(&utxo_of(&outputs[index]).value());
+        assert_eq!(
+            received(0),
+            BTreeMap::from([(AssetId::new(policy, name.clone()), 40)])
+        );
+        assert_eq!(
+            received(1),
+            BTreeMap::from([(AssetId::new(policy, name.clone()), 20)])
+        );
+        // the tokens not sent come back with the change
+        assert_eq!(utxo_of(&outputs[2]).address().unwrap(), address(1));
+        assert_eq!(
+            received(2),
+            BTreeMap::from([(AssetId::new(policy, name), 40)])
+        );
+    }
+
+    #[test]
+    fn lovelace_above_min_ada() {
+        let builder = TransactionBuilder::new(BuildParams::defaults(address(1)))
+            .send_lovelace(&address(2), 5_000_000)
+            .send_asset(
+                &address(2),
+                Hash::from([9; 28]),
+                b"token".to_vec().into(),
+                1,
+            );
+
+        let intent = builder.intent();
+
+        assert_eq!(intent.recipients.len(), 1);
+        assert_eq!(lovelace_of(&intent.recipients[0].value), 5_000_000);
+    }
+}
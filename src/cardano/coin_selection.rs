@@ -0,0 +1,314 @@
+/*!
+Local coin selection over already fetched UTxOs (CIP-2).
+
+[`ConnectedWallet::select_utxos`](crate::ConnectedWallet::select_utxos)
+leaves the selection to the wallet. When all the UTxOs are already known
+(e.g. from [`all_utxos`](crate::ConnectedWallet::all_utxos) or an
+indexer) the selection can be done locally:
+
+* [`largest_first`]: the UTxOs holding the most of each requested asset,
+  then of lovelace, are selected first. Few inputs, but the large UTxOs
+  are consumed and the wallet fragments over time;
+* [`random_improve`]: the UTxOs are picked at random until the target is
+  covered, then more are added while they bring the selected amount of
+  each asset closer to twice its target. The change outputs look like the
+  payments, keeping the UTxO set of the wallet healthy.
+
+The target is what the selected inputs need to cover: the payments, but
+also the fee and deposits if the change is to be computed from the
+[`Selection`].
+
+```
+# use cardano_connector::{Utxo, Value, cardano::coin_selection::random_improve};
+# fn test(utxos: &[Utxo], seed: u64) -> anyhow::Result<()> {
+let selection = random_improve(utxos, &Value::Coin(10_000_000), seed)?;
+# Ok(()) }
+```
+*/
+
+use super::{AssetId, Coin, Utxo, Value, ValueExt as _, WalletValue};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SelectionError {
+    #[error("Not enough funds in the UTxOs: {missing} more lovelace and {} more assets needed", .assets.len())]
+    InsufficientFunds {
+        missing: Coin,
+        /// the missing quantity of the assets
+        assets: BTreeMap<AssetId, u64>,
+    },
+}
+
+/// The result of a coin selection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selection {
+    /// the selected UTxOs, in the order they were selected
+    pub inputs: Vec<Utxo>,
+    /// what the selected UTxOs hold above the target
+    pub change: Value,
+}
+
+impl Selection {
+    fn new(inputs: Vec<Utxo>, target: &Dimensions) -> Self {
+        let mut change = total(&inputs);
+        for (dimension, quantity) in target {
+            if let Some(total) = change.get_mut(dimension) {
+                *total -= quantity;
+            }
+        }
+        Self {
+            inputs,
+            change: value(&change),
+        }
+    }
+}
+
+/// the quantities of a value, lovelace under `None`
+type Dimensions = BTreeMap<Option<AssetId>, u64>;
+
+/// select the UTxOs holding the most of each asset of the `target`, then
+/// the ones holding the most lovelace
+pub fn largest_first(utxos: &[Utxo], target: &Value) -> Result<Selection, SelectionError> {
+    let target = dimensions(target);
+    let mut remaining: Vec<&Utxo> = utxos.iter().collect();
+    let mut selected = Vec::new();
+
+    for dimension in order(&target) {
+        remaining.sort_by(|a, b| {
+            quantity(b, &dimension)
+                .cmp(&quantity(a, &dimension))
+                .then(a.input.cmp(&b.input))
+        });
+        while total_of(&selected, &dimension) < target[&dimension] {
+            match remaining.first() {
+                Some(utxo) if quantity(utxo, &dimension) > 0 => {
+                    selected.push(remaining.remove(0).clone());
+                }
+                _ => return Err(insufficient(&selected, &target)),
+            }
+        }
+    }
+
+    Ok(Selection::new(selected, &target))
+}
+
+/// the CIP-2 random-improve selection
+///
+/// The UTxOs are picked with a pseudo random generator initialised with
+/// `seed`: the same `seed` always gives the same selection.
+pub fn random_improve(
+    utxos: &[Utxo],
+    target: &Value,
+    seed: u64,
+) -> Result<Selection, SelectionError> {
+    let target = dimensions(target);
+    let mut random = SplitMix64(seed);
+    let mut remaining: Vec<&Utxo> = utxos.iter().collect();
+    let mut selected = Vec::new();
+    let dimensions = order(&target);
+
+    // random selection until each dimension is covered
+    for dimension in &dimensions {
+        while total_of(&selected, dimension) < target[dimension] {
+            let candidates = holding(&remaining, dimension);
+            if candidates.is_empty() {
+                return Err(insufficient(&selected, &target));
+            }
+            let index = candidates[random.below(candidates.len())];
+            selected.push(remaining.remove(index).clone());
+        }
+    }
+
+    // improvement, in reverse order: select more while it gets the
+    // selected quantity closer to twice the target without exceeding
+    // three times the target
+    for dimension in dimensions.iter().rev() {
+        let ideal = target[dimension].saturating_mul(2);
+        let maximum = target[dimension].saturating_mul(3);
+        loop {
+            let candidates = holding(&remaining, dimension);
+            if candidates.is_empty() {
+                break;
+            }
+            let index = candidates[random.below(candidates.len())];
+            let current = total_of(&selected, dimension);
+            let improved = current.saturating_add(quantity(remaining[index], dimension));
+            if improved > maximum || improved.abs_diff(ideal) >= current.abs_diff(ideal) {
+                break;
+            }
+            selected.push(remaining.remove(index).clone());
+        }
+    }
+
+    Ok(Selection::new(selected, &target))
+}
+
+/// the dimensions to cover: the assets first, as the UTxOs holding them
+/// also hold lovelace, then lovelace
+fn order(target: &Dimensions) -> Vec<Option<AssetId>> {
+    let mut order: Vec<_> = target
+        .iter()
+        .filter(|(dimension, quantity)| dimension.is_some() && **quantity > 0)
+        .map(|(dimension, _)| dimension.clone())
+        .collect();
+    if target.get(&None).is_some_and(|lovelace| *lovelace > 0) {
+        order.push(None);
+    }
+    order
+}
+
+fn dimensions(value: &Value) -> Dimensions {
+    value.dimensions()
+}
+
+fn quantity(utxo: &Utxo, dimension: &Option<AssetId>) -> u64 {
+    dimensions(&utxo.value())
+        .get(dimension)
+        .copied()
+        .unwrap_or_default()
+}
+
+/// the index in `utxos` of the ones holding some of `dimension`
+fn holding(utxos: &[&Utxo], dimension: &Option<AssetId>) -> Vec<usize> {
+    (0..utxos.len())
+        .filter(|index| quantity(utxos[*index], dimension) > 0)
+        .collect()
+}
+
+fn total(utxos: &[Utxo]) -> Dimensions {
+    let mut total = Dimensions::new();
+    for utxo in utxos {
+        for (dimension, quantity) in dimensions(&utxo.value()) {
+            *total.entry(dimension).or_default() += quantity;
+        }
+    }
+    total
+}
+
+fn total_of(utxos: &[Utxo], dimension: &Option<AssetId>) -> u64 {
+    utxos.iter().map(|utxo| quantity(utxo, dimension)).sum()
+}
+
+fn insufficient(selected: &[Utxo], target: &Dimensions) -> SelectionError {
+    let total = total(selected);
+    let mut missing = 0;
+    let mut assets = BTreeMap::new();
+    for (dimension, quantity) in target {
+        let lacking = quantity.saturating_sub(total.get(dimension).copied().unwrap_or_default());
+        match dimension {
+            None => missing = lacking,
+            Some(asset) if lacking > 0 => {
+                assets.insert(asset.clone(), lacking);
+            }
+            Some(_) => {}
+        }
+    }
+    SelectionError::InsufficientFunds { missing, assets }
+}
+
+fn value(dimensions: &Dimensions) -> Value {
+    let mut value = WalletValue::zero();
+    for (dimension, quantity) in dimensions {
+        match dimension {
+            None => value.add_lovelace(*quantity),
+            Some(asset) => value.add_asset(asset.clone(), *quantity),
+        }
+    }
+    value.into()
+}
+
+/// small deterministic generator, good enough to spread the selection
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// a number in `0..bound`
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{Hash, TransactionInput, TransactionOutput, assets_of, lovelace_of};
+    use pallas_primitives::babbage::PseudoPostAlonzoTransactionOutput;
+
+    fn token() -> AssetId {
+        AssetId::new(Hash::from([9; 28]), b"token".to_vec().into())
+    }
+
+    fn utxo(index: u64, coin: Coin, tokens: u64) -> Utxo {
+        let mut dimensions = Dimensions::from([(None, coin)]);
+        if tokens > 0 {
+            dimensions.insert(Some(token()), tokens);
+        }
+        Utxo {
+            input: TransactionInput {
+                transaction_id: Hash::from([0; 32]),
+                index,
+            },
+            output: TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+                address: vec![0x61; 29].into(),
+                value: value(&dimensions),
+                datum_option: None,
+                script_ref: None,
+            }),
+        }
+    }
+
+    fn utxos() -> Vec<Utxo> {
+        vec![
+            utxo(0, 1_000_000, 0),
+            utxo(1, 5_000_000, 0),
+            utxo(2, 2_000_000, 50),
+            utxo(3, 3_000_000, 0),
+            utxo(4, 1_500_000, 20),
+            utxo(5, 4_000_000, 0),
+        ]
+    }
+
+    #[test]
+    fn largest_first_selection() {
+        let target = value(&Dimensions::from([(None, 6_000_000), (Some(token()), 60)]));
+
+        let selection = largest_first(&utxos(), &target).unwrap();
+
+        let indexes: Vec<_> = selection.inputs.iter().map(Utxo::index).collect();
+        assert_eq!(indexes, vec![2, 4, 1]);
+        assert_eq!(lovelace_of(&selection.change), 2_500_000);
+        assert_eq!(assets_of(&selection.change)[&token()], 10);
+    }
+
+    #[test]
+    fn random_improve_selection() {
+        let target = value(&Dimensions::from([(None, 4_000_000), (Some(token()), 10)]));
+
+        let selection = random_improve(&utxos(), &target, 42).unwrap();
+
+        assert_eq!(selection, random_improve(&utxos(), &target, 42).unwrap());
+        let total = total(&selection.inputs);
+        assert!(total[&None] >= 4_000_000 && total[&None] <= 12_000_000);
+        assert!(total[&Some(token())] >= 10);
+        assert!(value(&total).meets_or_exceeds(&target));
+    }
+
+    #[test]
+    fn not_enough_funds() {
+        let target = value(&Dimensions::from([(None, 1_000_000), (Some(token()), 100)]));
+
+        let expected = SelectionError::InsufficientFunds {
+            missing: 0,
+            assets: BTreeMap::from([(token(), 30)]),
+        };
+        assert_eq!(largest_first(&utxos(), &target), Err(expected.clone()));
+        assert_eq!(random_improve(&utxos(), &target, 7), Err(expected));
+    }
+}
@@ -0,0 +1,187 @@
+/*!
+Plutus cost models, per protocol version.
+
+The cost models are part of the protocol parameters and are needed to
+evaluate the scripts (and to compute the `script_data_hash`). They change
+with the protocol version, a [`CostModelStore`] keeps the ones fetched
+from the [`ParamsProvider`] so they can be handed to the evaluator.
+
+[`ParamsProvider`]: crate::backend::ParamsProvider
+*/
+
+use super::{TransactionOutput, Tx, UtxoSet};
+use pallas_primitives::{
+    CostModel, ProtocolVersion,
+    conway::{CostModels, Language, ScriptRef},
+};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CostModelError {
+    #[error("No cost models are available, they need to be fetched first")]
+    Empty,
+    #[error("Plutus {0:?} scripts are not available in protocol version {1}")]
+    UnsupportedLanguage(Language, u64),
+    #[error("No cost model available for Plutus {0:?}")]
+    MissingCostModel(Language),
+}
+
+/// The cost models of every protocol version seen so far.
+#[derive(Debug, Clone, Default)]
+pub struct CostModelStore {
+    versions: BTreeMap<ProtocolVersion, CostModels>,
+}
+
+impl CostModelStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// store the cost models of the given protocol version, replacing
+    /// any previously stored for the same version
+    pub fn update(&mut self, version: ProtocolVersion, cost_models: CostModels) {
+        self.versions.insert(version, cost_models);
+    }
+
+    /// the latest protocol version known, with its cost models
+    pub fn current(&self) -> Option<(ProtocolVersion, &CostModels)> {
+        self.versions
+            .last_key_value()
+            .map(|(version, cost_models)| (*version, cost_models))
+    }
+
+    pub fn for_version(&self, version: ProtocolVersion) -> Option<&CostModels> {
+        self.versions.get(&version)
+    }
+
+    /// the cost model of the given language for the current protocol
+    /// version, this is what the evaluator needs
+    pub fn cost_model(&self, language: &Language) -> Option<&CostModel> {
+        let (_, cost_models) = self.current()?;
+        match language {
+            Language::PlutusV1 => cost_models.plutus_v1.as_ref(),
+            Language::PlutusV2 => cost_models.plutus_v2.as_ref(),
+            Language::PlutusV3 => cost_models.plutus_v3.as_ref(),
+        }
+    }
+
+    /// Check that every Plutus script the transaction runs can be
+    /// evaluated with the current protocol version.
+    ///
+    /// The scripts are looked up in the witness set and in the reference
+    /// scripts of the `resolved` reference inputs and spent inputs.
+    pub fn validate(&self, tx: &Tx, resolved: &UtxoSet) -> Result<(), CostModelError> {
+        let ((major, _), _) = self.current().ok_or(CostModelError::Empty)?;
+
+        for language in languages(tx, resolved) {
+            if major < introduced_in(&language) {
+                return Err(CostModelError::UnsupportedLanguage(language, major));
+            }
+            if self.cost_model(&language).is_none() {
+                return Err(CostModelError::MissingCostModel(language));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// first major protocol version supporting the given language
+fn introduced_in(language: &Language) -> u64 {
+    match language {
+        Language::PlutusV1 => 5,
+        Language::PlutusV2 => 7,
+        Language::PlutusV3 => 9,
+    }
+}
+
+/// the Plutus languages used by the transaction, without duplicates
+pub fn languages(tx: &Tx, resolved: &UtxoSet) -> Vec<Language> {
+    let witnesses = &tx.transaction_witness_set;
+    let body = &tx.transaction_body;
+    let mut used = [
+        witnesses.plutus_v1_script.is_some(),
+        witnesses.plutus_v2_script.is_some(),
+        witnesses.plutus_v3_script.is_some(),
+    ];
+
+    let referenced = body.inputs.iter().chain(
+        body.reference_inputs
+            .iter()
+            .flat_map(|inputs| inputs.iter()),
+    );
+    for input in referenced {
+        let Some(utxo) = resolved.iter().find(|utxo| &utxo.input == input) else {
+            continue;
+        };
+        if let TransactionOutput::PostAlonzo(output) = &utxo.output
+            && let Some(script) = &output.script_ref
+        {
+            match &script.0 {
+                ScriptRef::NativeScript(_) => {}
+                ScriptRef::PlutusV1Script(_) => used[0] = true,
+                ScriptRef::PlutusV2Script(_) => used[1] = true,
+                ScriptRef::PlutusV3Script(_) => used[2] = true,
+            }
+        }
+    }
+
+    [Language::PlutusV1, Language::PlutusV2, Language::PlutusV3]
+        .into_iter()
+        .zip(used)
+        .filter_map(|(language, used)| used.then_some(language))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::testing::{empty_body, empty_witness_set};
+    use pallas_codec::utils::{NonEmptySet, Nullable};
+    use pallas_primitives::PlutusScript;
+
+    fn cost_models(v3: bool) -> CostModels {
+        CostModels {
+            plutus_v1: Some(vec![1; 166]),
+            plutus_v2: Some(vec![2; 175]),
+            plutus_v3: v3.then(|| vec![3; 251]),
+        }
+    }
+
+    fn tx_with_v3_script() -> Tx {
+        let mut witnesses = empty_witness_set();
+        witnesses.plutus_v3_script = NonEmptySet::from_vec(vec![PlutusScript(vec![0x01].into())]);
+        Tx {
+            transaction_body: empty_body(),
+            transaction_witness_set: witnesses,
+            success: true,
+            auxiliary_data: Nullable::Null,
+        }
+    }
+
+    #[test]
+    fn validate_against_current_version() {
+        let tx = tx_with_v3_script();
+        let resolved = UtxoSet::new();
+        let mut store = CostModelStore::new();
+
+        assert_eq!(store.validate(&tx, &resolved), Err(CostModelError::Empty));
+
+        store.update((8, 0), cost_models(false));
+        assert_eq!(
+            store.validate(&tx, &resolved),
+            Err(CostModelError::UnsupportedLanguage(Language::PlutusV3, 8))
+        );
+
+        store.update((9, 0), cost_models(false));
+        assert_eq!(
+            store.validate(&tx, &resolved),
+            Err(CostModelError::MissingCostModel(Language::PlutusV3))
+        );
+
+        store.update((10, 0), cost_models(true));
+        assert_eq!(store.validate(&tx, &resolved), Ok(()));
+        assert_eq!(store.cost_model(&Language::PlutusV3).unwrap().len(), 251);
+        assert!(store.for_version((8, 0)).unwrap().plutus_v3.is_none());
+    }
+}
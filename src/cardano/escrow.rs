@@ -0,0 +1,292 @@
+/*!
+Two-party swap of native assets for ADA through a native script escrow.
+
+The seller locks the offered assets at the address of a native script
+that can be spent either:
+
+* by the seller and the buyer together: the swap, the buyer pays the
+  price to the seller and receives the assets;
+* by the seller alone once the deadline has passed: the refund.
+
+The buyer builds the [`swap`](SwapEscrow::swap) transaction and signs it
+(with a partial signature), the seller then adds their signature before
+submitting it.
+
+```
+# use cardano_connector::{Address, Value, cardano::escrow::SwapEscrow};
+# fn test(seller: &Address, buyer: &Address, nft: Value) -> anyhow::Result<()> {
+let escrow = SwapEscrow::new(seller, buyer, nft, 50_000_000, 90_000_000)?;
+let script_address = escrow.address();
+# Ok(()) }
+```
+
+This is also an example of how to build transactions spending script
+UTxOs with [`TxIntent::build_spending`].
+*/
+
+use super::{
+    AddrKeyhash, BuildError, BuildParams, Coin, Hash, Tx, TxIntent, Utxo, UtxoSet, Value,
+    ValueExt as _,
+    native_script::{key_address, script_address, script_hash},
+};
+use crate::Address;
+use pallas_addresses::ShelleyAddress;
+use pallas_primitives::conway::NativeScript;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum EscrowError {
+    #[error("The {0} address is not a Shelley address with a payment key")]
+    NotKeyAddress(&'static str),
+    #[error("The seller and the buyer are on different networks")]
+    NetworkMismatch,
+}
+
+/// The terms of the swap, see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapEscrow {
+    seller: ShelleyAddress,
+    buyer: ShelleyAddress,
+    /// the assets (and their min-ADA) the seller offers
+    pub offered: Value,
+    /// the lovelace the buyer pays to the seller
+    pub price: Coin,
+    /// the slot from which the seller can take the assets back
+    pub deadline: u64,
+}
+
+impl SwapEscrow {
+    pub fn new(
+        seller: &Address,
+        buyer: &Address,
+        offered: Value,
+        price: Coin,
+        deadline: u64,
+    ) -> Result<Self, EscrowError> {
+        let seller = key_address(seller).ok_or(EscrowError::NotKeyAddress("seller"))?;
+        let buyer = key_address(buyer).ok_or(EscrowError::NotKeyAddress("buyer"))?;
+        if seller.network() != buyer.network() {
+            return Err(EscrowError::NetworkMismatch);
+        }
+
+        Ok(Self {
+            seller,
+            buyer,
+            offered,
+            price,
+            deadline,
+        })
+    }
+
+    pub fn seller(&self) -> Address {
+        self.seller.clone().into()
+    }
+
+    pub fn buyer(&self) -> Address {
+        self.buyer.clone().into()
+    }
+
+    fn seller_key(&self) -> AddrKeyhash {
+        *self.seller.payment().as_hash()
+    }
+
+    fn buyer_key(&self) -> AddrKeyhash {
+        *self.buyer.payment().as_hash()
+    }
+
+    /// the native script locking the offered assets
+    pub fn script(&self) -> NativeScript {
+        NativeScript::ScriptAny(vec![
+            NativeScript::ScriptAll(vec![
+                NativeScript::ScriptPubkey(self.seller_key()),
+                NativeScript::ScriptPubkey(self.buyer_key()),
+            ]),
+            NativeScript::ScriptAll(vec![
+                NativeScript::ScriptPubkey(self.seller_key()),
+                NativeScript::InvalidBefore(self.deadline),
+            ]),
+        ])
+    }
+
+    pub fn script_hash(&self) -> Hash<28> {
+        script_hash(&self.script())
+    }
+
+    /// the address of the script, on the network of the seller
+    pub fn address(&self) -> Address {
+        script_address(&self.script(), self.seller.network())
+    }
+
+    /// the UTxO of `utxos` locking the offered assets at the script
+    /// address
+    pub fn find_locked(&self, utxos: &UtxoSet) -> Option<Utxo> {
+        let address = self.address();
+        utxos
+            .iter()
+            .find(|utxo| {
+                utxo.address().is_ok_and(|found| found == address)
+                    && utxo.value().meets_or_exceeds(&self.offered)
+            })
+            .cloned()
+    }
+
+    /// the seller locks the offered assets, spending from `utxos`
+    pub fn lock(&self, utxos: &UtxoSet, params: &BuildParams) -> Result<Tx, BuildError> {
+        TxIntent::new()
+            .pay(&self.address(), self.offered.clone())
+            .build(utxos, &self.params(params, self.seller()))
+    }
+
+    /// the buyer pays the price to the seller and receives the assets of
+    /// the `locked` UTxO, the fee is paid from `utxos`
+    ///
+    /// Both the seller and the buyer need to sign the transaction.
+    pub fn swap(
+        &self,
+        locked: &Utxo,
+        utxos: &UtxoSet,
+        params: &BuildParams,
+    ) -> Result<Tx, BuildError> {
+        // the locked assets go to the buyer with the change
+        self.unlock_intent()
+            .pay(&self.seller(), Value::Coin(self.price))
+            .with_required_signer(self.buyer_key())
+            .build_spending(
+                &vec![locked.clone()].into(),
+                utxos,
+                &self.params(params, self.buyer()),
+            )
+    }
+
+    /// the seller takes the assets of the `locked` UTxO back after the
+    /// deadline
+    ///
+    /// The `ttl` of the `params` needs to be after the deadline.
+    pub fn refund(
+        &self,
+        locked: &Utxo,
+        utxos: &UtxoSet,
+        params: &BuildParams,
+    ) -> Result<Tx, BuildError> {
+        self.unlock_intent()
+            .valid_from(self.deadline)
+            .build_spending(
+                &vec![locked.clone()].into(),
+                utxos,
+                &self.params(params, self.seller()),
+            )
+    }
+
+    fn unlock_intent(&self) -> TxIntent {
+        TxIntent::new()
+            .with_native_script(self.script())
+            .with_required_signer(self.seller_key())
+    }
+
+    fn params(&self, params: &BuildParams, change_address: Address) -> BuildParams {
+        BuildParams {
+            change_address,
+            ..params.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{
+        Multiasset, NonEmptyKeyValuePairs, TransactionInput,
+        testing::{address, params, utxo},
+    };
+    use pallas_addresses::{Network, ShelleyPaymentPart};
+
+    fn nft() -> Value {
+        Value::Multiasset(
+            2_000_000,
+            Multiasset::from_vec(vec![(
+                Hash::from([9; 28]),
+                NonEmptyKeyValuePairs::from_vec(vec![(
+                    b"nft".to_vec().into(),
+                    1.try_into().unwrap(),
+                )])
+                .unwrap(),
+            )])
+            .unwrap(),
+        )
+    }
+
+    fn escrow() -> SwapEscrow {
+        SwapEscrow::new(&address(1), &address(2), nft(), 10_000_000, 1_000).unwrap()
+    }
+
+    #[test]
+    fn script_address() {
+        let escrow = escrow();
+        let Address::Shelley(script_address) = escrow.address() else {
+            panic!("expected a shelley address");
+        };
+
+        assert_eq!(
+            script_address.payment(),
+            &ShelleyPaymentPart::script_hash(escrow.script_hash())
+        );
+        assert_eq!(script_address.network(), Network::Testnet);
+        assert!(SwapEscrow::new(&escrow.address(), &address(2), nft(), 1, 1).is_err());
+    }
+
+    #[test]
+    fn lock_and_swap() {
+        let escrow = escrow();
+        let seller_utxos = UtxoSet::from(vec![
+            utxo(1, &address(1), nft()),
+            utxo(2, &address(1), Value::Coin(5_000_000)),
+        ]);
+        let lock = escrow.lock(&seller_utxos, &params()).unwrap();
+        let locked = Utxo {
+            input: TransactionInput {
+                transaction_id: Hash::from([3; 32]),
+                index: 0,
+            },
+            output: lock.transaction_body.outputs[0].clone(),
+        };
+        assert_eq!(
+            escrow.find_locked(&vec![locked.clone()].into()),
+            Some(locked.clone())
+        );
+
+        let buyer_utxos = UtxoSet::from(vec![utxo(4, &address(2), Value::Coin(20_000_000))]);
+        let swap = escrow.swap(&locked, &buyer_utxos, &params()).unwrap();
+        let body = &swap.transaction_body;
+
+        assert!(body.inputs.contains(&locked.input));
+        assert_eq!(
+            body.outputs[0],
+            utxo(0, &escrow.seller(), Value::Coin(10_000_000)).output
+        );
+        let change = Utxo {
+            input: locked.input.clone(),
+            output: body.outputs[1].clone(),
+        };
+        assert_eq!(change.address().unwrap(), escrow.buyer());
+        assert!(change.value().meets_or_exceeds(&nft()));
+        assert_eq!(body.required_signers.as_ref().unwrap().len(), 2);
+        assert_eq!(
+            swap.transaction_witness_set.native_script.unwrap().to_vec(),
+            vec![escrow.script()]
+        );
+    }
+
+    #[test]
+    fn refund_after_deadline() {
+        let escrow = escrow();
+        let locked = utxo(3, &escrow.address(), nft());
+        let seller_utxos = UtxoSet::from(vec![utxo(2, &address(1), Value::Coin(5_000_000))]);
+
+        let refund = escrow.refund(&locked, &seller_utxos, &params()).unwrap();
+
+        assert_eq!(refund.transaction_body.validity_interval_start, Some(1_000));
+        assert_eq!(
+            refund.transaction_body.required_signers.unwrap().to_vec(),
+            vec![Hash::from([1; 28])]
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Exporting [`UtxoSet`]s for accounting and reporting.
+//!
+//! Both [`UtxoSet::to_csv`] and [`UtxoSet::to_json`] list every UTxO with
+//! its transaction id, address, lovelace and native assets. The asset
+//! quantities are formatted with the decimals of the [`TokenRegistry`];
+//! with `&()` as registry the raw quantities are written and the CSV can
+//! be read back with [`UtxoSet::from_csv`].
+
+use super::{
+    AssetId, UtxoSet, assets_of,
+    format::{NumberFormat, format_amount},
+    import::CSV_HEADER,
+    lovelace_of,
+};
+use std::collections::{BTreeMap, HashMap};
+
+/// Source of the number of decimals of the native assets.
+pub trait TokenRegistry {
+    /// `None` if the asset is unknown, its raw quantity is used
+    fn decimals(&self, asset: &AssetId) -> Option<u8>;
+}
+
+/// no registry: raw quantities
+impl TokenRegistry for () {
+    fn decimals(&self, _asset: &AssetId) -> Option<u8> {
+        None
+    }
+}
+
+impl TokenRegistry for HashMap<AssetId, u8> {
+    fn decimals(&self, asset: &AssetId) -> Option<u8> {
+        self.get(asset).copied()
+    }
+}
+
+impl TokenRegistry for BTreeMap<AssetId, u8> {
+    fn decimals(&self, asset: &AssetId) -> Option<u8> {
+        self.get(asset).copied()
+    }
+}
+
+impl<R: TokenRegistry> TokenRegistry for &R {
+    fn decimals(&self, asset: &AssetId) -> Option<u8> {
+        (*self).decimals(asset)
+    }
+}
+
+/// format the raw quantity with the given number of decimals, keeping
+/// all of them (`1500000` with 6 decimals is `1.500000`)
+///
+/// See [`format`](super::format) to render the quantities for the users.
+pub fn format_quantity(quantity: u64, decimals: u8) -> String {
+    format_amount(quantity, decimals, &NumberFormat::plain())
+}
+
+fn quantity(registry: &impl TokenRegistry, asset: &AssetId, quantity: u64) -> String {
+    match registry.decimals(asset) {
+        Some(decimals) => format_quantity(quantity, decimals),
+        None => quantity.to_string(),
+    }
+}
+
+fn address(utxo: &super::Utxo) -> String {
+    utxo.address()
+        .map(|address| address.to_string())
+        .unwrap_or_default()
+}
+
+impl UtxoSet {
+    /// export the UTxOs as CSV, one line per UTxO after the
+    /// [`CSV_HEADER`]
+    pub fn to_csv(&self, registry: &impl TokenRegistry) -> String {
+        let mut csv = format!("{CSV_HEADER}\n");
+        for utxo in self {
+            let value = utxo.value();
+            let assets = assets_of(&value)
+                .iter()
+                .map(|(asset, amount)| format!("{asset}:{}", quantity(registry, asset, *amount)))
+                .collect::<Vec<_>>()
+                .join(";");
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                utxo.transaction_id(),
+                utxo.index(),
+                address(utxo),
+                lovelace_of(&value),
+                assets
+            ));
+        }
+        csv
+    }
+
+    /// export the UTxOs as a JSON array
+    ///
+    /// The quantities are strings so they don't lose precision in
+    /// JavaScript.
+    pub fn to_json(&self, registry: &impl TokenRegistry) -> serde_json::Value {
+        self.iter()
+            .map(|utxo| {
+                let value = utxo.value();
+                let assets = assets_of(&value)
+                    .iter()
+                    .map(|(asset, amount)| {
+                        serde_json::json!({
+                            "unit": asset.to_string(),
+                            "policyId": asset.policy.to_string(),
+                            "assetName": hex::encode(asset.name.as_slice()),
+                            "quantity": quantity(registry, asset, *amount),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                serde_json::json!({
+                    "txHash": utxo.transaction_id().to_string(),
+                    "index": utxo.index(),
+                    "address": address(utxo),
+                    "lovelace": lovelace_of(&value).to_string(),
+                    "assets": assets,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::import::ImportError;
+    use pallas_addresses::{Network, ShelleyAddress, ShelleyDelegationPart, ShelleyPaymentPart};
+
+    fn utxos() -> Result<UtxoSet, ImportError> {
+        let address = crate::Address::from(ShelleyAddress::new(
+            Network::Testnet,
+            ShelleyPaymentPart::key_hash(crate::Hash::from([1; 28])),
+            ShelleyDelegationPart::Null,
+        ));
+        UtxoSet::from_csv(&format!(
+            "{},0,{},2000000,{}{}:1500\n",
+            "02".repeat(32),
+            address.to_bech32().unwrap(),
+            "04".repeat(28),
+            hex::encode("token"),
+        ))
+    }
+
+    #[test]
+    fn quantities() {
+        assert_eq!(format_quantity(1_500_000, 6), "1.500000");
+        assert_eq!(format_quantity(42, 3), "0.042");
+        assert_eq!(format_quantity(42, 0), "42");
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let utxos = utxos().unwrap();
+
+        let csv = utxos.to_csv(&());
+
+        assert_eq!(UtxoSet::from_csv(&csv).unwrap(), utxos);
+    }
+
+    #[test]
+    fn json_with_decimals() {
+        let utxos = utxos().unwrap();
+        let asset = crate::cardano::assets_of(&utxos.total())
+            .into_keys()
+            .next()
+            .unwrap();
+        let registry = BTreeMap::from([(asset, 2)]);
+
+        let json = utxos.to_json(&registry);
+
+        assert_eq!(json[0]["lovelace"], "2000000");
+        assert_eq!(json[0]["assets"][0]["quantity"], "15.00");
+        assert_eq!(json[0]["assets"][0]["assetName"], hex::encode("token"));
+    }
+}
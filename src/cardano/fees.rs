@@ -0,0 +1,270 @@
+/*!
+Transaction fees from the protocol parameters.
+
+The fee of a transaction is linear in its size (`min_fee_a` lovelace per
+byte plus a constant `min_fee_b`), plus the price of the execution units
+of its Plutus scripts. The vkey witnesses are part of the size: the fee of
+an unsigned transaction is estimated with the number of signatures it
+will carry.
+
+```
+# use cardano_connector::cardano::{Tx, fees::ProtocolParams};
+# fn test(tx: &Tx) {
+let params = ProtocolParams::MAINNET;
+// the transaction will be signed by one key
+let fee = params.estimate_fee_with_signers(tx, 1);
+# }
+```
+*/
+
+use super::{
+    BuildParams, Coin, DepositParams, LegacyValue, TransactionOutput, Tx, Value,
+    plutus::PlutusParams,
+};
+use crate::Address;
+use pallas_codec::minicbor;
+use pallas_primitives::{ExUnits, RationalNumber, conway::Redeemers};
+
+/// size of a vkey witness in the witness set: the CBOR array of the
+/// 32 bytes key and the 64 bytes signature
+pub const VKEY_WITNESS_SIZE: usize = 1 + 2 + 32 + 2 + 64;
+
+/// the constant overhead of a UTxO entry used for the min-ADA computation
+const UTXO_ENTRY_OVERHEAD: u64 = 160;
+
+/// the minimum lovelace the output needs to hold (Babbage and Conway
+/// rules)
+///
+/// The minimum is proportional to the size of the output, including the
+/// lovelace it holds: the result is the minimum the output needs once its
+/// lovelace is set to it.
+pub fn min_ada_for_output(output: &TransactionOutput, coins_per_utxo_byte: Coin) -> Coin {
+    let mut output = output.clone();
+    let mut minimum = 0;
+    loop {
+        let size = minicbor::to_vec(&output)
+            .map(|bytes| bytes.len())
+            .unwrap_or_default() as u64;
+        let required = (UTXO_ENTRY_OVERHEAD + size) * coins_per_utxo_byte;
+        // the size only grows with the lovelace, this converges
+        if required <= minimum {
+            return minimum;
+        }
+        minimum = required;
+        set_lovelace(&mut output, minimum);
+    }
+}
+
+fn set_lovelace(output: &mut TransactionOutput, lovelace: Coin) {
+    match output {
+        TransactionOutput::Legacy(output) => match &mut output.amount {
+            LegacyValue::Coin(coin) | LegacyValue::Multiasset(coin, _) => *coin = lovelace,
+        },
+        TransactionOutput::PostAlonzo(output) => match &mut output.value {
+            Value::Coin(coin) | Value::Multiasset(coin, _) => *coin = lovelace,
+        },
+    }
+}
+
+/// The protocol parameters involved in the fee and deposit computations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolParams {
+    /// lovelace per byte of the transaction
+    pub min_fee_a: Coin,
+    /// constant part of the fee
+    pub min_fee_b: Coin,
+    /// lovelace per unit of memory used by the scripts
+    pub price_mem: RationalNumber,
+    /// lovelace per CPU step used by the scripts
+    pub price_step: RationalNumber,
+    pub coins_per_utxo_byte: Coin,
+    /// the maximum size of a transaction, in bytes
+    pub max_tx_size: u64,
+    pub deposits: DepositParams,
+    /// the collateral of the transactions running scripts, in percent
+    /// of their fee
+    pub collateral_percentage: u64,
+}
+
+impl ProtocolParams {
+    /// the current parameters of mainnet (and of the public test
+    /// networks)
+    pub const MAINNET: Self = Self {
+        min_fee_a: 44,
+        min_fee_b: 155_381,
+        price_mem: RationalNumber {
+            numerator: 577,
+            denominator: 10_000,
+        },
+        price_step: RationalNumber {
+            numerator: 721,
+            denominator: 10_000_000,
+        },
+        coins_per_utxo_byte: 4_310,
+        max_tx_size: 16_384,
+        deposits: DepositParams {
+            key_deposit: 2_000_000,
+            pool_deposit: 500_000_000,
+        },
+        collateral_percentage: 150,
+    };
+
+    /// the fee of a transaction of `size` bytes, without scripts
+    pub fn estimate_fee_for_size(&self, size: usize) -> Coin {
+        self.min_fee_a * size as u64 + self.min_fee_b
+    }
+
+    /// the price of the execution units, rounded up
+    pub fn script_fee(&self, ex_units: &ExUnits) -> Coin {
+        script_fee(ex_units, &self.price_mem, &self.price_step)
+    }
+
+    /// the fee of the transaction as it is, with the witnesses it
+    /// already holds
+    pub fn estimate_fee(&self, tx: &Tx) -> Coin {
+        self.estimate_fee_with_signers(tx, 0)
+    }
+
+    /// the fee of the transaction once `signers` more vkey witnesses are
+    /// added to it
+    pub fn estimate_fee_with_signers(&self, tx: &Tx, signers: usize) -> Coin {
+        let size = minicbor::to_vec(tx)
+            .map(|bytes| bytes.len())
+            .unwrap_or_default()
+            + signers * VKEY_WITNESS_SIZE;
+
+        let ex_units: Vec<ExUnits> = match &tx.transaction_witness_set.redeemer {
+            None => Vec::new(),
+            Some(Redeemers::List(redeemers)) => {
+                redeemers.iter().map(|redeemer| redeemer.ex_units).collect()
+            }
+            Some(Redeemers::Map(redeemers)) => {
+                redeemers.iter().map(|(_, value)| value.ex_units).collect()
+            }
+        };
+        let scripts = ex_units
+            .iter()
+            .map(|ex_units| self.script_fee(ex_units))
+            .fold(0, Coin::saturating_add);
+
+        self.estimate_fee_for_size(size).saturating_add(scripts)
+    }
+
+    /// the parameters to build a [`TxIntent`](super::TxIntent), without
+    /// TTL
+    pub fn build_params(&self, change_address: Address) -> BuildParams {
+        BuildParams {
+            min_fee_a: self.min_fee_a,
+            min_fee_b: self.min_fee_b,
+            coins_per_utxo_byte: self.coins_per_utxo_byte,
+            deposits: self.deposits,
+            change_address,
+            ttl: None,
+            plutus: PlutusParams {
+                price_mem: self.price_mem.clone(),
+                price_step: self.price_step.clone(),
+                collateral_percentage: self.collateral_percentage,
+                ..PlutusParams::default()
+            },
+        }
+    }
+}
+
+/// the price of the execution units, rounded up
+pub(crate) fn script_fee(
+    ex_units: &ExUnits,
+    price_mem: &RationalNumber,
+    price_step: &RationalNumber,
+) -> Coin {
+    let price = |units: u64, price: &RationalNumber| {
+        (u128::from(units) * u128::from(price.numerator))
+            .div_ceil(u128::from(price.denominator.max(1)))
+    };
+    let fee = price(ex_units.mem, price_mem) + price(ex_units.steps, price_step);
+    fee.try_into().unwrap_or(Coin::MAX)
+}
+
+impl Default for ProtocolParams {
+    fn default() -> Self {
+        Self::MAINNET
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::testing::{empty_body, empty_witness_set};
+    use pallas_codec::utils::{MaybeIndefArray, Nullable};
+    use pallas_primitives::{
+        PlutusData,
+        babbage::PseudoPostAlonzoTransactionOutput,
+        conway::{Redeemer, RedeemerTag},
+    };
+
+    fn tx() -> Tx {
+        Tx {
+            transaction_body: empty_body(),
+            transaction_witness_set: empty_witness_set(),
+            success: true,
+            auxiliary_data: Nullable::Null,
+        }
+    }
+
+    #[test]
+    fn fee_of_size() {
+        let params = ProtocolParams::MAINNET;
+
+        assert_eq!(params.estimate_fee_for_size(0), 155_381);
+        assert_eq!(params.estimate_fee_for_size(200), 164_181);
+
+        let size = minicbor::to_vec(tx()).unwrap().len();
+        assert_eq!(
+            params.estimate_fee_with_signers(&tx(), 2),
+            params.estimate_fee_for_size(size + 2 * VKEY_WITNESS_SIZE)
+        );
+    }
+
+    #[test]
+    fn min_ada() {
+        let output = |coin| {
+            TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+                address: vec![0x61; 29].into(),
+                value: Value::Coin(coin),
+                datum_option: None,
+                script_ref: None,
+            })
+        };
+
+        let minimum = min_ada_for_output(&output(0), 4_310);
+
+        let size = minicbor::to_vec(output(minimum)).unwrap().len() as u64;
+        assert_eq!(minimum, (160 + size) * 4_310);
+        assert_eq!(min_ada_for_output(&output(minimum), 4_310), minimum);
+        assert_eq!(min_ada_for_output(&output(100_000_000), 4_310), minimum);
+    }
+
+    #[test]
+    fn fee_of_scripts() {
+        let params = ProtocolParams::MAINNET;
+        let ex_units = ExUnits {
+            mem: 1_000_000,
+            steps: 500_000_000,
+        };
+        // 57_700 for the memory, 36_050 for the steps
+        assert_eq!(params.script_fee(&ex_units), 93_750);
+
+        let mut tx = tx();
+        tx.transaction_witness_set.redeemer =
+            Some(Redeemers::List(MaybeIndefArray::Def(vec![Redeemer {
+                tag: RedeemerTag::Spend,
+                index: 0,
+                data: PlutusData::Array(MaybeIndefArray::Def(Vec::new())),
+                ex_units,
+            }])));
+        let size = minicbor::to_vec(&tx).unwrap().len();
+        assert_eq!(
+            params.estimate_fee(&tx),
+            params.estimate_fee_for_size(size) + 93_750
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Rendering of the amounts for the users.
+//!
+//! The amounts are split in their integer and fractional digits, then
+//! given to an [`AmountFormatter`]: a [`NumberFormat`] (thousands and
+//! decimal separators) or any closure, e.g. one calling the browser's
+//! `Intl.NumberFormat`.
+//!
+//! ```
+//! # use cardano_connector::cardano::format::{NumberFormat, format_ada, format_amount};
+//! assert_eq!(format_ada(1_234_567_890, &NumberFormat::en()), "1,234.56789");
+//! assert_eq!(format_ada(1_234_567_890, &NumberFormat::de()), "1.234,56789");
+//!
+//! let custom = |integer: &str, fraction: &str| format!("{integer}'{fraction}");
+//! assert_eq!(format_amount(1_500, 3, &custom), "1'500");
+//! ```
+
+/// decimals of the lovelace in an ADA
+pub const ADA_DECIMALS: u8 = 6;
+
+/// Render the digits of an amount.
+pub trait AmountFormatter {
+    /// `integer` has at least one digit, `fraction` has exactly the
+    /// decimals of the amount (possibly none)
+    fn format(&self, integer: &str, fraction: &str) -> String;
+}
+
+impl<F: Fn(&str, &str) -> String> AmountFormatter for F {
+    fn format(&self, integer: &str, fraction: &str) -> String {
+        self(integer, fraction)
+    }
+}
+
+/// Separators of a locale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    /// between the groups of three integer digits, none if `None`
+    pub thousands_separator: Option<char>,
+    pub decimal_separator: char,
+    /// drop the trailing zeros of the fraction (`1.5` instead of
+    /// `1.500000`)
+    pub trim_zeros: bool,
+}
+
+impl NumberFormat {
+    /// `1234.500000`, as written by [`format_quantity`](super::export::format_quantity)
+    pub const fn plain() -> Self {
+        Self {
+            thousands_separator: None,
+            decimal_separator: '.',
+            trim_zeros: false,
+        }
+    }
+
+    /// `1,234.5`
+    pub const fn en() -> Self {
+        Self {
+            thousands_separator: Some(','),
+            decimal_separator: '.',
+            trim_zeros: true,
+        }
+    }
+
+    /// `1.234,5`
+    pub const fn de() -> Self {
+        Self {
+            thousands_separator: Some('.'),
+            decimal_separator: ',',
+            trim_zeros: true,
+        }
+    }
+
+    /// `1 234,5`, with a narrow no-break space
+    pub const fn fr() -> Self {
+        Self {
+            thousands_separator: Some('\u{202f}'),
+            decimal_separator: ',',
+            trim_zeros: true,
+        }
+    }
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self::plain()
+    }
+}
+
+impl AmountFormatter for NumberFormat {
+    fn format(&self, integer: &str, fraction: &str) -> String {
+        let mut formatted = String::with_capacity(integer.len() * 2 + fraction.len() + 1);
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index).is_multiple_of(3) {
+                formatted.extend(self.thousands_separator);
+            }
+            formatted.push(digit);
+        }
+
+        let fraction = if self.trim_zeros {
+            fraction.trim_end_matches('0')
+        } else {
+            fraction
+        };
+        if !fraction.is_empty() {
+            formatted.push(self.decimal_separator);
+            formatted.push_str(fraction);
+        }
+        formatted
+    }
+}
+
+/// render the raw `quantity` of an asset with `decimals` decimals
+pub fn format_amount(quantity: u64, decimals: u8, formatter: &impl AmountFormatter) -> String {
+    let digits = format!("{quantity:0>width$}", width = usize::from(decimals) + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - usize::from(decimals));
+    formatter.format(integer, fraction)
+}
+
+/// render the lovelace in ADA
+pub fn format_ada(lovelace: u64, formatter: &impl AmountFormatter) -> String {
+    format_amount(lovelace, ADA_DECIMALS, formatter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separators() {
+        assert_eq!(format_ada(1_500_000, &NumberFormat::plain()), "1.500000");
+        assert_eq!(format_ada(42, &NumberFormat::en()), "0.000042");
+        assert_eq!(format_ada(1_000_000_000, &NumberFormat::en()), "1,000");
+        assert_eq!(
+            format_ada(123_456_789_000_000, &NumberFormat::fr()),
+            "123\u{202f}456\u{202f}789"
+        );
+        assert_eq!(
+            format_amount(1_234_567, 0, &NumberFormat::de()),
+            "1.234.567"
+        );
+        assert_eq!(format_amount(0, 0, &NumberFormat::en()), "0");
+    }
+}
@@ -1,25 +1,84 @@
-use cbored::CborRepr;
-use std::fmt;
+//! Parsing and encoding of the [`Hash`] used across the crate.
+//!
+//! There is a single hash type: `Hash<N>` from pallas, re-exported as
+//! [`crate::Hash`]. It already displays (and serializes with serde) as
+//! hex, [`HashExt`] adds the bech32 representation used for the pool ids,
+//! DRep ids and so on.
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, CborRepr)]
-pub struct Hash([u8; 32]);
+use bech32::{FromBase32 as _, ToBase32 as _, Variant};
+pub use pallas_crypto::hash::Hash;
 
-impl fmt::Debug for Hash {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_tuple("Hash")
-            .field(&hex::encode(self.as_ref()))
-            .finish()
+#[derive(Debug, thiserror::Error)]
+pub enum HashError {
+    #[error("Invalid hex: {0}")]
+    Hex(#[from] hex::FromHexError),
+    #[error("Invalid bech32: {0}")]
+    Bech32(#[from] bech32::Error),
+    #[error("Invalid hash length, expected {expected} bytes but got {found}")]
+    Length { expected: usize, found: usize },
+}
+
+pub trait HashExt: Sized {
+    /// build the hash from a slice of the exact expected length
+    fn try_from_slice(bytes: &[u8]) -> Result<Self, HashError>;
+
+    fn from_hex(hex: &str) -> Result<Self, HashError> {
+        Self::try_from_slice(&hex::decode(hex)?)
     }
+
+    /// decode a bech32 encoded hash, returning the prefix with the hash
+    fn from_bech32(bech32: &str) -> Result<(String, Self), HashError>;
+
+    /// decode either a hex or a bech32 encoded hash
+    fn parse(value: &str) -> Result<Self, HashError> {
+        match hex::decode(value) {
+            Ok(bytes) => Self::try_from_slice(&bytes),
+            Err(_) => Self::from_bech32(value).map(|(_, hash)| hash),
+        }
+    }
+
+    fn to_bech32(&self, hrp: &str) -> Result<String, HashError>;
 }
 
-impl fmt::Display for Hash {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", hex::encode(self.as_ref()))
+impl<const N: usize> HashExt for Hash<N> {
+    fn try_from_slice(bytes: &[u8]) -> Result<Self, HashError> {
+        <[u8; N]>::try_from(bytes)
+            .map(Hash::new)
+            .map_err(|_| HashError::Length {
+                expected: N,
+                found: bytes.len(),
+            })
+    }
+
+    fn from_bech32(bech32: &str) -> Result<(String, Self), HashError> {
+        let (hrp, data, _) = bech32::decode(bech32)?;
+        let bytes = Vec::<u8>::from_base32(&data)?;
+        Ok((hrp, Self::try_from_slice(&bytes)?))
+    }
+
+    fn to_bech32(&self, hrp: &str) -> Result<String, HashError> {
+        Ok(bech32::encode(hrp, self.to_base32(), Variant::Bech32)?)
     }
 }
 
-impl AsRef<[u8]> for Hash {
-    fn as_ref(&self) -> &[u8] {
-        &self.0
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_and_bech32() {
+        let pool = Hash::<28>::new([1; 28]);
+        let bech32 = pool.to_bech32("pool").unwrap();
+
+        assert_eq!(
+            Hash::<28>::from_bech32(&bech32).unwrap(),
+            ("pool".to_owned(), pool)
+        );
+        assert_eq!(Hash::<28>::parse(&bech32).unwrap(), pool);
+        assert_eq!(Hash::<28>::parse(&pool.to_string()).unwrap(), pool);
+        assert!(matches!(
+            Hash::<32>::parse(&pool.to_string()),
+            Err(HashError::Length { .. })
+        ));
     }
 }
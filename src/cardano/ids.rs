@@ -0,0 +1,296 @@
+//! Identifiers that dApps commonly receive as text (user input, config
+//! files, indexers' responses) and their parsing.
+//!
+//! All of them implement [`FromStr`] accepting either the hex or the
+//! bech32 representation. `Hash<N>` (and so [`PolicyId`]) and [`Address`]
+//! already implement [`FromStr`], see also [`HashExt::parse`] to accept
+//! bech32 encoded hashes.
+//!
+//! [`Address`]: crate::Address
+//! [`HashExt::parse`]: super::HashExt::parse
+
+use super::{AssetName, Hash, HashError, HashExt as _, PolicyId, StakeCredential};
+use crate::network::Prefixes;
+use bech32::FromBase32 as _;
+use pallas_crypto::hash::Hasher;
+use std::{fmt, str::FromStr};
+
+/// CIP-129 header of a DRep key hash credential
+const DREP_KEY_HEADER: u8 = 0x22;
+/// CIP-129 header of a DRep script hash credential
+const DREP_SCRIPT_HEADER: u8 = 0x23;
+
+#[derive(Debug, thiserror::Error)]
+pub enum IdError {
+    #[error(transparent)]
+    Hash(#[from] HashError),
+    #[error("Invalid hex: {0}")]
+    Hex(#[from] hex::FromHexError),
+    #[error("Unexpected prefix `{found}', expected `{expected}'")]
+    UnexpectedPrefix { found: String, expected: String },
+    #[error("Invalid asset name length {0}, expected at most 32 bytes")]
+    AssetNameLength(usize),
+    #[error("Invalid DRep id header {0:#04x}")]
+    DRepHeader(u8),
+}
+
+/// A native asset: the policy id and the asset name.
+///
+/// Parsed from the concatenated hex of the policy id and the asset name
+/// (the `unit` of most indexers), optionally separated with a `.`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AssetId {
+    pub policy: PolicyId,
+    pub name: AssetName,
+}
+
+impl AssetId {
+    pub fn new(policy: PolicyId, name: AssetName) -> Self {
+        Self { policy, name }
+    }
+
+    /// the CIP-14 fingerprint (`asset1...`), as displayed by the
+    /// explorers and wallets
+    pub fn fingerprint(&self) -> AssetFingerprint {
+        let mut hasher = Hasher::<160>::new();
+        hasher.input(self.policy.as_slice());
+        hasher.input(self.name.as_slice());
+        AssetFingerprint(hasher.finalize())
+    }
+}
+
+impl fmt::Display for AssetId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.policy, hex::encode(self.name.as_slice()))
+    }
+}
+
+impl FromStr for AssetId {
+    type Err = IdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (policy, name) = match s.split_once('.') {
+            Some(split) => split,
+            None => s.split_at_checked(s.len().min(56)).ok_or_else(|| {
+                // the policy id would end inside a multi-byte character
+                let (index, c) = s
+                    .char_indices()
+                    .find(|(_, c)| !c.is_ascii())
+                    .unwrap_or_default();
+                IdError::Hex(hex::FromHexError::InvalidHexCharacter { c, index })
+            })?,
+        };
+        let policy = PolicyId::from_hex(policy)?;
+        let name = hex::decode(name)?;
+        if name.len() > 32 {
+            return Err(IdError::AssetNameLength(name.len()));
+        }
+        Ok(Self::new(policy, name.into()))
+    }
+}
+
+/// The CIP-14 fingerprint of an [`AssetId`], displayed in bech32
+/// (`asset1...`).
+///
+/// This is a hash: the [`AssetId`] can't be recovered from it, but
+/// [`AssetFingerprint::matches`] tells whether an asset has this
+/// fingerprint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AssetFingerprint(pub Hash<20>);
+
+impl AssetFingerprint {
+    pub fn matches(&self, asset: &AssetId) -> bool {
+        asset.fingerprint() == *self
+    }
+}
+
+impl fmt::Display for AssetFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match Prefixes::mainnet().encode_asset(self.0.as_slice()) {
+            Ok(bech32) => f.write_str(&bech32),
+            Err(_) => write!(f, "{}", self.0),
+        }
+    }
+}
+
+impl FromStr for AssetFingerprint {
+    type Err = IdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expected = Prefixes::mainnet().asset;
+        let (hrp, hash) = Hash::from_bech32(s)?;
+        if hrp != expected {
+            return Err(IdError::UnexpectedPrefix {
+                found: hrp,
+                expected,
+            });
+        }
+        Ok(Self(hash))
+    }
+}
+
+/// Identifier of a stake pool, displayed in bech32 (`pool1...`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PoolId(pub Hash<28>);
+
+impl fmt::Display for PoolId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match Prefixes::mainnet().encode_pool(self.0.as_slice()) {
+            Ok(bech32) => f.write_str(&bech32),
+            Err(_) => write!(f, "{}", self.0),
+        }
+    }
+}
+
+impl FromStr for PoolId {
+    type Err = IdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(hash) = Hash::from_hex(s) {
+            return Ok(Self(hash));
+        }
+        let expected = Prefixes::mainnet().pool;
+        let (hrp, hash) = Hash::from_bech32(s)?;
+        if hrp != expected {
+            return Err(IdError::UnexpectedPrefix {
+                found: hrp,
+                expected,
+            });
+        }
+        Ok(Self(hash))
+    }
+}
+
+/// Identifier of a DRep, displayed in the CIP-129 bech32 format.
+///
+/// Parsing also accepts the legacy CIP-105 format (`drep1...` for key
+/// hashes and `drep_script1...` for script hashes) and hex.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DRepId(pub StakeCredential);
+
+impl DRepId {
+    /// the CIP-129 binary representation: header byte and hash
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (header, hash) = match &self.0 {
+            StakeCredential::AddrKeyhash(hash) => (DREP_KEY_HEADER, hash),
+            StakeCredential::ScriptHash(hash) => (DREP_SCRIPT_HEADER, hash),
+        };
+        std::iter::once(header)
+            .chain(hash.iter().copied())
+            .collect()
+    }
+
+    fn from_bytes(bytes: &[u8], script: bool) -> Result<Self, IdError> {
+        match bytes {
+            [DREP_KEY_HEADER, hash @ ..] if hash.len() == 28 => Ok(Self(
+                StakeCredential::AddrKeyhash(Hash::try_from_slice(hash)?),
+            )),
+            [DREP_SCRIPT_HEADER, hash @ ..] if hash.len() == 28 => Ok(Self(
+                StakeCredential::ScriptHash(Hash::try_from_slice(hash)?),
+            )),
+            [header, hash @ ..] if hash.len() == 28 => Err(IdError::DRepHeader(*header)),
+            hash if script => Ok(Self(StakeCredential::ScriptHash(Hash::try_from_slice(
+                hash,
+            )?))),
+            hash => Ok(Self(StakeCredential::AddrKeyhash(Hash::try_from_slice(
+                hash,
+            )?))),
+        }
+    }
+}
+
+impl fmt::Display for DRepId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match Prefixes::mainnet().encode_drep(&self.to_bytes()) {
+            Ok(bech32) => f.write_str(&bech32),
+            Err(_) => f.write_str(&hex::encode(self.to_bytes())),
+        }
+    }
+}
+
+impl FromStr for DRepId {
+    type Err = IdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(bytes) = hex::decode(s) {
+            return Self::from_bytes(&bytes, false);
+        }
+
+        let drep = Prefixes::mainnet().drep;
+        let script = format!("{drep}_script");
+        let (hrp, data, _) = bech32::decode(s).map_err(HashError::from)?;
+        let bytes = Vec::<u8>::from_base32(&data).map_err(HashError::from)?;
+        if hrp == drep {
+            Self::from_bytes(&bytes, false)
+        } else if hrp == script {
+            Self::from_bytes(&bytes, true)
+        } else {
+            Err(IdError::UnexpectedPrefix {
+                found: hrp,
+                expected: drep,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_asset_id() {
+        let unit = format!("{}{}", "01".repeat(28), hex::encode("token"));
+        let asset: AssetId = unit.parse().unwrap();
+
+        assert_eq!(asset.policy, Hash::from([1; 28]));
+        assert!(matches!(
+            format!("{}é", "0".repeat(55)).parse::<AssetId>(),
+            Err(IdError::Hex(hex::FromHexError::InvalidHexCharacter {
+                c: 'é',
+                index: 55
+            }))
+        ));
+        assert_eq!(asset.name.as_slice(), b"token");
+        assert_eq!(asset.to_string(), unit);
+        let dotted = format!("{}.{}", "01".repeat(28), hex::encode("token"));
+        assert_eq!(dotted.parse::<AssetId>().unwrap(), asset);
+    }
+
+    #[test]
+    fn asset_fingerprints() {
+        let policy = "7eae28af2208be856f7a119668ae52a49b73725e326dc16579dcc373";
+        let asset: AssetId = policy.parse().unwrap();
+        assert_eq!(
+            asset.fingerprint().to_string(),
+            "asset1rjklcrnsdzqp65wjgrg55sy9723kw09mlgvlc3"
+        );
+
+        let asset: AssetId = format!("{policy}504154415445").parse().unwrap();
+        let fingerprint: AssetFingerprint = "asset13n25uv0yaf5kus35fm2k86cqy60z58d9xmde92"
+            .parse()
+            .unwrap();
+        assert!(fingerprint.matches(&asset));
+        assert_eq!(asset.fingerprint(), fingerprint);
+
+        assert!(matches!(
+            PoolId(Hash::from([2; 28]))
+                .to_string()
+                .parse::<AssetFingerprint>(),
+            Err(IdError::Hash(_) | IdError::UnexpectedPrefix { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_pool_and_drep_ids() {
+        let pool = PoolId(Hash::from([2; 28]));
+        assert!(pool.to_string().starts_with("pool1"));
+        assert_eq!(pool.to_string().parse::<PoolId>().unwrap(), pool);
+        assert_eq!(pool.0.to_string().parse::<PoolId>().unwrap(), pool);
+
+        let drep = DRepId(StakeCredential::ScriptHash(Hash::from([3; 28])));
+        assert!(drep.to_string().starts_with("drep1"));
+        assert_eq!(drep.to_string().parse::<DRepId>().unwrap(), drep);
+        let legacy = Hash::<28>::from([3; 28]).to_bech32("drep_script").unwrap();
+        assert_eq!(legacy.parse::<DRepId>().unwrap(), drep);
+    }
+}
@@ -0,0 +1,303 @@
+//! Loading [`UtxoSet`]s from outside of the wallet.
+//!
+//! The builders and the coin selection only need a [`UtxoSet`]: it can
+//! come from the CIP-30 `getUtxos` but also from `cardano-cli` (see
+//! [`UtxoSet::from_cli_json`]), an indexer export in CSV (see
+//! [`UtxoSet::from_csv`]) or raw CBOR (see [`UtxoSet::from_cbor`]).
+
+use super::{
+    AssetId, Coin, HashError, HashExt as _, TransactionInput, TransactionOutput, TxHash, Utxo,
+    UtxoSet, Value, WalletValue, ids::IdError,
+};
+use crate::Address;
+use pallas_codec::{minicbor, utils::CborWrap};
+use pallas_primitives::{
+    PlutusData, babbage::PseudoPostAlonzoTransactionOutput, conway::DatumOption,
+};
+use std::collections::BTreeMap;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid CBOR: {0}")]
+    Cbor(#[from] minicbor::decode::Error),
+    #[error("Invalid hex: {0}")]
+    Hex(#[from] hex::FromHexError),
+    #[error("Invalid UTxO `{utxo}': {reason}")]
+    Utxo { utxo: String, reason: String },
+    #[error("Invalid CSV line {line}: {reason}")]
+    Csv { line: usize, reason: String },
+}
+
+/// the header of the CSV format, see [`UtxoSet::from_csv`]
+pub const CSV_HEADER: &str = "tx_hash,index,address,lovelace,assets";
+
+/// An entry of the `cardano-cli query utxo --output-json` output.
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CliOutput {
+    address: String,
+    value: BTreeMap<String, serde_json::Value>,
+    #[serde(default, rename = "datumhash")]
+    datum_hash: Option<String>,
+    #[serde(default)]
+    inline_datum_raw: Option<String>,
+}
+
+impl UtxoSet {
+    /// load the output of `cardano-cli query utxo --output-json`
+    ///
+    /// The address, the value and the datum (hash or inline) of the
+    /// outputs are kept, reference scripts are not.
+    pub fn from_cli_json(json: &str) -> Result<Self, ImportError> {
+        let entries: BTreeMap<String, CliOutput> = serde_json::from_str(json)?;
+
+        entries
+            .into_iter()
+            .map(|(reference, output)| {
+                let invalid = |reason: String| ImportError::Utxo {
+                    utxo: reference.clone(),
+                    reason,
+                };
+                let input = parse_input(&reference).map_err(invalid)?;
+                let mut coin = 0;
+                let mut assets = BTreeMap::new();
+                for (policy, quantity) in output.value {
+                    if policy == "lovelace" {
+                        coin = quantity
+                            .as_u64()
+                            .ok_or_else(|| invalid(format!("invalid lovelace {quantity}")))?;
+                        continue;
+                    }
+                    let names = quantity
+                        .as_object()
+                        .ok_or_else(|| invalid(format!("invalid assets of {policy}")))?;
+                    for (name, quantity) in names {
+                        let asset = format!("{policy}.{name}")
+                            .parse::<AssetId>()
+                            .map_err(|error| invalid(error.to_string()))?;
+                        let quantity = quantity
+                            .as_u64()
+                            .ok_or_else(|| invalid(format!("invalid quantity of {asset}")))?;
+                        assets.insert(asset, quantity);
+                    }
+                }
+
+                let datum_option = match (output.datum_hash, output.inline_datum_raw) {
+                    (_, Some(raw)) => {
+                        let data: PlutusData = minicbor::decode(&hex::decode(raw)?)?;
+                        Some(DatumOption::Data(CborWrap(data)))
+                    }
+                    (Some(hash), None) => Some(DatumOption::Hash(
+                        TxHash::from_hex(&hash).map_err(|error| invalid(error.to_string()))?,
+                    )),
+                    (None, None) => None,
+                };
+
+                let address = output
+                    .address
+                    .parse::<Address>()
+                    .map_err(|error| invalid(error.to_string()))?;
+                Ok(utxo(input, &address, value(coin, assets), datum_option))
+            })
+            .collect()
+    }
+
+    /// load UTxOs from CSV, one UTxO per line in the [`CSV_HEADER`]
+    /// format
+    ///
+    /// The address is in bech32 (or base58 for Byron), the assets are
+    /// separated by `;` and given as `unit:quantity` (see [`AssetId`]).
+    /// The header line is optional.
+    pub fn from_csv(csv: &str) -> Result<Self, ImportError> {
+        csv.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && line.trim() != CSV_HEADER)
+            .map(|(index, line)| {
+                parse_csv_line(line.trim()).map_err(|reason| ImportError::Csv {
+                    line: index + 1,
+                    reason,
+                })
+            })
+            .collect()
+    }
+
+    /// load the CBOR array of `[input, output]` pairs
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self, ImportError> {
+        Ok(Self::from(minicbor::decode::<Vec<Utxo>>(bytes)?))
+    }
+
+    /// load the hex encoded CBOR UTxOs as returned by CIP-30 `getUtxos`
+    pub fn from_cbor_hex<'a>(
+        utxos: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self, ImportError> {
+        utxos
+            .into_iter()
+            .map(|utxo| Ok(minicbor::decode(&hex::decode(utxo)?)?))
+            .collect()
+    }
+}
+
+/// parse a `tx_hash#index` reference
+fn parse_input(reference: &str) -> Result<TransactionInput, String> {
+    let (hash, index) = reference
+        .split_once('#')
+        .ok_or_else(|| format!("expected `tx_hash#index', got `{reference}'"))?;
+    Ok(TransactionInput {
+        transaction_id: TxHash::from_hex(hash).map_err(|error: HashError| error.to_string())?,
+        index: index
+            .parse()
+            .map_err(|_| format!("invalid index `{index}'"))?,
+    })
+}
+
+fn parse_csv_line(line: &str) -> Result<Utxo, String> {
+    let fields: Vec<_> = line.split(',').map(str::trim).collect();
+    let [hash, index, address, lovelace, rest @ ..] = fields.as_slice() else {
+        return Err(format!("expected the columns `{CSV_HEADER}'"));
+    };
+
+    let input = parse_input(&format!("{hash}#{index}"))?;
+    let address = address
+        .parse::<Address>()
+        .map_err(|error| error.to_string())?;
+    let coin = lovelace
+        .parse::<Coin>()
+        .map_err(|_| format!("invalid lovelace `{lovelace}'"))?;
+    let assets = rest
+        .first()
+        .map(|assets| {
+            assets
+                .split(';')
+                .filter(|asset| !asset.is_empty())
+                .map(|asset| {
+                    let (unit, quantity) = asset
+                        .split_once(':')
+                        .ok_or_else(|| format!("expected `unit:quantity', got `{asset}'"))?;
+                    let unit = unit.parse().map_err(|error: IdError| error.to_string())?;
+                    let quantity = quantity
+                        .parse()
+                        .map_err(|_| format!("invalid quantity `{quantity}'"))?;
+                    Ok((unit, quantity))
+                })
+                .collect::<Result<BTreeMap<_, _>, String>>()
+        })
+        .transpose()?
+        .unwrap_or_default();
+
+    Ok(utxo(input, &address, value(coin, assets), None))
+}
+
+fn utxo(
+    input: TransactionInput,
+    address: &Address,
+    value: Value,
+    datum_option: Option<DatumOption>,
+) -> Utxo {
+    Utxo {
+        input,
+        output: TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+            address: address.to_vec().into(),
+            value,
+            datum_option,
+            script_ref: None,
+        }),
+    }
+}
+
+/// build the [`Value`], dropping the assets with a `0` quantity
+fn value(coin: Coin, assets: BTreeMap<AssetId, u64>) -> Value {
+    assets
+        .into_iter()
+        .fold(
+            WalletValue::from_lovelace(coin),
+            |value, (asset, quantity)| value.with_asset(asset, quantity),
+        )
+        .to_value()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Hash, lovelace_of};
+    use pallas_addresses::{Network, ShelleyAddress, ShelleyDelegationPart, ShelleyPaymentPart};
+
+    fn address() -> Address {
+        ShelleyAddress::new(
+            Network::Testnet,
+            ShelleyPaymentPart::key_hash(Hash::from([1; 28])),
+            ShelleyDelegationPart::Null,
+        )
+        .into()
+    }
+
+    #[test]
+    fn import_cli_json() {
+        let json = serde_json::json!({
+            format!("{}#1", "02".repeat(32)): {
+                "address": address().to_bech32().unwrap(),
+                "datumhash": "03".repeat(32),
+                "value": {
+                    "lovelace": 1_500_000,
+                    "04".repeat(28): { hex::encode("token"): 7 },
+                },
+            },
+        });
+
+        let utxos = UtxoSet::from_cli_json(&json.to_string()).unwrap();
+
+        let [utxo] = utxos.as_slice() else {
+            panic!("expected one UTxO");
+        };
+        assert_eq!(utxo.input.index, 1);
+        assert_eq!(utxo.address().unwrap(), address());
+        assert_eq!(lovelace_of(&utxo.value()), 1_500_000);
+        assert_eq!(
+            super::super::assets_of(&utxo.value())
+                .into_values()
+                .sum::<u64>(),
+            7
+        );
+    }
+
+    #[test]
+    fn import_csv() {
+        let unit = format!("{}{}", "04".repeat(28), hex::encode("token"));
+        let csv = format!(
+            "{CSV_HEADER}\n{hash},0,{address},2000000,{unit}:3\n{hash},1,{address},1000000,\n",
+            hash = "02".repeat(32),
+            address = address().to_bech32().unwrap(),
+        );
+
+        let utxos = UtxoSet::from_csv(&csv).unwrap();
+
+        assert_eq!(utxos.len(), 2);
+        assert_eq!(lovelace_of(&utxos.total()), 3_000_000);
+        assert!(matches!(
+            UtxoSet::from_csv("not,a,utxo"),
+            Err(ImportError::Csv { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn import_cbor() {
+        let utxo = utxo(
+            parse_input(&format!("{}#0", "02".repeat(32))).unwrap(),
+            &address(),
+            Value::Coin(1),
+            None,
+        );
+        let cbor = minicbor::to_vec(vec![&utxo]).unwrap();
+        let hex = hex::encode(minicbor::to_vec(&utxo).unwrap());
+
+        assert_eq!(
+            UtxoSet::from_cbor(&cbor).unwrap().into_vec(),
+            vec![utxo.clone()]
+        );
+        assert_eq!(
+            UtxoSet::from_cbor_hex([hex.as_str()]).unwrap().into_vec(),
+            vec![utxo]
+        );
+    }
+}
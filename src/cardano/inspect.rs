@@ -0,0 +1,496 @@
+/*!
+Decoding of transactions produced in any of the eras a wallet may still be
+asked to co-sign.
+
+The tooling of this crate (e.g. [`TxSummary`]) works on the Conway
+representation of the transactions. However a lot of JS libraries that
+dApps are still using produce Alonzo or Babbage shaped transactions
+(empty sets, `0` quantities, legacy redeemers...) which are rejected by
+the strict Conway decoder. [`decode_tx`] accepts those and converts them
+into a Conway [`Tx`].
+
+[`TxSummary`]: super::TxSummary
+*/
+
+use super::{
+    Certificate, Multiasset, NonEmptyKeyValuePairs, TransactionOutput, Tx, TxHash,
+    value_from_legacy,
+};
+use pallas_codec::{
+    minicbor,
+    utils::{MaybeIndefArray, NonEmptySet, NonZeroInt, Set},
+};
+use pallas_primitives::{alonzo, babbage, conway};
+
+/// the era in which the decoded transaction was encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Era {
+    Alonzo,
+    Babbage,
+    Conway,
+}
+
+#[derive(Debug, Clone)]
+pub struct DecodedTx {
+    pub era: Era,
+    pub tx: Tx,
+    /// the hash of the body as encoded in the transaction, see
+    /// [`tx_hash`]
+    pub hash: TxHash,
+    /// the protocol parameters update proposed by the body, a field
+    /// removed in Conway so absent from [`tx`](Self::tx)
+    pub update: Option<babbage::Update>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InspectError {
+    #[error("Invalid transaction (conway: {conway}, babbage: {babbage})")]
+    Decode {
+        conway: Box<minicbor::decode::Error>,
+        babbage: Box<minicbor::decode::Error>,
+    },
+    #[error("The {0} certificate is not supported since Conway")]
+    UnsupportedCertificate(&'static str),
+    #[error("Invalid transaction body: {0}")]
+    Body(Box<minicbor::decode::Error>),
+}
+
+/// decode a CBOR transaction of the Alonzo, Babbage or Conway era
+///
+/// The transaction is converted into its Conway representation.
+pub fn decode_tx(bytes: &[u8]) -> Result<DecodedTx, InspectError> {
+    let (era, tx, update) = decode_any_era(bytes)?;
+    let hash = tx_hash(bytes).map_err(|error| InspectError::Body(Box::new(error)))?;
+    Ok(DecodedTx {
+        era,
+        tx,
+        hash,
+        update,
+    })
+}
+
+/// the era, the Conway representation and the update proposal of the
+/// transaction
+fn decode_any_era(bytes: &[u8]) -> Result<(Era, Tx, Option<babbage::Update>), InspectError> {
+    // the conway decoder ignores the update field, which conway rejects
+    let conway = match minicbor::decode::<Tx>(bytes) {
+        Ok(tx) if !has_update(bytes) => return Ok((Era::Conway, tx, None)),
+        Ok(_) => minicbor::decode::Error::message("update field in a conway body"),
+        Err(error) => error,
+    };
+
+    match minicbor::decode::<babbage::Tx>(bytes) {
+        Ok(mut tx) => {
+            let era = babbage_or_alonzo(&tx);
+            let update = tx.transaction_body.update.take();
+            Ok((era, from_babbage(tx)?, update))
+        }
+        Err(babbage) => Err(InspectError::Decode {
+            conway: Box::new(conway),
+            babbage: Box::new(babbage),
+        }),
+    }
+}
+
+/// hash of a CBOR encoded transaction, of any era
+///
+/// The hash is computed on the body's bytes as they appear in the
+/// transaction (re-encoding a decoded body may not give the same bytes).
+pub fn tx_hash(bytes: &[u8]) -> Result<TxHash, minicbor::decode::Error> {
+    let mut decoder = minicbor::Decoder::new(bytes);
+    decoder.array()?;
+    let start = decoder.position();
+    decoder.skip()?;
+    let end = decoder.position();
+
+    Ok(pallas_crypto::hash::Hasher::<256>::hash(&bytes[start..end]))
+}
+
+/// the body of the transaction has an update field (6)
+fn has_update(bytes: &[u8]) -> bool {
+    body_keys(bytes).is_ok_and(|keys| keys.contains(&6))
+}
+
+/// the keys of the body map of a CBOR encoded transaction
+fn body_keys(bytes: &[u8]) -> Result<Vec<u64>, minicbor::decode::Error> {
+    let mut decoder = minicbor::Decoder::new(bytes);
+    decoder.array()?;
+    let length = decoder.map()?;
+    let mut keys = Vec::new();
+    loop {
+        let done = match length {
+            Some(length) => keys.len() as u64 == length,
+            None => decoder.datatype()? == minicbor::data::Type::Break,
+        };
+        if done {
+            return Ok(keys);
+        }
+        keys.push(decoder.u64()?);
+        decoder.skip()?;
+    }
+}
+
+/// babbage decoder is also able to decode alonzo transactions, check if
+/// any of the fields introduced with babbage are used.
+fn babbage_or_alonzo(tx: &babbage::Tx) -> Era {
+    let body = &tx.transaction_body;
+    let uses_babbage = body.reference_inputs.is_some()
+        || body.collateral_return.is_some()
+        || body.total_collateral.is_some()
+        || body
+            .outputs
+            .iter()
+            .any(|output| matches!(output, babbage::TransactionOutput::PostAlonzo(_)))
+        || tx.transaction_witness_set.plutus_v2_script.is_some();
+
+    if uses_babbage {
+        Era::Babbage
+    } else {
+        Era::Alonzo
+    }
+}
+
+fn from_babbage(tx: babbage::Tx) -> Result<Tx, InspectError> {
+    let body = tx.transaction_body;
+    let witnesses = tx.transaction_witness_set;
+
+    let certificates = body
+        .certificates
+        .map(|certificates| {
+            certificates
+                .into_iter()
+                .map(certificate)
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()?
+        .and_then(NonEmptySet::from_vec);
+
+    let transaction_body = conway::TransactionBody {
+        inputs: Set::from(body.inputs),
+        outputs: body.outputs.into_iter().map(output).collect(),
+        fee: body.fee,
+        ttl: body.ttl,
+        certificates,
+        withdrawals: body
+            .withdrawals
+            .and_then(|withdrawals| NonEmptyKeyValuePairs::from_vec(withdrawals.to_vec())),
+        auxiliary_data_hash: body.auxiliary_data_hash,
+        validity_interval_start: body.validity_interval_start,
+        mint: body.mint.and_then(mint),
+        script_data_hash: body.script_data_hash,
+        collateral: body.collateral.and_then(NonEmptySet::from_vec),
+        required_signers: body.required_signers.and_then(NonEmptySet::from_vec),
+        network_id: body.network_id,
+        collateral_return: body.collateral_return.map(output),
+        total_collateral: body.total_collateral,
+        reference_inputs: body.reference_inputs.and_then(NonEmptySet::from_vec),
+        voting_procedures: None,
+        proposal_procedures: None,
+        treasury_value: None,
+        donation: None,
+    };
+
+    let transaction_witness_set = conway::WitnessSet {
+        vkeywitness: witnesses.vkeywitness.and_then(NonEmptySet::from_vec),
+        native_script: witnesses.native_script.and_then(NonEmptySet::from_vec),
+        bootstrap_witness: witnesses.bootstrap_witness.and_then(NonEmptySet::from_vec),
+        plutus_v1_script: witnesses.plutus_v1_script.and_then(NonEmptySet::from_vec),
+        plutus_data: witnesses.plutus_data.and_then(NonEmptySet::from_vec),
+        redeemer: witnesses.redeemer.map(|redeemers| {
+            conway::Redeemers::List(MaybeIndefArray::Def(
+                redeemers.into_iter().map(redeemer).collect(),
+            ))
+        }),
+        plutus_v2_script: witnesses.plutus_v2_script.and_then(NonEmptySet::from_vec),
+        plutus_v3_script: None,
+    };
+
+    Ok(Tx {
+        transaction_body,
+        transaction_witness_set,
+        success: tx.success,
+        auxiliary_data: tx.auxiliary_data,
+    })
+}
+
+fn output(output: babbage::TransactionOutput) -> TransactionOutput {
+    match output {
+        babbage::TransactionOutput::Legacy(output) => TransactionOutput::Legacy(output),
+        babbage::TransactionOutput::PostAlonzo(output) => {
+            TransactionOutput::PostAlonzo(babbage::PseudoPostAlonzoTransactionOutput {
+                address: output.address,
+                value: value_from_legacy(&output.value),
+                datum_option: output.datum_option,
+                script_ref: output.script_ref.map(|script| {
+                    pallas_codec::utils::CborWrap(match script.unwrap() {
+                        babbage::PseudoScript::NativeScript(script) => {
+                            conway::PseudoScript::NativeScript(script)
+                        }
+                        babbage::PseudoScript::PlutusV1Script(script) => {
+                            conway::PseudoScript::PlutusV1Script(script)
+                        }
+                        babbage::PseudoScript::PlutusV2Script(script) => {
+                            conway::PseudoScript::PlutusV2Script(script)
+                        }
+                    })
+                }),
+            })
+        }
+    }
+}
+
+fn mint(mint: alonzo::Multiasset<i64>) -> Option<conway::Mint> {
+    let policies = mint
+        .iter()
+        .filter_map(|(policy, assets)| {
+            let assets = assets
+                .iter()
+                .filter_map(|(name, amount)| {
+                    NonZeroInt::try_from(*amount)
+                        .ok()
+                        .map(|amount| (name.clone(), amount))
+                })
+                .collect();
+            NonEmptyKeyValuePairs::from_vec(assets).map(|assets| (*policy, assets))
+        })
+        .collect();
+    Multiasset::from_vec(policies)
+}
+
+fn redeemer(redeemer: alonzo::Redeemer) -> conway::Redeemer {
+    conway::Redeemer {
+        tag: match redeemer.tag {
+            alonzo::RedeemerTag::Spend => conway::RedeemerTag::Spend,
+            alonzo::RedeemerTag::Mint => conway::RedeemerTag::Mint,
+            alonzo::RedeemerTag::Cert => conway::RedeemerTag::Cert,
+            alonzo::RedeemerTag::Reward => conway::RedeemerTag::Reward,
+        },
+        index: redeemer.index,
+        data: redeemer.data,
+        ex_units: redeemer.ex_units,
+    }
+}
+
+fn certificate(certificate: alonzo::Certificate) -> Result<Certificate, InspectError> {
+    Ok(match certificate {
+        alonzo::Certificate::StakeRegistration(credential) => {
+            Certificate::StakeRegistration(credential)
+        }
+        alonzo::Certificate::StakeDeregistration(credential) => {
+            Certificate::StakeDeregistration(credential)
+        }
+        alonzo::Certificate::StakeDelegation(credential, pool) => {
+            Certificate::StakeDelegation(credential, pool)
+        }
+        alonzo::Certificate::PoolRegistration {
+            operator,
+            vrf_keyhash,
+            pledge,
+            cost,
+            margin,
+            reward_account,
+            pool_owners,
+            relays,
+            pool_metadata,
+        } => Certificate::PoolRegistration {
+            operator,
+            vrf_keyhash,
+            pledge,
+            cost,
+            margin,
+            reward_account,
+            pool_owners: Set::from(pool_owners),
+            relays,
+            pool_metadata,
+        },
+        alonzo::Certificate::PoolRetirement(pool, epoch) => {
+            Certificate::PoolRetirement(pool, epoch)
+        }
+        alonzo::Certificate::GenesisKeyDelegation(..) => {
+            return Err(InspectError::UnsupportedCertificate(
+                "genesis key delegation",
+            ));
+        }
+        alonzo::Certificate::MoveInstantaneousRewardsCert(..) => {
+            return Err(InspectError::UnsupportedCertificate(
+                "move instantaneous rewards",
+            ));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{Hash, TransactionInput};
+    use pallas_codec::utils::{KeyValuePairs, Nullable};
+
+    fn babbage_tx(outputs: Vec<babbage::TransactionOutput>) -> babbage::Tx {
+        babbage::Tx {
+            transaction_body: babbage::TransactionBody {
+                inputs: vec![TransactionInput {
+                    transaction_id: Hash::from([1; 32]),
+                    index: 0,
+                }],
+                outputs,
+                fee: 170_000,
+                ttl: Some(42),
+                certificates: Some(Vec::new()),
+                withdrawals: None,
+                update: None,
+                auxiliary_data_hash: None,
+                validity_interval_start: None,
+                mint: Some(KeyValuePairs::from(vec![(
+                    Hash::from([2; 28]),
+                    KeyValuePairs::from(vec![(vec![1].into(), 0), (vec![2].into(), -1)]),
+                )])),
+                script_data_hash: None,
+                collateral: None,
+                required_signers: Some(Vec::new()),
+                network_id: None,
+                collateral_return: None,
+                total_collateral: None,
+                reference_inputs: None,
+            },
+            transaction_witness_set: babbage::WitnessSet {
+                vkeywitness: Some(Vec::new()),
+                native_script: None,
+                bootstrap_witness: None,
+                plutus_v1_script: None,
+                plutus_data: None,
+                redeemer: None,
+                plutus_v2_script: None,
+            },
+            success: true,
+            auxiliary_data: Nullable::Null,
+        }
+    }
+
+    #[test]
+    fn tx_hash_of_the_body() {
+        let mut transaction_body = crate::cardano::testing::empty_body();
+        transaction_body.fee = 170_000;
+        let tx = Tx {
+            transaction_body,
+            transaction_witness_set: crate::cardano::testing::empty_witness_set(),
+            success: true,
+            auxiliary_data: Nullable::Null,
+        };
+        let bytes = minicbor::to_vec(&tx).unwrap();
+
+        assert_eq!(
+            crate::cardano::tx_hash(&tx.transaction_body),
+            tx_hash(&bytes).unwrap()
+        );
+    }
+
+    #[test]
+    fn decode_alonzo_tx() {
+        let tx = babbage_tx(vec![babbage::TransactionOutput::Legacy(
+            alonzo::TransactionOutput {
+                address: vec![0x61; 29].into(),
+                amount: alonzo::Value::Coin(1_000_000),
+                datum_hash: None,
+            },
+        )]);
+        let bytes = minicbor::to_vec(&tx).unwrap();
+
+        // empty sets and 0 mint are rejected by the conway decoder
+        assert!(minicbor::decode::<Tx>(&bytes).is_err());
+
+        let decoded = decode_tx(&bytes).unwrap();
+
+        assert_eq!(decoded.era, Era::Alonzo);
+        let body = decoded.tx.transaction_body;
+        assert_eq!(body.fee, 170_000);
+        assert_eq!(body.ttl, Some(42));
+        assert!(body.certificates.is_none());
+        assert!(body.required_signers.is_none());
+        let mint = body.mint.unwrap();
+        assert_eq!(mint.iter().next().unwrap().1.len(), 1);
+    }
+
+    #[test]
+    fn decode_babbage_tx() {
+        let tx = babbage_tx(vec![babbage::TransactionOutput::PostAlonzo(
+            babbage::PseudoPostAlonzoTransactionOutput {
+                address: vec![0x61; 29].into(),
+                value: alonzo::Value::Coin(1_000_000),
+                datum_option: None,
+                script_ref: None,
+            },
+        )]);
+        let bytes = minicbor::to_vec(&tx).unwrap();
+
+        let decoded = decode_tx(&bytes).unwrap();
+
+        assert_eq!(decoded.era, Era::Babbage);
+        assert_eq!(decoded.tx.transaction_body.outputs.len(), 1);
+        assert_eq!(
+            tx_hash(&bytes).unwrap(),
+            pallas_crypto::hash::Hasher::<256>::hash_cbor(&tx.transaction_body)
+        );
+    }
+
+    #[test]
+    fn keep_the_update_proposal() {
+        let mut tx = babbage_tx(Vec::new());
+        let body = &mut tx.transaction_body;
+        // otherwise a valid conway body
+        body.certificates = None;
+        body.required_signers = None;
+        body.mint = None;
+        body.update = Some(babbage::Update {
+            proposed_protocol_parameter_updates: KeyValuePairs::from(Vec::new()),
+            epoch: 500,
+        });
+        tx.transaction_witness_set.vkeywitness = None;
+        let bytes = minicbor::to_vec(&tx).unwrap();
+
+        let decoded = decode_tx(&bytes).unwrap();
+
+        assert_ne!(decoded.era, Era::Conway);
+        assert_eq!(decoded.update.map(|update| update.epoch), Some(500));
+        assert_eq!(decoded.hash, tx_hash(&bytes).unwrap());
+    }
+
+    #[test]
+    fn verify_the_witnesses_of_the_encoded_body() {
+        use crate::cardano::{
+            UtxoSet,
+            witness::{verify_decoded_witnesses, verify_witnesses},
+        };
+        use pallas_crypto::key::ed25519::SecretKey;
+
+        // the inputs are an array, not a set tagged with 258
+        let mut tx = babbage_tx(Vec::new());
+        let key = SecretKey::from([7; 32]);
+        let body = minicbor::to_vec(&tx.transaction_body).unwrap();
+        tx.transaction_witness_set.vkeywitness = Some(vec![alonzo::VKeyWitness {
+            vkey: key.public_key().as_ref().to_vec().into(),
+            signature: key
+                .sign(pallas_crypto::hash::Hasher::<256>::hash(&body))
+                .as_ref()
+                .to_vec()
+                .into(),
+        }]);
+        let bytes = minicbor::to_vec(&tx).unwrap();
+
+        let decoded = decode_tx(&bytes).unwrap();
+
+        // the body encoded again has another hash
+        assert!(verify_witnesses(&decoded.tx).is_err());
+        assert_eq!(
+            verify_decoded_witnesses(&decoded, &UtxoSet::default()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn decode_invalid_tx() {
+        assert!(matches!(
+            decode_tx(&[0x80]),
+            Err(InspectError::Decode { .. })
+        ));
+    }
+}
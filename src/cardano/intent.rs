@@ -0,0 +1,1065 @@
+/*!
+Transactions described by what they do rather than by what they spend.
+
+A [`TxIntent`] lists the payments, metadata and certificates of a
+transaction without committing to any UTxO. It can be serialized (e.g.
+kept along a queued transaction) and [`build`] turns it into a
+transaction against the current UTxOs of the wallet. If the transaction
+expires or fails, the same intent is simply built again against the
+fresh chain state.
+
+The UTxOs locked at Plutus script addresses are spent with
+[`spend_script`](TxIntent::spend_script), see [`plutus`](super::plutus):
+the build adds the scripts, datums and redeemers to the witness set, pays
+for the execution units and sets the collateral and the
+`script_data_hash`.
+
+The build is deterministic: the same intent, UTxOs and parameters always
+produce the same transaction.
+*/
+
+use super::{
+    AddrKeyhash, AssetId, AssetName, Certificate, Coin, DepositParams, Metadata, Metadatum,
+    MetadatumLabel, NonEmptyKeyValuePairs, PendingTx, PolicyId, TransactionInput,
+    TransactionOutput, Tx, Utxo, UtxoSet, Value, WalletValue,
+    cost_models::CostModelError,
+    fees::{ProtocolParams, VKEY_WITNESS_SIZE, min_ada_for_output},
+    native_script,
+    plutus::{self, PlutusParams, Script, ScriptInput, ScriptSource},
+    summary::{SummaryError, TxSummary, certificates_deposits},
+    witness::RequiredSigners,
+};
+use crate::{
+    Address,
+    redact::{self, Redact},
+};
+use pallas_codec::{
+    minicbor,
+    utils::{Bytes, CborWrap, NonEmptySet, NonZeroInt, Nullable, Set},
+};
+use pallas_primitives::{
+    PlutusData,
+    babbage::PseudoPostAlonzoTransactionOutput,
+    conway::{
+        DatumOption, Mint, NativeScript, RedeemerTag, Redeemers, RedeemersKey, RedeemersValue,
+        TransactionBody, WitnessSet,
+    },
+};
+use std::{collections::BTreeMap, fmt};
+
+#[derive(Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Recipient {
+    /// raw bytes of the recipient's address
+    pub address: Bytes,
+    pub value: Value,
+    /// the inline datum of the output
+    #[serde(default)]
+    pub datum: Option<PlutusData>,
+}
+
+impl Redact for Recipient {
+    fn fmt_debug(&self, f: &mut fmt::Formatter<'_>, redact: bool) -> fmt::Result {
+        f.debug_struct("Recipient")
+            .field(
+                "address",
+                &redact::Bytes {
+                    bytes: &self.address,
+                    redact,
+                },
+            )
+            .field("value", &self.value)
+            .field("datum", &self.datum)
+            .finish()
+    }
+}
+
+impl Recipient {
+    pub(crate) fn output(&self) -> TransactionOutput {
+        let mut output = output(self.address.clone(), self.value.clone());
+        if let (TransactionOutput::PostAlonzo(output), Some(datum)) = (&mut output, &self.datum) {
+            output.datum_option = Some(DatumOption::Data(CborWrap(datum.clone())));
+        }
+        output
+    }
+
+    /// the recipient with at least the min-ADA of its output
+    pub(crate) fn with_min_ada(mut self, coins_per_utxo_byte: Coin) -> Self {
+        let minimum = min_ada_for_output(&self.output(), coins_per_utxo_byte);
+        match &mut self.value {
+            Value::Coin(coin) | Value::Multiasset(coin, _) => *coin = (*coin).max(minimum),
+        }
+        self
+    }
+}
+
+impl fmt::Debug for Recipient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_debug(f, redact::enabled())
+    }
+}
+
+/// What a transaction needs to achieve, independently of the UTxOs used.
+#[derive(Debug, Clone, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub struct TxIntent {
+    pub recipients: Vec<Recipient>,
+    pub metadata: Vec<(MetadatumLabel, Metadatum)>,
+    pub certificates: Vec<Certificate>,
+    /// keys that must sign the transaction besides the owners of the
+    /// spent UTxOs (e.g. the signers of a native script)
+    #[serde(default)]
+    pub required_signers: Vec<AddrKeyhash>,
+    /// the native scripts of the spent script UTxOs
+    #[serde(default)]
+    pub native_scripts: Vec<NativeScript>,
+    /// the slot from which the transaction is valid
+    #[serde(default)]
+    pub valid_from: Option<u64>,
+    /// the assets minted (positive quantity) or burned (negative
+    /// quantity), their policy scripts go in `native_scripts`
+    #[serde(default)]
+    pub mint: Vec<(PolicyId, AssetName, i64)>,
+    /// the UTxOs locked at Plutus script addresses spent by the
+    /// transaction, with what unlocks them
+    #[serde(default)]
+    pub script_inputs: Vec<ScriptInput>,
+    /// the UTxOs forfeited if a script fails, needed as soon as a
+    /// Plutus script is spent from
+    #[serde(default)]
+    pub collateral: Vec<TransactionInput>,
+    /// attach the [`TxSummary`] of the transaction under
+    /// [`SUMMARY_LABEL`](super::SUMMARY_LABEL)
+    #[serde(default)]
+    pub summary: bool,
+}
+
+/// The parameters needed to [`build`] a [`TxIntent`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildParams {
+    pub min_fee_a: Coin,
+    pub min_fee_b: Coin,
+    pub coins_per_utxo_byte: Coin,
+    pub deposits: DepositParams,
+    /// where to send the change
+    pub change_address: Address,
+    /// the slot from which the transaction will no longer be valid
+    pub ttl: Option<u64>,
+    /// the prices, collateral and cost models of the Plutus scripts
+    pub plutus: PlutusParams,
+}
+
+impl BuildParams {
+    /// the current parameters of mainnet and of the public test networks,
+    /// without TTL
+    pub fn defaults(change_address: Address) -> Self {
+        ProtocolParams::MAINNET.build_params(change_address)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BuildError {
+    #[error("Not enough funds: {missing} more lovelace needed")]
+    InsufficientFunds { missing: Coin },
+    #[error("Not enough of the asset {policy}.{}: {missing} more needed", hex::encode(.name.as_slice()))]
+    InsufficientAsset {
+        policy: PolicyId,
+        name: AssetName,
+        missing: u64,
+    },
+    #[error("Input {}#{} is not in the available UTxOs", .0.transaction_id, .0.index)]
+    UnresolvedInput(TransactionInput),
+    #[error("Not enough collateral: {missing} more lovelace needed")]
+    InsufficientCollateral { missing: Coin },
+    #[error("Input {}#{} only holds the hash of its datum, the datum is needed", .0.transaction_id, .0.index)]
+    MissingDatum(TransactionInput),
+    #[error(transparent)]
+    CostModel(#[from] CostModelError),
+    #[error("The summary of the transaction can't be attached: {0}")]
+    Summary(#[from] SummaryError),
+    #[error("The quantity of the asset {policy}.{} minted or burned overflows", hex::encode(.name.as_slice()))]
+    MintOverflow { policy: PolicyId, name: AssetName },
+}
+
+impl TxIntent {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn pay(mut self, address: &Address, value: Value) -> Self {
+        self.recipients.push(Recipient {
+            address: address.to_vec().into(),
+            value,
+            datum: None,
+        });
+        self
+    }
+
+    /// pay `value` to `address` with `datum` inlined in the output (e.g.
+    /// to a script address)
+    pub fn pay_with_datum(mut self, address: &Address, value: Value, datum: PlutusData) -> Self {
+        self.recipients.push(Recipient {
+            address: address.to_vec().into(),
+            value,
+            datum: Some(datum),
+        });
+        self
+    }
+
+    pub fn with_metadata(mut self, label: MetadatumLabel, metadatum: Metadatum) -> Self {
+        self.metadata.retain(|(existing, _)| *existing != label);
+        self.metadata.push((label, metadatum));
+        self
+    }
+
+    /// set all the entries of `metadata`, see [`with_metadata`](Self::with_metadata)
+    pub fn with_all_metadata(self, metadata: &Metadata) -> Self {
+        metadata
+            .entries()
+            .iter()
+            .fold(self, |intent, (label, metadatum)| {
+                intent.with_metadata(*label, metadatum.clone())
+            })
+    }
+
+    pub fn with_certificate(mut self, certificate: Certificate) -> Self {
+        self.certificates.push(certificate);
+        self
+    }
+
+    pub fn with_required_signer(mut self, signer: AddrKeyhash) -> Self {
+        if !self.required_signers.contains(&signer) {
+            self.required_signers.push(signer);
+        }
+        self
+    }
+
+    pub fn with_native_script(mut self, script: NativeScript) -> Self {
+        if !self.native_scripts.contains(&script) {
+            self.native_scripts.push(script);
+        }
+        self
+    }
+
+    /// mint (or burn if `quantity` is negative) the asset, adding to
+    /// what is already minted of it
+    ///
+    /// A quantity overflowing what is already minted is kept apart, for
+    /// [`build`] to fail with [`BuildError::MintOverflow`].
+    pub fn mint(mut self, policy: PolicyId, name: AssetName, quantity: i64) -> Self {
+        match self
+            .mint
+            .iter_mut()
+            .find(|(known_policy, known_name, _)| *known_policy == policy && *known_name == name)
+        {
+            Some((_, _, total)) if total.checked_add(quantity).is_some() => *total += quantity,
+            _ => self.mint.push((policy, name, quantity)),
+        }
+        self
+    }
+
+    /// spend a UTxO locked at a Plutus script address, replacing what
+    /// unlocks it if it was already spent
+    pub fn spend_script(mut self, input: ScriptInput) -> Self {
+        self.script_inputs
+            .retain(|existing| existing.input != input.input);
+        self.script_inputs.push(input);
+        self
+    }
+
+    /// set the `utxos` aside as collateral, e.g. the ones returned by
+    /// the wallet's `getCollateral`
+    ///
+    /// They need to be among the UTxOs the intent is built with, they are
+    /// not spent by the transaction.
+    pub fn with_collateral(mut self, utxos: &[Utxo]) -> Self {
+        for utxo in utxos {
+            if !self.collateral.contains(&utxo.input) {
+                self.collateral.push(utxo.input.clone());
+            }
+        }
+        self
+    }
+
+    pub fn valid_from(mut self, slot: u64) -> Self {
+        self.valid_from = Some(slot);
+        self
+    }
+
+    /// attach the [`TxSummary`] of the built transaction to its metadata
+    ///
+    /// The summary is attached while balancing, so the fee covers its
+    /// bytes.
+    pub fn with_summary(mut self) -> Self {
+        self.summary = true;
+        self
+    }
+
+    /// see [`build`]
+    pub fn build(&self, utxos: &UtxoSet, params: &BuildParams) -> Result<Tx, BuildError> {
+        build(self, utxos, params)
+    }
+
+    /// see [`build_spending`]
+    pub fn build_spending(
+        &self,
+        spent: &UtxoSet,
+        utxos: &UtxoSet,
+        params: &BuildParams,
+    ) -> Result<Tx, BuildError> {
+        build_spending(self, spent, utxos, params)
+    }
+}
+
+/// Build the unsigned transaction achieving the `intent`, spending from
+/// `utxos`.
+///
+/// The UTxOs holding the requested assets are selected first, then the
+/// largest ones until the outputs, deposits and fee are covered. What is
+/// left is sent back to the change address (or added to the fee if it is
+/// too small for an output of its own).
+pub fn build(intent: &TxIntent, utxos: &UtxoSet, params: &BuildParams) -> Result<Tx, BuildError> {
+    select_and_balance(intent, utxos, params, UtxoSet::new(), 0)
+}
+
+/// Same as [`build`] but always spending the `spent` UTxOs (e.g. locked
+/// at a script address), completed with UTxOs from `utxos` if needed.
+pub fn build_spending(
+    intent: &TxIntent,
+    spent: &UtxoSet,
+    utxos: &UtxoSet,
+    params: &BuildParams,
+) -> Result<Tx, BuildError> {
+    select_and_balance(intent, utxos, params, spent.clone(), 0)
+}
+
+/// Build a replacement of a pending transaction, paying `extra_fee` more.
+///
+/// The replacement spends all the inputs of the `pending` transaction
+/// (so only one of the two can make it on chain), adding more inputs
+/// from `utxos` if needed. The `utxos` need to include the inputs of the
+/// pending transaction.
+pub fn rebuild_with_margin(
+    intent: &TxIntent,
+    pending: &PendingTx,
+    utxos: &UtxoSet,
+    params: &BuildParams,
+    extra_fee: Coin,
+) -> Result<Tx, BuildError> {
+    let mut selected = UtxoSet::new();
+    for input in &pending.inputs {
+        let utxo = utxos
+            .iter()
+            .find(|utxo| &utxo.input == input)
+            .ok_or_else(|| BuildError::UnresolvedInput(input.clone()))?;
+        selected.push(utxo.clone());
+    }
+
+    select_and_balance(
+        intent,
+        utxos,
+        params,
+        selected,
+        pending.fee.saturating_add(extra_fee),
+    )
+}
+
+fn select_and_balance(
+    intent: &TxIntent,
+    utxos: &UtxoSet,
+    params: &BuildParams,
+    mut selected: UtxoSet,
+    min_fee: Coin,
+) -> Result<Tx, BuildError> {
+    check_mint(&intent.mint)?;
+    let collateral = resolve_plutus_inputs(intent, utxos, &mut selected)?;
+
+    // the outputs below their min-ADA are topped up
+    let recipients: Vec<Recipient> = intent
+        .recipients
+        .iter()
+        .map(|recipient| recipient.clone().with_min_ada(params.coins_per_utxo_byte))
+        .collect();
+    let outputs: Vec<TransactionOutput> = recipients.iter().map(Recipient::output).collect();
+
+    // the collateral is kept aside
+    let mut candidates: Vec<_> = utxos
+        .iter()
+        .filter(|utxo| !selected.iter().any(|selected| selected.input == utxo.input))
+        .filter(|utxo| !intent.collateral.contains(&utxo.input))
+        .collect();
+    candidates.sort_by(|a, b| b.amount().cmp(&a.amount()).then(a.input.cmp(&b.input)));
+
+    // the minted assets don't need to be found in the UTxOs, the burned
+    // ones do
+    let (minted, burned) = minted_and_burned(&intent.mint);
+    let requested: WalletValue = recipients
+        .iter()
+        .map(|recipient| WalletValue::from(&recipient.value))
+        .sum();
+    let required = (requested + burned).saturating_sub(&minted);
+
+    // first the UTxOs holding the assets to send
+    let mut remaining = Vec::new();
+    for utxo in candidates {
+        let available: WalletValue = selected
+            .iter()
+            .map(|utxo| WalletValue::from(utxo.value()))
+            .sum();
+        let needed = WalletValue::from(utxo.value())
+            .assets()
+            .keys()
+            .any(|asset| available.quantity(asset) < required.quantity(asset));
+        if needed {
+            selected.push(utxo.clone());
+        } else {
+            remaining.push(utxo);
+        }
+    }
+
+    // then the largest ones until the transaction balances
+    let mut remaining = remaining.into_iter();
+    loop {
+        match balance(intent, &outputs, &selected, &collateral, params, min_fee) {
+            Ok(tx) => return Ok(tx),
+            Err(
+                error @ (BuildError::InsufficientAsset { .. }
+                | BuildError::InsufficientCollateral { .. }
+                | BuildError::CostModel(_)
+                | BuildError::Summary(_)),
+            ) => return Err(error),
+            Err(error) => match remaining.next() {
+                Some(utxo) => selected.push(utxo.clone()),
+                None => return Err(error),
+            },
+        }
+    }
+}
+
+/// add the script inputs of the intent to the `selected` UTxOs, and
+/// return the collateral UTxOs
+fn resolve_plutus_inputs(
+    intent: &TxIntent,
+    utxos: &UtxoSet,
+    selected: &mut UtxoSet,
+) -> Result<UtxoSet, BuildError> {
+    let resolve = |selected: &UtxoSet, input: &TransactionInput| {
+        selected
+            .iter()
+            .chain(utxos.iter())
+            .find(|utxo| &utxo.input == input)
+            .cloned()
+            .ok_or_else(|| BuildError::UnresolvedInput(input.clone()))
+    };
+
+    for script_input in &intent.script_inputs {
+        let utxo = resolve(selected, &script_input.input)?;
+        if let TransactionOutput::PostAlonzo(output) = &utxo.output
+            && let Some(DatumOption::Hash(_)) = output.datum_option
+            && script_input.datum.is_none()
+        {
+            return Err(BuildError::MissingDatum(utxo.input));
+        }
+        if !selected.iter().any(|selected| selected.input == utxo.input) {
+            selected.push(utxo);
+        }
+    }
+
+    intent
+        .collateral
+        .iter()
+        .map(|input| resolve(selected, input))
+        .collect()
+}
+
+/// build the transaction spending all the `selected` UTxOs
+fn balance(
+    intent: &TxIntent,
+    outputs: &[TransactionOutput],
+    selected: &UtxoSet,
+    collateral: &UtxoSet,
+    params: &BuildParams,
+    min_fee: Coin,
+) -> Result<Tx, BuildError> {
+    let mut inputs: Vec<_> = selected.iter().map(|utxo| utxo.input.clone()).collect();
+    inputs.sort();
+
+    let mut tx = Tx {
+        transaction_body: TransactionBody {
+            inputs: Set::from(inputs),
+            outputs: Vec::new(),
+            fee: 0,
+            ttl: params.ttl,
+            certificates: NonEmptySet::from_vec(intent.certificates.clone()),
+            withdrawals: None,
+            auxiliary_data_hash: None,
+            validity_interval_start: intent.valid_from,
+            mint: mint(&intent.mint),
+            script_data_hash: None,
+            collateral: None,
+            required_signers: NonEmptySet::from_vec(intent.required_signers.clone()),
+            network_id: None,
+            collateral_return: None,
+            total_collateral: None,
+            reference_inputs: None,
+            voting_procedures: None,
+            proposal_procedures: None,
+            treasury_value: None,
+            donation: None,
+        },
+        transaction_witness_set: WitnessSet {
+            vkeywitness: None,
+            native_script: NonEmptySet::from_vec(intent.native_scripts.clone()),
+            bootstrap_witness: None,
+            plutus_v1_script: None,
+            plutus_data: None,
+            redeemer: None,
+            plutus_v2_script: None,
+            plutus_v3_script: None,
+        },
+        success: true,
+        auxiliary_data: Nullable::Null,
+    };
+
+    Metadata::from_iter(intent.metadata.iter().cloned()).attach(&mut tx);
+    let script_fee = add_plutus_witnesses(intent, &mut tx, params)?;
+    if !intent.script_inputs.is_empty() {
+        let mut inputs: Vec<_> = collateral.iter().map(|utxo| utxo.input.clone()).collect();
+        inputs.sort();
+        tx.transaction_body.collateral = NonEmptySet::from_vec(inputs);
+    }
+
+    let (deposits, refunds) = certificates_deposits(&tx.transaction_body, &params.deposits);
+    let resolved: UtxoSet = selected.iter().chain(collateral.iter()).cloned().collect();
+    let mut signers = RequiredSigners::infer(&tx.transaction_body, &resolved).signers;
+    signers.extend(
+        intent
+            .native_scripts
+            .iter()
+            .flat_map(native_script::key_hashes),
+    );
+    let witnesses = signers.len();
+
+    let (minted, burned) = minted_and_burned(&intent.mint);
+    let mut available: WalletValue = selected
+        .iter()
+        .map(|utxo| WalletValue::from(utxo.value()))
+        .sum();
+    available.add_lovelace(refunds);
+    available += minted;
+    let mut spent: WalletValue = outputs
+        .iter()
+        .map(|output| WalletValue::from(output_value(output)))
+        .sum();
+    spent.add_lovelace(deposits);
+    spent += burned;
+
+    let mut fee = min_fee;
+    loop {
+        let mut required = spent.clone();
+        required.add_lovelace(fee);
+        let change = Value::from(remainder(&available, &required)?);
+
+        tx.transaction_body.fee = fee;
+        tx.transaction_body.outputs = outputs.to_vec();
+        let change_output = output(params.change_address.to_vec().into(), change.clone());
+        let change_coin = super::lovelace_of(&change);
+        if change_coin >= min_ada_for_output(&change_output, params.coins_per_utxo_byte) {
+            tx.transaction_body.outputs.push(change_output);
+        } else if matches!(change, Value::Multiasset(..)) {
+            // the assets need to go somewhere
+            return Err(BuildError::InsufficientFunds {
+                missing: min_ada_for_output(&change_output, params.coins_per_utxo_byte)
+                    - change_coin,
+            });
+        } else {
+            // too small to be an output, leave it to the fee
+            tx.transaction_body.fee += change_coin;
+        }
+        if !intent.script_inputs.is_empty() {
+            set_collateral(&mut tx.transaction_body, collateral, params)?;
+        }
+        if intent.summary {
+            TxSummary::new(&tx.transaction_body, selected, &params.deposits)
+                .and_then(|summary| summary.attach(&mut tx))?;
+        }
+
+        let size = minicbor::to_vec(&tx)
+            .map(|bytes| bytes.len())
+            .unwrap_or_default()
+            + witnesses * VKEY_WITNESS_SIZE;
+        let required_fee =
+            (params.min_fee_a * size as u64 + params.min_fee_b + script_fee).max(min_fee);
+        if tx.transaction_body.fee >= required_fee {
+            return Ok(tx);
+        }
+        fee = required_fee;
+    }
+}
+
+/// add the scripts, datums and redeemers of the script inputs to the
+/// transaction, with its reference inputs and `script_data_hash`, and
+/// return the price of the execution units
+///
+/// The inputs of the body need to be set already, the redeemers point to
+/// them by index.
+fn add_plutus_witnesses(
+    intent: &TxIntent,
+    tx: &mut Tx,
+    params: &BuildParams,
+) -> Result<Coin, BuildError> {
+    if intent.script_inputs.is_empty() {
+        return Ok(0);
+    }
+    let inputs: Vec<_> = tx.transaction_body.inputs.iter().cloned().collect();
+
+    let mut v1 = Vec::new();
+    let mut v2 = Vec::new();
+    let mut v3 = Vec::new();
+    let mut datums = Vec::new();
+    let mut reference_inputs = Vec::new();
+    let mut languages = Vec::new();
+    let mut redeemers = Vec::new();
+    let mut script_fee: Coin = 0;
+    for script_input in &intent.script_inputs {
+        match &script_input.script {
+            ScriptSource::Attached(Script::V1(script)) => v1.push(script.clone()),
+            ScriptSource::Attached(Script::V2(script)) => v2.push(script.clone()),
+            ScriptSource::Attached(Script::V3(script)) => v3.push(script.clone()),
+            ScriptSource::Reference { input, .. } => reference_inputs.push(input.clone()),
+        }
+        let language = script_input.script.language();
+        if !languages.contains(&language) {
+            languages.push(language);
+        }
+        if let Some(datum) = &script_input.datum
+            && !datums.contains(datum)
+        {
+            datums.push(datum.clone());
+        }
+
+        let index = inputs
+            .iter()
+            .position(|input| *input == script_input.input)
+            .ok_or_else(|| BuildError::UnresolvedInput(script_input.input.clone()))?;
+        redeemers.push((
+            RedeemersKey {
+                tag: RedeemerTag::Spend,
+                index: index as u32,
+            },
+            RedeemersValue {
+                data: script_input.redeemer.clone(),
+                ex_units: script_input.ex_units,
+            },
+        ));
+        script_fee = script_fee.saturating_add(params.plutus.script_fee(&script_input.ex_units));
+    }
+    redeemers.sort_by_key(|(key, _)| key.index);
+    reference_inputs.sort();
+    reference_inputs.dedup();
+
+    let witnesses = &mut tx.transaction_witness_set;
+    witnesses.plutus_v1_script = NonEmptySet::from_vec(dedup(v1));
+    witnesses.plutus_v2_script = NonEmptySet::from_vec(dedup(v2));
+    witnesses.plutus_v3_script = NonEmptySet::from_vec(dedup(v3));
+    witnesses.plutus_data = NonEmptySet::from_vec(datums);
+    witnesses.redeemer = NonEmptyKeyValuePairs::from_vec(redeemers).map(Redeemers::from);
+    tx.transaction_body.reference_inputs = NonEmptySet::from_vec(reference_inputs);
+    tx.transaction_body.script_data_hash =
+        plutus::script_data_hash(witnesses, &languages, &params.plutus)?;
+
+    Ok(script_fee)
+}
+
+fn dedup<T: PartialEq>(items: Vec<T>) -> Vec<T> {
+    let mut unique = Vec::with_capacity(items.len());
+    for item in items {
+        if !unique.contains(&item) {
+            unique.push(item);
+        }
+    }
+    unique
+}
+
+/// set the total collateral of the transaction for its fee, the rest of
+/// the collateral going back to the change address
+fn set_collateral(
+    body: &mut TransactionBody,
+    collateral: &UtxoSet,
+    params: &BuildParams,
+) -> Result<(), BuildError> {
+    let total = params.plutus.collateral_for(body.fee);
+    let available: WalletValue = collateral
+        .iter()
+        .map(|utxo| WalletValue::from(utxo.value()))
+        .sum();
+    let excess = available
+        .checked_sub(&WalletValue::from_lovelace(total))
+        .ok_or_else(|| BuildError::InsufficientCollateral {
+            missing: total - available.lovelace(),
+        })?
+        .to_value();
+
+    let collateral_return = output(params.change_address.to_vec().into(), excess.clone());
+    let minimum = min_ada_for_output(&collateral_return, params.coins_per_utxo_byte);
+    let excess_coin = super::lovelace_of(&excess);
+    if excess_coin >= minimum {
+        body.collateral_return = Some(collateral_return);
+        body.total_collateral = Some(total);
+    } else if matches!(excess, Value::Multiasset(..)) {
+        return Err(BuildError::InsufficientCollateral {
+            missing: minimum - excess_coin,
+        });
+    } else {
+        // too small to come back, all the collateral is at stake
+        body.collateral_return = None;
+        body.total_collateral = None;
+    }
+    Ok(())
+}
+
+/// fail if the quantities of an asset in the `mint` of an intent
+/// overflow once added
+fn check_mint(mint: &[(PolicyId, AssetName, i64)]) -> Result<(), BuildError> {
+    let mut totals: BTreeMap<(&PolicyId, &AssetName), i64> = BTreeMap::new();
+    for (policy, name, quantity) in mint {
+        let total = totals.entry((policy, name)).or_default();
+        *total = total
+            .checked_add(*quantity)
+            .ok_or_else(|| BuildError::MintOverflow {
+                policy: *policy,
+                name: name.clone(),
+            })?;
+    }
+    Ok(())
+}
+
+/// the mint field of the body, without the assets netting to zero
+fn mint(assets: &[(PolicyId, AssetName, i64)]) -> Option<Mint> {
+    let mut policies: BTreeMap<PolicyId, Vec<(AssetName, NonZeroInt)>> = BTreeMap::new();
+    for (policy, name, quantity) in assets {
+        if let Ok(quantity) = NonZeroInt::try_from(*quantity) {
+            policies
+                .entry(*policy)
+                .or_default()
+                .push((name.clone(), quantity));
+        }
+    }
+
+    Mint::from_vec(
+        policies
+            .into_iter()
+            .filter_map(|(policy, assets)| {
+                NonEmptyKeyValuePairs::from_vec(assets).map(|assets| (policy, assets))
+            })
+            .collect(),
+    )
+}
+
+fn output(address: Bytes, value: Value) -> TransactionOutput {
+    TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+        address,
+        value,
+        datum_option: None,
+        script_ref: None,
+    })
+}
+
+fn output_value(output: &TransactionOutput) -> &Value {
+    match output {
+        TransactionOutput::PostAlonzo(output) => &output.value,
+        // only post alonzo outputs are built here
+        TransactionOutput::Legacy(_) => &Value::Coin(0),
+    }
+}
+
+/// the assets minted and burned by the `mint` of an intent
+fn minted_and_burned(mint: &[(PolicyId, AssetName, i64)]) -> (WalletValue, WalletValue) {
+    let mut minted = WalletValue::zero();
+    let mut burned = WalletValue::zero();
+    for (policy, name, quantity) in mint {
+        let asset = AssetId::new(*policy, name.clone());
+        if *quantity > 0 {
+            minted.add_asset(asset, quantity.unsigned_abs());
+        } else {
+            burned.add_asset(asset, quantity.unsigned_abs());
+        }
+    }
+    (minted, burned)
+}
+
+/// `available - required`, failing with what is missing if `available`
+/// doesn't cover `required`
+fn remainder(available: &WalletValue, required: &WalletValue) -> Result<WalletValue, BuildError> {
+    available.checked_sub(required).ok_or_else(|| {
+        let missing = required.saturating_sub(available);
+        match missing.assets().iter().next() {
+            Some((asset, quantity)) if missing.lovelace() == 0 => BuildError::InsufficientAsset {
+                policy: asset.policy,
+                name: asset.name.clone(),
+                missing: *quantity,
+            },
+            _ => BuildError::InsufficientFunds {
+                missing: missing.lovelace(),
+            },
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{
+        Hash, Multiasset, PendingUtxos, PositiveCoin, SUMMARY_LABEL, lovelace_of,
+        testing::{address, params, utxo},
+    };
+    use pallas_addresses::{Network, ShelleyAddress, ShelleyDelegationPart, ShelleyPaymentPart};
+    use pallas_primitives::{
+        BigInt, ExUnits, PlutusScript,
+        conway::{CostModels, Language},
+    };
+
+    fn token(amount: u64) -> Value {
+        Value::Multiasset(
+            1_500_000,
+            Multiasset::from_vec(vec![(
+                Hash::from([9; 28]),
+                NonEmptyKeyValuePairs::from_vec(vec![(
+                    b"token".to_vec().into(),
+                    PositiveCoin::try_from(amount).unwrap(),
+                )])
+                .unwrap(),
+            )])
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn build_is_balanced_and_deterministic() {
+        let utxos: UtxoSet = vec![
+            utxo(0, &address(1), Value::Coin(3_000_000)),
+            utxo(1, &address(1), token(10)),
+            utxo(2, &address(1), Value::Coin(10_000_000)),
+        ]
+        .into();
+        let intent = TxIntent::new()
+            .pay(&address(2), token(4))
+            .with_metadata(674, Metadatum::Text("hello".to_owned()));
+
+        let tx = build(&intent, &utxos, &params()).unwrap();
+        let body = &tx.transaction_body;
+
+        assert_eq!(body.inputs.len(), 2);
+        assert_eq!(body.outputs.len(), 2);
+        assert!(body.auxiliary_data_hash.is_some());
+        assert!(body.fee > params().min_fee_b);
+        let inputs: WalletValue = utxos
+            .iter()
+            .filter(|utxo| body.inputs.contains(&utxo.input))
+            .map(|utxo| WalletValue::from(utxo.value()))
+            .sum();
+        let mut outputs: WalletValue = body
+            .outputs
+            .iter()
+            .map(|output| WalletValue::from(output_value(output)))
+            .sum();
+        outputs.add_lovelace(body.fee);
+        assert_eq!(inputs, outputs);
+
+        // serializing the intent and building it again gives the same tx
+        let json = serde_json::to_string(&intent).unwrap();
+        let intent: TxIntent = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            minicbor::to_vec(intent.build(&utxos, &params()).unwrap()).unwrap(),
+            minicbor::to_vec(&tx).unwrap()
+        );
+    }
+
+    #[test]
+    fn build_insufficient_funds() {
+        let utxos: UtxoSet = vec![utxo(0, &address(1), Value::Coin(2_000_000))].into();
+
+        assert!(matches!(
+            build(
+                &TxIntent::new().pay(&address(2), Value::Coin(5_000_000)),
+                &utxos,
+                &params()
+            ),
+            Err(BuildError::InsufficientFunds { .. })
+        ));
+        assert!(matches!(
+            build(
+                &TxIntent::new().pay(&address(2), token(1)),
+                &utxos,
+                &params()
+            ),
+            Err(BuildError::InsufficientAsset { missing: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn summary_is_covered_by_the_fee() {
+        let utxos: UtxoSet = vec![utxo(0, &address(1), Value::Coin(10_000_000))].into();
+        let intent = TxIntent::new()
+            .pay(&address(2), Value::Coin(2_000_000))
+            .with_summary();
+
+        let tx = build(&intent, &utxos, &params()).unwrap();
+
+        let summary = TxSummary::new(&tx.transaction_body, &utxos, &params().deposits).unwrap();
+        assert_eq!(
+            Metadata::of_tx(&tx).get(SUMMARY_LABEL),
+            Some(&summary.to_metadatum().unwrap())
+        );
+        let size = minicbor::to_vec(&tx).unwrap().len() + VKEY_WITNESS_SIZE;
+        assert!(tx.transaction_body.fee >= params().min_fee_a * size as u64 + params().min_fee_b);
+    }
+
+    #[test]
+    fn small_outputs_are_topped_up() {
+        let utxos: UtxoSet = vec![utxo(0, &address(1), Value::Coin(10_000_000))].into();
+        let intent = TxIntent::new().pay(&address(2), Value::Coin(1));
+
+        let tx = build(&intent, &utxos, &params()).unwrap();
+
+        let output = &tx.transaction_body.outputs[0];
+        assert_eq!(
+            lovelace_of(output_value(output)),
+            min_ada_for_output(output, params().coins_per_utxo_byte)
+        );
+    }
+
+    #[test]
+    fn speed_up_pending_tx() {
+        let utxos: UtxoSet = vec![
+            utxo(0, &address(1), Value::Coin(3_000_000)),
+            utxo(1, &address(1), Value::Coin(10_000_000)),
+        ]
+        .into();
+        let intent = TxIntent::new().pay(&address(2), Value::Coin(2_000_000));
+        let mut pending = PendingUtxos::new();
+        let tx = intent.build(&utxos, &params()).unwrap();
+        let hash = pending.track(&tx);
+
+        // the spent UTxO is not available to other transactions
+        assert_eq!(pending.available(&utxos).len(), 1);
+
+        let replacement = rebuild_with_margin(
+            &intent,
+            pending.get(&hash).unwrap(),
+            &utxos,
+            &params(),
+            10_000,
+        )
+        .unwrap();
+
+        assert_eq!(
+            replacement.transaction_body.inputs,
+            tx.transaction_body.inputs
+        );
+        assert!(replacement.transaction_body.fee >= tx.transaction_body.fee + 10_000);
+
+        let new_hash = pending.replace(&hash, &replacement);
+        assert!(pending.get(&hash).is_none());
+        assert!(pending.get(&new_hash).is_some());
+    }
+
+    #[test]
+    fn spend_from_a_plutus_script() {
+        let script = Script::V3(PlutusScript(vec![0x01, 0x02].into()));
+        let script_address: Address = ShelleyAddress::new(
+            Network::Testnet,
+            ShelleyPaymentPart::script_hash(script.hash()),
+            ShelleyDelegationPart::Null,
+        )
+        .into();
+        let mut locked = utxo(5, &address(1), Value::Coin(20_000_000));
+        locked.input.transaction_id = Hash::from([5; 32]);
+        if let TransactionOutput::PostAlonzo(output) = &mut locked.output {
+            output.address = script_address.to_vec().into();
+            output.datum_option = Some(DatumOption::Hash(Hash::from([6; 32])));
+        }
+        let utxos: UtxoSet = vec![
+            utxo(0, &address(1), Value::Coin(5_000_000)),
+            utxo(1, &address(1), Value::Coin(10_000_000)),
+        ]
+        .into();
+        let spent: UtxoSet = vec![locked.clone()].into();
+        let datum = PlutusData::BigInt(BigInt::Int(42.into()));
+        let ex_units = ExUnits {
+            mem: 500_000,
+            steps: 200_000_000,
+        };
+        let spend = ScriptInput::new(
+            locked.input.clone(),
+            ScriptSource::Attached(script.clone()),
+            PlutusData::BigInt(BigInt::Int(0.into())),
+            ex_units,
+        );
+        let mut params = params();
+        params.plutus = PlutusParams::default().with_cost_models(CostModels {
+            plutus_v1: None,
+            plutus_v2: None,
+            plutus_v3: Some(vec![1; 251]),
+        });
+
+        let intent = TxIntent::new()
+            .pay(&address(2), Value::Coin(25_000_000))
+            .spend_script(spend.clone());
+        assert_eq!(
+            intent.build_spending(&spent, &utxos, &params).err(),
+            Some(BuildError::MissingDatum(locked.input.clone()))
+        );
+        let intent = intent.spend_script(spend.with_datum(datum.clone()));
+        assert!(matches!(
+            intent.build_spending(&spent, &utxos, &params),
+            Err(BuildError::InsufficientCollateral { .. })
+        ));
+        let intent = intent.with_collateral(&[utxos.iter().next().unwrap().clone()]);
+
+        let tx = intent.build_spending(&spent, &utxos, &params).unwrap();
+        let body = &tx.transaction_body;
+        let witnesses = &tx.transaction_witness_set;
+
+        // the collateral is not spent, even if short of funds
+        assert_eq!(body.inputs.len(), 2);
+        assert!(
+            !body
+                .inputs
+                .contains(&utxo(0, &address(1), Value::Coin(0)).input)
+        );
+        assert_eq!(
+            body.collateral.as_deref(),
+            Some(&vec![utxo(0, &address(1), Value::Coin(0)).input])
+        );
+        assert_eq!(
+            body.total_collateral,
+            Some(params.plutus.collateral_for(body.fee))
+        );
+        assert_eq!(
+            lovelace_of(output_value(body.collateral_return.as_ref().unwrap())),
+            5_000_000 - body.total_collateral.unwrap()
+        );
+        // the redeemer points to the script input among the sorted inputs
+        let index = body.inputs.iter().position(|input| *input == locked.input);
+        let Some(Redeemers::Map(redeemers)) = &witnesses.redeemer else {
+            panic!("expected redeemers");
+        };
+        assert_eq!(redeemers.len(), 1);
+        assert_eq!(Some(redeemers[0].0.index as usize), index);
+        assert_eq!(witnesses.plutus_data.as_deref(), Some(&vec![datum]));
+        assert_eq!(witnesses.plutus_v3_script.iter().flatten().count(), 1);
+        assert_eq!(
+            body.script_data_hash,
+            plutus::script_data_hash(witnesses, &[Language::PlutusV3], &params.plutus).unwrap()
+        );
+        assert!(body.fee > params.plutus.script_fee(&ex_units) + params.min_fee_b);
+
+        // without the cost model the hash cannot be computed
+        params.plutus.cost_models.plutus_v3 = None;
+        assert_eq!(
+            intent.build_spending(&spent, &utxos, &params).err(),
+            Some(BuildError::CostModel(CostModelError::MissingCostModel(
+                Language::PlutusV3
+            )))
+        );
+    }
+}
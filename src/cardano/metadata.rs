@@ -0,0 +1,273 @@
+/*!
+Transaction metadata, the auxiliary data of a transaction.
+
+The [`Metadata`] gathers the entries of a transaction, one [`Metadatum`]
+per label, and [`attach`](Metadata::attach)es them to the transaction
+along with the `auxiliary_data_hash` of the body. The values are built
+with [`int`], [`text`], [`bytes`], [`list`] and [`map`]; the strings and
+byte strings are limited to 64 bytes on chain, the longer ones are split
+into a list of chunks.
+
+```
+# use cardano_connector::cardano::metadata::{Metadata, int, list, map, text};
+let metadata = Metadata::new()
+    // CIP-20 message, shown by the explorers
+    .with_message(["Invoice #42", "Thank you!"])
+    .with(1337, map([(text("id"), int(42)), (text("tags"), list([text("a"), text("b")]))]));
+assert_eq!(metadata.entries().len(), 2);
+```
+*/
+
+use super::{AuxiliaryData, Metadatum, MetadatumLabel, Tx};
+use pallas_codec::utils::{Int, KeyValuePairs, Nullable};
+use pallas_crypto::hash::Hasher;
+use pallas_primitives::alonzo::PostAlonzoAuxiliaryData;
+
+/// the label of the CIP-20 transaction messages
+pub const MESSAGE_LABEL: MetadatumLabel = 674;
+
+/// the maximum length of the strings and byte strings of the metadata
+pub const MAX_CHUNK_LEN: usize = 64;
+
+pub fn int(value: i64) -> Metadatum {
+    Metadatum::Int(Int::from(value))
+}
+
+/// the string, or the list of its chunks if longer than
+/// [`MAX_CHUNK_LEN`] bytes
+///
+/// The string is only split on character boundaries.
+pub fn text(value: &str) -> Metadatum {
+    if value.len() <= MAX_CHUNK_LEN {
+        return Metadatum::Text(value.to_owned());
+    }
+    Metadatum::Array(text_chunks(value).map(Metadatum::Text).collect())
+}
+
+/// the bytes, or the list of their chunks if longer than
+/// [`MAX_CHUNK_LEN`]
+pub fn bytes(value: &[u8]) -> Metadatum {
+    if value.len() <= MAX_CHUNK_LEN {
+        return Metadatum::Bytes(value.to_vec().into());
+    }
+    Metadatum::Array(
+        value
+            .chunks(MAX_CHUNK_LEN)
+            .map(|chunk| Metadatum::Bytes(chunk.to_vec().into()))
+            .collect(),
+    )
+}
+
+pub fn list(items: impl IntoIterator<Item = Metadatum>) -> Metadatum {
+    Metadatum::Array(items.into_iter().collect())
+}
+
+pub fn map(entries: impl IntoIterator<Item = (Metadatum, Metadatum)>) -> Metadatum {
+    Metadatum::Map(KeyValuePairs::from(entries.into_iter().collect::<Vec<_>>()))
+}
+
+fn text_chunks(value: &str) -> impl Iterator<Item = String> + '_ {
+    let mut rest = value;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let mut end = rest.len().min(MAX_CHUNK_LEN);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (chunk, tail) = rest.split_at(end);
+        rest = tail;
+        Some(chunk.to_owned())
+    })
+}
+
+/// see the [module documentation](self)
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Metadata {
+    entries: Vec<(MetadatumLabel, Metadatum)>,
+}
+
+impl Metadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the metadata of the transaction, empty if it has none
+    pub fn of_tx(tx: &Tx) -> Self {
+        let entries = match &tx.auxiliary_data {
+            Nullable::Some(AuxiliaryData::Shelley(metadata)) => Some(metadata.clone()),
+            Nullable::Some(AuxiliaryData::ShelleyMa(data)) => {
+                Some(data.transaction_metadata.clone())
+            }
+            Nullable::Some(AuxiliaryData::PostAlonzo(data)) => data.metadata.clone(),
+            Nullable::Null | Nullable::Undefined => None,
+        };
+        entries.into_iter().flat_map(Vec::from).collect()
+    }
+
+    /// set the entry of `label`, replacing the previous one
+    pub fn with(mut self, label: MetadatumLabel, metadatum: Metadatum) -> Self {
+        self.insert(label, metadatum);
+        self
+    }
+
+    /// set the CIP-20 message, one line per item, the lines longer than
+    /// [`MAX_CHUNK_LEN`] bytes are split
+    pub fn with_message<S: AsRef<str>>(self, lines: impl IntoIterator<Item = S>) -> Self {
+        let lines = lines
+            .into_iter()
+            .flat_map(|line| text_chunks(line.as_ref()).collect::<Vec<_>>())
+            .map(Metadatum::Text);
+        self.with(MESSAGE_LABEL, map([(text("msg"), list(lines))]))
+    }
+
+    pub fn insert(&mut self, label: MetadatumLabel, metadatum: Metadatum) {
+        self.entries.retain(|(existing, _)| *existing != label);
+        self.entries.push((label, metadatum));
+    }
+
+    pub fn get(&self, label: MetadatumLabel) -> Option<&Metadatum> {
+        self.entries
+            .iter()
+            .find(|(existing, _)| *existing == label)
+            .map(|(_, metadatum)| metadatum)
+    }
+
+    /// the entries, in the order they were set
+    pub fn entries(&self) -> &[(MetadatumLabel, Metadatum)] {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// the auxiliary data holding only this metadata, `None` if empty
+    pub fn to_auxiliary_data(&self) -> Option<AuxiliaryData> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(AuxiliaryData::PostAlonzo(PostAlonzoAuxiliaryData {
+            metadata: Some(KeyValuePairs::from(self.entries.clone())),
+            native_scripts: None,
+            plutus_scripts: None,
+        }))
+    }
+
+    /// add the entries to the transaction's metadata, replacing the
+    /// entries of the same labels, and update the `auxiliary_data_hash`
+    /// of the body
+    ///
+    /// The auxiliary scripts of the transaction are kept. This needs to
+    /// be done before the transaction is signed.
+    pub fn attach(&self, tx: &mut Tx) {
+        if self.is_empty() {
+            return;
+        }
+        let merge = |metadata: Option<pallas_primitives::Metadata>| {
+            let mut merged: Metadata = metadata.into_iter().flat_map(Vec::from).collect();
+            for (label, metadatum) in &self.entries {
+                merged.insert(*label, metadatum.clone());
+            }
+            KeyValuePairs::from(merged.entries)
+        };
+
+        let auxiliary_data = match std::mem::replace(&mut tx.auxiliary_data, Nullable::Null) {
+            Nullable::Some(AuxiliaryData::Shelley(metadata)) => {
+                AuxiliaryData::Shelley(merge(Some(metadata)))
+            }
+            Nullable::Some(AuxiliaryData::ShelleyMa(mut data)) => {
+                data.transaction_metadata = merge(Some(data.transaction_metadata));
+                AuxiliaryData::ShelleyMa(data)
+            }
+            Nullable::Some(AuxiliaryData::PostAlonzo(mut data)) => {
+                data.metadata = Some(merge(data.metadata));
+                AuxiliaryData::PostAlonzo(data)
+            }
+            Nullable::Null | Nullable::Undefined => {
+                AuxiliaryData::PostAlonzo(PostAlonzoAuxiliaryData {
+                    metadata: Some(merge(None)),
+                    native_scripts: None,
+                    plutus_scripts: None,
+                })
+            }
+        };
+
+        let hash = Hasher::<256>::hash_cbor(&auxiliary_data);
+        tx.transaction_body.auxiliary_data_hash = Some(hash.to_vec().into());
+        tx.auxiliary_data = Nullable::Some(auxiliary_data);
+    }
+}
+
+impl FromIterator<(MetadatumLabel, Metadatum)> for Metadata {
+    fn from_iter<I: IntoIterator<Item = (MetadatumLabel, Metadatum)>>(iter: I) -> Self {
+        let mut metadata = Self::new();
+        for (label, metadatum) in iter {
+            metadata.insert(label, metadatum);
+        }
+        metadata
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{assemble_tx, testing::empty_body};
+
+    #[test]
+    fn long_values_are_chunked() {
+        assert_eq!(text("hello"), Metadatum::Text("hello".to_owned()));
+
+        let long = "é".repeat(40);
+        let Metadatum::Array(chunks) = text(&long) else {
+            panic!("expected chunks");
+        };
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks.iter().all(|chunk| matches!(
+            chunk,
+            Metadatum::Text(chunk) if chunk.len() <= MAX_CHUNK_LEN
+        )));
+        let joined: String = chunks
+            .iter()
+            .filter_map(|chunk| match chunk {
+                Metadatum::Text(chunk) => Some(chunk.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(joined, long);
+
+        let Metadatum::Array(chunks) = bytes(&[1; 130]) else {
+            panic!("expected chunks");
+        };
+        assert_eq!(chunks.len(), 3);
+    }
+
+    #[test]
+    fn attach_to_a_transaction() {
+        let mut tx = assemble_tx(empty_body(), &[], true, None);
+        Metadata::new().attach(&mut tx);
+        assert_eq!(tx.auxiliary_data, Nullable::Null);
+        assert_eq!(tx.transaction_body.auxiliary_data_hash, None);
+
+        Metadata::new()
+            .with_message(["hello"])
+            .with(1, int(1))
+            .attach(&mut tx);
+        Metadata::new().with(1, int(2)).attach(&mut tx);
+
+        let metadata = Metadata::of_tx(&tx);
+        assert_eq!(metadata.get(1), Some(&int(2)));
+        assert_eq!(
+            metadata.get(MESSAGE_LABEL),
+            Some(&map([(text("msg"), list([text("hello")]))]))
+        );
+        let Nullable::Some(auxiliary_data) = &tx.auxiliary_data else {
+            panic!("expected auxiliary data");
+        };
+        assert_eq!(
+            tx.transaction_body.auxiliary_data_hash,
+            Some(Hasher::<256>::hash_cbor(auxiliary_data).to_vec().into())
+        );
+    }
+}
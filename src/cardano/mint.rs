@@ -0,0 +1,643 @@
+/*!
+Minting and burning of native assets under a native script policy.
+
+The [`MintBuilder`] gathers the assets to mint (or burn) under one policy
+with the metadata of the tokens and turns them into a [`TxIntent`]: the
+policy script is added to the witness set and the minted assets go to
+the change address unless they are paid to someone.
+
+# Royalties (CIP-27)
+
+The royalties of a collection are declared by minting, under the policy
+of the collection, a token with an empty asset name along with a
+[`ROYALTY_LABEL`] metadata holding the [`Royalty`] rate and address.
+Marketplaces only honour the first royalty token of a policy: it has to
+be minted before (or with) the first tokens of the collection.
+
+```
+# use cardano_connector::{Address, cardano::mint::{MintBuilder, Royalty}};
+# use pallas_primitives::conway::NativeScript;
+# fn test(policy: NativeScript, artist: Address) -> anyhow::Result<()> {
+let royalty = Royalty::new(0.05, &artist)?;
+let mint = MintBuilder::new(policy)
+    .with_royalty(&royalty)
+    .mint(b"Artwork #1".to_vec(), 1);
+# Ok(()) }
+```
+
+# Rich tokens (CIP-68)
+
+A CIP-68 token is a pair of assets under the same policy: the user token
+held by its owner and a reference NFT, locked at a script address (or any
+address the issuer controls), whose inline datum holds the metadata. The
+asset names are the same name prefixed with the CIP-67 label of their
+role, see [`cip68_asset_name`].
+
+```
+# use cardano_connector::{Address, cardano::mint::{MintBuilder, NFT_LABEL, cip68_datum}};
+# use pallas_primitives::{PlutusData, conway::NativeScript};
+# fn test(policy: NativeScript, reference: Address, metadata: Vec<(PlutusData, PlutusData)>) {
+let mint = MintBuilder::new(policy).mint_cip68(
+    b"Artwork #1",
+    NFT_LABEL,
+    1,
+    &reference,
+    cip68_datum(metadata, 1),
+);
+# }
+```
+*/
+
+use super::{
+    AssetName, BuildError, BuildParams, Coin, Metadatum, MetadatumLabel, Multiasset,
+    NonEmptyKeyValuePairs, PolicyId, Recipient, Tx, TxIntent, UtxoSet, Value, assets_of,
+    native_script::{script_hash, validity_interval},
+};
+use crate::Address;
+use pallas_codec::utils::{KeyValuePairs, MaybeIndefArray};
+use pallas_primitives::{BigInt, Constr, PlutusData, conway::NativeScript};
+use std::collections::BTreeMap;
+
+/// Metadata label of the CIP-27 royalty records.
+pub const ROYALTY_LABEL: MetadatumLabel = 777;
+
+/// CIP-67 label of the CIP-68 reference NFT, holding the metadata datum
+pub const REFERENCE_LABEL: u16 = 100;
+/// CIP-67 label of the CIP-68 user NFT
+pub const NFT_LABEL: u16 = 222;
+/// CIP-67 label of the CIP-68 user fungible token
+pub const FT_LABEL: u16 = 333;
+/// CIP-67 label of the CIP-68 user rich fungible token
+pub const RFT_LABEL: u16 = 444;
+
+/// the metadata strings are limited to 64 bytes, longer strings are split
+/// in an array of chunks
+const MAX_METADATA_STRING: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RoyaltyError {
+    #[error("Invalid royalty rate `{0}', expected a decimal between 0 and 1")]
+    InvalidRate(String),
+    #[error("Invalid royalty address `{0}'")]
+    InvalidAddress(String),
+    #[error("Missing `{0}' entry in the royalty record")]
+    Missing(&'static str),
+    #[error("The royalty record is not a metadata map")]
+    NotAMap,
+}
+
+/// The royalties of a collection, as defined by CIP-27.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Royalty {
+    /// the share of the sale price paid to the `address`, as a decimal
+    /// string between `0` and `1` (e.g. `"0.05"` for 5%)
+    rate: String,
+    /// the bech32 address receiving the royalties
+    address: String,
+}
+
+impl Royalty {
+    pub fn new(rate: f64, address: &Address) -> Result<Self, RoyaltyError> {
+        let address = address
+            .to_bech32()
+            .map_err(|error| RoyaltyError::InvalidAddress(error.to_string()))?;
+        Self::parse(rate.to_string(), address)
+    }
+
+    fn parse(rate: String, address: String) -> Result<Self, RoyaltyError> {
+        match rate.parse::<f64>() {
+            Ok(value) if (0.0..=1.0).contains(&value) => {}
+            _ => return Err(RoyaltyError::InvalidRate(rate)),
+        }
+        if Address::from_bech32(&address).is_err() {
+            return Err(RoyaltyError::InvalidAddress(address));
+        }
+
+        Ok(Self { rate, address })
+    }
+
+    pub fn rate(&self) -> &str {
+        &self.rate
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// the `{ "rate": ..., "addr": ... }` record, to attach under the
+    /// [`ROYALTY_LABEL`]
+    pub fn to_metadatum(&self) -> Metadatum {
+        Metadatum::Map(KeyValuePairs::from(vec![
+            (text("rate"), text(&self.rate)),
+            (text("addr"), chunked(&self.address)),
+        ]))
+    }
+
+    /// parse and validate a royalty record, e.g. found on chain
+    ///
+    /// The `pct` key of the first version of CIP-27 is accepted in place
+    /// of `rate`, the address may be a string or an array of chunks.
+    pub fn from_metadatum(metadatum: &Metadatum) -> Result<Self, RoyaltyError> {
+        let Metadatum::Map(entries) = metadatum else {
+            return Err(RoyaltyError::NotAMap);
+        };
+        let entry = |key: &str| {
+            entries.iter().find_map(|(found, value)| match found {
+                Metadatum::Text(found) if found == key => Some(value),
+                _ => None,
+            })
+        };
+
+        let rate = entry("rate")
+            .or_else(|| entry("pct"))
+            .ok_or(RoyaltyError::Missing("rate"))?;
+        let rate = joined(rate).ok_or_else(|| RoyaltyError::InvalidRate(format!("{rate:?}")))?;
+        let address = entry("addr").ok_or(RoyaltyError::Missing("addr"))?;
+        let address =
+            joined(address).ok_or_else(|| RoyaltyError::InvalidAddress(format!("{address:?}")))?;
+
+        Self::parse(rate, address)
+    }
+}
+
+fn text(text: &str) -> Metadatum {
+    Metadatum::Text(text.to_owned())
+}
+
+/// the string as is if short enough, an array of chunks otherwise
+fn chunked(string: &str) -> Metadatum {
+    if string.len() <= MAX_METADATA_STRING {
+        return text(string);
+    }
+    // bech32 strings are ASCII, the chunks are valid UTF-8
+    Metadatum::Array(
+        string
+            .as_bytes()
+            .chunks(MAX_METADATA_STRING)
+            .map(|chunk| text(&String::from_utf8_lossy(chunk)))
+            .collect(),
+    )
+}
+
+/// the string or the concatenation of an array of strings
+fn joined(metadatum: &Metadatum) -> Option<String> {
+    match metadatum {
+        Metadatum::Text(text) => Some(text.clone()),
+        Metadatum::Array(chunks) => chunks
+            .iter()
+            .map(|chunk| match chunk {
+                Metadatum::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+/// the 4 bytes CIP-67 prefix of the asset names of the given label
+///
+/// The label and its CRC-8 checksum are enclosed in zero nibbles:
+/// `0 | label (16 bits) | crc-8 | 0`.
+pub fn label_prefix(label: u16) -> [u8; 4] {
+    let prefix = (u32::from(label) << 12) | (u32::from(crc8(&label.to_be_bytes())) << 4);
+    prefix.to_be_bytes()
+}
+
+/// the asset `name` prefixed with the CIP-67 `label`
+pub fn cip68_asset_name(label: u16, name: &[u8]) -> AssetName {
+    let mut asset_name = label_prefix(label).to_vec();
+    asset_name.extend_from_slice(name);
+    asset_name.into()
+}
+
+/// the CIP-67 label and the name of a label prefixed asset name, `None`
+/// if the asset name has no valid prefix
+pub fn parse_asset_name(asset_name: &[u8]) -> Option<(u16, &[u8])> {
+    let (prefix, name) = asset_name.split_first_chunk::<4>()?;
+    let label = (u32::from_be_bytes(*prefix) >> 12) as u16;
+    (label_prefix(label) == *prefix).then_some((label, name))
+}
+
+/// the CRC-8 (polynomial `0x07`) used for the checksum of the CIP-67 labels
+fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |crc, byte| {
+        (0..8).fold(crc ^ byte, |crc, _| {
+            if crc & 0x80 == 0 {
+                crc << 1
+            } else {
+                (crc << 1) ^ 0x07
+            }
+        })
+    })
+}
+
+/// the inline datum of a CIP-68 reference NFT: `metadata` is the map of
+/// the token properties (`name`, `image`... as bytes)
+pub fn cip68_datum(metadata: Vec<(PlutusData, PlutusData)>, version: u64) -> PlutusData {
+    PlutusData::Constr(Constr {
+        tag: 121,
+        any_constructor: None,
+        fields: MaybeIndefArray::Def(vec![
+            PlutusData::Map(KeyValuePairs::from(metadata)),
+            PlutusData::BigInt(BigInt::Int((version as i64).into())),
+        ]),
+    })
+}
+
+/// see the [module documentation](self)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MintBuilder {
+    policy: NativeScript,
+    intent: TxIntent,
+    /// the outputs of the CIP-68 reference NFTs, their lovelace is only
+    /// known with the protocol parameters
+    references: Vec<Recipient>,
+}
+
+impl MintBuilder {
+    pub fn new(policy: NativeScript) -> Self {
+        Self {
+            intent: TxIntent::new().with_native_script(policy.clone()),
+            policy,
+            references: Vec::new(),
+        }
+    }
+
+    pub fn policy(&self) -> &NativeScript {
+        &self.policy
+    }
+
+    pub fn policy_id(&self) -> PolicyId {
+        script_hash(&self.policy)
+    }
+
+    /// mint `quantity` of the asset, or burn it if `quantity` is negative
+    pub fn mint(mut self, name: impl Into<AssetName>, quantity: i64) -> Self {
+        let policy_id = self.policy_id();
+        self.intent = self.intent.mint(policy_id, name.into(), quantity);
+        self
+    }
+
+    /// burn every asset of the policy held in `utxos`
+    ///
+    /// The UTxOs holding the assets are selected when building the
+    /// transaction.
+    pub fn burn_all(self, utxos: &UtxoSet) -> Self {
+        let policy_id = self.policy_id();
+        let mut held: BTreeMap<AssetName, u64> = BTreeMap::new();
+        for (asset, quantity) in utxos.iter().flat_map(|utxo| assets_of(&utxo.value())) {
+            if asset.policy == policy_id {
+                *held.entry(asset.name).or_default() += quantity;
+            }
+        }
+
+        held.into_iter().fold(self, |builder, (name, quantity)| {
+            // the mint quantities are signed 64 bits, a larger supply
+            // needs more than one burn
+            builder.mint(name, -i64::try_from(quantity).unwrap_or(i64::MAX))
+        })
+    }
+
+    /// whether the builder mints or burns anything
+    pub fn is_empty(&self) -> bool {
+        self.intent
+            .mint
+            .iter()
+            .all(|(_, _, quantity)| *quantity == 0)
+    }
+
+    pub fn with_metadata(mut self, label: MetadatumLabel, metadatum: Metadatum) -> Self {
+        self.intent = self.intent.with_metadata(label, metadatum);
+        self
+    }
+
+    /// mint the CIP-27 royalty token of the policy with its record
+    pub fn with_royalty(self, royalty: &Royalty) -> Self {
+        self.mint(Vec::new(), 1)
+            .with_metadata(ROYALTY_LABEL, royalty.to_metadatum())
+    }
+
+    /// mint the CIP-68 pair of `name`: the reference NFT, paid to
+    /// `reference_address` with the metadata `datum`, and `quantity` of
+    /// the user token of the given `label` (e.g. [`NFT_LABEL`])
+    ///
+    /// The user tokens go to the change address unless they are paid to
+    /// someone.
+    pub fn mint_cip68(
+        mut self,
+        name: &[u8],
+        label: u16,
+        quantity: i64,
+        reference_address: &Address,
+        datum: PlutusData,
+    ) -> Self {
+        let reference = cip68_asset_name(REFERENCE_LABEL, name);
+        let assets =
+            NonEmptyKeyValuePairs::from_vec(vec![(reference.clone(), 1.try_into().unwrap())])
+                .and_then(|assets| Multiasset::from_vec(vec![(self.policy_id(), assets)]))
+                .unwrap();
+        self.references.push(Recipient {
+            address: reference_address.to_vec().into(),
+            value: Value::Multiasset(0, assets),
+            datum: Some(datum),
+        });
+        self.mint(reference, 1)
+            .mint(cip68_asset_name(label, name), quantity)
+    }
+
+    /// the intent minting the assets, to complete with payments or
+    /// certificates before building it
+    ///
+    /// The CIP-68 reference outputs hold the min-ADA for the given
+    /// `coins_per_utxo_byte`.
+    pub fn intent(&self, coins_per_utxo_byte: Coin) -> TxIntent {
+        let mut intent = self.intent.clone();
+        for reference in &self.references {
+            intent
+                .recipients
+                .push(reference.clone().with_min_ada(coins_per_utxo_byte));
+        }
+        intent
+    }
+
+    /// build the transaction, the minted assets go to the change address
+    ///
+    /// The validity interval of the transaction is narrowed to the time
+    /// locks of the policy, see [`validity_interval`].
+    pub fn build(&self, utxos: &UtxoSet, params: &BuildParams) -> Result<Tx, BuildError> {
+        let (valid_from, valid_until) = validity_interval(&self.policy);
+        let mut intent = self.intent(params.coins_per_utxo_byte);
+        if let Some(slot) = valid_from {
+            intent = intent.valid_from(slot);
+        }
+        let params = BuildParams {
+            ttl: match (params.ttl, valid_until) {
+                (Some(ttl), Some(until)) => Some(ttl.min(until)),
+                (ttl, until) => ttl.or(until),
+            },
+            ..params.clone()
+        };
+        intent.build(utxos, &params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{
+        Hash, TransactionInput, TransactionOutput, Utxo, assets_of, lovelace_of,
+        min_ada_for_output, testing::base_address,
+    };
+    use pallas_codec::utils::CborWrap;
+    use pallas_primitives::{babbage::PseudoPostAlonzoTransactionOutput, conway::DatumOption};
+
+    #[test]
+    fn royalty_record() {
+        let royalty = Royalty::new(0.05, &base_address(1)).unwrap();
+        let metadatum = royalty.to_metadatum();
+
+        // a base address is longer than 64 characters
+        let Metadatum::Map(entries) = &metadatum else {
+            panic!("expected a map");
+        };
+        assert!(matches!(entries[1].1, Metadatum::Array(_)));
+        assert_eq!(Royalty::from_metadatum(&metadatum), Ok(royalty));
+
+        assert_eq!(
+            Royalty::new(1.5, &base_address(1)),
+            Err(RoyaltyError::InvalidRate("1.5".to_owned()))
+        );
+        let legacy = Metadatum::Map(KeyValuePairs::from(vec![
+            (text("pct"), text("0.1")),
+            (text("addr"), text("not an address")),
+        ]));
+        assert!(matches!(
+            Royalty::from_metadatum(&legacy),
+            Err(RoyaltyError::InvalidAddress(_))
+        ));
+    }
+
+    #[test]
+    fn cip67_labels() {
+        // the prefixes listed in CIP-68
+        assert_eq!(hex::encode(label_prefix(REFERENCE_LABEL)), "000643b0");
+        assert_eq!(hex::encode(label_prefix(NFT_LABEL)), "000de140");
+        assert_eq!(hex::encode(label_prefix(FT_LABEL)), "0014df10");
+        assert_eq!(hex::encode(label_prefix(RFT_LABEL)), "001bc280");
+
+        let name = cip68_asset_name(NFT_LABEL, b"nft");
+        assert_eq!(parse_asset_name(&name), Some((NFT_LABEL, &b"nft"[..])));
+        assert_eq!(parse_asset_name(b"nft"), None);
+        assert_eq!(parse_asset_name(b"\x00\x0d\xe1\x41nft"), None);
+    }
+
+    #[test]
+    fn mint_cip68_pair() {
+        let policy = NativeScript::ScriptPubkey(Hash::from([1; 28]));
+        let utxos = UtxoSet::from(vec![Utxo {
+            input: TransactionInput {
+                transaction_id: Hash::from([0; 32]),
+                index: 0,
+            },
+            output: TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+                address: base_address(1).to_vec().into(),
+                value: Value::Coin(10_000_000),
+                datum_option: None,
+                script_ref: None,
+            }),
+        }]);
+        let datum = cip68_datum(Vec::new(), 1);
+        let builder = MintBuilder::new(policy).mint_cip68(
+            b"nft",
+            NFT_LABEL,
+            1,
+            &base_address(2),
+            datum.clone(),
+        );
+
+        let tx = builder
+            .build(&utxos, &BuildParams::defaults(base_address(1)))
+            .unwrap();
+        let body = &tx.transaction_body;
+
+        let minted: Vec<_> = body.mint.as_ref().unwrap()[0].1.iter().cloned().collect();
+        assert_eq!(
+            minted,
+            vec![
+                (
+                    cip68_asset_name(REFERENCE_LABEL, b"nft"),
+                    1.try_into().unwrap()
+                ),
+                (cip68_asset_name(NFT_LABEL, b"nft"), 1.try_into().unwrap()),
+            ]
+        );
+        let TransactionOutput::PostAlonzo(reference) = &body.outputs[0] else {
+            panic!("expected a post alonzo output");
+        };
+        assert_eq!(reference.address.as_slice(), base_address(2).to_vec());
+        assert_eq!(
+            reference.datum_option,
+            Some(DatumOption::Data(CborWrap(datum)))
+        );
+        let coin = lovelace_of(&reference.value);
+        assert_eq!(coin, min_ada_for_output(&body.outputs[0], 4_310));
+    }
+
+    #[test]
+    fn time_locked_policy() {
+        use crate::cardano::native_script::{after, all, before, sig};
+
+        let policy = all([sig(Hash::from([1; 28])), after(100), before(1_000)]);
+        let utxos = UtxoSet::from(vec![Utxo {
+            input: TransactionInput {
+                transaction_id: Hash::from([0; 32]),
+                index: 0,
+            },
+            output: TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+                address: base_address(1).to_vec().into(),
+                value: Value::Coin(10_000_000),
+                datum_option: None,
+                script_ref: None,
+            }),
+        }]);
+        let params = BuildParams {
+            ttl: Some(5_000),
+            ..BuildParams::defaults(base_address(1))
+        };
+
+        let tx = MintBuilder::new(policy.clone())
+            .mint(b"token".to_vec(), 1_000)
+            .build(&utxos, &params)
+            .unwrap();
+
+        assert_eq!(tx.transaction_body.validity_interval_start, Some(100));
+        assert_eq!(tx.transaction_body.ttl, Some(1_000));
+        assert_eq!(
+            tx.transaction_witness_set
+                .native_script
+                .map(|scripts| scripts.to_vec()),
+            Some(vec![policy])
+        );
+    }
+
+    #[test]
+    fn mint_overflow() {
+        let policy = NativeScript::ScriptPubkey(Hash::from([1; 28]));
+        let utxos = UtxoSet::from(vec![Utxo {
+            input: TransactionInput {
+                transaction_id: Hash::from([0; 32]),
+                index: 0,
+            },
+            output: TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+                address: base_address(1).to_vec().into(),
+                value: Value::Coin(10_000_000),
+                datum_option: None,
+                script_ref: None,
+            }),
+        }]);
+
+        let result = MintBuilder::new(policy.clone())
+            .mint(b"token".to_vec(), i64::MAX)
+            .mint(b"token".to_vec(), 1)
+            .build(&utxos, &BuildParams::defaults(base_address(1)));
+
+        assert_eq!(
+            result.err(),
+            Some(BuildError::MintOverflow {
+                policy: script_hash(&policy),
+                name: b"token".to_vec().into(),
+            })
+        );
+    }
+
+    #[test]
+    fn burn_all_of_policy() {
+        let policy = NativeScript::ScriptPubkey(Hash::from([1; 28]));
+        let policy_id = script_hash(&policy);
+        let held = |index: u64, name: &[u8], quantity: u64| {
+            let assets = NonEmptyKeyValuePairs::from_vec(vec![(
+                name.to_vec().into(),
+                quantity.try_into().unwrap(),
+            )])
+            .unwrap();
+            Utxo {
+                input: TransactionInput {
+                    transaction_id: Hash::from([0; 32]),
+                    index,
+                },
+                output: TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+                    address: base_address(1).to_vec().into(),
+                    value: Value::Multiasset(
+                        5_000_000,
+                        Multiasset::from_vec(vec![(policy_id, assets)]).unwrap(),
+                    ),
+                    datum_option: None,
+                    script_ref: None,
+                }),
+            }
+        };
+        let utxos = UtxoSet::from(vec![held(0, b"a", 10), held(1, b"b", 1), held(2, b"a", 5)]);
+
+        let builder = MintBuilder::new(policy).burn_all(&utxos);
+        let tx = builder
+            .build(&utxos, &BuildParams::defaults(base_address(1)))
+            .unwrap();
+
+        let burned: Vec<_> = tx.transaction_body.mint.as_ref().unwrap()[0]
+            .1
+            .iter()
+            .map(|(name, quantity)| (name.to_vec(), i64::from(quantity)))
+            .collect();
+        assert_eq!(burned, vec![(b"a".to_vec(), -15), (b"b".to_vec(), -1)]);
+        assert_eq!(tx.transaction_body.inputs.len(), 3);
+        let change = Utxo {
+            input: utxos.as_slice()[0].input.clone(),
+            output: tx.transaction_body.outputs[0].clone(),
+        };
+        assert!(assets_of(&change.value()).is_empty());
+        assert!(
+            MintBuilder::new(NativeScript::InvalidBefore(0))
+                .burn_all(&utxos)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn mint_with_royalty() {
+        let policy = NativeScript::ScriptPubkey(Hash::from([1; 28]));
+        let utxos = UtxoSet::from(vec![Utxo {
+            input: TransactionInput {
+                transaction_id: Hash::from([0; 32]),
+                index: 0,
+            },
+            output: TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+                address: base_address(1).to_vec().into(),
+                value: Value::Coin(10_000_000),
+                datum_option: None,
+                script_ref: None,
+            }),
+        }]);
+        let builder = MintBuilder::new(policy.clone())
+            .with_royalty(&Royalty::new(0.05, &base_address(1)).unwrap())
+            .mint(b"nft".to_vec(), 1);
+
+        let tx = builder
+            .build(&utxos, &BuildParams::defaults(base_address(1)))
+            .unwrap();
+
+        let mint = tx.transaction_body.mint.as_ref().unwrap();
+        assert_eq!(mint.len(), 1);
+        assert_eq!(mint[0].0, builder.policy_id());
+        assert_eq!(mint[0].1.len(), 2);
+        let change = Utxo {
+            input: utxos.as_slice()[0].input.clone(),
+            output: tx.transaction_body.outputs[0].clone(),
+        };
+        assert_eq!(assets_of(&change.value()).len(), 2);
+        assert!(tx.transaction_body.auxiliary_data_hash.is_some());
+        assert_eq!(
+            tx.transaction_witness_set.native_script.unwrap().to_vec(),
+            vec![policy]
+        );
+    }
+}
@@ -1,25 +1,88 @@
+pub mod assets;
+mod builder;
+pub mod coin_selection;
+pub mod cost_models;
+pub mod escrow;
+pub mod export;
+pub mod fees;
+pub mod format;
+mod hash;
+mod ids;
+pub mod import;
+pub mod inspect;
+pub mod intent;
+pub mod metadata;
+pub mod mint;
+pub mod native_script;
+mod pending;
+pub mod plutus;
+pub mod script_context;
+mod slot;
+mod summary;
+#[cfg(test)]
+pub(crate) mod testing;
+#[cfg(feature = "transaction")]
+pub mod txbuilder;
+mod utxo_set;
+mod validity;
+mod value;
+pub mod vesting;
+pub mod witness;
+
+pub use self::{
+    builder::TransactionBuilder,
+    fees::min_ada_for_output,
+    hash::{HashError, HashExt},
+    ids::{AssetFingerprint, AssetId, DRepId, IdError, PoolId},
+    intent::{BuildError, BuildParams, Recipient, TxIntent},
+    metadata::Metadata,
+    pending::{PendingTx, PendingUtxos},
+    slot::{EpochInfo, SlotConfig},
+    summary::{AddressChange, DepositParams, SUMMARY_LABEL, SummaryError, TxSummary},
+    utxo_set::UtxoSet,
+    validity::{ValidityError, check_validity, with_fresh_ttl},
+    value::{ValueExt, WalletValue},
+    witness::{WitnessSetExt, assemble_tx, verify_witnesses},
+};
 use crate::Address;
 use pallas_codec::minicbor;
 #[cfg(feature = "transaction")]
 use pallas_primitives::babbage::PseudoPostAlonzoTransactionOutput;
 use pallas_primitives::conway::PseudoTransactionOutput;
 pub use pallas_primitives::{
-    AssetName, Coin, Hash, NonEmptyKeyValuePairs, PolicyId, PositiveCoin, TransactionIndex,
-    TransactionInput,
+    AddrKeyhash, AssetName, Coin, Hash, Metadatum, MetadatumLabel, NonEmptyKeyValuePairs, PolicyId,
+    PositiveCoin, StakeCredential, TransactionIndex, TransactionInput,
     alonzo::Value as LegacyValue,
-    conway::{Multiasset, TransactionBody, TransactionOutput, Tx, Value, WitnessSet},
+    conway::{
+        AuxiliaryData, Certificate, Multiasset, TransactionBody, TransactionOutput, Tx, Value,
+        WitnessSet,
+    },
 };
 #[cfg(feature = "transaction")]
-use pallas_txbuilder::{Input, StagingTransaction};
-use std::collections::HashMap;
+use pallas_txbuilder::StagingTransaction;
+use std::collections::BTreeMap;
 #[cfg(feature = "transaction")]
 use thiserror::Error;
+#[cfg(feature = "transaction")]
+use txbuilder::StagingTransactionExt as _;
 
 pub type TxHash = Hash<32>;
 
+/// the id of the transaction of this body: the Blake2b-256 of its CBOR
+///
+/// This is what the vkey witnesses sign and what `submitTx` returns. The
+/// body is encoded again: for a transaction decoded from the bytes of a
+/// wallet, [`inspect::tx_hash`] on these bytes gives the id even if they
+/// are not in the canonical encoding.
+pub fn tx_hash(body: &TransactionBody) -> TxHash {
+    pallas_crypto::hash::Hasher::<256>::hash_cbor(body)
+}
+
 /// decode the CBOR encoded UTxO as returned from the CIP30 getUtxos
 /// API.
-#[derive(Debug, PartialEq, Eq, Clone, pallas_codec::minicbor::Decode)]
+#[derive(
+    Debug, PartialEq, Eq, Clone, pallas_codec::minicbor::Decode, pallas_codec::minicbor::Encode,
+)]
 #[cbor(array)]
 pub struct Utxo {
     #[n(0)]
@@ -53,31 +116,7 @@ impl Utxo {
     /// Return the full [`Value`] held by this UTxO, including native assets.
     pub fn value(&self) -> Value {
         match &self.output {
-            TransactionOutput::Legacy(output) => match &output.amount {
-                LegacyValue::Coin(coin) => Value::Coin(*coin),
-                LegacyValue::Multiasset(coin, multiasset) => {
-                    let converted_assets = multiasset
-                        .iter()
-                        .filter_map(|(policy, assets)| {
-                            let converted_assets = assets
-                                .iter()
-                                .filter_map(|(asset_name, amount)| {
-                                    PositiveCoin::try_from(*amount)
-                                        .ok()
-                                        .map(|amount| (asset_name.clone(), amount))
-                                })
-                                .collect::<Vec<_>>();
-                            NonEmptyKeyValuePairs::from_vec(converted_assets)
-                                .map(|converted_assets| (*policy, converted_assets))
-                        })
-                        .collect::<Vec<_>>();
-
-                    match Multiasset::from_vec(converted_assets) {
-                        Some(multiasset) => Value::Multiasset(*coin, multiasset),
-                        None => Value::Coin(*coin),
-                    }
-                }
-            },
+            TransactionOutput::Legacy(output) => value_from_legacy(&output.amount),
             TransactionOutput::PostAlonzo(output) => output.value.clone(),
         }
     }
@@ -90,6 +129,27 @@ impl Utxo {
     }
 }
 
+/// convert a pre-Conway [`LegacyValue`] into a [`Value`]
+///
+/// Assets with a `0` quantity (allowed before Conway) are dropped.
+pub fn value_from_legacy(value: &LegacyValue) -> Value {
+    match value {
+        LegacyValue::Coin(coin) => Value::Coin(*coin),
+        LegacyValue::Multiasset(coin, multiasset) => multiasset
+            .iter()
+            .flat_map(|(policy, assets)| {
+                assets
+                    .iter()
+                    .map(|(name, amount)| (AssetId::new(*policy, name.clone()), *amount))
+            })
+            .fold(
+                WalletValue::from_lovelace(*coin),
+                |value, (asset, amount)| value.with_asset(asset, amount),
+            )
+            .to_value(),
+    }
+}
+
 /// Extract lovelace from a [`Value`], ignoring native assets.
 pub fn lovelace_of(value: &Value) -> Coin {
     match value {
@@ -97,127 +157,188 @@ pub fn lovelace_of(value: &Value) -> Coin {
     }
 }
 
+/// The native assets of a [`Value`] with their quantity.
+pub fn assets_of(value: &Value) -> BTreeMap<AssetId, u64> {
+    match value {
+        Value::Coin(_) => BTreeMap::new(),
+        Value::Multiasset(_, multiasset) => multiasset
+            .iter()
+            .flat_map(|(policy, assets)| {
+                assets.iter().map(|(name, quantity)| {
+                    (AssetId::new(*policy, name.clone()), u64::from(quantity))
+                })
+            })
+            .collect(),
+    }
+}
+
 #[derive(Debug, Error)]
 #[cfg(feature = "transaction")]
 pub enum GroupUtxoError {
     #[error("Not enough to pay the fee ({fee}), available funds are {sum}.")]
     CantPayFee { fee: Coin, sum: Coin },
+    #[error("The destination address has no network id")]
+    UnknownNetwork,
+    #[error("Input {}#{} is on the network {found}, expected {expected}", .input.transaction_id, .input.index)]
+    NetworkMismatch {
+        input: TransactionInput,
+        expected: u8,
+        found: u8,
+    },
+    #[error(transparent)]
+    Conversion(#[from] txbuilder::ConversionError),
+    #[error("Couldn't decode the built transaction: {0}")]
+    Decode(String),
 }
 
 /// function to group the given list of UTxO into one output
 ///
-/// TODO:
-///
-/// - [x] minus the fees
-/// - [x] output address
-/// - [ ] return the built transaction
+/// The returned transaction spends all the `utxos`, pays the fee
+/// estimated from the protocol `params` and sends the rest to `to` in a
+/// single output. The network id is taken from the `to` address and all
+/// the spent UTxOs must be on the same network.
 #[cfg(feature = "transaction")]
 pub fn group_utxos<'a>(
     utxos: impl IntoIterator<Item = &'a Utxo>,
-    fee: Coin,
+    params: &fees::ProtocolParams,
     to: Address,
-) -> Result<TransactionOutput, GroupUtxoError> {
+) -> Result<Tx, GroupUtxoError> {
+    use pallas_txbuilder::BuildConway as _;
+
     // extract the network id from the received address and validate it against
     // the utxos outputs in the list
-    let network_id = todo!();
+    let network_id = to.network().ok_or(GroupUtxoError::UnknownNetwork)?.value();
 
-    let mut inputs = Vec::new();
-    let mut value = sumup(utxos.into_iter().map(|utxo| {
-        inputs.push(utxo.input.clone());
-        &utxo.output
-    }));
+    let utxos: UtxoSet = utxos.into_iter().cloned().collect();
+    for utxo in &utxos {
+        let address = utxo.address().map_err(txbuilder::ConversionError::from)?;
+        if let Some(found) = address.network().map(|network| network.value())
+            && found != network_id
+        {
+            return Err(GroupUtxoError::NetworkMismatch {
+                input: utxo.input.clone(),
+                expected: network_id,
+                found,
+            });
+        }
+    }
 
-    let staging = inputs
-        .into_iter()
-        .fold(StagingTransaction::new(), |staging, input| {
-            staging.input(Input::new(input.transaction_id, input.index))
+    let total = utxos.total();
+    let mut fee = params.min_fee_b;
+    loop {
+        // deduce the fees
+        let mut value = total.clone();
+        match &mut value {
+            Value::Coin(c) | Value::Multiasset(c, _) => {
+                let Some(rem) = c.checked_sub(fee) else {
+                    return Err(GroupUtxoError::CantPayFee { fee, sum: *c });
+                };
+
+                *c = rem;
+            }
+        }
+
+        let address = to.to_vec();
+        let output = TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+            address: address.into(),
+            value,
+            datum_option: None,
+            script_ref: None,
         });
-    let staging = staging.network_id(network_id);
 
-    // deduce the fees
-    match &mut value {
-        Value::Coin(c) | Value::Multiasset(c, _) => {
-            let Some(rem) = c.checked_sub(fee) else {
-                return Err(GroupUtxoError::CantPayFee { fee, sum: *c });
-            };
+        let built = StagingTransaction::new()
+            .inputs(&utxos)
+            .output(txbuilder::to_output(&output)?)
+            .fee(fee)
+            .network_id(network_id)
+            .build_conway_raw()
+            .map_err(txbuilder::ConversionError::from)?;
+        let tx: Tx = minicbor::decode(&built.tx_bytes.0)
+            .map_err(|error| GroupUtxoError::Decode(error.to_string()))?;
 
-            *c = rem;
+        let signers = witness::RequiredSigners::infer(&tx.transaction_body, &utxos)
+            .signers
+            .len();
+        let required = params.estimate_fee_with_signers(&tx, signers);
+        if fee >= required {
+            return Ok(tx);
         }
+        fee = required;
     }
-
-    let address = to.to_vec();
-    let output = PseudoPostAlonzoTransactionOutput {
-        address: address.into(),
-        value,
-        datum_option: None,
-        script_ref: None,
-    };
-
-    Ok(TransactionOutput::PostAlonzo(output))
 }
 
 pub fn sumup<'a>(outputs: impl IntoIterator<Item = &'a TransactionOutput>) -> Value {
-    let mut coin = 0;
-    let mut assets: HashMap<PolicyId, HashMap<AssetName, PositiveCoin>> = HashMap::new();
-
-    for output in outputs {
-        match output {
-            PseudoTransactionOutput::Legacy(tx) => match &tx.amount {
-                pallas_primitives::alonzo::Value::Coin(c) => {
-                    coin += c;
-                }
-                pallas_primitives::alonzo::Value::Multiasset(c, multiasset) => {
-                    coin += c;
-
-                    for (cert, asset) in multiasset.iter() {
-                        let entry = assets.entry(*cert).or_default();
-
-                        for (asset_name, amount) in asset.iter() {
-                            entry
-                                .entry(asset_name.clone())
-                                .and_modify(|t| {
-                                    *t = PositiveCoin::try_from(u64::from(*t) + amount).unwrap()
-                                })
-                                .or_insert_with(|| PositiveCoin::try_from(*amount).unwrap());
-                        }
-                    }
-                }
-            },
-            PseudoTransactionOutput::PostAlonzo(tx) => match &tx.value {
-                Value::Coin(c) => {
-                    coin += c;
-                }
-                Value::Multiasset(c, multiasset) => {
-                    coin += c;
-
-                    for (cert, asset) in multiasset.iter() {
-                        let entry = assets.entry(*cert).or_default();
-
-                        for (asset_name, amount) in asset.iter() {
-                            entry
-                                .entry(asset_name.clone())
-                                .and_modify(|t| {
-                                    *t = PositiveCoin::try_from(u64::from(*t) + u64::from(amount))
-                                        .unwrap()
-                                })
-                                .or_insert_with(|| *amount);
-                        }
-                    }
-                }
+    outputs
+        .into_iter()
+        .map(|output| match output {
+            PseudoTransactionOutput::Legacy(output) => {
+                WalletValue::from(value_from_legacy(&output.amount))
+            }
+            PseudoTransactionOutput::PostAlonzo(output) => WalletValue::from(&output.value),
+        })
+        .sum::<WalletValue>()
+        .into()
+}
+
+#[cfg(all(test, feature = "transaction"))]
+mod tests {
+    use super::*;
+    use pallas_addresses::{Network, ShelleyAddress, ShelleyDelegationPart, ShelleyPaymentPart};
+
+    fn address(network: Network) -> Address {
+        ShelleyAddress::new(
+            network,
+            ShelleyPaymentPart::key_hash(Hash::from([1; 28])),
+            ShelleyDelegationPart::Null,
+        )
+        .into()
+    }
+
+    fn utxo(index: u64, network: Network) -> Utxo {
+        Utxo {
+            input: TransactionInput {
+                transaction_id: Hash::from([2; 32]),
+                index,
             },
+            output: TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+                address: address(network).to_vec().into(),
+                value: Value::Coin(3_000_000),
+                datum_option: None,
+                script_ref: None,
+            }),
         }
     }
 
-    let assets = Multiasset::from_vec(
-        assets
-            .into_iter()
-            .map(|(key, value)| (key, NonEmptyKeyValuePairs::Def(value.into_iter().collect())))
-            .collect(),
-    );
+    #[test]
+    fn group_into_one_output() {
+        let utxos = [utxo(0, Network::Testnet), utxo(1, Network::Testnet)];
+
+        let params = fees::ProtocolParams::MAINNET;
+        let tx = group_utxos(&utxos, &params, address(Network::Testnet)).unwrap();
+        let body = &tx.transaction_body;
+
+        assert_eq!(body.inputs.len(), 2);
+        // both inputs are owned by the same key
+        assert_eq!(body.fee, params.estimate_fee_with_signers(&tx, 1));
+        assert_eq!(body.outputs.len(), 1);
+        assert_eq!(sumup(&body.outputs), Value::Coin(6_000_000 - body.fee));
+        assert_eq!(
+            body.network_id,
+            Some(pallas_primitives::conway::NetworkId::Testnet)
+        );
+    }
+
+    #[test]
+    fn group_across_networks() {
+        let utxos = [utxo(0, Network::Testnet), utxo(1, Network::Mainnet)];
 
-    if let Some(assets) = assets {
-        Value::Multiasset(coin, assets)
-    } else {
-        Value::Coin(coin)
+        assert!(matches!(
+            group_utxos(
+                &utxos,
+                &fees::ProtocolParams::MAINNET,
+                address(Network::Testnet)
+            ),
+            Err(GroupUtxoError::NetworkMismatch { found: 1, .. })
+        ));
     }
 }
@@ -0,0 +1,137 @@
+//! Native scripts: their construction, hashes and addresses.
+//!
+//! ```
+//! # use cardano_connector::cardano::{Hash, native_script::{all, before, policy_id, sig}};
+//! // a minting policy of one key, closed after slot 150_000_000
+//! let policy = all([sig(Hash::from([1; 28])), before(150_000_000)]);
+//! let policy_id = policy_id(&policy);
+//! ```
+
+use super::{AddrKeyhash, Hash, PolicyId};
+use crate::Address;
+use pallas_addresses::{Network, ShelleyAddress, ShelleyDelegationPart, ShelleyPaymentPart};
+use pallas_crypto::hash::Hasher;
+use pallas_primitives::conway::NativeScript;
+use std::collections::BTreeSet;
+
+/// tag prepended to the CBOR of a native script to compute its hash
+const NATIVE_SCRIPT_TAG: u8 = 0;
+
+/// requires a signature of `key`
+pub fn sig(key: AddrKeyhash) -> NativeScript {
+    NativeScript::ScriptPubkey(key)
+}
+
+/// requires all the `scripts`
+pub fn all(scripts: impl IntoIterator<Item = NativeScript>) -> NativeScript {
+    NativeScript::ScriptAll(scripts.into_iter().collect())
+}
+
+/// requires one of the `scripts`
+pub fn any(scripts: impl IntoIterator<Item = NativeScript>) -> NativeScript {
+    NativeScript::ScriptAny(scripts.into_iter().collect())
+}
+
+/// requires `required` of the `scripts`
+pub fn at_least(required: u32, scripts: impl IntoIterator<Item = NativeScript>) -> NativeScript {
+    NativeScript::ScriptNOfK(required, scripts.into_iter().collect())
+}
+
+/// only valid from `slot` (`InvalidBefore`)
+pub fn after(slot: u64) -> NativeScript {
+    NativeScript::InvalidBefore(slot)
+}
+
+/// only valid until `slot` (`InvalidHereafter`)
+pub fn before(slot: u64) -> NativeScript {
+    NativeScript::InvalidHereafter(slot)
+}
+
+pub fn script_hash(script: &NativeScript) -> Hash<28> {
+    Hasher::<224>::hash_tagged_cbor(script, NATIVE_SCRIPT_TAG)
+}
+
+/// the id of the assets minted under the `policy` script, its hash
+pub fn policy_id(policy: &NativeScript) -> PolicyId {
+    script_hash(policy)
+}
+
+/// the validity interval a transaction needs for the time locks the
+/// script always requires: the first valid slot and the slot from which
+/// the transaction is no longer valid (its TTL)
+///
+/// The time locks under `any` or `at_least` are optional, they are not
+/// part of the interval.
+pub fn validity_interval(script: &NativeScript) -> (Option<u64>, Option<u64>) {
+    match script {
+        NativeScript::InvalidBefore(slot) => (Some(*slot), None),
+        NativeScript::InvalidHereafter(slot) => (None, Some(*slot)),
+        NativeScript::ScriptAll(scripts) => scripts.iter().map(validity_interval).fold(
+            (None, None),
+            |(from, until), (other_from, other_until)| {
+                (from.max(other_from), min_some(until, other_until))
+            },
+        ),
+        NativeScript::ScriptPubkey(_)
+        | NativeScript::ScriptAny(_)
+        | NativeScript::ScriptNOfK(_, _) => (None, None),
+    }
+}
+
+fn min_some(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// the enterprise address of the script on the given network
+pub fn script_address(script: &NativeScript, network: Network) -> Address {
+    ShelleyAddress::new(
+        network,
+        ShelleyPaymentPart::script_hash(script_hash(script)),
+        ShelleyDelegationPart::Null,
+    )
+    .into()
+}
+
+/// the keys the script may require a signature from
+pub fn key_hashes(script: &NativeScript) -> BTreeSet<AddrKeyhash> {
+    match script {
+        NativeScript::ScriptPubkey(hash) => BTreeSet::from([*hash]),
+        NativeScript::ScriptAll(scripts)
+        | NativeScript::ScriptAny(scripts)
+        | NativeScript::ScriptNOfK(_, scripts) => scripts.iter().flat_map(key_hashes).collect(),
+        NativeScript::InvalidBefore(_) | NativeScript::InvalidHereafter(_) => BTreeSet::new(),
+    }
+}
+
+/// the Shelley address if its payment part is a key, i.e. if it can be
+/// required with a `ScriptPubkey`
+pub(crate) fn key_address(address: &Address) -> Option<ShelleyAddress> {
+    match address {
+        Address::Shelley(address) if !address.payment().is_script() => Some(address.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_locks() {
+        let key = Hash::from([1; 28]);
+        assert_eq!(validity_interval(&sig(key)), (None, None));
+        assert_eq!(
+            validity_interval(&all([sig(key), after(10), before(50), all([before(40)])])),
+            (Some(10), Some(40))
+        );
+        // optional time locks
+        assert_eq!(
+            validity_interval(&any([before(40), at_least(1, [after(10)])])),
+            (None, None)
+        );
+        assert_eq!(policy_id(&sig(key)), script_hash(&sig(key)));
+    }
+}
@@ -0,0 +1,96 @@
+use super::{Coin, TransactionInput, Tx, TxHash, UtxoSet};
+use pallas_crypto::hash::Hasher;
+
+/// A transaction submitted but not yet seen on chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingTx {
+    pub hash: TxHash,
+    pub inputs: Vec<TransactionInput>,
+    pub fee: Coin,
+    pub ttl: Option<u64>,
+}
+
+impl PendingTx {
+    pub fn new(tx: &Tx) -> Self {
+        let body = &tx.transaction_body;
+        Self {
+            hash: Hasher::<256>::hash_cbor(body),
+            inputs: body.inputs.iter().cloned().collect(),
+            fee: body.fee,
+            ttl: body.ttl,
+        }
+    }
+}
+
+/// Track the UTxOs spent by the pending transactions.
+///
+/// Until a transaction is confirmed the wallet keeps returning the UTxOs
+/// it spends, they must not be used to build another transaction.
+#[derive(Debug, Clone, Default)]
+pub struct PendingUtxos {
+    pending: Vec<PendingTx>,
+}
+
+impl PendingUtxos {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// start tracking the given (submitted) transaction
+    pub fn track(&mut self, tx: &Tx) -> TxHash {
+        let pending = PendingTx::new(tx);
+        let hash = pending.hash;
+        self.confirm(&hash);
+        self.pending.push(pending);
+        hash
+    }
+
+    /// the transaction made it on chain (or is known to be dropped)
+    pub fn confirm(&mut self, hash: &TxHash) {
+        self.pending.retain(|pending| &pending.hash != hash);
+    }
+
+    /// the transaction `hash` was replaced by `tx`, see
+    /// [`rebuild_with_margin`](super::intent::rebuild_with_margin)
+    pub fn replace(&mut self, hash: &TxHash, tx: &Tx) -> TxHash {
+        self.confirm(hash);
+        self.track(tx)
+    }
+
+    pub fn get(&self, hash: &TxHash) -> Option<&PendingTx> {
+        self.pending.iter().find(|pending| &pending.hash == hash)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, PendingTx> {
+        self.pending.iter()
+    }
+
+    pub fn is_spent(&self, input: &TransactionInput) -> bool {
+        self.pending
+            .iter()
+            .any(|pending| pending.inputs.contains(input))
+    }
+
+    /// the UTxOs not spent by any of the pending transactions
+    pub fn available(&self, utxos: &UtxoSet) -> UtxoSet {
+        utxos
+            .iter()
+            .filter(|utxo| !self.is_spent(&utxo.input))
+            .cloned()
+            .collect()
+    }
+
+    /// The pending transactions expiring within `margin` slots.
+    ///
+    /// These are the candidates for the UI to offer a "speed up".
+    pub fn lingering(&self, current_slot: u64, margin: u64) -> Vec<&PendingTx> {
+        self.pending
+            .iter()
+            .filter(|pending| {
+                pending
+                    .ttl
+                    .is_some_and(|ttl| ttl > current_slot && ttl - current_slot <= margin)
+            })
+            .collect()
+    }
+}
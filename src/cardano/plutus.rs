@@ -0,0 +1,385 @@
+/*!
+Spending from Plutus script addresses.
+
+A UTxO locked at a Plutus script address is unlocked by a [`ScriptInput`]:
+its validator (attached to the transaction or the reference script of a
+reference input), its datum when the UTxO only holds the datum's hash, and
+the redeemer with the execution units the validator needs. The scripts
+are paid for by their execution units (see [`PlutusParams`]), and a
+transaction running scripts also needs collateral inputs, forfeited if a
+script fails, and the [`script_data_hash`] committing to its redeemers,
+datums and the cost models of the languages used.
+
+[`TxIntent::spend_script`] and [`TxIntent::with_collateral`] add them to a
+transaction, the build takes care of the rest.
+
+```no_run
+# use cardano_connector::{ConnectedWallet, cardano::{BuildParams, TxIntent, UtxoSet, plutus::{Script, ScriptInput, ScriptSource}}};
+# use pallas_primitives::{ExUnits, PlutusData};
+# async fn test(wallet: &ConnectedWallet, locked: UtxoSet, validator: Script, redeemer: PlutusData, params: BuildParams) -> anyhow::Result<()> {
+let mut intent = TxIntent::new().with_collateral(&wallet.collateral(5_000_000).await?);
+for utxo in locked.iter() {
+    intent = intent.spend_script(ScriptInput::new(
+        utxo.input.clone(),
+        ScriptSource::Attached(validator.clone()),
+        redeemer.clone(),
+        ExUnits { mem: 500_000, steps: 200_000_000 },
+    ));
+}
+let utxos = UtxoSet::from(wallet.all_utxos(None).await?);
+let tx = intent.build_spending(&locked, &utxos, &params)?;
+# Ok(()) }
+```
+
+[`TxIntent::spend_script`]: super::TxIntent::spend_script
+[`TxIntent::with_collateral`]: super::TxIntent::with_collateral
+*/
+
+use super::{
+    Coin, Hash, TransactionInput, WitnessSet, cost_models::CostModelError, fees::ProtocolParams,
+};
+use pallas_codec::minicbor::{self, Encoder};
+use pallas_crypto::hash::Hasher;
+use pallas_primitives::{
+    CostModel, ExUnits, PlutusData, PlutusScript, RationalNumber,
+    conway::{CostModels, Language, ScriptRef},
+};
+
+/// A Plutus script, in the language it is written in.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Script {
+    V1(PlutusScript<1>),
+    V2(PlutusScript<2>),
+    V3(PlutusScript<3>),
+}
+
+impl Script {
+    pub fn language(&self) -> Language {
+        match self {
+            Self::V1(_) => Language::PlutusV1,
+            Self::V2(_) => Language::PlutusV2,
+            Self::V3(_) => Language::PlutusV3,
+        }
+    }
+
+    /// the hash of the script, the payment credential of its address
+    pub fn hash(&self) -> Hash<28> {
+        let (tag, bytes) = match self {
+            Self::V1(script) => (1, &script.0),
+            Self::V2(script) => (2, &script.0),
+            Self::V3(script) => (3, &script.0),
+        };
+        Hasher::<224>::hash_tagged(bytes, tag)
+    }
+
+    /// the script as the reference script of an output
+    pub fn to_script_ref(&self) -> ScriptRef {
+        match self {
+            Self::V1(script) => ScriptRef::PlutusV1Script(script.clone()),
+            Self::V2(script) => ScriptRef::PlutusV2Script(script.clone()),
+            Self::V3(script) => ScriptRef::PlutusV3Script(script.clone()),
+        }
+    }
+}
+
+/// Where the validator of a [`ScriptInput`] comes from.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ScriptSource {
+    /// the script is added to the witness set of the transaction
+    Attached(Script),
+    /// the script is the reference script of the UTxO at `input`, which
+    /// is added to the reference inputs of the transaction
+    Reference {
+        input: TransactionInput,
+        language: Language,
+    },
+}
+
+impl ScriptSource {
+    pub fn language(&self) -> Language {
+        match self {
+            Self::Attached(script) => script.language(),
+            Self::Reference { language, .. } => language.clone(),
+        }
+    }
+}
+
+/// A UTxO locked at a Plutus script address, and what unlocks it.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ScriptInput {
+    pub input: TransactionInput,
+    pub script: ScriptSource,
+    /// the datum of the UTxO, needed when it only holds the datum's
+    /// hash (`None` for an inline datum)
+    #[serde(default)]
+    pub datum: Option<PlutusData>,
+    pub redeemer: PlutusData,
+    /// the execution budget of the validator, e.g. as evaluated by the
+    /// wallet or a backend
+    pub ex_units: ExUnits,
+}
+
+impl ScriptInput {
+    /// spend the UTxO at `input` holding an inline datum
+    pub fn new(
+        input: TransactionInput,
+        script: ScriptSource,
+        redeemer: PlutusData,
+        ex_units: ExUnits,
+    ) -> Self {
+        Self {
+            input,
+            script,
+            datum: None,
+            redeemer,
+            ex_units,
+        }
+    }
+
+    /// provide the datum of a UTxO holding only its hash
+    pub fn with_datum(mut self, datum: PlutusData) -> Self {
+        self.datum = Some(datum);
+        self
+    }
+}
+
+/// The protocol parameters involved in running Plutus scripts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlutusParams {
+    /// lovelace per unit of memory used by the scripts
+    pub price_mem: RationalNumber,
+    /// lovelace per CPU step used by the scripts
+    pub price_step: RationalNumber,
+    /// the collateral needed, in percent of the fee
+    pub collateral_percentage: u64,
+    /// the cost models of the languages, see [`CostModelStore`]
+    ///
+    /// [`CostModelStore`]: super::cost_models::CostModelStore
+    pub cost_models: CostModels,
+}
+
+impl PlutusParams {
+    pub fn with_cost_models(mut self, cost_models: CostModels) -> Self {
+        self.cost_models = cost_models;
+        self
+    }
+
+    /// the price of the execution units, rounded up
+    pub fn script_fee(&self, ex_units: &ExUnits) -> Coin {
+        super::fees::script_fee(ex_units, &self.price_mem, &self.price_step)
+    }
+
+    /// the collateral needed by a transaction paying `fee`, rounded up
+    pub fn collateral_for(&self, fee: Coin) -> Coin {
+        let collateral = (u128::from(fee) * u128::from(self.collateral_percentage)).div_ceil(100);
+        collateral.try_into().unwrap_or(Coin::MAX)
+    }
+
+    fn cost_model(&self, language: &Language) -> Option<&CostModel> {
+        match language {
+            Language::PlutusV1 => self.cost_models.plutus_v1.as_ref(),
+            Language::PlutusV2 => self.cost_models.plutus_v2.as_ref(),
+            Language::PlutusV3 => self.cost_models.plutus_v3.as_ref(),
+        }
+    }
+}
+
+impl Default for PlutusParams {
+    /// the prices and collateral of mainnet, without cost models
+    fn default() -> Self {
+        let params = ProtocolParams::MAINNET;
+        Self {
+            price_mem: params.price_mem,
+            price_step: params.price_step,
+            collateral_percentage: params.collateral_percentage,
+            cost_models: CostModels {
+                plutus_v1: None,
+                plutus_v2: None,
+                plutus_v3: None,
+            },
+        }
+    }
+}
+
+/// the `script_data_hash` of a transaction with these witnesses,
+/// running scripts of the given `languages`
+///
+/// The hash covers the redeemers, the datums and the cost models of the
+/// languages; it is `None` if the transaction has neither redeemers nor
+/// datums. The languages of a transaction are given by
+/// [`cost_models::languages`](super::cost_models::languages).
+pub fn script_data_hash(
+    witnesses: &WitnessSet,
+    languages: &[Language],
+    params: &PlutusParams,
+) -> Result<Option<Hash<32>>, CostModelError> {
+    let mut preimage = match (&witnesses.redeemer, &witnesses.plutus_data) {
+        (None, None) => return Ok(None),
+        (Some(redeemers), _) => minicbor::to_vec(redeemers).unwrap(),
+        // only datums: no redeemers and no language views, both encoded
+        // as empty maps
+        (None, Some(_)) => vec![0xa0],
+    };
+    if let Some(datums) = &witnesses.plutus_data {
+        preimage.extend(minicbor::to_vec(datums).unwrap());
+    }
+    if witnesses.redeemer.is_some() {
+        preimage.extend(language_views(languages, params)?);
+    } else {
+        preimage.push(0xa0);
+    }
+    Ok(Some(Hasher::<256>::hash(&preimage)))
+}
+
+/// the CBOR map of the cost models of the `languages`, as the ledger
+/// encodes it in the `script_data_hash`
+fn language_views(
+    languages: &[Language],
+    params: &PlutusParams,
+) -> Result<Vec<u8>, CostModelError> {
+    let mut views: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+    for language in languages {
+        let cost_model = params
+            .cost_model(language)
+            .ok_or_else(|| CostModelError::MissingCostModel(language.clone()))?;
+
+        let mut key = Vec::new();
+        let mut value = Vec::new();
+        match language {
+            // Plutus V1 keeps the encoding of the Alonzo era: the key is
+            // the CBOR of the language id in a byte string, the value the
+            // indefinite list of the costs in a byte string
+            Language::PlutusV1 => {
+                let mut costs = Encoder::new(Vec::new());
+                costs.begin_array().unwrap();
+                for cost in cost_model {
+                    costs.i64(*cost).unwrap();
+                }
+                costs.end().unwrap();
+                Encoder::new(&mut key).bytes(&[0x00]).unwrap();
+                Encoder::new(&mut value)
+                    .bytes(&costs.into_writer())
+                    .unwrap();
+            }
+            Language::PlutusV2 | Language::PlutusV3 => {
+                let id = if *language == Language::PlutusV2 {
+                    1
+                } else {
+                    2
+                };
+                Encoder::new(&mut key).u8(id).unwrap();
+                value = minicbor::to_vec(cost_model).unwrap();
+            }
+        }
+        views.push((key, value));
+    }
+    // canonical order: the shorter keys first
+    views.sort_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then(a.cmp(b)));
+    views.dedup();
+
+    let mut encoded = Vec::new();
+    Encoder::new(&mut encoded).map(views.len() as u64).unwrap();
+    for (key, value) in views {
+        encoded.extend(key);
+        encoded.extend(value);
+    }
+    Ok(encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::testing::empty_witness_set;
+    use pallas_codec::utils::NonEmptySet;
+    use pallas_primitives::{
+        BigInt, Constr, MaybeIndefArray,
+        conway::{RedeemerTag, Redeemers, RedeemersKey, RedeemersValue},
+    };
+
+    fn unit() -> PlutusData {
+        PlutusData::Constr(Constr {
+            tag: 121,
+            any_constructor: None,
+            fields: MaybeIndefArray::Def(Vec::new()),
+        })
+    }
+
+    #[test]
+    fn script_hash_is_tagged_by_language() {
+        let bytes = vec![0x4e, 0x4d, 0x01, 0x00, 0x00];
+        let v2 = Script::V2(PlutusScript(bytes.clone().into()));
+        let v3 = Script::V3(PlutusScript(bytes.clone().into()));
+
+        assert_eq!(v2.hash(), Hasher::<224>::hash_tagged(&bytes, 2));
+        assert_ne!(v2.hash(), v3.hash());
+        assert_eq!(v3.language(), Language::PlutusV3);
+    }
+
+    #[test]
+    fn collateral_is_rounded_up() {
+        let params = PlutusParams::default();
+        assert_eq!(params.collateral_for(200_000), 300_000);
+        assert_eq!(params.collateral_for(200_001), 300_002);
+    }
+
+    #[test]
+    fn script_data_hash_preimage() {
+        let params = PlutusParams::default().with_cost_models(CostModels {
+            plutus_v1: Some(vec![1, -2]),
+            plutus_v2: None,
+            plutus_v3: Some(vec![3]),
+        });
+        let mut witnesses = empty_witness_set();
+        assert_eq!(script_data_hash(&witnesses, &[], &params), Ok(None));
+
+        // only a datum
+        witnesses.plutus_data =
+            NonEmptySet::from_vec(vec![PlutusData::BigInt(BigInt::Int(42.into()))]);
+        let datums = minicbor::to_vec(&witnesses.plutus_data).unwrap();
+        let expected = [&[0xa0][..], &datums, &[0xa0]].concat();
+        assert_eq!(
+            script_data_hash(&witnesses, &[], &params),
+            Ok(Some(Hasher::<256>::hash(&expected)))
+        );
+
+        witnesses.redeemer = Some(Redeemers::from(
+            pallas_codec::utils::NonEmptyKeyValuePairs::from_vec(vec![(
+                RedeemersKey {
+                    tag: RedeemerTag::Spend,
+                    index: 0,
+                },
+                RedeemersValue {
+                    data: unit(),
+                    ex_units: ExUnits { mem: 1, steps: 2 },
+                },
+            )])
+            .unwrap(),
+        ));
+        assert_eq!(
+            script_data_hash(&witnesses, &[Language::PlutusV2], &params),
+            Err(CostModelError::MissingCostModel(Language::PlutusV2))
+        );
+
+        // V3 (key 2) sorts before V1 (key as a byte string)
+        let views = language_views(&[Language::PlutusV1, Language::PlutusV3], &params).unwrap();
+        assert_eq!(
+            views,
+            [
+                0xa2, // map of 2
+                0x02, 0x81, 0x03, // V3: [3]
+                0x41, 0x00, // V1: the byte string of its id
+                0x44, 0x9f, 0x01, 0x21, 0xff // the byte string of [_ 1, -2]
+            ]
+        );
+        let redeemers = minicbor::to_vec(&witnesses.redeemer).unwrap();
+        let expected = [&redeemers[..], &datums, &views].concat();
+        assert_eq!(
+            script_data_hash(
+                &witnesses,
+                &[Language::PlutusV3, Language::PlutusV1],
+                &params
+            ),
+            Ok(Some(Hasher::<256>::hash(&expected)))
+        );
+    }
+}
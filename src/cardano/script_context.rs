@@ -0,0 +1,333 @@
+/*!
+Approximate preview of what the Plutus validators will see.
+
+When a validator fails on-chain (or in the wallet's evaluation) the only
+feedback is usually an opaque error. [`TxInfo::preview`] rebuilds, from
+the transaction and its resolved inputs, the information the ledger
+passes to the scripts so it can be inspected (`{:#?}`) while debugging.
+
+This is an approximation: the ledger works on the exact bytes of the
+transaction and converts slots to POSIX time, this preview works on the
+decoded transaction and keeps the slots.
+*/
+
+use super::{
+    AddrKeyhash, Certificate, Coin, Hash, PolicyId, TransactionInput, TransactionOutput, Tx,
+    UtxoSet,
+};
+use pallas_crypto::hash::Hasher;
+use pallas_primitives::{
+    ExUnits, PlutusData, RewardAccount,
+    conway::{DatumOption, Mint, RedeemerTag, Redeemers},
+};
+
+/// an input with the output it spends (if it could be resolved)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TxInInfo {
+    pub out_ref: TransactionInput,
+    pub resolved: Option<TransactionOutput>,
+}
+
+/// the validity interval of the transaction, in slots
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ValidityRange {
+    /// inclusive lower bound
+    pub from: Option<u64>,
+    /// exclusive upper bound
+    pub to: Option<u64>,
+}
+
+/// what a script is validating
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptPurpose {
+    Spending(TransactionInput),
+    Minting(PolicyId),
+    Rewarding(RewardAccount),
+    Certifying(u32, Certificate),
+    Voting(u32),
+    Proposing(u32),
+    /// the redeemer points to nothing in the transaction
+    Invalid(RedeemerTag, u32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RedeemerInfo {
+    pub purpose: ScriptPurpose,
+    pub data: PlutusData,
+    pub ex_units: ExUnits,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TxInfo {
+    pub id: Hash<32>,
+    /// inputs, sorted as the ledger sorts them
+    pub inputs: Vec<TxInInfo>,
+    pub reference_inputs: Vec<TxInInfo>,
+    pub outputs: Vec<TransactionOutput>,
+    pub fee: Coin,
+    pub mint: Option<Mint>,
+    pub certificates: Vec<Certificate>,
+    /// withdrawals, sorted by reward account
+    pub withdrawals: Vec<(RewardAccount, Coin)>,
+    pub valid_range: ValidityRange,
+    pub signatories: Vec<AddrKeyhash>,
+    pub redeemers: Vec<RedeemerInfo>,
+    /// the datums of the witness set, indexed by their hash
+    pub data: Vec<(Hash<32>, PlutusData)>,
+}
+
+/// the context of one of the scripts executed by the transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptContext<'a> {
+    pub tx_info: &'a TxInfo,
+    pub purpose: &'a ScriptPurpose,
+    pub redeemer: &'a PlutusData,
+    /// the datum of the spent output, for spending scripts
+    pub datum: Option<PlutusData>,
+}
+
+impl TxInfo {
+    /// build the preview of the given transaction
+    ///
+    /// `resolved` needs to contain the outputs spent or referenced by the
+    /// transaction, see [`InputResolver`] to resolve foreign inputs.
+    ///
+    /// [`InputResolver`]: crate::backend::InputResolver
+    pub fn preview(tx: &Tx, resolved: &UtxoSet) -> Self {
+        let body = &tx.transaction_body;
+        let witnesses = &tx.transaction_witness_set;
+
+        let resolve = |inputs: Vec<TransactionInput>| {
+            let mut inputs: Vec<_> = inputs
+                .into_iter()
+                .map(|out_ref| TxInInfo {
+                    resolved: resolved
+                        .iter()
+                        .find(|utxo| utxo.input == out_ref)
+                        .map(|utxo| utxo.output.clone()),
+                    out_ref,
+                })
+                .collect();
+            inputs.sort_by(|a, b| a.out_ref.cmp(&b.out_ref));
+            inputs
+        };
+
+        let inputs = resolve(body.inputs.iter().cloned().collect());
+        let reference_inputs = resolve(
+            body.reference_inputs
+                .iter()
+                .flat_map(|inputs| inputs.iter().cloned())
+                .collect(),
+        );
+
+        let mut withdrawals: Vec<_> = body
+            .withdrawals
+            .iter()
+            .flat_map(|withdrawals| withdrawals.iter().cloned())
+            .collect();
+        withdrawals.sort();
+
+        let certificates: Vec<_> = body
+            .certificates
+            .iter()
+            .flat_map(|certificates| certificates.iter().cloned())
+            .collect();
+
+        let mut policies: Vec<PolicyId> = body
+            .mint
+            .iter()
+            .flat_map(|mint| mint.iter().map(|(policy, _)| *policy))
+            .collect();
+        policies.sort();
+
+        let purpose = |tag: RedeemerTag, index: u32| {
+            let i = index as usize;
+            let purpose = match tag {
+                RedeemerTag::Spend => inputs
+                    .get(i)
+                    .map(|input| ScriptPurpose::Spending(input.out_ref.clone())),
+                RedeemerTag::Mint => policies.get(i).copied().map(ScriptPurpose::Minting),
+                RedeemerTag::Reward => withdrawals
+                    .get(i)
+                    .map(|(account, _)| ScriptPurpose::Rewarding(account.clone())),
+                RedeemerTag::Cert => certificates
+                    .get(i)
+                    .map(|certificate| ScriptPurpose::Certifying(index, certificate.clone())),
+                RedeemerTag::Vote => Some(ScriptPurpose::Voting(index)),
+                RedeemerTag::Propose => Some(ScriptPurpose::Proposing(index)),
+            };
+            purpose.unwrap_or(ScriptPurpose::Invalid(tag, index))
+        };
+
+        let redeemers = match &witnesses.redeemer {
+            None => Vec::new(),
+            Some(Redeemers::List(redeemers)) => redeemers
+                .iter()
+                .map(|redeemer| RedeemerInfo {
+                    purpose: purpose(redeemer.tag, redeemer.index),
+                    data: redeemer.data.clone(),
+                    ex_units: redeemer.ex_units,
+                })
+                .collect(),
+            Some(Redeemers::Map(redeemers)) => redeemers
+                .iter()
+                .map(|(key, value)| RedeemerInfo {
+                    purpose: purpose(key.tag, key.index),
+                    data: value.data.clone(),
+                    ex_units: value.ex_units,
+                })
+                .collect(),
+        };
+
+        let data = witnesses
+            .plutus_data
+            .iter()
+            .flat_map(|data| data.iter())
+            .map(|datum| (Hasher::<256>::hash_cbor(datum), datum.clone()))
+            .collect();
+
+        Self {
+            id: Hasher::<256>::hash_cbor(body),
+            fee: body.fee,
+            outputs: body.outputs.clone(),
+            mint: body.mint.clone(),
+            valid_range: ValidityRange {
+                from: body.validity_interval_start,
+                to: body.ttl,
+            },
+            signatories: body
+                .required_signers
+                .iter()
+                .flat_map(|signers| signers.iter().copied())
+                .collect(),
+            inputs,
+            reference_inputs,
+            certificates,
+            withdrawals,
+            redeemers,
+            data,
+        }
+    }
+
+    /// the context of every script executed by the transaction
+    pub fn contexts(&self) -> Vec<ScriptContext<'_>> {
+        self.redeemers
+            .iter()
+            .map(|redeemer| ScriptContext {
+                tx_info: self,
+                purpose: &redeemer.purpose,
+                redeemer: &redeemer.data,
+                datum: match &redeemer.purpose {
+                    ScriptPurpose::Spending(out_ref) => self.spent_datum(out_ref),
+                    _ => None,
+                },
+            })
+            .collect()
+    }
+
+    fn spent_datum(&self, out_ref: &TransactionInput) -> Option<PlutusData> {
+        let input = self.inputs.iter().find(|input| &input.out_ref == out_ref)?;
+        let datum_hash = match input.resolved.as_ref()? {
+            TransactionOutput::Legacy(output) => output.datum_hash?,
+            TransactionOutput::PostAlonzo(output) => match output.datum_option.as_ref()? {
+                DatumOption::Data(data) => return Some(data.0.clone()),
+                DatumOption::Hash(hash) => *hash,
+            },
+        };
+
+        self.data
+            .iter()
+            .find(|(hash, _)| hash == &datum_hash)
+            .map(|(_, datum)| datum.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{
+        Utxo, Value,
+        testing::{empty_body, empty_witness_set},
+    };
+    use pallas_codec::utils::{CborWrap, MaybeIndefArray, NonEmptySet, Nullable, Set};
+    use pallas_primitives::{
+        BigInt, Constr, babbage::PseudoPostAlonzoTransactionOutput, conway::Redeemer,
+    };
+
+    fn input(index: u64) -> TransactionInput {
+        TransactionInput {
+            transaction_id: Hash::from([index as u8; 32]),
+            index,
+        }
+    }
+
+    fn datum(value: i64) -> PlutusData {
+        PlutusData::BigInt(BigInt::Int(value.into()))
+    }
+
+    fn script_utxo(index: u64, datum_option: DatumOption) -> Utxo {
+        Utxo {
+            input: input(index),
+            output: TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+                address: vec![0x71; 29].into(),
+                value: Value::Coin(2_000_000),
+                datum_option: Some(datum_option),
+                script_ref: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn preview_spending_contexts() {
+        let hashed_datum = datum(2);
+        let resolved: UtxoSet = vec![
+            script_utxo(
+                2,
+                DatumOption::Hash(Hasher::<256>::hash_cbor(&hashed_datum)),
+            ),
+            script_utxo(1, DatumOption::Data(CborWrap(datum(1)))),
+        ]
+        .into();
+
+        let mut body = empty_body();
+        // out of order on purpose, the ledger sorts the inputs
+        body.inputs = Set::from(vec![input(2), input(1)]);
+        let mut witnesses = empty_witness_set();
+        witnesses.plutus_data = NonEmptySet::from_vec(vec![hashed_datum.clone()]);
+        let redeemer = |index| Redeemer {
+            tag: RedeemerTag::Spend,
+            index,
+            data: PlutusData::Constr(Constr {
+                tag: 121,
+                any_constructor: None,
+                fields: MaybeIndefArray::Def(Vec::new()),
+            }),
+            ex_units: ExUnits { mem: 1, steps: 1 },
+        };
+        witnesses.redeemer = Some(Redeemers::List(MaybeIndefArray::Def(vec![
+            redeemer(0),
+            redeemer(1),
+            redeemer(2),
+        ])));
+        let tx = Tx {
+            transaction_body: body,
+            transaction_witness_set: witnesses,
+            success: true,
+            auxiliary_data: Nullable::Null,
+        };
+
+        let info = TxInfo::preview(&tx, &resolved);
+        let contexts = info.contexts();
+
+        assert_eq!(info.inputs[0].out_ref, input(1));
+        assert_eq!(contexts.len(), 3);
+        assert_eq!(contexts[0].purpose, &ScriptPurpose::Spending(input(1)));
+        assert_eq!(contexts[0].datum, Some(datum(1)));
+        assert_eq!(contexts[1].purpose, &ScriptPurpose::Spending(input(2)));
+        assert_eq!(contexts[1].datum, Some(hashed_datum));
+        assert_eq!(
+            contexts[2].purpose,
+            &ScriptPurpose::Invalid(RedeemerTag::Spend, 2)
+        );
+    }
+}
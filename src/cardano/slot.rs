@@ -0,0 +1,173 @@
+use crate::NetworkId;
+use std::time::Duration;
+
+/// How the slots of a network map to POSIX time.
+///
+/// The native scripts and the validity interval of the transactions are
+/// expressed in slots while the users think in dates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotConfig {
+    /// POSIX time, in milliseconds, of the `zero_slot`
+    pub zero_time: u64,
+    /// first slot of the Shelley era
+    pub zero_slot: u64,
+    /// length of a slot, in milliseconds
+    pub slot_length: u64,
+    /// the epoch starting at the `zero_slot`
+    pub zero_epoch: u64,
+    /// number of slots per epoch
+    pub epoch_length: u64,
+}
+
+impl SlotConfig {
+    pub const MAINNET: Self = Self {
+        zero_time: 1_596_059_091_000,
+        zero_slot: 4_492_800,
+        slot_length: 1_000,
+        zero_epoch: 208,
+        epoch_length: 432_000,
+    };
+
+    pub const PRE_PRODUCTION: Self = Self {
+        zero_time: 1_655_769_600_000,
+        zero_slot: 86_400,
+        slot_length: 1_000,
+        zero_epoch: 4,
+        epoch_length: 432_000,
+    };
+
+    pub const PREVIEW: Self = Self {
+        zero_time: 1_666_656_000_000,
+        zero_slot: 0,
+        slot_length: 1_000,
+        zero_epoch: 0,
+        epoch_length: 86_400,
+    };
+
+    /// the configuration of the public networks, `None` for the unknown
+    /// ones
+    pub fn for_network(network: NetworkId) -> Option<Self> {
+        match network {
+            NetworkId::Mainnet => Some(Self::MAINNET),
+            NetworkId::PreProduction => Some(Self::PRE_PRODUCTION),
+            NetworkId::Preview => Some(Self::PREVIEW),
+            NetworkId::Unknown(_) => None,
+        }
+    }
+
+    /// the slot containing the given POSIX time (in milliseconds)
+    ///
+    /// Times before the `zero_time` map to the `zero_slot`.
+    pub fn slot_at(&self, time: u64) -> u64 {
+        self.zero_slot + time.saturating_sub(self.zero_time) / self.slot_length
+    }
+
+    /// the POSIX time (in milliseconds) at which the slot starts
+    pub fn time_of(&self, slot: u64) -> u64 {
+        self.zero_time + slot.saturating_sub(self.zero_slot) * self.slot_length
+    }
+
+    /// the epoch of the slot
+    pub fn epoch_of(&self, slot: u64) -> u64 {
+        self.zero_epoch + slot.saturating_sub(self.zero_slot) / self.epoch_length.max(1)
+    }
+
+    /// the first slot of the epoch
+    pub fn epoch_start(&self, epoch: u64) -> u64 {
+        self.zero_slot + epoch.saturating_sub(self.zero_epoch) * self.epoch_length
+    }
+
+    /// the epoch of the slot, and where the slot is in the epoch
+    pub fn epoch_info(&self, slot: u64) -> EpochInfo {
+        let epoch = self.epoch_of(slot);
+        let first_slot = self.epoch_start(epoch);
+        EpochInfo {
+            epoch,
+            slot,
+            first_slot,
+            epoch_length: self.epoch_length,
+            start_time: self.time_of(first_slot),
+            end_time: self.time_of(first_slot + self.epoch_length),
+        }
+    }
+}
+
+/// Where a slot is in its epoch, e.g. to render the countdown to the
+/// next epoch boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpochInfo {
+    pub epoch: u64,
+    pub slot: u64,
+    /// the first slot of the epoch
+    pub first_slot: u64,
+    /// number of slots of the epoch
+    pub epoch_length: u64,
+    /// POSIX time, in milliseconds, of the start of the epoch
+    pub start_time: u64,
+    /// POSIX time, in milliseconds, of the start of the next epoch
+    pub end_time: u64,
+}
+
+impl EpochInfo {
+    /// the slot number within the epoch
+    ///
+    /// `None` if the slot is before the start of the epoch, e.g. for a
+    /// slot before the `zero_slot` of the [`SlotConfig`].
+    pub fn slot_in_epoch(&self) -> Option<u64> {
+        self.slot.checked_sub(self.first_slot)
+    }
+
+    /// the elapsed part of the epoch at `now` (POSIX time in
+    /// milliseconds), between 0 and 1
+    pub fn progress(&self, now: u64) -> f64 {
+        let length = self.end_time.saturating_sub(self.start_time).max(1);
+        (now.saturating_sub(self.start_time) as f64 / length as f64).min(1.0)
+    }
+
+    /// how long until the next epoch at `now` (POSIX time in
+    /// milliseconds)
+    pub fn time_to_next(&self, now: u64) -> Duration {
+        Duration::from_millis(self.end_time.saturating_sub(now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slot_time_round_trip() {
+        let config = SlotConfig::MAINNET;
+
+        assert_eq!(config.slot_at(config.zero_time), 4_492_800);
+        assert_eq!(config.slot_at(config.zero_time + 1_999), 4_492_801);
+        assert_eq!(config.time_of(4_492_802), config.zero_time + 2_000);
+        assert_eq!(config.slot_at(0), config.zero_slot);
+        assert_eq!(
+            SlotConfig::PREVIEW.time_of(SlotConfig::PREVIEW.slot_at(1_700_000_000_000)),
+            1_700_000_000_000
+        );
+    }
+
+    #[test]
+    fn epoch_boundaries() {
+        let config = SlotConfig::MAINNET;
+
+        assert_eq!(config.epoch_of(4_492_800), 208);
+        assert_eq!(config.epoch_start(500), 4_492_800 + 292 * 432_000);
+
+        let slot = config.epoch_start(500) + 108_000;
+        let info = config.epoch_info(slot);
+        assert_eq!(info.epoch, 500);
+        assert_eq!(info.slot_in_epoch(), Some(108_000));
+        assert_eq!(info.progress(config.time_of(slot)), 0.25);
+        assert_eq!(
+            info.time_to_next(config.time_of(slot)),
+            Duration::from_secs(324_000)
+        );
+        assert_eq!(SlotConfig::PREVIEW.epoch_of(86_400 * 3 + 1), 3);
+
+        // before the shelley era
+        assert_eq!(config.epoch_info(1_000).slot_in_epoch(), None);
+    }
+}
@@ -0,0 +1,362 @@
+use super::{
+    Certificate, Coin, Metadata, Metadatum, MetadatumLabel, TransactionBody, TransactionInput,
+    TransactionOutput, Tx, UtxoSet,
+};
+use crate::Address;
+use pallas_codec::utils::{Int, KeyValuePairs};
+
+/// Metadata label reserved by this crate for the [`TxSummary`].
+///
+/// The content follows the CIP-20 layout (a `msg` entry with human
+/// readable lines) so wallets rendering CIP-20 messages will display it,
+/// while the other entries are meant to be parsed by wallets wanting to
+/// show a verified breakdown.
+pub const SUMMARY_LABEL: MetadatumLabel = 6740;
+
+/// the deposits defined in the protocol parameters that are not
+/// explicitly set in the certificates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepositParams {
+    pub key_deposit: Coin,
+    pub pool_deposit: Coin,
+}
+
+/// net lovelace movement of one of the addresses involved in the transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressChange {
+    pub address: Address,
+    /// positive if the address receives more than it spends
+    pub lovelace: i128,
+}
+
+/// Machine readable summary of what a transaction does with the funds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxSummary {
+    pub fee: Coin,
+    /// deposits locked by the certificates and proposals of the transaction
+    pub deposits: Coin,
+    /// deposits returned by the certificates of the transaction
+    pub refunds: Coin,
+    /// net change per address, ordered by address bytes
+    pub net_change: Vec<AddressChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SummaryError {
+    #[error("Input {}#{} is not in the resolved UTxOs", .0.transaction_id, .0.index)]
+    UnresolvedInput(TransactionInput),
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+    #[error("Amount {0} doesn't fit in a metadatum")]
+    AmountOutOfRange(i128),
+}
+
+impl TxSummary {
+    /// compute the summary of the given transaction body
+    ///
+    /// all the inputs of the body need to be present in `inputs` so we
+    /// can know what is being spent.
+    pub fn new(
+        body: &TransactionBody,
+        inputs: &UtxoSet,
+        params: &DepositParams,
+    ) -> Result<Self, SummaryError> {
+        let mut changes: Vec<(Vec<u8>, i128)> = Vec::new();
+        let mut add = |address: &[u8], lovelace: i128| match changes
+            .iter_mut()
+            .find(|(known, _)| known == address)
+        {
+            Some((_, total)) => *total += lovelace,
+            None => changes.push((address.to_vec(), lovelace)),
+        };
+
+        for input in body.inputs.iter() {
+            let utxo = inputs
+                .iter()
+                .find(|utxo| &utxo.input == input)
+                .ok_or_else(|| SummaryError::UnresolvedInput(input.clone()))?;
+            add(output_address(&utxo.output), -i128::from(utxo.amount()));
+        }
+        for output in &body.outputs {
+            add(output_address(output), i128::from(output_coin(output)));
+        }
+
+        let (deposits, refunds) = certificates_deposits(body, params);
+
+        changes.sort();
+        let net_change = changes
+            .into_iter()
+            .map(|(address, lovelace)| {
+                Ok(AddressChange {
+                    address: Address::from_bytes(&address)
+                        .map_err(|error| SummaryError::InvalidAddress(error.to_string()))?,
+                    lovelace,
+                })
+            })
+            .collect::<Result<_, SummaryError>>()?;
+
+        Ok(Self {
+            fee: body.fee,
+            deposits,
+            refunds,
+            net_change,
+        })
+    }
+
+    /// encode the summary as a CIP-20 style metadatum
+    ///
+    /// Fails if a net change doesn't fit in a metadatum integer (beyond
+    /// 64 bits).
+    pub fn to_metadatum(&self) -> Result<Metadatum, SummaryError> {
+        let text = |s: &str| Metadatum::Text(s.to_owned());
+        let int = |i: i128| {
+            Int::try_from(i)
+                .map(Metadatum::Int)
+                .map_err(|_| SummaryError::AmountOutOfRange(i))
+        };
+
+        let msg = vec![
+            Metadatum::Text(format!("fee: {} lovelace", self.fee)),
+            Metadatum::Text(format!("deposits: {} lovelace", self.deposits)),
+            Metadatum::Text(format!("refunds: {} lovelace", self.refunds)),
+        ];
+        let net = self
+            .net_change
+            .iter()
+            .map(|change| {
+                Ok((
+                    Metadatum::Bytes(change.address.to_vec().into()),
+                    int(change.lovelace)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, SummaryError>>()?;
+
+        Ok(Metadatum::Map(KeyValuePairs::from(vec![
+            (text("msg"), Metadatum::Array(msg)),
+            (text("fee"), int(self.fee.into())?),
+            (text("deposits"), int(self.deposits.into())?),
+            (text("refunds"), int(self.refunds.into())?),
+            (text("net"), Metadatum::Map(KeyValuePairs::from(net))),
+        ])))
+    }
+
+    /// attach the summary to the transaction's metadata under [`SUMMARY_LABEL`]
+    ///
+    /// The `auxiliary_data_hash` of the body is updated accordingly, this
+    /// needs to be done before the transaction is signed. The metadata
+    /// makes the transaction bigger, the fee may no longer cover it:
+    /// prefer building the transaction with
+    /// [`TxIntent::with_summary`](super::TxIntent::with_summary).
+    pub fn attach(&self, tx: &mut Tx) -> Result<(), SummaryError> {
+        Metadata::new()
+            .with(SUMMARY_LABEL, self.to_metadatum()?)
+            .attach(tx);
+        Ok(())
+    }
+}
+
+fn output_address(output: &TransactionOutput) -> &[u8] {
+    match output {
+        TransactionOutput::Legacy(output) => &output.address,
+        TransactionOutput::PostAlonzo(output) => &output.address,
+    }
+}
+
+fn output_coin(output: &TransactionOutput) -> Coin {
+    match output {
+        TransactionOutput::Legacy(output) => match output.amount {
+            super::LegacyValue::Coin(coin) | super::LegacyValue::Multiasset(coin, _) => coin,
+        },
+        TransactionOutput::PostAlonzo(output) => super::lovelace_of(&output.value),
+    }
+}
+
+pub(super) fn certificates_deposits(
+    body: &TransactionBody,
+    params: &DepositParams,
+) -> (Coin, Coin) {
+    let mut deposits: Coin = body
+        .proposal_procedures
+        .iter()
+        .flat_map(|proposals| proposals.iter())
+        .map(|proposal| proposal.deposit)
+        .sum();
+    let mut refunds = 0;
+
+    for certificate in body.certificates.iter().flat_map(|certs| certs.iter()) {
+        match certificate {
+            Certificate::StakeRegistration(_) => deposits += params.key_deposit,
+            Certificate::StakeDeregistration(_) => refunds += params.key_deposit,
+            Certificate::PoolRegistration { .. } => deposits += params.pool_deposit,
+            Certificate::Reg(_, coin)
+            | Certificate::StakeRegDeleg(_, _, coin)
+            | Certificate::VoteRegDeleg(_, _, coin)
+            | Certificate::StakeVoteRegDeleg(_, _, _, coin)
+            | Certificate::RegDRepCert(_, coin, _) => deposits += coin,
+            Certificate::UnReg(_, coin) | Certificate::UnRegDRepCert(_, coin) => refunds += coin,
+            Certificate::StakeDelegation(..)
+            | Certificate::PoolRetirement(..)
+            | Certificate::VoteDeleg(..)
+            | Certificate::StakeVoteDeleg(..)
+            | Certificate::AuthCommitteeHot(..)
+            | Certificate::ResignCommitteeCold(..)
+            | Certificate::UpdateDRepCert(..) => {}
+        }
+    }
+
+    (deposits, refunds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{AuxiliaryData, Hash, StakeCredential, Utxo, Value, testing::address};
+    use pallas_codec::utils::{NonEmptySet, Nullable, Set};
+    use pallas_primitives::babbage::PseudoPostAlonzoTransactionOutput;
+
+    const PARAMS: DepositParams = DepositParams {
+        key_deposit: 2_000_000,
+        pool_deposit: 500_000_000,
+    };
+
+    fn output(address: &Address, coin: Coin) -> TransactionOutput {
+        TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+            address: address.to_vec().into(),
+            value: Value::Coin(coin),
+            datum_option: None,
+            script_ref: None,
+        })
+    }
+
+    fn body(inputs: Vec<TransactionInput>, outputs: Vec<TransactionOutput>) -> TransactionBody {
+        TransactionBody {
+            inputs: Set::from(inputs),
+            outputs,
+            fee: 200_000,
+            ttl: None,
+            certificates: NonEmptySet::from_vec(vec![Certificate::StakeRegistration(
+                StakeCredential::AddrKeyhash(Hash::from([9; 28])),
+            )]),
+            withdrawals: None,
+            auxiliary_data_hash: None,
+            validity_interval_start: None,
+            mint: None,
+            script_data_hash: None,
+            collateral: None,
+            required_signers: None,
+            network_id: None,
+            collateral_return: None,
+            total_collateral: None,
+            reference_inputs: None,
+            voting_procedures: None,
+            proposal_procedures: None,
+            treasury_value: None,
+            donation: None,
+        }
+    }
+
+    #[test]
+    fn summary_of_payment() {
+        let sender = address(1);
+        let receiver = address(2);
+        let input = TransactionInput {
+            transaction_id: Hash::from([0; 32]),
+            index: 0,
+        };
+        let inputs: UtxoSet = vec![Utxo {
+            input: input.clone(),
+            output: output(&sender, 10_000_000),
+        }]
+        .into();
+        let body = body(
+            vec![input],
+            vec![output(&receiver, 5_000_000), output(&sender, 2_800_000)],
+        );
+
+        let summary = TxSummary::new(&body, &inputs, &PARAMS).unwrap();
+
+        assert_eq!(summary.fee, 200_000);
+        assert_eq!(summary.deposits, 2_000_000);
+        assert_eq!(summary.refunds, 0);
+        assert_eq!(
+            summary.net_change,
+            vec![
+                AddressChange {
+                    address: sender,
+                    lovelace: -7_200_000
+                },
+                AddressChange {
+                    address: receiver,
+                    lovelace: 5_000_000
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn summary_unresolved_input() {
+        let body = body(
+            vec![TransactionInput {
+                transaction_id: Hash::from([0; 32]),
+                index: 0,
+            }],
+            Vec::new(),
+        );
+
+        assert!(matches!(
+            TxSummary::new(&body, &UtxoSet::new(), &PARAMS),
+            Err(SummaryError::UnresolvedInput(_))
+        ));
+    }
+
+    #[test]
+    fn attach_sets_auxiliary_data_hash() {
+        let body = body(Vec::new(), vec![output(&address(1), 1_000_000)]);
+        let summary = TxSummary::new(&body, &UtxoSet::new(), &PARAMS).unwrap();
+        let mut tx = Tx {
+            transaction_body: body,
+            transaction_witness_set: crate::cardano::testing::empty_witness_set(),
+            success: true,
+            auxiliary_data: Nullable::Null,
+        };
+
+        summary.attach(&mut tx).unwrap();
+
+        let Nullable::Some(auxiliary_data) = &tx.auxiliary_data else {
+            panic!("expected auxiliary data");
+        };
+        let expected = pallas_crypto::hash::Hasher::<256>::hash_cbor(auxiliary_data);
+        assert_eq!(
+            tx.transaction_body
+                .auxiliary_data_hash
+                .as_ref()
+                .map(|hash| hash.as_slice()),
+            Some(expected.as_ref())
+        );
+
+        // attaching twice replaces the previous summary
+        summary.attach(&mut tx).unwrap();
+        let Nullable::Some(AuxiliaryData::PostAlonzo(data)) = &tx.auxiliary_data else {
+            panic!("expected post alonzo auxiliary data");
+        };
+        assert_eq!(data.metadata.as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn amount_out_of_range() {
+        let summary = TxSummary {
+            fee: 200_000,
+            deposits: 0,
+            refunds: 0,
+            net_change: vec![AddressChange {
+                address: address(1),
+                lovelace: i128::MAX,
+            }],
+        };
+
+        assert!(matches!(
+            summary.to_metadatum(),
+            Err(SummaryError::AmountOutOfRange(i128::MAX))
+        ));
+    }
+}
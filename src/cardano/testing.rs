@@ -0,0 +1,94 @@
+//! helpers to build the fixtures of the tests
+
+use super::{
+    BuildParams, Hash, TransactionBody, TransactionInput, TransactionOutput, Utxo, Value,
+    WitnessSet,
+};
+use crate::Address;
+use pallas_addresses::{Network, ShelleyAddress, ShelleyDelegationPart, ShelleyPaymentPart};
+use pallas_codec::utils::Set;
+use pallas_primitives::babbage::PseudoPostAlonzoTransactionOutput;
+
+/// a test network enterprise address of the key hash `[key; 28]`
+pub fn address(key: u8) -> Address {
+    ShelleyAddress::new(
+        Network::Testnet,
+        ShelleyPaymentPart::key_hash(Hash::from([key; 28])),
+        ShelleyDelegationPart::Null,
+    )
+    .into()
+}
+
+/// a test network base address, of the payment and stake key hashes
+/// `[key; 28]`
+pub fn base_address(key: u8) -> Address {
+    ShelleyAddress::new(
+        Network::Testnet,
+        ShelleyPaymentPart::key_hash(Hash::from([key; 28])),
+        ShelleyDelegationPart::key_hash(Hash::from([key; 28])),
+    )
+    .into()
+}
+
+/// the mainnet parameters, sending the change to `address(1)`, with a
+/// TTL
+pub fn params() -> BuildParams {
+    BuildParams {
+        ttl: Some(1_000),
+        ..BuildParams::defaults(address(1))
+    }
+}
+
+/// the `index`-th output of a transaction, without datum
+pub fn utxo(index: u64, address: &Address, value: Value) -> Utxo {
+    Utxo {
+        input: TransactionInput {
+            transaction_id: Hash::from([0; 32]),
+            index,
+        },
+        output: TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+            address: address.to_vec().into(),
+            value,
+            datum_option: None,
+            script_ref: None,
+        }),
+    }
+}
+
+pub fn empty_body() -> TransactionBody {
+    TransactionBody {
+        inputs: Set::from(Vec::new()),
+        outputs: Vec::new(),
+        fee: 0,
+        ttl: None,
+        certificates: None,
+        withdrawals: None,
+        auxiliary_data_hash: None,
+        validity_interval_start: None,
+        mint: None,
+        script_data_hash: None,
+        collateral: None,
+        required_signers: None,
+        network_id: None,
+        collateral_return: None,
+        total_collateral: None,
+        reference_inputs: None,
+        voting_procedures: None,
+        proposal_procedures: None,
+        treasury_value: None,
+        donation: None,
+    }
+}
+
+pub fn empty_witness_set() -> WitnessSet {
+    WitnessSet {
+        vkeywitness: None,
+        native_script: None,
+        bootstrap_witness: None,
+        plutus_v1_script: None,
+        plutus_data: None,
+        redeemer: None,
+        plutus_v2_script: None,
+        plutus_v3_script: None,
+    }
+}
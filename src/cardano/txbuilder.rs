@@ -0,0 +1,154 @@
+//! conversions from this crate's types to the `pallas_txbuilder` ones
+
+use super::{TransactionOutput, Utxo, UtxoSet, Value};
+use crate::Address;
+use pallas_codec::minicbor;
+use pallas_primitives::conway::{DatumOption, ScriptRef};
+use pallas_txbuilder::{Input, Output, ScriptKind, StagingTransaction, TxBuilderError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConversionError {
+    #[error("Invalid address: {0}")]
+    Address(#[from] pallas_addresses::Error),
+    #[error(transparent)]
+    Builder(#[from] TxBuilderError),
+}
+
+impl From<&Utxo> for Input {
+    fn from(utxo: &Utxo) -> Self {
+        Input::new(utxo.input.transaction_id, utxo.input.index)
+    }
+}
+
+impl TryFrom<&Utxo> for Output {
+    type Error = ConversionError;
+
+    fn try_from(utxo: &Utxo) -> Result<Self, Self::Error> {
+        to_output(&utxo.output)
+    }
+}
+
+/// convert the output into a `pallas_txbuilder` [`Output`], keeping the
+/// assets, the datum and the reference script
+pub fn to_output(output: &TransactionOutput) -> Result<Output, ConversionError> {
+    match output {
+        TransactionOutput::Legacy(legacy) => {
+            let address = Address::from_bytes(&legacy.address)?;
+            let value = super::value_from_legacy(&legacy.amount);
+            let output = with_assets(Output::new(address, super::lovelace_of(&value)), &value)?;
+            Ok(match legacy.datum_hash {
+                Some(hash) => output.set_datum_hash(hash),
+                None => output,
+            })
+        }
+        TransactionOutput::PostAlonzo(post_alonzo) => {
+            let address = Address::from_bytes(&post_alonzo.address)?;
+            let value = &post_alonzo.value;
+            let mut output = with_assets(Output::new(address, super::lovelace_of(value)), value)?;
+
+            output = match &post_alonzo.datum_option {
+                Some(DatumOption::Hash(hash)) => output.set_datum_hash(*hash),
+                Some(DatumOption::Data(data)) => {
+                    // encoding into a vec never fails
+                    output.set_inline_datum(minicbor::to_vec(&data.0).unwrap_or_default())
+                }
+                None => output,
+            };
+
+            if let Some(script) = &post_alonzo.script_ref {
+                let (kind, bytes) = match &script.0 {
+                    ScriptRef::NativeScript(script) => (
+                        ScriptKind::Native,
+                        minicbor::to_vec(script).unwrap_or_default(),
+                    ),
+                    ScriptRef::PlutusV1Script(script) => (ScriptKind::PlutusV1, script.0.to_vec()),
+                    ScriptRef::PlutusV2Script(script) => (ScriptKind::PlutusV2, script.0.to_vec()),
+                    ScriptRef::PlutusV3Script(script) => (ScriptKind::PlutusV3, script.0.to_vec()),
+                };
+                output = output.set_inline_script(kind, bytes);
+            }
+
+            Ok(output)
+        }
+    }
+}
+
+fn with_assets(mut output: Output, value: &Value) -> Result<Output, ConversionError> {
+    if let Value::Multiasset(_, multiasset) = value {
+        for (policy, assets) in multiasset.iter() {
+            for (name, amount) in assets.iter() {
+                output = output.add_asset(*policy, name.to_vec(), amount.into())?;
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// add [`UtxoSet`]s to a [`StagingTransaction`]
+pub trait StagingTransactionExt {
+    /// spend all the UTxOs of the set
+    fn inputs(self, utxos: &UtxoSet) -> Self;
+
+    fn collateral_inputs(self, utxos: &UtxoSet) -> Self;
+
+    fn reference_inputs(self, utxos: &UtxoSet) -> Self;
+}
+
+impl StagingTransactionExt for StagingTransaction {
+    fn inputs(self, utxos: &UtxoSet) -> Self {
+        utxos
+            .iter()
+            .fold(self, |staging, utxo| staging.input(utxo.into()))
+    }
+
+    fn collateral_inputs(self, utxos: &UtxoSet) -> Self {
+        utxos
+            .iter()
+            .fold(self, |staging, utxo| staging.collateral_input(utxo.into()))
+    }
+
+    fn reference_inputs(self, utxos: &UtxoSet) -> Self {
+        utxos
+            .iter()
+            .fold(self, |staging, utxo| staging.reference_input(utxo.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{Hash, TransactionInput};
+    use pallas_addresses::{Network, ShelleyAddress, ShelleyDelegationPart, ShelleyPaymentPart};
+    use pallas_primitives::babbage::PseudoPostAlonzoTransactionOutput;
+
+    #[test]
+    fn utxo_to_builder_types() {
+        let address: Address = ShelleyAddress::new(
+            Network::Testnet,
+            ShelleyPaymentPart::key_hash(Hash::from([1; 28])),
+            ShelleyDelegationPart::Null,
+        )
+        .into();
+        let utxo = Utxo {
+            input: TransactionInput {
+                transaction_id: Hash::from([2; 32]),
+                index: 3,
+            },
+            output: TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+                address: address.to_vec().into(),
+                value: Value::Coin(1_000_000),
+                datum_option: Some(DatumOption::Hash(Hash::from([4; 32]))),
+                script_ref: None,
+            }),
+        };
+
+        let input = Input::from(&utxo);
+        let output = Output::try_from(&utxo).unwrap();
+        let staging = StagingTransaction::new().inputs(&UtxoSet::from(vec![utxo]));
+
+        assert_eq!(input, Input::new(Hash::from([2; 32]), 3));
+        assert_eq!(output.lovelace, 1_000_000);
+        assert!(output.datum.is_some());
+        assert_eq!(staging.inputs, Some(vec![input]));
+    }
+}
@@ -0,0 +1,149 @@
+use super::{StakeCredential, Utxo, Value, sumup};
+use crate::address::AddressExt as _;
+use std::collections::BTreeMap;
+
+/// A collection of [`Utxo`], typically as returned by the wallet's
+/// `getUtxos`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct UtxoSet(Vec<Utxo>);
+
+impl UtxoSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, utxo: Utxo) {
+        self.0.push(utxo)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Utxo> {
+        self.0.iter()
+    }
+
+    pub fn as_slice(&self) -> &[Utxo] {
+        &self.0
+    }
+
+    pub fn into_vec(self) -> Vec<Utxo> {
+        self.0
+    }
+
+    /// sum up the [`Value`] of all the UTxOs of the set
+    pub fn total(&self) -> Value {
+        sumup(self.0.iter().map(|utxo| &utxo.output))
+    }
+
+    /// Group the UTxOs by the stake credential of their address.
+    ///
+    /// Multi-account wallets return the UTxOs of all the accounts of the
+    /// user. Each account has its own stake credential so this allows to
+    /// respect account boundaries when selecting inputs.
+    ///
+    /// UTxOs without a resolvable stake credential (enterprise, byron or
+    /// pointer addresses, or invalid addresses) are grouped under `None`.
+    pub fn group_by_stake_credential(&self) -> BTreeMap<Option<StakeCredential>, UtxoSet> {
+        let mut groups: BTreeMap<_, UtxoSet> = BTreeMap::new();
+
+        for utxo in self.iter() {
+            let credential = utxo
+                .address()
+                .ok()
+                .and_then(|address| address.stake_credential());
+            groups.entry(credential).or_default().push(utxo.clone());
+        }
+
+        groups
+    }
+}
+
+impl From<Vec<Utxo>> for UtxoSet {
+    fn from(utxos: Vec<Utxo>) -> Self {
+        Self(utxos)
+    }
+}
+
+impl FromIterator<Utxo> for UtxoSet {
+    fn from_iter<T: IntoIterator<Item = Utxo>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for UtxoSet {
+    type Item = Utxo;
+    type IntoIter = std::vec::IntoIter<Utxo>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a UtxoSet {
+    type Item = &'a Utxo;
+    type IntoIter = std::slice::Iter<'a, Utxo>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{Hash, TransactionInput, TransactionOutput};
+    use pallas_addresses::{Network, ShelleyAddress, ShelleyDelegationPart, ShelleyPaymentPart};
+    use pallas_primitives::babbage::PseudoPostAlonzoTransactionOutput;
+
+    fn utxo(index: u64, delegation: ShelleyDelegationPart, coin: u64) -> Utxo {
+        let address = ShelleyAddress::new(
+            Network::Testnet,
+            ShelleyPaymentPart::key_hash(Hash::from([1; 28])),
+            delegation,
+        );
+        Utxo {
+            input: TransactionInput {
+                transaction_id: Hash::from([0; 32]),
+                index,
+            },
+            output: TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+                address: address.to_vec().into(),
+                value: Value::Coin(coin),
+                datum_option: None,
+                script_ref: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn group_by_stake_credential() {
+        let account_a = Hash::from([2; 28]);
+        let account_b = Hash::from([3; 28]);
+        let set: UtxoSet = vec![
+            utxo(0, ShelleyDelegationPart::Key(account_a), 1),
+            utxo(1, ShelleyDelegationPart::Key(account_b), 2),
+            utxo(2, ShelleyDelegationPart::Key(account_a), 3),
+            utxo(3, ShelleyDelegationPart::Null, 4),
+        ]
+        .into();
+
+        let groups = set.group_by_stake_credential();
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!(
+            groups[&Some(StakeCredential::AddrKeyhash(account_a))].total(),
+            Value::Coin(4)
+        );
+        assert_eq!(
+            groups[&Some(StakeCredential::AddrKeyhash(account_b))].total(),
+            Value::Coin(2)
+        );
+        assert_eq!(groups[&None].total(), Value::Coin(4));
+    }
+}
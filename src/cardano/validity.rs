@@ -0,0 +1,129 @@
+use super::{Tx, TxHash, inspect::DecodedTx};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum ValidityError {
+    #[error("Transaction {hash} expired at slot {ttl} (current slot {current_slot})")]
+    Expired {
+        hash: TxHash,
+        ttl: u64,
+        current_slot: u64,
+    },
+    #[error("Transaction {hash} is not valid before slot {start} (current slot {current_slot})")]
+    NotYetValid {
+        hash: TxHash,
+        start: u64,
+        current_slot: u64,
+    },
+}
+
+/// Check the validity interval of the transaction against the current
+/// slot, i.e. that it can still be included in a block.
+///
+/// The errors carry the hash of the body as it was decoded, see
+/// [`tx_hash`](super::inspect::tx_hash).
+pub fn check_validity(tx: &DecodedTx, current_slot: u64) -> Result<(), ValidityError> {
+    let body = &tx.tx.transaction_body;
+
+    if let Some(ttl) = body.ttl
+        && ttl <= current_slot
+    {
+        return Err(ValidityError::Expired {
+            hash: tx.hash,
+            ttl,
+            current_slot,
+        });
+    }
+    if let Some(start) = body.validity_interval_start
+        && current_slot < start
+    {
+        return Err(ValidityError::NotYetValid {
+            hash: tx.hash,
+            start,
+            current_slot,
+        });
+    }
+
+    Ok(())
+}
+
+/// Rebuild an expired transaction with a TTL of `validity` slots from
+/// `current_slot`.
+///
+/// Changing the TTL changes the body, so the vkey and bootstrap witnesses
+/// are removed: the returned transaction needs to be signed again. The
+/// scripts, datums and redeemers are kept.
+pub fn with_fresh_ttl(tx: &Tx, current_slot: u64, validity: u64) -> Tx {
+    let mut tx = tx.clone();
+    let body = &mut tx.transaction_body;
+
+    body.ttl = Some(current_slot.saturating_add(validity));
+    if body
+        .validity_interval_start
+        .is_some_and(|start| start > current_slot)
+    {
+        body.validity_interval_start = Some(current_slot);
+    }
+
+    let witnesses = &mut tx.transaction_witness_set;
+    witnesses.vkeywitness = None;
+    witnesses.bootstrap_witness = None;
+
+    tx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{
+        inspect::{decode_tx, tx_hash},
+        testing::{empty_body, empty_witness_set},
+    };
+    use pallas_codec::{minicbor, utils::Nullable};
+    use pallas_crypto::hash::Hasher;
+
+    fn decoded(tx: &Tx) -> DecodedTx {
+        decode_tx(&minicbor::to_vec(tx).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn refresh_expired_tx() {
+        let mut body = empty_body();
+        body.ttl = Some(100);
+        let tx = Tx {
+            transaction_body: body,
+            transaction_witness_set: empty_witness_set(),
+            success: true,
+            auxiliary_data: Nullable::Null,
+        };
+
+        assert_eq!(check_validity(&decoded(&tx), 99), Ok(()));
+        assert!(matches!(
+            check_validity(&decoded(&tx), 100),
+            Err(ValidityError::Expired { ttl: 100, .. })
+        ));
+
+        let refreshed = with_fresh_ttl(&tx, 150, 3600);
+        assert_eq!(refreshed.transaction_body.ttl, Some(3750));
+        assert_eq!(check_validity(&decoded(&refreshed), 150), Ok(()));
+    }
+
+    #[test]
+    fn hash_of_the_original_body() {
+        // a fee of 1 encoded on 2 bytes, re-encoded on 1 byte
+        let bytes = [
+            0x84, 0xa4, 0x00, 0x80, 0x01, 0x80, 0x02, 0x19, 0x00, 0x01, 0x03, 0x18, 0x64, 0xa0,
+            0xf5, 0xf6,
+        ];
+        let tx = decode_tx(&bytes).unwrap();
+        assert_ne!(tx.hash, Hasher::<256>::hash_cbor(&tx.tx.transaction_body));
+
+        assert_eq!(
+            check_validity(&tx, 100),
+            Err(ValidityError::Expired {
+                hash: tx_hash(&bytes).unwrap(),
+                ttl: 100,
+                current_slot: 100,
+            })
+        );
+    }
+}
@@ -0,0 +1,424 @@
+//! Helpers on the pallas [`Value`].
+//!
+//! A [`Value`] has one dimension per asset (lovelace being one of them),
+//! so two values are not always comparable: `{ 5 ADA }` and
+//! `{ 1 ADA, 10 HOSKY }` are neither greater nor lower than the other.
+//! [`ValueExt::partial_cmp_value`] implements this partial order.
+//!
+//! [`WalletValue`] is the arithmetic on the values (sums of outputs, coin
+//! selection targets, change): it keeps the quantities in a flat map and
+//! converts from and to the pallas [`Value`] at the edges.
+//!
+//! ```
+//! # use cardano_connector::cardano::{Value, WalletValue};
+//! let inputs = WalletValue::from(&Value::Coin(10_000_000));
+//! let outputs = WalletValue::from_lovelace(7_000_000);
+//! let change = inputs.checked_sub(&outputs).unwrap();
+//! assert_eq!(Value::from(change), Value::Coin(3_000_000));
+//! assert!(outputs.checked_sub(&inputs).is_none());
+//! ```
+
+use super::{
+    AssetId, Coin, Multiasset, NonEmptyKeyValuePairs, PolicyId, Value, assets_of, lovelace_of,
+};
+use crate::backend::{BackendError, FiatEstimate, PriceProvider};
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    iter::Sum,
+    ops::{Add, AddAssign},
+};
+
+#[allow(async_fn_in_trait)]
+pub trait ValueExt {
+    /// the quantity of every dimension: lovelace under `None`, the
+    /// native assets under their [`AssetId`]
+    fn dimensions(&self) -> BTreeMap<Option<AssetId>, u64>;
+
+    /// compare the values dimension by dimension
+    ///
+    /// `Some(Ordering::Greater)` if every quantity of `self` is at least
+    /// the one of `other` and one is strictly greater, `None` if some
+    /// quantities are greater and others lower.
+    fn partial_cmp_value(&self, other: &Value) -> Option<Ordering>;
+
+    /// `self` covers `required`: it has at least as much lovelace and at
+    /// least as much of every asset of `required`
+    fn meets_or_exceeds(&self, required: &Value) -> bool {
+        matches!(
+            self.partial_cmp_value(required),
+            Some(Ordering::Greater | Ordering::Equal)
+        )
+    }
+
+    /// the approximate value in fiat `currency`, see [`fiat_estimate`]
+    ///
+    /// [`fiat_estimate`]: crate::backend::fiat_estimate
+    async fn fiat_estimate<P: PriceProvider>(
+        &self,
+        provider: &P,
+        currency: &str,
+    ) -> Result<FiatEstimate, BackendError>;
+}
+
+impl ValueExt for Value {
+    fn dimensions(&self) -> BTreeMap<Option<AssetId>, u64> {
+        std::iter::once((None, lovelace_of(self)))
+            .chain(
+                assets_of(self)
+                    .into_iter()
+                    .map(|(asset, quantity)| (Some(asset), quantity)),
+            )
+            .collect()
+    }
+
+    fn partial_cmp_value(&self, other: &Value) -> Option<Ordering> {
+        let ours = self.dimensions();
+        let theirs = other.dimensions();
+
+        ours.keys()
+            .chain(theirs.keys())
+            .map(|dimension| {
+                let ours = ours.get(dimension).copied().unwrap_or_default();
+                let theirs = theirs.get(dimension).copied().unwrap_or_default();
+                ours.cmp(&theirs)
+            })
+            .try_fold(Ordering::Equal, |acc, ordering| match (acc, ordering) {
+                (acc, Ordering::Equal) => Some(acc),
+                (Ordering::Equal, ordering) => Some(ordering),
+                (acc, ordering) if acc == ordering => Some(acc),
+                _ => None,
+            })
+    }
+
+    async fn fiat_estimate<P: PriceProvider>(
+        &self,
+        provider: &P,
+        currency: &str,
+    ) -> Result<FiatEstimate, BackendError> {
+        crate::backend::fiat_estimate(self, provider, currency).await
+    }
+}
+
+/// A multi-asset value: lovelace and the quantities of the native assets.
+///
+/// The assets of quantity zero are not kept, so two equal values have
+/// the same representation. Ordered like [`ValueExt::partial_cmp_value`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct WalletValue {
+    lovelace: Coin,
+    assets: BTreeMap<AssetId, u64>,
+}
+
+impl WalletValue {
+    pub const fn zero() -> Self {
+        Self {
+            lovelace: 0,
+            assets: BTreeMap::new(),
+        }
+    }
+
+    pub const fn from_lovelace(lovelace: Coin) -> Self {
+        Self {
+            lovelace,
+            assets: BTreeMap::new(),
+        }
+    }
+
+    /// add `quantity` of `asset`
+    pub fn with_asset(mut self, asset: AssetId, quantity: u64) -> Self {
+        self.add_asset(asset, quantity);
+        self
+    }
+
+    pub fn lovelace(&self) -> Coin {
+        self.lovelace
+    }
+
+    pub fn assets(&self) -> &BTreeMap<AssetId, u64> {
+        &self.assets
+    }
+
+    /// the quantity of `asset`, zero if the value does not hold any
+    pub fn quantity(&self, asset: &AssetId) -> u64 {
+        self.assets.get(asset).copied().unwrap_or_default()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.lovelace == 0 && self.assets.is_empty()
+    }
+
+    pub fn has_assets(&self) -> bool {
+        !self.assets.is_empty()
+    }
+
+    pub fn add_lovelace(&mut self, lovelace: Coin) {
+        self.lovelace += lovelace;
+    }
+
+    pub fn add_asset(&mut self, asset: AssetId, quantity: u64) {
+        if quantity > 0 {
+            *self.assets.entry(asset).or_default() += quantity;
+        }
+    }
+
+    /// `None` if a quantity overflows
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        let mut sum = self.clone();
+        sum.lovelace = sum.lovelace.checked_add(other.lovelace)?;
+        for (asset, quantity) in &other.assets {
+            let entry = sum.assets.entry(asset.clone()).or_default();
+            *entry = entry.checked_add(*quantity)?;
+        }
+        Some(sum)
+    }
+
+    /// `self - other`, `None` if `self` does not [`contain`](Self::contains)
+    /// `other`
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        let mut difference = self.clone();
+        difference.lovelace = difference.lovelace.checked_sub(other.lovelace)?;
+        for (asset, quantity) in &other.assets {
+            let remaining = difference.quantity(asset).checked_sub(*quantity)?;
+            if remaining == 0 {
+                difference.assets.remove(asset);
+            } else {
+                difference.assets.insert(asset.clone(), remaining);
+            }
+        }
+        Some(difference)
+    }
+
+    /// `self - other` with every quantity floored at zero, e.g. what is
+    /// still missing from `self` to reach `other` is
+    /// `other.saturating_sub(&self)`
+    pub fn saturating_sub(&self, other: &Self) -> Self {
+        let assets = self
+            .assets
+            .iter()
+            .map(|(asset, quantity)| {
+                (
+                    asset.clone(),
+                    quantity.saturating_sub(other.quantity(asset)),
+                )
+            })
+            .filter(|(_, quantity)| *quantity > 0)
+            .collect();
+        Self {
+            lovelace: self.lovelace.saturating_sub(other.lovelace),
+            assets,
+        }
+    }
+
+    /// `self` has at least as much of every dimension as `other`
+    pub fn contains(&self, other: &Self) -> bool {
+        self.lovelace >= other.lovelace
+            && other
+                .assets
+                .iter()
+                .all(|(asset, quantity)| self.quantity(asset) >= *quantity)
+    }
+
+    pub fn to_value(&self) -> Value {
+        let mut policies: BTreeMap<PolicyId, Vec<_>> = BTreeMap::new();
+        for (asset, quantity) in &self.assets {
+            if let Ok(quantity) = (*quantity).try_into() {
+                policies
+                    .entry(asset.policy)
+                    .or_default()
+                    .push((asset.name.clone(), quantity));
+            }
+        }
+
+        let assets = Multiasset::from_vec(
+            policies
+                .into_iter()
+                .filter_map(|(policy, assets)| {
+                    NonEmptyKeyValuePairs::from_vec(assets).map(|assets| (policy, assets))
+                })
+                .collect(),
+        );
+        match assets {
+            Some(assets) => Value::Multiasset(self.lovelace, assets),
+            None => Value::Coin(self.lovelace),
+        }
+    }
+}
+
+impl From<&Value> for WalletValue {
+    fn from(value: &Value) -> Self {
+        Self {
+            lovelace: lovelace_of(value),
+            assets: assets_of(value)
+                .into_iter()
+                .filter(|(_, quantity)| *quantity > 0)
+                .collect(),
+        }
+    }
+}
+
+impl From<Value> for WalletValue {
+    fn from(value: Value) -> Self {
+        Self::from(&value)
+    }
+}
+
+impl From<&WalletValue> for Value {
+    fn from(value: &WalletValue) -> Self {
+        value.to_value()
+    }
+}
+
+impl From<WalletValue> for Value {
+    fn from(value: WalletValue) -> Self {
+        value.to_value()
+    }
+}
+
+impl PartialOrd for WalletValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self.contains(other), other.contains(self)) {
+            (true, true) => Some(Ordering::Equal),
+            (true, false) => Some(Ordering::Greater),
+            (false, true) => Some(Ordering::Less),
+            (false, false) => None,
+        }
+    }
+}
+
+impl AddAssign<&WalletValue> for WalletValue {
+    /// panics if a quantity overflows, see
+    /// [`checked_add`](WalletValue::checked_add)
+    fn add_assign(&mut self, other: &WalletValue) {
+        self.lovelace += other.lovelace;
+        for (asset, quantity) in &other.assets {
+            self.add_asset(asset.clone(), *quantity);
+        }
+    }
+}
+
+impl AddAssign for WalletValue {
+    fn add_assign(&mut self, other: WalletValue) {
+        *self += &other;
+    }
+}
+
+impl Add for WalletValue {
+    type Output = WalletValue;
+
+    fn add(mut self, other: WalletValue) -> WalletValue {
+        self += &other;
+        self
+    }
+}
+
+impl Sum for WalletValue {
+    fn sum<I: Iterator<Item = WalletValue>>(iter: I) -> Self {
+        iter.fold(Self::zero(), Add::add)
+    }
+}
+
+impl<'a> Sum<&'a WalletValue> for WalletValue {
+    fn sum<I: Iterator<Item = &'a WalletValue>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |mut sum, value| {
+            sum += value;
+            sum
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{Hash, Multiasset, NonEmptyKeyValuePairs};
+
+    fn tokens(coin: u64, quantity: u64) -> Value {
+        let assets = NonEmptyKeyValuePairs::from_vec(vec![(
+            b"token".to_vec().into(),
+            quantity.try_into().unwrap(),
+        )])
+        .unwrap();
+        Value::Multiasset(
+            coin,
+            Multiasset::from_vec(vec![(Hash::from([1; 28]), assets)]).unwrap(),
+        )
+    }
+
+    #[test]
+    fn partial_order() {
+        assert_eq!(
+            tokens(5, 10).partial_cmp_value(&tokens(5, 10)),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            tokens(6, 10).partial_cmp_value(&tokens(5, 10)),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            Value::Coin(5).partial_cmp_value(&tokens(5, 1)),
+            Some(Ordering::Less)
+        );
+        assert_eq!(Value::Coin(6).partial_cmp_value(&tokens(5, 1)), None);
+    }
+
+    #[test]
+    fn meets_or_exceeds() {
+        assert!(tokens(10, 3).meets_or_exceeds(&tokens(10, 3)));
+        assert!(tokens(10, 3).meets_or_exceeds(&Value::Coin(2)));
+        assert!(!Value::Coin(100).meets_or_exceeds(&tokens(1, 1)));
+        assert!(!tokens(1, 3).meets_or_exceeds(&tokens(2, 1)));
+    }
+
+    fn token() -> AssetId {
+        AssetId::new(Hash::from([1; 28]), b"token".to_vec().into())
+    }
+
+    #[test]
+    fn wallet_value_arithmetic() {
+        let a = WalletValue::from(&tokens(5, 10));
+        let b = WalletValue::from_lovelace(2).with_asset(token(), 4);
+
+        assert_eq!(Value::from(a.clone() + b.clone()), tokens(7, 14));
+        assert_eq!(Value::from(a.checked_sub(&b).unwrap()), tokens(3, 6));
+        assert_eq!(b.checked_sub(&a), None);
+        assert_eq!(
+            a.checked_sub(&WalletValue::from(&tokens(5, 10))),
+            Some(WalletValue::zero())
+        );
+        assert_eq!(
+            b.saturating_sub(&a),
+            WalletValue::zero(),
+            "nothing of b is missing from a"
+        );
+        assert_eq!(
+            WalletValue::from_lovelace(9).saturating_sub(&a),
+            WalletValue::from_lovelace(4)
+        );
+        assert_eq!(
+            [a.clone(), b.clone()].iter().sum::<WalletValue>(),
+            a.checked_add(&b).unwrap()
+        );
+        assert_eq!(
+            WalletValue::from_lovelace(u64::MAX).checked_add(&WalletValue::from_lovelace(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn wallet_value_order() {
+        let a = WalletValue::from(&tokens(5, 10));
+        assert!(a.contains(&WalletValue::from_lovelace(5)));
+        assert!(a > WalletValue::from_lovelace(5));
+        assert!(a >= a.clone());
+        assert_eq!(
+            a.partial_cmp(&WalletValue::from_lovelace(6)),
+            None,
+            "more tokens but less lovelace"
+        );
+        assert!(WalletValue::zero().is_zero());
+        assert_eq!(
+            WalletValue::from_lovelace(1).with_asset(token(), 0),
+            WalletValue::from_lovelace(1)
+        );
+    }
+}
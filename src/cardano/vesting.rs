@@ -0,0 +1,293 @@
+/*!
+Timelocked outputs: funds the beneficiary can only spend after a given
+slot.
+
+The funds are locked at the address of a native script requiring the
+signature of the beneficiary and a validity interval starting at the
+unlock slot. Optionally, the owner can take the funds back until the
+unlock slot. Spending a native script needs no redeemer nor collateral,
+only the script in the witness set and the signatures.
+
+```
+# use cardano_connector::{Address, cardano::{SlotConfig, vesting::Vesting}};
+# fn test(beneficiary: &Address, owner: &Address) -> anyhow::Result<()> {
+// 2030-01-01T00:00:00Z
+let unlock = SlotConfig::MAINNET.slot_at(1_893_456_000_000);
+let vesting = Vesting::new(beneficiary, unlock)?.revocable_by(owner)?;
+let script_address = vesting.address();
+# Ok(()) }
+```
+*/
+
+use super::{
+    AddrKeyhash, BuildError, BuildParams, Hash, SlotConfig, Tx, TxIntent, UtxoSet, Value,
+    native_script::{key_address, script_address, script_hash},
+};
+use crate::Address;
+use pallas_addresses::ShelleyAddress;
+use pallas_primitives::conway::NativeScript;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum VestingError {
+    #[error("The {0} address is not a Shelley address with a payment key")]
+    NotKeyAddress(&'static str),
+    #[error("The beneficiary and the owner are on different networks")]
+    NetworkMismatch,
+}
+
+/// The terms of the timelock, see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vesting {
+    beneficiary: ShelleyAddress,
+    owner: Option<ShelleyAddress>,
+    /// the slot from which the beneficiary can spend the funds
+    pub unlock: u64,
+}
+
+impl Vesting {
+    pub fn new(beneficiary: &Address, unlock: u64) -> Result<Self, VestingError> {
+        Ok(Self {
+            beneficiary: key_address(beneficiary)
+                .ok_or(VestingError::NotKeyAddress("beneficiary"))?,
+            owner: None,
+            unlock,
+        })
+    }
+
+    /// same as [`Vesting::new`] with the unlock given as a POSIX time, in
+    /// milliseconds
+    pub fn unlocking_at(
+        beneficiary: &Address,
+        time: u64,
+        config: &SlotConfig,
+    ) -> Result<Self, VestingError> {
+        // the funds must not unlock before `time`: round up to the next slot
+        let slot = config.slot_at(time);
+        let unlock = if config.time_of(slot) < time {
+            slot + 1
+        } else {
+            slot
+        };
+        Self::new(beneficiary, unlock)
+    }
+
+    /// let the `owner` take the funds back before the unlock slot
+    pub fn revocable_by(mut self, owner: &Address) -> Result<Self, VestingError> {
+        let owner = key_address(owner).ok_or(VestingError::NotKeyAddress("owner"))?;
+        if owner.network() != self.beneficiary.network() {
+            return Err(VestingError::NetworkMismatch);
+        }
+        self.owner = Some(owner);
+        Ok(self)
+    }
+
+    pub fn beneficiary(&self) -> Address {
+        self.beneficiary.clone().into()
+    }
+
+    pub fn owner(&self) -> Option<Address> {
+        self.owner.clone().map(Address::from)
+    }
+
+    fn beneficiary_key(&self) -> AddrKeyhash {
+        *self.beneficiary.payment().as_hash()
+    }
+
+    /// the native script locking the funds
+    pub fn script(&self) -> NativeScript {
+        let claim = NativeScript::ScriptAll(vec![
+            NativeScript::ScriptPubkey(self.beneficiary_key()),
+            NativeScript::InvalidBefore(self.unlock),
+        ]);
+
+        match &self.owner {
+            None => claim,
+            Some(owner) => NativeScript::ScriptAny(vec![
+                claim,
+                NativeScript::ScriptAll(vec![
+                    NativeScript::ScriptPubkey(*owner.payment().as_hash()),
+                    NativeScript::InvalidHereafter(self.unlock),
+                ]),
+            ]),
+        }
+    }
+
+    pub fn script_hash(&self) -> Hash<28> {
+        script_hash(&self.script())
+    }
+
+    /// the address of the script, on the network of the beneficiary
+    pub fn address(&self) -> Address {
+        script_address(&self.script(), self.beneficiary.network())
+    }
+
+    /// the UTxOs of `utxos` locked at the script address
+    pub fn locked(&self, utxos: &UtxoSet) -> UtxoSet {
+        let address = self.address();
+        utxos
+            .iter()
+            .filter(|utxo| utxo.address().is_ok_and(|found| found == address))
+            .cloned()
+            .collect()
+    }
+
+    /// the total value locked in `utxos` at the script address
+    pub fn locked_value(&self, utxos: &UtxoSet) -> Value {
+        self.locked(utxos).total()
+    }
+
+    /// whether the beneficiary can claim the funds at `current_slot`
+    pub fn is_unlocked(&self, current_slot: u64) -> bool {
+        current_slot >= self.unlock
+    }
+
+    /// lock `value` at the script address, spending from `utxos`
+    ///
+    /// Anyone can fund the vesting, the change goes to the
+    /// `change_address` of the `params`.
+    pub fn lock(
+        &self,
+        value: Value,
+        utxos: &UtxoSet,
+        params: &BuildParams,
+    ) -> Result<Tx, BuildError> {
+        TxIntent::new()
+            .pay(&self.address(), value)
+            .build(utxos, params)
+    }
+
+    /// the beneficiary spends the `locked` UTxOs once the unlock slot is
+    /// reached, the funds go to the beneficiary
+    ///
+    /// The fee is paid from the locked funds, completed with `utxos` if
+    /// needed. The `ttl` of the `params` needs to be after the unlock slot.
+    pub fn claim(
+        &self,
+        locked: &UtxoSet,
+        utxos: &UtxoSet,
+        params: &BuildParams,
+    ) -> Result<Tx, BuildError> {
+        TxIntent::new()
+            .with_native_script(self.script())
+            .with_required_signer(self.beneficiary_key())
+            .valid_from(self.unlock)
+            .build_spending(
+                locked,
+                utxos,
+                &BuildParams {
+                    change_address: self.beneficiary(),
+                    ..params.clone()
+                },
+            )
+    }
+
+    /// the owner takes the `locked` UTxOs back before the unlock slot
+    ///
+    /// `None` if the vesting is not revocable. The `ttl` of the `params`
+    /// is capped to the unlock slot.
+    pub fn revoke(
+        &self,
+        locked: &UtxoSet,
+        utxos: &UtxoSet,
+        params: &BuildParams,
+    ) -> Option<Result<Tx, BuildError>> {
+        let owner = self.owner.as_ref()?;
+        let ttl = params.ttl.map_or(self.unlock, |ttl| ttl.min(self.unlock));
+
+        Some(
+            TxIntent::new()
+                .with_native_script(self.script())
+                .with_required_signer(*owner.payment().as_hash())
+                .build_spending(
+                    locked,
+                    utxos,
+                    &BuildParams {
+                        change_address: owner.clone().into(),
+                        ttl: Some(ttl),
+                        ..params.clone()
+                    },
+                ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{
+        TransactionInput, Utxo,
+        testing::{address, params, utxo},
+    };
+
+    #[test]
+    fn unlock_from_time() {
+        let config = SlotConfig::PREVIEW;
+
+        let exact = Vesting::unlocking_at(&address(1), config.time_of(100), &config).unwrap();
+        let within = Vesting::unlocking_at(&address(1), config.time_of(100) + 1, &config).unwrap();
+
+        assert_eq!(exact.unlock, 100);
+        assert_eq!(within.unlock, 101);
+    }
+
+    #[test]
+    fn lock_and_claim() {
+        let vesting = Vesting::new(&address(1), 1_000).unwrap();
+        let funder = UtxoSet::from(vec![utxo(1, &address(0), Value::Coin(20_000_000))]);
+
+        let lock = vesting
+            .lock(Value::Coin(10_000_000), &funder, &params())
+            .unwrap();
+        let locked = UtxoSet::from(vec![Utxo {
+            input: TransactionInput {
+                transaction_id: Hash::from([2; 32]),
+                index: 0,
+            },
+            output: lock.transaction_body.outputs[0].clone(),
+        }]);
+        assert_eq!(vesting.locked(&locked), locked);
+        assert_eq!(vesting.locked_value(&locked), Value::Coin(10_000_000));
+
+        let claim = vesting.claim(&locked, &UtxoSet::new(), &params()).unwrap();
+        let body = &claim.transaction_body;
+
+        assert_eq!(body.validity_interval_start, Some(1_000));
+        assert_eq!(body.inputs.len(), 1);
+        assert!(body.collateral.is_none());
+        assert_eq!(
+            claim
+                .transaction_witness_set
+                .native_script
+                .unwrap()
+                .to_vec(),
+            vec![vesting.script()]
+        );
+        assert!(
+            vesting
+                .revoke(&locked, &UtxoSet::new(), &params())
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn revoke_before_unlock() {
+        let vesting = Vesting::new(&address(1), 1_000)
+            .unwrap()
+            .revocable_by(&address(2))
+            .unwrap();
+        let locked = UtxoSet::from(vec![utxo(3, &vesting.address(), Value::Coin(10_000_000))]);
+
+        let revoke = vesting
+            .revoke(&locked, &UtxoSet::new(), &params())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(revoke.transaction_body.ttl, Some(1_000));
+        assert_eq!(
+            revoke.transaction_body.required_signers.unwrap().to_vec(),
+            vec![Hash::from([2; 28])]
+        );
+        assert!(!vesting.is_unlocked(999));
+        assert!(vesting.is_unlocked(1_000));
+    }
+}
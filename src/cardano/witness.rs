@@ -0,0 +1,638 @@
+use super::{
+    AddrKeyhash, Certificate, StakeCredential, TransactionBody, TransactionInput, Tx, TxHash,
+    UtxoSet, WitnessSet, inspect::DecodedTx,
+};
+use crate::{Address, error::VerifyError};
+use pallas_addresses::ShelleyPaymentPart;
+use pallas_codec::utils::{MaybeIndefArray, NonEmptyKeyValuePairs, NonEmptySet, Nullable};
+use pallas_crypto::{
+    hash::Hasher,
+    key::ed25519::{PublicKey, Signature},
+};
+use pallas_primitives::conway::{
+    AuxiliaryData, Redeemer, Redeemers, RedeemersKey, RedeemersValue, VKeyWitness,
+};
+use std::collections::BTreeSet;
+
+/// The payment key hashes that need to sign a transaction body.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RequiredSigners {
+    pub signers: BTreeSet<AddrKeyhash>,
+    /// inputs (or collateral inputs) that were not found in the resolved
+    /// UTxOs, their signers (if any) are not part of `signers`
+    pub unresolved: Vec<TransactionInput>,
+}
+
+impl RequiredSigners {
+    /// Infer the key hashes that must sign the given body.
+    ///
+    /// This looks at the payment credential of the spent inputs and
+    /// collateral (resolved with `inputs`), the declared
+    /// `required_signers`, the withdrawals and the certificates. Script
+    /// credentials are ignored as they are not satisfied by a vkey witness.
+    pub fn infer(body: &TransactionBody, inputs: &UtxoSet) -> Self {
+        let mut required = Self::default();
+
+        let spent = body.inputs.iter().chain(
+            body.collateral
+                .iter()
+                .flat_map(|collateral| collateral.iter()),
+        );
+        for input in spent {
+            match inputs.iter().find(|utxo| &utxo.input == input) {
+                Some(utxo) => {
+                    if let Ok(Address::Shelley(address)) = utxo.address()
+                        && let ShelleyPaymentPart::Key(hash) = address.payment()
+                    {
+                        required.signers.insert(*hash);
+                    }
+                }
+                None => required.unresolved.push(input.clone()),
+            }
+        }
+
+        required.signers.extend(
+            body.required_signers
+                .iter()
+                .flat_map(|signers| signers.iter().copied()),
+        );
+
+        for (reward_account, _) in body.withdrawals.iter().flat_map(|w| w.iter()) {
+            if let Ok(Address::Stake(address)) = Address::from_bytes(reward_account)
+                && let pallas_addresses::StakePayload::Stake(hash) = address.payload()
+            {
+                required.signers.insert(*hash);
+            }
+        }
+
+        for certificate in body.certificates.iter().flat_map(|certs| certs.iter()) {
+            required.signers.extend(certificate_signers(certificate));
+        }
+
+        required
+    }
+
+    /// check if the given keys are enough to sign the whole transaction,
+    /// if not the wallet needs to be asked for a partial signature.
+    pub fn is_covered_by(&self, keys: &BTreeSet<AddrKeyhash>) -> bool {
+        self.unresolved.is_empty() && self.signers.is_subset(keys)
+    }
+}
+
+fn certificate_signers(certificate: &Certificate) -> Vec<AddrKeyhash> {
+    let key = |credential: &StakeCredential| match credential {
+        StakeCredential::AddrKeyhash(hash) => vec![*hash],
+        StakeCredential::ScriptHash(_) => Vec::new(),
+    };
+
+    match certificate {
+        // registering the legacy way does not need a witness
+        Certificate::StakeRegistration(_) => Vec::new(),
+        Certificate::StakeDeregistration(credential)
+        | Certificate::StakeDelegation(credential, _)
+        | Certificate::Reg(credential, _)
+        | Certificate::UnReg(credential, _)
+        | Certificate::VoteDeleg(credential, _)
+        | Certificate::StakeVoteDeleg(credential, _, _)
+        | Certificate::StakeRegDeleg(credential, _, _)
+        | Certificate::VoteRegDeleg(credential, _, _)
+        | Certificate::StakeVoteRegDeleg(credential, _, _, _)
+        | Certificate::AuthCommitteeHot(credential, _)
+        | Certificate::ResignCommitteeCold(credential, _)
+        | Certificate::RegDRepCert(credential, _, _)
+        | Certificate::UnRegDRepCert(credential, _)
+        | Certificate::UpdateDRepCert(credential, _) => key(credential),
+        Certificate::PoolRegistration {
+            operator,
+            pool_owners,
+            ..
+        } => std::iter::once(*operator)
+            .chain(pool_owners.iter().copied())
+            .collect(),
+        Certificate::PoolRetirement(operator, _) => vec![*operator],
+    }
+}
+
+/// Report of which of the expected signers have provided a witness.
+///
+/// This is typically computed after [`sign_tx`] in multisig flows to
+/// display the progress across the different signers.
+///
+/// [`sign_tx`]: crate::ConnectedWallet::sign_tx
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct WitnessReport {
+    /// expected signers with a vkey witness
+    pub provided: BTreeSet<AddrKeyhash>,
+    /// expected signers still without a vkey witness
+    pub missing: BTreeSet<AddrKeyhash>,
+    /// vkey witnesses from keys that were not expected
+    pub unexpected: BTreeSet<AddrKeyhash>,
+}
+
+impl WitnessReport {
+    /// compute the report of the `expected` signers against the vkey
+    /// witnesses collected so far.
+    pub fn new<'a>(
+        expected: impl IntoIterator<Item = AddrKeyhash>,
+        witness_sets: impl IntoIterator<Item = &'a WitnessSet>,
+    ) -> Self {
+        let expected: BTreeSet<_> = expected.into_iter().collect();
+        let signed: BTreeSet<_> = witness_sets.into_iter().flat_map(vkey_hashes).collect();
+
+        Self {
+            provided: expected.intersection(&signed).copied().collect(),
+            missing: expected.difference(&signed).copied().collect(),
+            unexpected: signed.difference(&expected).copied().collect(),
+        }
+    }
+
+    /// same as [`WitnessReport::new`], expecting the `required_signers`
+    /// declared in the transaction body in addition to `expected`.
+    pub fn for_body<'a>(
+        body: &TransactionBody,
+        expected: impl IntoIterator<Item = AddrKeyhash>,
+        witness_sets: impl IntoIterator<Item = &'a WitnessSet>,
+    ) -> Self {
+        let required = body
+            .required_signers
+            .iter()
+            .flat_map(|signers| signers.iter().copied());
+        Self::new(required.chain(expected), witness_sets)
+    }
+
+    /// all the expected signers have provided their witness
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+/// the key hashes of the vkey witnesses of the given witness set
+pub fn vkey_hashes(witness_set: &WitnessSet) -> Vec<AddrKeyhash> {
+    witness_set
+        .vkeywitness
+        .iter()
+        .flat_map(|witnesses| witnesses.iter())
+        .map(|witness| Hasher::<224>::hash(&witness.vkey))
+        .collect()
+}
+
+/// the key hashes of the vkey witnesses of `witness_set` whose signature
+/// of `tx_hash` is not valid (e.g. made for another body)
+pub fn invalid_vkey_witnesses(tx_hash: &TxHash, witness_set: &WitnessSet) -> Vec<AddrKeyhash> {
+    witness_set
+        .vkeywitness
+        .iter()
+        .flat_map(|witnesses| witnesses.iter())
+        .filter(|witness| !verify_vkey_witness(tx_hash, witness))
+        .map(|witness| Hasher::<224>::hash(&witness.vkey))
+        .collect()
+}
+
+/// check the vkey witnesses of `tx` before submitting it
+///
+/// Every signature must be for the transaction's body and every signer
+/// required by the body (its `required_signers`, withdrawals and
+/// certificates) must have a witness. The payment keys of the spent
+/// inputs are only known with the UTxOs, see
+/// [`verify_witnesses_with_inputs`].
+///
+/// The signatures are checked against the hash of the body encoded
+/// again: a transaction received as CBOR may have been encoded
+/// differently (sets without their tag, a body of an era before Conway),
+/// use [`verify_decoded_witnesses`] for it.
+pub fn verify_witnesses(tx: &Tx) -> Result<(), VerifyError> {
+    verify_witnesses_with_inputs(tx, &UtxoSet::default())
+}
+
+/// [`verify_witnesses`], also expecting the payment keys of the inputs
+/// (and collateral) resolved with `inputs`
+pub fn verify_witnesses_with_inputs(tx: &Tx, inputs: &UtxoSet) -> Result<(), VerifyError> {
+    verify_witnesses_of(tx, &super::tx_hash(&tx.transaction_body), inputs)
+}
+
+/// [`verify_witnesses_with_inputs`] for a transaction decoded with
+/// [`decode_tx`](super::inspect::decode_tx), the signatures being
+/// checked against the hash of the body as it was encoded
+pub fn verify_decoded_witnesses(decoded: &DecodedTx, inputs: &UtxoSet) -> Result<(), VerifyError> {
+    verify_witnesses_of(&decoded.tx, &decoded.hash, inputs)
+}
+
+fn verify_witnesses_of(tx: &Tx, tx_hash: &TxHash, inputs: &UtxoSet) -> Result<(), VerifyError> {
+    let witness_set = &tx.transaction_witness_set;
+    let invalid = invalid_vkey_witnesses(tx_hash, witness_set);
+    let signed: BTreeSet<_> = vkey_hashes(witness_set).into_iter().collect();
+    let missing: BTreeSet<_> = RequiredSigners::infer(&tx.transaction_body, inputs)
+        .signers
+        .difference(&signed)
+        .copied()
+        .collect();
+
+    if invalid.is_empty() && missing.is_empty() {
+        Ok(())
+    } else {
+        Err(VerifyError { invalid, missing })
+    }
+}
+
+/// the signature of the witness is valid for `tx_hash`
+pub fn verify_vkey_witness(tx_hash: &TxHash, witness: &VKeyWitness) -> bool {
+    let (Ok(key), Ok(signature)) = (
+        <[u8; 32]>::try_from(witness.vkey.as_slice()),
+        <[u8; 64]>::try_from(witness.signature.as_slice()),
+    ) else {
+        return false;
+    };
+    PublicKey::from(key).verify(tx_hash, &Signature::from(signature))
+}
+
+/// add the vkey witnesses of `signed` (e.g. returned by the wallet) to the
+/// transaction, skipping the ones it already has
+pub fn add_vkey_witnesses(mut tx: Tx, signed: WitnessSet) -> Tx {
+    let witnesses = tx.transaction_witness_set.vkeywitness.take();
+    tx.transaction_witness_set.vkeywitness = union(witnesses, signed.vkeywitness);
+    tx
+}
+
+/// Combine witness sets, e.g. the partial ones of a multi-signature.
+pub trait WitnessSetExt {
+    /// add the witnesses of `other`, skipping the ones `self` already has
+    ///
+    /// The redeemers are merged by purpose (tag and index): the ones of
+    /// `self` are kept over the ones of `other`.
+    fn merge(&mut self, other: WitnessSet);
+}
+
+impl WitnessSetExt for WitnessSet {
+    fn merge(&mut self, other: WitnessSet) {
+        self.vkeywitness = union(self.vkeywitness.take(), other.vkeywitness);
+        self.native_script = union(self.native_script.take(), other.native_script);
+        self.bootstrap_witness = union(self.bootstrap_witness.take(), other.bootstrap_witness);
+        self.plutus_v1_script = union(self.plutus_v1_script.take(), other.plutus_v1_script);
+        self.plutus_data = union(self.plutus_data.take(), other.plutus_data);
+        self.redeemer = merge_redeemers(self.redeemer.take(), other.redeemer);
+        self.plutus_v2_script = union(self.plutus_v2_script.take(), other.plutus_v2_script);
+        self.plutus_v3_script = union(self.plutus_v3_script.take(), other.plutus_v3_script);
+    }
+}
+
+/// the transaction of `body` with all the witnesses of `witness_sets`
+///
+/// `is_valid` is `false` only for a transaction expected to fail phase-2
+/// validation (its collateral is then consumed). The `auxiliary_data` must
+/// be the one the body's `auxiliary_data_hash` commits to.
+pub fn assemble_tx(
+    body: TransactionBody,
+    witness_sets: &[WitnessSet],
+    is_valid: bool,
+    auxiliary_data: Option<AuxiliaryData>,
+) -> Tx {
+    let mut witness_set = empty_witness_set();
+    for other in witness_sets {
+        witness_set.merge(other.clone());
+    }
+    Tx {
+        transaction_body: body,
+        transaction_witness_set: witness_set,
+        success: is_valid,
+        auxiliary_data: auxiliary_data.map_or(Nullable::Null, Nullable::Some),
+    }
+}
+
+fn empty_witness_set() -> WitnessSet {
+    WitnessSet {
+        vkeywitness: None,
+        native_script: None,
+        bootstrap_witness: None,
+        plutus_v1_script: None,
+        plutus_data: None,
+        redeemer: None,
+        plutus_v2_script: None,
+        plutus_v3_script: None,
+    }
+}
+
+fn union<T: PartialEq>(
+    ours: Option<NonEmptySet<T>>,
+    theirs: Option<NonEmptySet<T>>,
+) -> Option<NonEmptySet<T>> {
+    let mut items = ours.map(NonEmptySet::to_vec).unwrap_or_default();
+    for item in theirs.into_iter().flat_map(NonEmptySet::to_vec) {
+        if !items.contains(&item) {
+            items.push(item);
+        }
+    }
+    NonEmptySet::from_vec(items)
+}
+
+fn merge_redeemers(ours: Option<Redeemers>, theirs: Option<Redeemers>) -> Option<Redeemers> {
+    let (Some(ours), Some(theirs)) = (&ours, &theirs) else {
+        return ours.or(theirs);
+    };
+    let entries = |redeemers: &Redeemers| -> Vec<(RedeemersKey, RedeemersValue)> {
+        match redeemers {
+            Redeemers::List(list) => list
+                .iter()
+                .map(|redeemer| {
+                    let key = RedeemersKey {
+                        tag: redeemer.tag,
+                        index: redeemer.index,
+                    };
+                    let value = RedeemersValue {
+                        data: redeemer.data.clone(),
+                        ex_units: redeemer.ex_units,
+                    };
+                    (key, value)
+                })
+                .collect(),
+            Redeemers::Map(map) => map.iter().cloned().collect(),
+        }
+    };
+
+    let mut merged = entries(ours);
+    for (key, value) in entries(theirs) {
+        if !merged.iter().any(|(existing, _)| *existing == key) {
+            merged.push((key, value));
+        }
+    }
+    // keep the encoding of `self`
+    match ours {
+        Redeemers::List(_) => Some(Redeemers::List(MaybeIndefArray::Def(
+            merged
+                .into_iter()
+                .map(|(key, value)| Redeemer {
+                    tag: key.tag,
+                    index: key.index,
+                    data: value.data,
+                    ex_units: value.ex_units,
+                })
+                .collect(),
+        ))),
+        Redeemers::Map(_) => NonEmptyKeyValuePairs::try_from(merged)
+            .ok()
+            .map(Redeemers::Map),
+    }
+}
+
+/// Sign the body with a key held by the application.
+///
+/// The returned witness set only holds the vkey witness of `secret_key`,
+/// it is meant to be merged with the witness set returned by the wallet.
+/// The transaction id is computed on the CBOR encoding of `body`, the
+/// transaction must be submitted with the same encoding of the body.
+#[cfg(any(feature = "local-keys", feature = "mock"))]
+pub fn sign_body_with_key(body: &TransactionBody, secret_key: [u8; 32]) -> WitnessSet {
+    use pallas_crypto::key::ed25519::SecretKey;
+
+    let secret_key = SecretKey::from(secret_key);
+    let tx_hash = super::tx_hash(body);
+    let witness = VKeyWitness {
+        vkey: secret_key.public_key().as_ref().to_vec().into(),
+        signature: secret_key.sign(tx_hash).as_ref().to_vec().into(),
+    };
+
+    WitnessSet {
+        vkeywitness: NonEmptySet::from_vec(vec![witness]),
+        ..empty_witness_set()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{Hash, TransactionOutput, Utxo, Value};
+    use pallas_addresses::{Network, ShelleyAddress, ShelleyDelegationPart};
+    use pallas_codec::utils::Set;
+    use pallas_primitives::babbage::PseudoPostAlonzoTransactionOutput;
+
+    fn input(index: u64) -> TransactionInput {
+        TransactionInput {
+            transaction_id: Hash::from([0; 32]),
+            index,
+        }
+    }
+
+    fn utxo(index: u64, payment: ShelleyPaymentPart) -> Utxo {
+        let address = ShelleyAddress::new(Network::Testnet, payment, ShelleyDelegationPart::Null);
+        Utxo {
+            input: input(index),
+            output: TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+                address: address.to_vec().into(),
+                value: Value::Coin(1_000_000),
+                datum_option: None,
+                script_ref: None,
+            }),
+        }
+    }
+
+    fn witness_set(keys: &[[u8; 32]]) -> WitnessSet {
+        WitnessSet {
+            vkeywitness: NonEmptySet::from_vec(
+                keys.iter()
+                    .map(|key| VKeyWitness {
+                        vkey: key.to_vec().into(),
+                        signature: vec![0; 64].into(),
+                    })
+                    .collect(),
+            ),
+            native_script: None,
+            bootstrap_witness: None,
+            plutus_v1_script: None,
+            plutus_data: None,
+            redeemer: None,
+            plutus_v2_script: None,
+            plutus_v3_script: None,
+        }
+    }
+
+    #[test]
+    fn add_witnesses() {
+        let mut tx = Tx {
+            transaction_body: crate::cardano::testing::empty_body(),
+            transaction_witness_set: witness_set(&[[1; 32]]),
+            success: true,
+            auxiliary_data: pallas_codec::utils::Nullable::Null,
+        };
+        tx = add_vkey_witnesses(tx, witness_set(&[[1; 32], [2; 32]]));
+
+        assert_eq!(tx.transaction_witness_set, witness_set(&[[1; 32], [2; 32]]));
+    }
+
+    #[test]
+    fn merge_witness_sets() {
+        use pallas_primitives::{ExUnits, PlutusData, conway::RedeemerTag};
+
+        let redeemer = |index, steps| Redeemer {
+            tag: RedeemerTag::Spend,
+            index,
+            data: PlutusData::Array(MaybeIndefArray::Def(Vec::new())),
+            ex_units: ExUnits { mem: 1, steps },
+        };
+        let mut ours = WitnessSet {
+            redeemer: Some(Redeemers::List(MaybeIndefArray::Def(vec![redeemer(0, 1)]))),
+            ..witness_set(&[[1; 32]])
+        };
+        let theirs = WitnessSet {
+            redeemer: Some(Redeemers::List(MaybeIndefArray::Def(vec![
+                redeemer(0, 2),
+                redeemer(1, 2),
+            ]))),
+            ..witness_set(&[[1; 32], [2; 32]])
+        };
+        ours.merge(theirs);
+
+        assert_eq!(
+            ours.vkeywitness,
+            witness_set(&[[1; 32], [2; 32]]).vkeywitness
+        );
+        assert_eq!(
+            ours.redeemer,
+            Some(Redeemers::List(MaybeIndefArray::Def(vec![
+                redeemer(0, 1),
+                redeemer(1, 2),
+            ])))
+        );
+    }
+
+    #[cfg(feature = "local-keys")]
+    #[test]
+    fn verify_before_submit() {
+        let key = [7; 32];
+        let signer = vkey_hashes(&sign_body_with_key(
+            &crate::cardano::testing::empty_body(),
+            key,
+        ))[0];
+        let body = TransactionBody {
+            required_signers: NonEmptySet::from_vec(vec![signer]),
+            ..crate::cardano::testing::empty_body()
+        };
+
+        let unsigned = assemble_tx(body.clone(), &[], true, None);
+        assert_eq!(
+            verify_witnesses(&unsigned),
+            Err(VerifyError {
+                invalid: Vec::new(),
+                missing: BTreeSet::from([signer]),
+            })
+        );
+
+        let signed = assemble_tx(body.clone(), &[sign_body_with_key(&body, key)], true, None);
+        assert_eq!(verify_witnesses(&signed), Ok(()));
+
+        // signed for another body
+        let other = crate::cardano::testing::empty_body();
+        let wrong = assemble_tx(body, &[sign_body_with_key(&other, key)], true, None);
+        assert_eq!(
+            verify_witnesses(&wrong),
+            Err(VerifyError {
+                invalid: vec![signer],
+                missing: BTreeSet::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn assemble_a_transaction() {
+        let body = crate::cardano::testing::empty_body();
+        let tx = assemble_tx(
+            body.clone(),
+            &[witness_set(&[[1; 32]]), witness_set(&[[2; 32], [1; 32]])],
+            true,
+            None,
+        );
+
+        assert_eq!(tx.transaction_body, body);
+        assert_eq!(tx.transaction_witness_set, witness_set(&[[1; 32], [2; 32]]));
+        assert!(tx.success);
+        assert_eq!(tx.auxiliary_data, Nullable::Null);
+        assert_eq!(
+            assemble_tx(body, &[], true, None).transaction_witness_set,
+            empty_witness_set()
+        );
+    }
+
+    #[test]
+    fn report_progress() {
+        let key_a = [1; 32];
+        let key_b = [2; 32];
+        let key_c = [3; 32];
+        let hash = |key: &[u8; 32]| Hasher::<224>::hash(key);
+
+        let first = witness_set(&[key_a]);
+        let report = WitnessReport::new([hash(&key_a), hash(&key_b)], [&first]);
+        assert_eq!(report.provided, BTreeSet::from([hash(&key_a)]));
+        assert_eq!(report.missing, BTreeSet::from([hash(&key_b)]));
+        assert!(report.unexpected.is_empty());
+        assert!(!report.is_complete());
+
+        let second = witness_set(&[key_b, key_c]);
+        let report = WitnessReport::new([hash(&key_a), hash(&key_b)], [&first, &second]);
+        assert!(report.is_complete());
+        assert_eq!(report.unexpected, BTreeSet::from([hash(&key_c)]));
+    }
+
+    #[test]
+    fn infer_required_signers() {
+        let key_a = Hash::from([1; 28]);
+        let key_b = Hash::from([2; 28]);
+        let script = Hash::from([3; 28]);
+        let delegator = Hash::from([4; 28]);
+        let inputs: UtxoSet = vec![
+            utxo(0, ShelleyPaymentPart::Key(key_a)),
+            utxo(1, ShelleyPaymentPart::Script(script)),
+        ]
+        .into();
+        let body = TransactionBody {
+            inputs: Set::from(vec![input(0), input(1), input(2)]),
+            outputs: Vec::new(),
+            fee: 0,
+            ttl: None,
+            certificates: NonEmptySet::from_vec(vec![Certificate::StakeDelegation(
+                StakeCredential::AddrKeyhash(delegator),
+                Hash::from([5; 28]),
+            )]),
+            withdrawals: None,
+            auxiliary_data_hash: None,
+            validity_interval_start: None,
+            mint: None,
+            script_data_hash: None,
+            collateral: None,
+            required_signers: NonEmptySet::from_vec(vec![key_b]),
+            network_id: None,
+            collateral_return: None,
+            total_collateral: None,
+            reference_inputs: None,
+            voting_procedures: None,
+            proposal_procedures: None,
+            treasury_value: None,
+            donation: None,
+        };
+
+        let required = RequiredSigners::infer(&body, &inputs);
+
+        assert_eq!(required.signers, BTreeSet::from([key_a, key_b, delegator]));
+        assert_eq!(required.unresolved, vec![input(2)]);
+        assert!(!required.is_covered_by(&BTreeSet::from([key_a, key_b, delegator])));
+    }
+
+    #[cfg(feature = "local-keys")]
+    #[test]
+    fn sign_with_local_key() {
+        let body = crate::cardano::testing::empty_body();
+        let witness_set = sign_body_with_key(&body, [7; 32]);
+
+        let witness = witness_set.vkeywitness.as_ref().unwrap().first().unwrap();
+        let key = PublicKey::from(<[u8; 32]>::try_from(witness.vkey.as_slice()).unwrap());
+        let signature =
+            Signature::from(<[u8; 64]>::try_from(witness.signature.as_slice()).unwrap());
+        assert!(key.verify(Hasher::<256>::hash_cbor(&body), &signature));
+        assert!(invalid_vkey_witnesses(&crate::cardano::tx_hash(&body), &witness_set).is_empty());
+        assert_eq!(
+            invalid_vkey_witnesses(&Hash::from([0; 32]), &witness_set),
+            vkey_hashes(&witness_set)
+        );
+        assert_eq!(
+            vkey_hashes(&witness_set),
+            vec![Hasher::<224>::hash(&witness.vkey)]
+        );
+    }
+}
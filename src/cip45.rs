@@ -0,0 +1,194 @@
+/*!
+Mobile wallets connected peer to peer (CIP-45).
+
+The dApp shows a QR code (or the [`address`](PeerConnect::address) it
+encodes) that the user scans with a mobile wallet (e.g. Vespr, Eternl
+mobile). The wallet then connects to the dApp over WebRTC and its CIP-30
+API is injected in `window.cardano`, like the one of a browser
+extension: the [`Wallet`] given to the callback enables into the same
+[`ConnectedWallet`](crate::ConnectedWallet) as an injected wallet.
+
+```no_run
+# use cardano_connector::cip45::{PeerConnect, PeerConnectConfig, PeerEvent};
+# fn test(canvas: &wasm_bindgen::JsValue) -> anyhow::Result<()> {
+let config = PeerConnectConfig::new("My dApp", "https://my-dapp.io");
+let peer = PeerConnect::new(config, |event| {
+    if let PeerEvent::Connected(wallet) = event {
+        wasm_bindgen_futures::spawn_local(async move {
+            let connected = wallet.enable().await;
+            // ...
+        });
+    }
+})
+.map_err(|error| anyhow::anyhow!("{error:?}"))?;
+peer.generate_qr_code(canvas);
+# Ok(()) }
+```
+
+This needs the `cip45` feature and the `@fabianbormann/cardano-peer-connect`
+package.
+*/
+
+use crate::{Wallet, ffi::peer_connect::DAppPeerConnect, wallet};
+use std::rc::Rc;
+use wasm_bindgen::{JsValue, closure::Closure};
+
+/// How the dApp presents itself to the wallets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerConnectConfig {
+    pub name: String,
+    pub url: String,
+    /// the WebTorrent trackers the peers meet on, the package's default
+    /// ones if empty
+    pub announce: Vec<String>,
+}
+
+impl PeerConnectConfig {
+    pub fn new(name: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            url: url.into(),
+            announce: Vec::new(),
+        }
+    }
+
+    pub fn with_announce(mut self, announce: Vec<String>) -> Self {
+        self.announce = announce;
+        self
+    }
+}
+
+/// The remote wallet asking to connect.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+pub struct PeerWalletInfo {
+    pub name: String,
+    /// the peer address of the wallet
+    pub address: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub icon: Option<String>,
+}
+
+#[derive(Clone)]
+pub enum PeerEvent {
+    /// the wallet is connected and injected in `window.cardano`
+    Connected(Wallet),
+    /// the wallet of the given name disconnected
+    Disconnected { name: String },
+}
+
+/// The dApp side of the peer to peer connection, see the
+/// [module documentation](self).
+///
+/// The connection lasts as long as this value is kept.
+pub struct PeerConnect {
+    peer: DAppPeerConnect,
+    _on_inject: Closure<dyn Fn(String, String)>,
+    _on_eject: Closure<dyn Fn(String, String)>,
+    _verify: Closure<dyn Fn(JsValue, js_sys::Function)>,
+}
+
+impl PeerConnect {
+    /// accept every wallet scanning the QR code
+    pub fn new(
+        config: PeerConnectConfig,
+        on_event: impl Fn(PeerEvent) + 'static,
+    ) -> Result<Self, JsValue> {
+        Self::with_verification(config, |_| true, on_event)
+    }
+
+    /// accept the wallets for which `verify` returns `true`, e.g. after
+    /// the user compared the [`identicon`](Self::identicon) with the one
+    /// shown by the wallet
+    pub fn with_verification(
+        config: PeerConnectConfig,
+        verify: impl Fn(&PeerWalletInfo) -> bool + 'static,
+        on_event: impl Fn(PeerEvent) + 'static,
+    ) -> Result<Self, JsValue> {
+        let on_event = Rc::new(on_event);
+
+        let on_inject = {
+            let on_event = Rc::clone(&on_event);
+            Closure::<dyn Fn(String, String)>::new(move |name: String, _address: String| {
+                if let Some(wallet) = wallet(&name) {
+                    on_event(PeerEvent::Connected(wallet));
+                }
+            })
+        };
+        let on_eject =
+            Closure::<dyn Fn(String, String)>::new(move |name: String, _address: String| {
+                on_event(PeerEvent::Disconnected { name });
+            });
+        let verify = Closure::<dyn Fn(JsValue, js_sys::Function)>::new(
+            move |info: JsValue, callback: js_sys::Function| {
+                let granted = serde_wasm_bindgen::from_value::<PeerWalletInfo>(info)
+                    .is_ok_and(|info| verify(&info));
+                // (granted, allow auto connect)
+                let _ = callback.call2(
+                    &JsValue::NULL,
+                    &JsValue::from_bool(granted),
+                    &JsValue::from_bool(false),
+                );
+            },
+        );
+
+        let peer = DAppPeerConnect::new(&js_config(&config, &on_inject, &on_eject, &verify)?)?;
+        Ok(Self {
+            peer,
+            _on_inject: on_inject,
+            _on_eject: on_eject,
+            _verify: verify,
+        })
+    }
+
+    /// the identifier the wallet connects to, encoded in the QR code
+    pub fn address(&self) -> String {
+        self.peer.get_address()
+    }
+
+    /// draw the QR code in the given `<canvas>` element
+    pub fn generate_qr_code(&self, canvas: &JsValue) {
+        self.peer.generate_qr_code(canvas)
+    }
+
+    /// a picture (data URL) derived from the [`address`](Self::address),
+    /// also shown by the wallet
+    pub fn identicon(&self) -> String {
+        self.peer.get_identicon()
+    }
+}
+
+impl std::fmt::Debug for PeerConnect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PeerConnect")
+            .field("address", &self.address())
+            .finish()
+    }
+}
+
+fn js_config(
+    config: &PeerConnectConfig,
+    on_inject: &Closure<dyn Fn(String, String)>,
+    on_eject: &Closure<dyn Fn(String, String)>,
+    verify: &Closure<dyn Fn(JsValue, js_sys::Function)>,
+) -> Result<JsValue, JsValue> {
+    let dapp_info = js_sys::Object::new();
+    js_sys::Reflect::set(&dapp_info, &"name".into(), &config.name.as_str().into())?;
+    js_sys::Reflect::set(&dapp_info, &"url".into(), &config.url.as_str().into())?;
+
+    let object = js_sys::Object::new();
+    js_sys::Reflect::set(&object, &"dAppInfo".into(), &dapp_info)?;
+    if !config.announce.is_empty() {
+        let announce: js_sys::Array = config
+            .announce
+            .iter()
+            .map(|url| JsValue::from_str(url))
+            .collect();
+        js_sys::Reflect::set(&object, &"announce".into(), &announce)?;
+    }
+    js_sys::Reflect::set(&object, &"onApiInject".into(), on_inject.as_ref())?;
+    js_sys::Reflect::set(&object, &"onApiEject".into(), on_eject.as_ref())?;
+    js_sys::Reflect::set(&object, &"verifyConnection".into(), verify.as_ref())?;
+    Ok(object.into())
+}
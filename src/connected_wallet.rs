@@ -1,15 +1,50 @@
 use crate::{
-    Address, Wallet,
-    cardano::{Hash, TransactionBody, Tx, Utxo, Value, WitnessSet},
-    error::{APIError, APIErrorCode, PaginateError},
+    Address, Wallet, WalletQuirks,
+    address::{AddressInfo, decode_address_info},
+    audit::{AuditAction, AuditLog, AuditOutcome, AuditRecord},
+    auth::Envelope,
+    cardano::{
+        self, AddrKeyhash, AssetId, BuildParams, Coin, Hash, HashExt as _, TransactionBody, Tx,
+        TxHash, Utxo, UtxoSet, Value, ValueExt as _, WitnessSet, WitnessSetExt as _, assets_of,
+        inspect, lovelace_of,
+        mint::MintBuilder,
+        witness::{self, WitnessReport},
+    },
+    connector::Settings,
+    error::{
+        APIError, APIErrorCode, BurnError, RawError, SendError, SignTxError, SubmitTxError,
+        VerifyDataError,
+    },
+    extension::{
+        self, ExtensionApi,
+        cip103::{self, Cip103Api, SignTxRequest},
+        cip106::{Cip106Api, MultisigWallet},
+        experimental::ExperimentalApi,
+    },
     ffi::{
         self,
         cip30_api::{self, DataSignature, Paginate},
     },
+    interaction::{InteractionGuard, Interactions, WalletAction},
+    pagination::{self, PageResult},
+    redact::{self, Redact},
+    strictness::{self, Strictness},
+    telemetry::{self, QuirkReason},
+    wallet_api::WalletApi,
 };
 use core::fmt;
+use futures::Stream;
+use pallas_addresses::{ShelleyPaymentPart, StakePayload};
+use pallas_crypto::{
+    hash::Hasher,
+    key::ed25519::{PublicKey, Signature},
+};
+use pallas_primitives::conway::NativeScript;
+use std::{collections::BTreeMap, rc::Rc};
+use wasm_bindgen::JsValue;
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum NetworkId {
     PreProduction,
     Preview,
@@ -31,6 +66,20 @@ impl From<NetworkId> for u8 {
 pub struct ConnectedWallet {
     wallet: Wallet,
     cip30_api: cip30_api::Cip30Api,
+    interactions: Option<Interactions>,
+    audit: Option<AuditLog>,
+    settings: Option<Rc<Settings>>,
+    granted_extensions: Vec<ffi::Extension>,
+}
+
+impl From<u8> for NetworkId {
+    fn from(network_id: u8) -> Self {
+        match network_id {
+            0 => NetworkId::PreProduction,
+            1 => NetworkId::Mainnet,
+            unknown => NetworkId::Unknown(unknown),
+        }
+    }
 }
 
 impl fmt::Display for NetworkId {
@@ -46,7 +95,201 @@ impl fmt::Display for NetworkId {
 
 impl ConnectedWallet {
     pub(crate) fn new(wallet: Wallet, cip30_api: cip30_api::Cip30Api) -> Self {
-        Self { wallet, cip30_api }
+        Self {
+            wallet,
+            cip30_api,
+            interactions: None,
+            audit: None,
+            settings: None,
+            granted_extensions: Vec::new(),
+        }
+    }
+
+    pub(crate) fn with_granted_extensions(mut self, extensions: Vec<ffi::Extension>) -> Self {
+        self.granted_extensions = extensions;
+        self
+    }
+
+    /// track the actions waiting for the user (signing...) with the
+    /// given [`Interactions`]
+    pub fn with_interactions(mut self, interactions: Interactions) -> Self {
+        self.interactions = Some(interactions);
+        self
+    }
+
+    /// record the signature requests in the given [`AuditLog`]
+    pub fn with_audit_log(mut self, audit: AuditLog) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// follow the strictness, reporter and quirks of a [`Connector`](crate::connector::Connector)
+    /// instead of the crate's defaults
+    pub(crate) fn with_settings(mut self, settings: Rc<Settings>) -> Self {
+        self.settings = Some(settings);
+        self
+    }
+
+    /// the [`Strictness`] of the connector, or else the one set with
+    /// [`strictness::set_strictness`]
+    fn strictness(&self) -> Strictness {
+        self.settings
+            .as_ref()
+            .map_or_else(strictness::strictness, |settings| settings.strictness())
+    }
+
+    /// the quirks of the wallet, as overridden by the connector if any
+    pub fn quirks(&self) -> WalletQuirks {
+        match &self.settings {
+            Some(settings) => settings.quirks(&self.wallet.kind()),
+            None => self.wallet.quirks(),
+        }
+    }
+
+    /// Enable the wallet again, requesting the extensions granted to
+    /// this connection and keeping its [`Interactions`] and [`AuditLog`].
+    ///
+    /// Needed once the user switched account: the calls then fail with
+    /// [`APIErrorCode::AccountChange`].
+    pub async fn reenable(&self) -> Result<ConnectedWallet, APIError> {
+        let wallet = if self.granted_extensions.is_empty() {
+            self.wallet.enable().await?
+        } else {
+            self.wallet
+                .enable_with_extensions(&self.granted_extensions)
+                .await?
+        };
+        Ok(Self {
+            interactions: self.interactions.clone(),
+            audit: self.audit.clone(),
+            settings: self.settings.clone(),
+            ..wallet
+        })
+    }
+
+    /// the non standard `experimental` namespace of the wallet, see
+    /// [`ExperimentalApi`]
+    pub fn experimental(&self) -> ExperimentalApi<'_> {
+        ExperimentalApi::new(self, self.cip30_api.experimental())
+    }
+
+    fn begin(&self, action: WalletAction) -> Option<InteractionGuard> {
+        self.interactions
+            .as_ref()
+            .map(|interactions| interactions.begin(action))
+    }
+
+    /// add the request to the audit log, if any
+    ///
+    /// Failing to write the log does not fail the request: the user
+    /// already signed.
+    fn audit(
+        &self,
+        action: AuditAction,
+        payload_hash: Hash<32>,
+        address: Option<String>,
+        outcome: AuditOutcome,
+    ) {
+        if let Some(audit) = &self.audit {
+            let _ = audit.record(AuditRecord {
+                wallet: self.name(),
+                action,
+                at: js_sys::Date::now() as u64,
+                payload_hash,
+                address,
+                outcome,
+            });
+        }
+    }
+
+    /// decode the CIP-30 `APIError` the wallet rejected with, reporting
+    /// the non conforming ones (see [`telemetry`])
+    fn api_error(&self, endpoint: &str, error: JsValue) -> APIError {
+        serde_wasm_bindgen::from_value(error.clone()).unwrap_or_else(|decode_error| {
+            self.quirk(endpoint, &error, QuirkReason::NotAnApiError);
+            APIError {
+                code: APIErrorCode::InternalError,
+                info: format!("Couldn't decode the error content: {decode_error}"),
+            }
+        })
+    }
+
+    /// decode the `{ code, info }` object the wallet rejected with,
+    /// before knowing which of the CIP-30 errors it is
+    fn raw_error(&self, endpoint: &str, error: JsValue) -> Result<RawError, APIError> {
+        serde_wasm_bindgen::from_value(error.clone()).map_err(|decode_error| {
+            self.quirk(endpoint, &error, QuirkReason::NotAnApiError);
+            APIError {
+                code: APIErrorCode::InternalError,
+                info: format!("Couldn't decode the error content: {decode_error}"),
+            }
+        })
+    }
+
+    /// decode the hex encoded addresses, following the [`strictness`]
+    fn decode_addresses(
+        &self,
+        endpoint: &str,
+        addresses: js_sys::Array,
+    ) -> Result<Vec<Address>, APIError> {
+        strictness::decode_all(
+            self.strictness(),
+            addresses,
+            |address| {
+                address
+                    .as_string()
+                    .and_then(|address| Address::from_hex(&address).ok())
+                    .ok_or_else(|| APIError {
+                        code: APIErrorCode::InternalError,
+                        info: format!("Invalid address: {address:?}"),
+                    })
+            },
+            |address, _| {
+                let reason = QuirkReason::of_hex(address, QuirkReason::InvalidAddress);
+                self.quirk(endpoint, address, reason)
+            },
+        )
+    }
+
+    /// decode the hex encoded CBOR UTxOs, following the [`strictness`]
+    pub(crate) fn decode_utxos(
+        &self,
+        endpoint: &str,
+        utxos: js_sys::Array,
+    ) -> Result<Vec<Utxo>, APIError> {
+        strictness::decode_all(
+            self.strictness(),
+            utxos,
+            |utxo| {
+                utxo.as_string()
+                    .as_deref()
+                    .and_then(decode_utxo)
+                    .ok_or_else(|| APIError {
+                        code: APIErrorCode::InternalError,
+                        info: format!("Invalid UTxO: {utxo:?}"),
+                    })
+            },
+            |utxo, _| {
+                let reason = QuirkReason::of_hex(utxo, QuirkReason::InvalidCbor);
+                self.quirk(endpoint, utxo, reason)
+            },
+        )
+    }
+
+    /// report a response of the wallet that could not be decoded
+    fn quirk(&self, endpoint: &str, response: &JsValue, reason: QuirkReason) {
+        let reporter = self
+            .settings
+            .as_ref()
+            .and_then(|settings| settings.reporter());
+        telemetry::report(
+            reporter,
+            self.name(),
+            self.version(),
+            endpoint,
+            response,
+            reason,
+        );
     }
 
     /// return the name of the wallet connector application
@@ -69,6 +312,15 @@ impl ConnectedWallet {
         self.wallet.supported_extensions()
     }
 
+    /// the extensions the wallet granted when enabled with
+    /// [`Wallet::enable_with_extensions`]
+    ///
+    /// Empty when enabled with [`Wallet::enable`], see
+    /// [`enabled_extensions`](Self::enabled_extensions) to query the wallet.
+    pub fn granted_extensions(&self) -> &[ffi::Extension] {
+        &self.granted_extensions
+    }
+
     /// list the enabled extensions with this wallet connector
     pub async fn enabled_extensions(&self) -> Result<Vec<ffi::Extension>, APIError> {
         match self.cip30_api.get_extensions().await {
@@ -76,23 +328,42 @@ impl ConnectedWallet {
                 code: APIErrorCode::InternalError,
                 info: format!("Couldn't decode the extension list: {decode_array}"),
             }),
-            Err(error) => serde_wasm_bindgen::from_value(error)
-                .map_err(|decode_error| APIError {
-                    code: APIErrorCode::InternalError,
-                    info: format!("Couldn't decode the error content: {decode_error}"),
-                })
-                .and_then(Err),
+            Err(error) => Err(self.api_error("getExtensions", error)),
         }
     }
 
+    /// the typed bindings of an enabled extension, see
+    /// [`define_extension_api!`]
+    ///
+    /// Returns `None` if the wallet does not expose the extension's
+    /// namespace (e.g. it was not enabled).
+    ///
+    /// [`define_extension_api!`]: crate::define_extension_api
+    pub fn extension_api<E: ExtensionApi>(&self) -> Option<E> {
+        extension::namespace(&self.cip30_api, E::CIP).map(E::from_namespace)
+    }
+
+    /// the CIP-106 interface of a multisig wallet, `None` if the wallet
+    /// does not expose the extension
+    pub fn multisig(&self) -> Option<MultisigWallet> {
+        self.extension_api::<Cip106Api>().map(MultisigWallet::new)
+    }
+
     /// re-enable the connection to the wallet
     ///
     /// This is particularly useful is we received an [`APIErrorCode::AccountChange`]
     ///
+    /// Same as [`reenable`](Self::reenable), the granted extensions are
+    /// requested again, but the connection is replaced in place.
+    ///
     /// [`APIErrorCode::AccountChange`]: crate::error::APIErrorCode::AccountChange
     ///
     pub async fn enable(&mut self) -> Result<(), APIError> {
-        self.cip30_api = self.wallet.enable().await?.cip30_api;
+        let reenabled = {
+            let _interaction = self.begin(WalletAction::Enable);
+            self.reenable().await?
+        };
+        *self = reenabled;
         Ok(())
     }
 
@@ -104,11 +375,7 @@ impl ConnectedWallet {
         match self.cip30_api.network_id().await {
             Ok(id) => {
                 if let Some(number) = id.as_f64() {
-                    match number as u8 {
-                        0 => Ok(NetworkId::PreProduction),
-                        1 => Ok(NetworkId::Mainnet),
-                        unknown => Ok(NetworkId::Unknown(unknown)),
-                    }
+                    Ok(NetworkId::from(number as u8))
                 } else {
                     Err(APIError {
                         code: APIErrorCode::InternalError,
@@ -116,12 +383,7 @@ impl ConnectedWallet {
                     })
                 }
             }
-            Err(error) => serde_wasm_bindgen::from_value(error)
-                .map_err(|decode_error| APIError {
-                    code: APIErrorCode::InternalError,
-                    info: format!("Couldn't decode the error content: {decode_error}"),
-                })
-                .and_then(Err),
+            Err(error) => Err(self.api_error("getNetworkId", error)),
         }
     }
 
@@ -136,80 +398,125 @@ impl ConnectedWallet {
         match self.cip30_api.balance().await {
             Ok(balance) => {
                 let Some(balance_hex) = balance.as_string() else {
+                    self.quirk("getBalance", &balance, QuirkReason::UnexpectedType);
                     return Err(APIError {
                         code: APIErrorCode::InternalError,
                         info: format!("Unknown balance: {balance:?}"),
                     });
                 };
-                decode_balance_value(&balance_hex)
-            }
-            Err(error) => serde_wasm_bindgen::from_value(error)
-                .map_err(|decode_error| APIError {
-                    code: APIErrorCode::InternalError,
-                    info: format!("Couldn't decode the error content: {decode_error}"),
+                decode_balance_value(&balance_hex).inspect_err(|_| {
+                    let reason = QuirkReason::of_hex(&balance, QuirkReason::InvalidCbor);
+                    self.quirk("getBalance", &balance, reason)
                 })
-                .and_then(Err),
+            }
+            Err(error) => Err(self.api_error("getBalance", error)),
         }
     }
 
+    /// the lovelace part of the [`balance`](Self::balance)
+    pub async fn lovelace(&self) -> Result<Coin, APIError> {
+        self.balance().await.map(|balance| lovelace_of(&balance))
+    }
+
+    /// the native assets part of the [`balance`](Self::balance)
+    pub async fn assets(&self) -> Result<BTreeMap<AssetId, u64>, APIError> {
+        self.balance().await.map(|balance| assets_of(&balance))
+    }
+
+    /// check whether the wallet holds at least `required` (lovelace and
+    /// native assets), e.g. before starting a trade
+    pub async fn can_cover(&self, required: &Value) -> Result<bool, APIError> {
+        Ok(self.balance().await?.meets_or_exceeds(required))
+    }
+
     /// list all the used address of this connected wallet
+    ///
+    /// Returns an empty list if the page is beyond the last one.
     pub async fn used_addresses(
         &self,
         pagination: Option<Paginate>,
     ) -> Result<Vec<Address>, APIError> {
+        Ok(self
+            .used_addresses_page(pagination)
+            .await?
+            .unwrap_or_default())
+    }
+
+    /// every used address of the wallet, walking the pages of
+    /// `getUsedAddresses`
+    ///
+    /// The pages are fetched as the stream is polled, with the page size
+    /// reduced to the wallet's `maxSize` hint if it rejects the first
+    /// one. The stream ends after the first error.
+    pub fn used_address_stream(&self) -> impl Stream<Item = Result<Address, APIError>> + '_ {
+        pagination::pages(Paginate::DEFAULT_LIMIT, move |page| {
+            self.used_addresses_page(Some(page))
+        })
+    }
+
+    /// the addresses of the page, or the pagination error of the wallet
+    async fn used_addresses_page(&self, pagination: Option<Paginate>) -> PageResult<Address> {
         match self.cip30_api.get_used_addresses(pagination).await {
-            Ok(addresses) => {
-                let mut unused_addresses = Vec::with_capacity(addresses.length() as usize);
-                for address in addresses {
-                    let Some(address) = address.as_string() else {
-                        return Err(APIError {
-                            code: APIErrorCode::InternalError,
-                            info: format!("Invalid address: {address:?}"),
-                        });
-                    };
-                    let address = Address::from_hex(&address).map_err(|err| APIError {
-                        code: APIErrorCode::InternalError,
-                        info: err.to_string(),
-                    })?;
-                    unused_addresses.push(address);
+            Ok(addresses) => self.decode_addresses("getUsedAddresses", addresses).map(Ok),
+            Err(error) => {
+                if let Ok(paginate_error) = serde_wasm_bindgen::from_value(error.clone()) {
+                    return Ok(Err(paginate_error));
                 }
-                Ok(unused_addresses)
+
+                Err(self.api_error("getUsedAddresses", error))
             }
-            Err(error) => serde_wasm_bindgen::from_value(error)
-                .map_err(|decode_error| APIError {
-                    code: APIErrorCode::InternalError,
-                    info: format!("Couldn't decode the error content: {decode_error}"),
-                })
-                .and_then(Err),
+        }
+    }
+
+    /// same as [`ConnectedWallet::used_addresses`] but attach the
+    /// [`AddressInfo`] of every address when the wallet exposes them.
+    pub async fn used_addresses_with_info(
+        &self,
+        pagination: Option<Paginate>,
+    ) -> Result<Vec<(Address, Option<AddressInfo>)>, APIError> {
+        let addresses = self.used_addresses(pagination).await?;
+        let mut result = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let info = self.address_info(&address).await?;
+            result.push((address, info));
+        }
+        Ok(result)
+    }
+
+    /// query the derivation metadata of one of the wallet's addresses
+    ///
+    /// This relies on the non standard `experimental.getAddressInfo`
+    /// endpoint. Returns `None` if the wallet does not expose it or if it
+    /// has no derivation information for this address.
+    pub async fn address_info(&self, address: &Address) -> Result<Option<AddressInfo>, APIError> {
+        let experimental = self.cip30_api.experimental();
+        if !experimental.is_object() {
+            return Ok(None);
+        }
+        let Some(get_address_info) = extension::endpoint(&experimental, "getAddressInfo") else {
+            return Ok(None);
+        };
+
+        let result = get_address_info
+            .call1(&experimental, &JsValue::from_str(&address.to_hex()))
+            .map(|value| js_sys::Promise::resolve(&value))
+            .map(wasm_bindgen_futures::JsFuture::from);
+        let result = match result {
+            Ok(future) => future.await,
+            Err(error) => Err(error),
+        };
+
+        match result {
+            Ok(value) => decode_address_info(address, value),
+            Err(error) => Err(self.api_error("getAddressInfo", error)),
         }
     }
 
     /// list the unused addresses of the connected wallet
     pub async fn unused_addresses(&self) -> Result<Vec<Address>, APIError> {
         match self.cip30_api.get_unused_addresses().await {
-            Ok(addresses) => {
-                let mut unused_addresses = Vec::with_capacity(addresses.length() as usize);
-                for address in addresses {
-                    let Some(address) = address.as_string() else {
-                        return Err(APIError {
-                            code: APIErrorCode::InternalError,
-                            info: format!("Invalid address: {address:?}"),
-                        });
-                    };
-                    let address = Address::from_hex(&address).map_err(|err| APIError {
-                        code: APIErrorCode::InternalError,
-                        info: err.to_string(),
-                    })?;
-                    unused_addresses.push(address);
-                }
-                Ok(unused_addresses)
-            }
-            Err(error) => serde_wasm_bindgen::from_value(error)
-                .map_err(|decode_error| APIError {
-                    code: APIErrorCode::InternalError,
-                    info: format!("Couldn't decode the error content: {decode_error}"),
-                })
-                .and_then(Err),
+            Ok(addresses) => self.decode_addresses("getUnusedAddresses", addresses),
+            Err(error) => Err(self.api_error("getUnusedAddresses", error)),
         }
     }
 
@@ -229,41 +536,15 @@ impl ConnectedWallet {
                 })?;
                 Ok(address)
             }
-            Err(error) => serde_wasm_bindgen::from_value(error)
-                .map_err(|decode_error| APIError {
-                    code: APIErrorCode::InternalError,
-                    info: format!("Couldn't decode the error content: {decode_error}"),
-                })
-                .and_then(Err),
+            Err(error) => Err(self.api_error("getChangeAddress", error)),
         }
     }
 
     /// list the reward address
     pub async fn reward_addresses(&self) -> Result<Vec<Address>, APIError> {
         match self.cip30_api.reward_addresses().await {
-            Ok(addresses) => {
-                let mut unused_addresses = Vec::with_capacity(addresses.length() as usize);
-                for address in addresses {
-                    let Some(address) = address.as_string() else {
-                        return Err(APIError {
-                            code: APIErrorCode::InternalError,
-                            info: format!("Invalid address: {address:?}"),
-                        });
-                    };
-                    let address = Address::from_hex(&address).map_err(|err| APIError {
-                        code: APIErrorCode::InternalError,
-                        info: err.to_string(),
-                    })?;
-                    unused_addresses.push(address);
-                }
-                Ok(unused_addresses)
-            }
-            Err(error) => serde_wasm_bindgen::from_value(error)
-                .map_err(|decode_error| APIError {
-                    code: APIErrorCode::InternalError,
-                    info: format!("Couldn't decode the error content: {decode_error}"),
-                })
-                .and_then(Err),
+            Ok(addresses) => self.decode_addresses("getRewardAddresses", addresses),
+            Err(error) => Err(self.api_error("getRewardAddresses", error)),
         }
     }
 
@@ -281,11 +562,35 @@ impl ConnectedWallet {
         self._utxos(None, pagination).await
     }
 
+    /// every UTxO of the wallet, walking the pages of `getUtxos`
+    ///
+    /// Unlike [`all_utxos`](Self::all_utxos) this is not cut at the
+    /// wallet's pagination limit: the pages are requested one after the
+    /// other, following the wallet's `maxSize` hint, until the last one.
+    /// The stream ends after the first error.
+    pub fn utxo_stream(&self) -> impl Stream<Item = Result<Utxo, APIError>> + '_ {
+        pagination::pages(Paginate::DEFAULT_LIMIT, move |page| {
+            self.utxos_page(None, Some(page))
+        })
+    }
+
     async fn _utxos(
         &self,
         value: Option<&Value>,
         pagination: Option<Paginate>,
     ) -> Result<Vec<Utxo>, APIError> {
+        Ok(self
+            .utxos_page(value, pagination)
+            .await?
+            .unwrap_or_default())
+    }
+
+    /// the UTxOs of the page, or the pagination error of the wallet
+    async fn utxos_page(
+        &self,
+        value: Option<&Value>,
+        pagination: Option<Paginate>,
+    ) -> PageResult<Utxo> {
         let value = if let Some(value) = value {
             let bytes = pallas_codec::minicbor::to_vec(value).map_err(|error| APIError {
                 code: APIErrorCode::InternalError,
@@ -299,109 +604,437 @@ impl ConnectedWallet {
         match self.cip30_api.get_utxos(value, pagination).await {
             Ok(cbored_utxos) => {
                 if cbored_utxos.is_null() {
-                    return Ok(Vec::new());
+                    return Ok(Ok(Vec::new()));
                 }
 
-                let mut utxos = Vec::new();
-
-                for element in cbored_utxos {
-                    let hex = hex::decode(element.as_string().unwrap()).unwrap();
-                    let utxo: Utxo = pallas_codec::minicbor::decode(&hex).unwrap();
-                    utxos.push(utxo);
-                }
-
-                Ok(utxos)
+                self.decode_utxos("getUtxos", cbored_utxos).map(Ok)
             }
             Err(error) => {
-                if let Ok(PaginateError { .. }) = serde_wasm_bindgen::from_value(error.clone()) {
-                    return Ok(Vec::new());
+                if let Ok(paginate_error) = serde_wasm_bindgen::from_value(error.clone()) {
+                    return Ok(Err(paginate_error));
                 }
 
-                serde_wasm_bindgen::from_value(error)
-                    .map_err(|decode_error| APIError {
-                        code: APIErrorCode::InternalError,
-                        info: format!("Couldn't decode the error content: {decode_error}"),
-                    })
-                    .and_then(Err)
+                Err(self.api_error("getUtxos", error))
             }
         }
     }
 
+    /// the UTxOs the wallet set aside to be used as collateral (CIP-40),
+    /// covering at least `amount` lovelace
+    ///
+    /// Returns an empty list if the wallet cannot provide enough
+    /// collateral. The wallets with the
+    /// [`experimental_collateral`](WalletQuirks::experimental_collateral)
+    /// quirk are asked with `experimental.getCollateral`.
+    pub async fn collateral(&self, amount: Coin) -> Result<Vec<Utxo>, APIError> {
+        let experimental = self.experimental();
+        if self.quirks().experimental_collateral && experimental.has("getCollateral") {
+            return experimental.collateral().await;
+        }
+
+        let amount = pallas_codec::minicbor::to_vec(amount).map_err(|error| APIError {
+            code: APIErrorCode::InternalError,
+            info: format!("Failed to encode amount in cbor: {error}"),
+        })?;
+        let params = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &params,
+            &JsValue::from_str("amount"),
+            &JsValue::from_str(&hex::encode(amount)),
+        )
+        .map_err(|error| APIError {
+            code: APIErrorCode::InternalError,
+            info: format!("Failed to prepare the parameters: {error:?}"),
+        })?;
+
+        match self.cip30_api.get_collateral(&params).await {
+            Ok(value) if value.is_null() || value.is_undefined() => Ok(Vec::new()),
+            Ok(value) => self.decode_utxos("getCollateral", js_sys::Array::from(&value)),
+            Err(error) => Err(self.api_error("getCollateral", error)),
+        }
+    }
+
     pub async fn sign_data(
         &self,
         address: &Address,
         payload: impl AsRef<[u8]>,
     ) -> Result<SignedData, APIError> {
+        let payload_hash = Hasher::<256>::hash(payload.as_ref());
         // encode the payload in hexadecimal as required by the CIP-30 api
-        let address = address.to_hex();
+        let address_hex = address.to_hex();
         let payload = hex::encode(payload);
 
         // sign the payload using the connected wallet
-        match self.cip30_api.sign_data(&address, &payload).await {
-            Ok(signature) => SignedData::try_from(signature),
-            Err(error) => {
-                // TODO: handle signature error
-
-                serde_wasm_bindgen::from_value(error.clone())
-                    .map_err(|decode_error| APIError {
+        let _interaction = self.begin(WalletAction::SignData);
+        let result = match self.cip30_api.sign_data(&address_hex, &payload).await {
+            Ok(signature) => SignedData::try_from(signature).and_then(|signed| {
+                if self.strictness().verifies_signatures() {
+                    signed.verify_address(address).map_err(|error| APIError {
                         code: APIErrorCode::InternalError,
-                        info: format!(
-                            "Couldn't decode the error content: {decode_error} ({error:?})"
-                        ),
-                    })
-                    .and_then(Err)
-            }
-        }
+                        info: format!("The wallet returned an invalid signature: {error}"),
+                    })?;
+                }
+                Ok(signed)
+            }),
+            // TODO: handle signature error
+            Err(error) => Err(self.api_error("signData", error)),
+        };
+
+        let outcome = match &result {
+            Ok(signed) => AuditOutcome::Signed {
+                signature_hash: Hasher::<256>::hash(&signed.signature),
+                signers: Vec::new(),
+            },
+            Err(error) => AuditOutcome::failed(error.kind()),
+        };
+        self.audit(
+            AuditAction::SignData,
+            payload_hash,
+            Some(address_hex),
+            outcome,
+        );
+        result
+    }
+
+    /// sign the payload of the `envelope`, binding the signature to the
+    /// dApp, a purpose and a nonce (see [`auth`](crate::auth))
+    pub async fn sign_envelope(
+        &self,
+        address: &Address,
+        envelope: &Envelope,
+    ) -> Result<SignedData, APIError> {
+        self.sign_data(address, envelope.to_payload()).await
+    }
+
+    /// sign the Blake2b-224 hash of the payload instead of the payload
+    ///
+    /// Large payloads are unreadable in the wallet's prompt and some
+    /// wallets limit their size. [`SignedData::signs`] checks the
+    /// signature is for the original payload.
+    pub async fn sign_data_hashed(
+        &self,
+        address: &Address,
+        payload: impl AsRef<[u8]>,
+    ) -> Result<SignedData, APIError> {
+        self.sign_data(address, Hasher::<224>::hash(payload.as_ref()))
+            .await
     }
 
     /// sign the given transaction
+    ///
+    /// Only the body is sent to the wallet, some wallets refuse it: see
+    /// [`ConnectedWallet::sign_transaction`].
     pub async fn sign_tx(
         &self,
         transaction: &TransactionBody,
         partial_sign: bool,
-    ) -> Result<WitnessSet, APIError> {
+    ) -> Result<WitnessSet, SignTxError> {
         let transaction_cbor = pallas_codec::minicbor::to_vec(transaction).unwrap();
-        let transaction_hex = hex::encode(transaction_cbor);
-        match self.cip30_api.sign_tx(&transaction_hex, partial_sign).await {
+        let payload_hash = Hasher::<256>::hash(&transaction_cbor);
+        self.sign_cbor(payload_hash, hex::encode(transaction_cbor), partial_sign)
+            .await
+    }
+
+    /// sign the whole transaction (body, witness set and auxiliary data)
+    /// as CIP-30 specifies, and add the returned witnesses to it
+    ///
+    /// The returned transaction is ready to be submitted if the wallet
+    /// provided all the missing witnesses.
+    pub async fn sign_transaction(&self, tx: &Tx, partial_sign: bool) -> Result<Tx, SignTxError> {
+        let transaction_cbor = pallas_codec::minicbor::to_vec(tx).unwrap();
+        let witness_set = self
+            .sign_cbor(
+                cardano::tx_hash(&tx.transaction_body),
+                hex::encode(transaction_cbor),
+                partial_sign,
+            )
+            .await?;
+
+        let mut tx = tx.clone();
+        tx.transaction_witness_set.merge(witness_set);
+        Ok(tx)
+    }
+
+    /// `signTx` of the hexadecimal CBOR of a body or a transaction whose
+    /// id is `payload_hash`
+    async fn sign_cbor(
+        &self,
+        payload_hash: TxHash,
+        transaction_hex: String,
+        partial_sign: bool,
+    ) -> Result<WitnessSet, SignTxError> {
+        let _interaction = self.begin(WalletAction::SignTx);
+        let result = match self.cip30_api.sign_tx(&transaction_hex, partial_sign).await {
             Ok(set_js) => {
-                let set_hex = set_js.as_string().unwrap();
-                let set_cbor = hex::decode(set_hex).map_err(|error| APIError {
-                    code: APIErrorCode::InternalError,
-                    info: format!("Couldn't decode the witness set: {error}"),
-                })?;
-                pallas_codec::minicbor::decode(&set_cbor).map_err(|decode_error| APIError {
-                    code: APIErrorCode::InternalError,
-                    info: format!("Couldn't decode the witness set: {decode_error}"),
-                })
+                let set_hex = String::from(set_js.clone());
+                decode_signed(&set_hex, self.quirks().sign_tx_returns_tx)
+                    .inspect_err(|_| {
+                        let reason = QuirkReason::of_hex(&set_js, QuirkReason::InvalidCbor);
+                        self.quirk("signTx", &set_js, reason)
+                    })
+                    .map(|witness_set| {
+                        let set_cbor = hex::decode(&set_hex).unwrap_or_default();
+                        (witness_set, Hasher::<256>::hash(&set_cbor))
+                    })
+                    .map_err(SignTxError::Api)
+                    .and_then(|signed| self.check_witnesses(&payload_hash, signed))
+            }
+            Err(error) => match self.raw_error("signTx", error) {
+                Ok(RawError { code, info }) => Err(SignTxError::from_code(code, info)),
+                Err(error) => Err(SignTxError::Api(error)),
+            },
+        };
+
+        let outcome = match &result {
+            Ok((witness_set, signature_hash)) => AuditOutcome::Signed {
+                signature_hash: *signature_hash,
+                signers: witness::vkey_hashes(witness_set),
+            },
+            Err(error) => AuditOutcome::failed(error.kind()),
+        };
+        self.audit(AuditAction::SignTx, payload_hash, None, outcome);
+        result.map(|(witness_set, _)| witness_set)
+    }
+
+    /// with a [`Paranoid`](strictness::Strictness::Paranoid) strictness,
+    /// refuse the witness sets with a vkey witness not signing `tx_hash`
+    fn check_witnesses<T>(
+        &self,
+        tx_hash: &TxHash,
+        signed: (WitnessSet, T),
+    ) -> Result<(WitnessSet, T), SignTxError> {
+        if !self.strictness().verifies_signatures() {
+            return Ok(signed);
+        }
+        let invalid = witness::invalid_vkey_witnesses(tx_hash, &signed.0);
+        if invalid.is_empty() {
+            Ok(signed)
+        } else {
+            Err(SignTxError::Api(APIError {
+                code: APIErrorCode::InternalError,
+                info: format!(
+                    "The wallet returned {} invalid vkey witness(es)",
+                    invalid.len()
+                ),
+            }))
+        }
+    }
+
+    /// sign all the given transactions, prompting the user only once if
+    /// the wallet supports the CIP-103 extension
+    ///
+    /// The outer error is returned if the whole request failed (e.g. the
+    /// user declined), otherwise there is one result per transaction in
+    /// the same order. Without the CIP-103 extension, the transactions are
+    /// signed one after the other with [`ConnectedWallet::sign_tx`].
+    pub async fn sign_txs(
+        &self,
+        transactions: &[TransactionBody],
+        partial_sign: bool,
+    ) -> Result<Vec<Result<WitnessSet, SignTxError>>, APIError> {
+        let Some(cip103) = self.extension_api::<Cip103Api>() else {
+            let mut results = Vec::with_capacity(transactions.len());
+            for transaction in transactions {
+                results.push(self.sign_tx(transaction, partial_sign).await);
             }
-            Err(error) => serde_wasm_bindgen::from_value(error)
-                .map_err(|decode_error| APIError {
+            return Ok(results);
+        };
+
+        let bodies: Vec<_> = transactions
+            .iter()
+            .map(|transaction| pallas_codec::minicbor::to_vec(transaction).unwrap())
+            .collect();
+        let payload_hashes: Vec<_> = bodies
+            .iter()
+            .map(|body| Hasher::<256>::hash(body))
+            .collect();
+        let requests = bodies
+            .iter()
+            .map(|body| SignTxRequest {
+                cbor: hex::encode(body),
+                partial_sign,
+            })
+            .collect();
+        let _interaction = self.begin(WalletAction::SignTxs);
+        let entries = cip103
+            .sign_txs(requests)
+            .await
+            .and_then(|entries| {
+                if entries.len() == transactions.len() {
+                    return Ok(entries);
+                }
+                Err(APIError {
                     code: APIErrorCode::InternalError,
-                    info: format!("Couldn't decode the error content: {decode_error}"),
+                    info: format!(
+                        "Expected {} witness sets, the wallet returned {}",
+                        transactions.len(),
+                        entries.len()
+                    ),
                 })
-                .and_then(Err),
+            })
+            .inspect_err(|error| {
+                // every presented transaction was refused
+                for payload_hash in &payload_hashes {
+                    let outcome = AuditOutcome::failed(error.kind());
+                    self.audit(AuditAction::SignTx, *payload_hash, None, outcome);
+                }
+            })?;
+
+        let results: Vec<_> = entries
+            .into_iter()
+            .zip(&payload_hashes)
+            .map(|(entry, payload_hash)| {
+                cip103::decode_entry(entry)
+                    .map(|witness_set| (witness_set, ()))
+                    .and_then(|signed| self.check_witnesses(payload_hash, signed))
+                    .map(|(witness_set, ())| witness_set)
+            })
+            .collect();
+        for (payload_hash, result) in payload_hashes.into_iter().zip(&results) {
+            let outcome = match result {
+                Ok(witness_set) => AuditOutcome::Signed {
+                    signature_hash: Hasher::<256>::hash(
+                        &pallas_codec::minicbor::to_vec(witness_set).unwrap(),
+                    ),
+                    signers: witness::vkey_hashes(witness_set),
+                },
+                Err(error) => AuditOutcome::failed(error.kind()),
+            };
+            self.audit(AuditAction::SignTx, payload_hash, None, outcome);
+        }
+        Ok(results)
+    }
+
+    /// sign the given transaction and report which of the `expected`
+    /// signers (in addition to the body's `required_signers`) have been
+    /// provided by the wallet and which ones are still missing.
+    pub async fn sign_tx_with_report(
+        &self,
+        transaction: &TransactionBody,
+        partial_sign: bool,
+        expected: impl IntoIterator<Item = AddrKeyhash>,
+    ) -> Result<(WitnessSet, WitnessReport), SignTxError> {
+        let witness_set = self.sign_tx(transaction, partial_sign).await?;
+        let report = WitnessReport::for_body(transaction, expected, [&witness_set]);
+        Ok((witness_set, report))
+    }
+
+    /// send `amount` lovelace to `to`, with the default fee parameters
+    ///
+    /// See [`ConnectedWallet::send_with`].
+    pub async fn send(&self, to: &Address, amount: Coin) -> Result<TxHash, SendError> {
+        let change_address = self.change_address().await?;
+        self.send_with(to, amount, &BuildParams::defaults(change_address))
+            .await
+    }
+
+    /// send `amount` lovelace to `to` in one call: build the transaction
+    /// from the UTxOs of the wallet, ask the user to sign it and submit
+    /// it through the wallet
+    ///
+    /// The change goes back to the wallet's change address, whatever the
+    /// `change_address` of the `params`.
+    pub async fn send_with(
+        &self,
+        to: &Address,
+        amount: Coin,
+        params: &BuildParams,
+    ) -> Result<TxHash, SendError> {
+        WalletApi::send_with(self, to, amount, params).await
+    }
+
+    /// prepare the transaction burning every asset of the `policy` held by
+    /// the wallet
+    ///
+    /// The policy script is added to the witness set, the transaction
+    /// still needs the signatures required by the script (and the ones of
+    /// the spent UTxOs) before it can be submitted. The change goes back
+    /// to the wallet's change address, whatever the `change_address` of
+    /// the `params`.
+    pub async fn burn_all(
+        &self,
+        policy: &NativeScript,
+        params: &BuildParams,
+    ) -> Result<Tx, BurnError> {
+        let utxos = UtxoSet::from(self.all_utxos(None).await?);
+        let params = BuildParams {
+            change_address: self.change_address().await?,
+            ..params.clone()
+        };
+
+        let builder = MintBuilder::new(policy.clone()).burn_all(&utxos);
+        if builder.is_empty() {
+            return Err(BurnError::NothingToBurn(builder.policy_id()));
         }
+        Ok(builder.build(&utxos, &params)?)
     }
 
     /// ask the wallet connector application to submit the given transaction
-    pub async fn submit_tx(&self, transaction: &Tx) -> Result<Hash<32>, APIError> {
+    ///
+    /// The transaction id returned by the wallet is checked against the
+    /// hash of the submitted transaction.
+    pub async fn submit_tx(&self, transaction: &Tx) -> Result<TxHash, SubmitTxError> {
         let transaction_cbor = pallas_codec::minicbor::to_vec(transaction).unwrap();
+        let expected = inspect::tx_hash(&transaction_cbor).map_err(|error| APIError {
+            code: APIErrorCode::InternalError,
+            info: format!("Couldn't hash the transaction: {error}"),
+        })?;
         let transaction_hex = hex::encode(transaction_cbor);
         match self.cip30_api.submit_tx(&transaction_hex).await {
-            Ok(tx_hash_js) => {
-                // TODO
-                panic!("Don't know yet what is the output of submit: {tx_hash_js:?}");
-            }
-            Err(error) => serde_wasm_bindgen::from_value(error)
-                .map_err(|decode_error| APIError {
-                    code: APIErrorCode::InternalError,
-                    info: format!("Couldn't decode the error content: {decode_error}"),
-                })
-                .and_then(Err),
+            Ok(tx_hash_js) => check_submitted_hash(expected, &String::from(tx_hash_js)),
+            Err(error) => match self.raw_error("submitTx", error) {
+                Ok(RawError { code, info }) => Err(SubmitTxError::from_code(code, info)),
+                Err(error) => Err(SubmitTxError::Api(error)),
+            },
         }
     }
 }
 
+/// check the transaction id returned by `submitTx` is the one of the
+/// submitted transaction
+fn check_submitted_hash(expected: TxHash, returned: &str) -> Result<TxHash, SubmitTxError> {
+    let returned = TxHash::from_hex(returned).map_err(|error| APIError {
+        code: APIErrorCode::InternalError,
+        info: format!("Invalid transaction id `{returned}': {error}"),
+    })?;
+    if returned != expected {
+        return Err(SubmitTxError::HashMismatch { expected, returned });
+    }
+    Ok(returned)
+}
+
+/// decode a hex encoded CBOR `TransactionUnspentOutput`
+fn decode_utxo(hex: &str) -> Option<Utxo> {
+    let bytes = hex::decode(hex).ok()?;
+    pallas_codec::minicbor::decode(&bytes).ok()
+}
+
+/// decode the hex encoded CBOR witness set returned by the wallet
+pub(crate) fn decode_witness_set(set_hex: &str) -> Result<WitnessSet, APIError> {
+    let set_cbor = hex::decode(set_hex).map_err(|error| APIError {
+        code: APIErrorCode::InternalError,
+        info: format!("Couldn't decode the witness set: {error}"),
+    })?;
+    pallas_codec::minicbor::decode(&set_cbor).map_err(|decode_error| APIError {
+        code: APIErrorCode::InternalError,
+        info: format!("Couldn't decode the witness set: {decode_error}"),
+    })
+}
+
+/// the witness set of a `signTx` response, or the one of the signed
+/// transaction for the wallets returning it instead (see
+/// [`WalletQuirks::sign_tx_returns_tx`])
+fn decode_signed(signed_hex: &str, returns_tx: bool) -> Result<WitnessSet, APIError> {
+    if returns_tx
+        && let Some(tx) = hex::decode(signed_hex)
+            .ok()
+            .and_then(|cbor| pallas_codec::minicbor::decode::<Tx>(&cbor).ok())
+    {
+        return Ok(tx.transaction_witness_set);
+    }
+    decode_witness_set(signed_hex)
+}
+
 fn decode_balance_value(balance_hex: &str) -> Result<Value, APIError> {
     let balance_cbor = hex::decode(balance_hex).map_err(|error| APIError {
         code: APIErrorCode::InternalError,
@@ -419,6 +1052,32 @@ pub struct SignedData {
     pub signature: [u8; 64],
     pub signed_data: Vec<u8>,
     pub address: Vec<u8>,
+    /// the payload of the COSE_Sign1 structure: the Blake2b-224 hash of
+    /// the original payload if [`hashed`](Self::hashed)
+    pub payload: Vec<u8>,
+    /// the `hashed` header (CIP-8): the wallet signed the hash of the
+    /// payload
+    pub hashed: bool,
+}
+
+impl Redact for SignedData {
+    fn fmt_debug(&self, f: &mut fmt::Formatter<'_>, redact: bool) -> fmt::Result {
+        let bytes = |bytes| redact::Bytes { bytes, redact };
+        f.debug_struct("SignedData")
+            .field("key", &bytes(&self.key))
+            .field("signature", &bytes(&self.signature))
+            .field("signed_data", &hex::encode(&self.signed_data))
+            .field("address", &bytes(&self.address))
+            .field("payload", &hex::encode(&self.payload))
+            .field("hashed", &self.hashed)
+            .finish()
+    }
+}
+
+impl fmt::Debug for SignedData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_debug(f, redact::enabled())
+    }
 }
 
 fn cbor_to_api(error: cbor_event::Error) -> APIError {
@@ -481,11 +1140,11 @@ fn extract_cose_key(bytes: &[u8]) -> Result<[u8; 32], APIError> {
             break;
         } else if Value::I64(-2) == key {
             // don't dwell, we can already return and stop there
-            let mut key = [0; 32];
             let key_bytes = cbor.bytes().map_err(cbor_to_api)?;
-            assert_eq!(key_bytes.len(), 32);
-            key.copy_from_slice(&key_bytes);
-            return Ok(key);
+            return <[u8; 32]>::try_from(key_bytes.as_slice()).map_err(|_| APIError {
+                code: APIErrorCode::Unknown(42),
+                info: format!("Invalid key, {} bytes instead of 32", key_bytes.len()),
+            });
         } else {
             // ignore the value and move to the next entry
             let _value = Value::deserialize(&mut cbor).map_err(cbor_to_api)?;
@@ -511,10 +1170,16 @@ fn decode_cose_sig1(bytes: &[u8]) -> Result<SignedData, APIError> {
     let address = extract_address_from_protected_header(&protected_header)?;
 
     // unprotected
+    let mut hashed = false;
     let () = cbor
         .map_with(|cbor| {
-            let _key = Value::deserialize(cbor)?;
-            let _value = Value::deserialize(cbor)?;
+            let key = Value::deserialize(cbor)?;
+            let value = Value::deserialize(cbor)?;
+            if let (Value::Text(key), Value::Special(cbor_event::Special::Bool(value))) =
+                (key, value)
+            {
+                hashed |= key == "hashed" && value;
+            }
             Ok(())
         })
         .map_err(cbor_to_api)?;
@@ -522,9 +1187,13 @@ fn decode_cose_sig1(bytes: &[u8]) -> Result<SignedData, APIError> {
     let data = cbor.bytes().map_err(cbor_to_api)?;
 
     let signature_bytes = cbor.bytes().map_err(cbor_to_api)?;
-    assert_eq!(signature_bytes.len(), 64);
-    let mut signature = [0; 64];
-    signature.copy_from_slice(&signature_bytes);
+    let signature = <[u8; 64]>::try_from(signature_bytes.as_slice()).map_err(|_| APIError {
+        code: APIErrorCode::Unknown(42),
+        info: format!(
+            "Invalid signature, {} bytes instead of 64",
+            signature_bytes.len()
+        ),
+    })?;
 
     let mut signed_data = Serializer::new_vec();
 
@@ -534,7 +1203,7 @@ fn decode_cose_sig1(bytes: &[u8]) -> Result<SignedData, APIError> {
         .write_bytes(&protected_header)
         .map_err(cbor_to_api)?;
     signed_data.write_bytes([]).map_err(cbor_to_api)?; // external aad empty
-    signed_data.write_bytes(data).map_err(cbor_to_api)?;
+    signed_data.write_bytes(&data).map_err(cbor_to_api)?;
 
     let signed_data = signed_data.finalize();
 
@@ -543,10 +1212,59 @@ fn decode_cose_sig1(bytes: &[u8]) -> Result<SignedData, APIError> {
         signature,
         signed_data,
         address,
+        payload: data,
+        hashed,
     })
 }
 
 impl SignedData {
+    /// check the Ed25519 signature of the data with the key
+    ///
+    /// This says nothing about who owns the key, see
+    /// [`verify_address`](Self::verify_address).
+    pub fn verify(&self) -> bool {
+        let key = PublicKey::from(self.key);
+        key.verify(&self.signed_data, &Signature::from(self.signature))
+    }
+
+    /// the signed payload is `payload`, or its Blake2b-224 hash (signed
+    /// with the `hashed` header or with
+    /// [`ConnectedWallet::sign_data_hashed`])
+    pub fn signs(&self, payload: &[u8]) -> bool {
+        self.payload == payload || self.payload == Hasher::<224>::hash(payload).as_ref()
+    }
+
+    /// check the signature and that the data was signed for `address` by
+    /// the key of its payment credential (of its stake credential for a
+    /// reward address)
+    ///
+    /// This is what a dApp needs to accept a login signature without a
+    /// backend.
+    pub fn verify_address(&self, address: &Address) -> Result<(), VerifyDataError> {
+        if !self.verify() {
+            return Err(VerifyDataError::InvalidSignature);
+        }
+        if self.address != address.to_vec() {
+            return Err(VerifyDataError::AddressMismatch);
+        }
+
+        let key_hash = match address {
+            Address::Shelley(address) => match address.payment() {
+                ShelleyPaymentPart::Key(hash) => *hash,
+                ShelleyPaymentPart::Script(_) => return Err(VerifyDataError::NoKeyCredential),
+            },
+            Address::Stake(address) => match address.payload() {
+                StakePayload::Stake(hash) => *hash,
+                StakePayload::Script(_) => return Err(VerifyDataError::NoKeyCredential),
+            },
+            Address::Byron(_) => return Err(VerifyDataError::NoKeyCredential),
+        };
+        if Hasher::<224>::hash(&self.key) != key_hash {
+            return Err(VerifyDataError::KeyMismatch);
+        }
+        Ok(())
+    }
+
     fn try_from(signature: DataSignature) -> Result<Self, APIError> {
         Self::from_bytes(&signature.key(), &signature.signature())
     }
@@ -572,11 +1290,57 @@ impl SignedData {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::cardano::{AssetName, Multiasset, NonEmptyKeyValuePairs, PolicyId, PositiveCoin};
+    use crate::cardano::{
+        AssetName, Hash, Multiasset, NonEmptyKeyValuePairs, PolicyId, PositiveCoin,
+    };
+    use pallas_addresses::{Network, ShelleyAddress, ShelleyDelegationPart};
 
     const COSE_KEY: &str = "a50101025839012abad624652d7b0fa0d00744a767b09dd0c9e8ef890966103802be7875d8bdc2a0facf8b85d2457c2417098703cf29067cea3eec03071f6e0327200621582074647c101ed98ade960ebad955f60961d1fcf77cb8a0bac9d6b778227685d1ae";
     const COSE_SIG: &str = "845882a30127045839012abad624652d7b0fa0d00744a767b09dd0c9e8ef890966103802be7875d8bdc2a0facf8b85d2457c2417098703cf29067cea3eec03071f6e67616464726573735839012abad624652d7b0fa0d00744a767b09dd0c9e8ef890966103802be7875d8bdc2a0facf8b85d2457c2417098703cf29067cea3eec03071f6ea166686173686564f4446461746158402b45771561fdb6041326331a101a99d4bfe4f1a5c5b007f3d2f4f2e7f3f34d45aa5fedcd3f520e1799974c707996475693170531e2ad4a05ece3beb456f35a0f";
 
+    #[test]
+    fn witness_set_of_the_signed_tx() {
+        use crate::cardano::testing::{empty_body, empty_witness_set};
+
+        let mut witness_set = empty_witness_set();
+        witness_set.native_script =
+            pallas_codec::utils::NonEmptySet::from_vec(vec![NativeScript::InvalidBefore(1)]);
+        let tx = Tx {
+            transaction_body: empty_body(),
+            transaction_witness_set: witness_set.clone(),
+            success: true,
+            auxiliary_data: pallas_codec::utils::Nullable::Null,
+        };
+        let tx_hex = hex::encode(pallas_codec::minicbor::to_vec(&tx).unwrap());
+        let set_hex = hex::encode(pallas_codec::minicbor::to_vec(&witness_set).unwrap());
+
+        assert_eq!(decode_signed(&tx_hex, true).unwrap(), witness_set);
+        assert_eq!(decode_signed(&set_hex, true).unwrap(), witness_set);
+        assert!(decode_signed(&tx_hex, false).is_err());
+    }
+
+    #[test]
+    fn decode_collateral_utxo() {
+        let utxo = Utxo {
+            input: crate::cardano::TransactionInput {
+                transaction_id: TxHash::from([1; 32]),
+                index: 0,
+            },
+            output: crate::cardano::TransactionOutput::PostAlonzo(
+                pallas_primitives::babbage::PseudoPostAlonzoTransactionOutput {
+                    address: vec![0x61; 29].into(),
+                    value: Value::Coin(5_000_000),
+                    datum_option: None,
+                    script_ref: None,
+                },
+            ),
+        };
+        let utxo_hex = hex::encode(pallas_codec::minicbor::to_vec(&utxo).unwrap());
+
+        assert_eq!(decode_utxo(&utxo_hex), Some(utxo));
+        assert_eq!(decode_utxo("not hex"), None);
+    }
+
     #[test]
     fn decode_balance_coin() {
         let balance_hex = hex::encode(pallas_codec::minicbor::to_vec(Value::Coin(42)).unwrap());
@@ -601,12 +1365,33 @@ mod tests {
         assert_eq!(balance, value);
     }
 
+    #[test]
+    fn submitted_hash() {
+        let expected = TxHash::from([1; 32]);
+
+        assert_eq!(
+            check_submitted_hash(expected, &expected.to_string()),
+            Ok(expected)
+        );
+        assert_eq!(
+            check_submitted_hash(expected, &TxHash::from([2; 32]).to_string()),
+            Err(SubmitTxError::HashMismatch {
+                expected,
+                returned: TxHash::from([2; 32])
+            })
+        );
+        assert!(matches!(
+            check_submitted_hash(expected, "not hex"),
+            Err(SubmitTxError::Api(_))
+        ));
+    }
+
     #[test]
     fn signed_data_from_bytes() {
         let result = SignedData::from_bytes(COSE_KEY, COSE_SIG).unwrap();
 
-        dbg!(hex::encode(&result.key));
-        dbg!(hex::encode(&result.signature));
+        dbg!(hex::encode(result.key));
+        dbg!(hex::encode(result.signature));
         dbg!(hex::encode(&result.signed_data));
 
         assert!(cryptoxide::ed25519::verify(
@@ -614,5 +1399,58 @@ mod tests {
             &result.key,
             &result.signature
         ));
+        assert!(result.verify());
+        assert!(!result.hashed);
+        assert!(result.signs(b"data"));
+        assert!(!result.signs(b"other data"));
+
+        let hashed = COSE_SIG.replace("66686173686564f4", "66686173686564f5");
+        assert!(SignedData::from_bytes(COSE_KEY, &hashed).unwrap().hashed);
+    }
+
+    #[test]
+    fn truncated_signed_data() {
+        // the last byte of the 64 bytes signature is missing
+        let (sig1, signature) = COSE_SIG.split_at(COSE_SIG.len() - 132);
+        let truncated = format!("{sig1}583f{}", &signature[4..130]);
+        let error = SignedData::from_bytes(COSE_KEY, &truncated).unwrap_err();
+        assert!(error.info.contains("63 bytes"));
+
+        // same for the 32 bytes key
+        let (cose_key, key) = COSE_KEY.split_at(COSE_KEY.len() - 68);
+        let truncated = format!("{cose_key}581f{}", &key[4..66]);
+        let error = SignedData::from_bytes(&truncated, COSE_SIG).unwrap_err();
+        assert!(error.info.contains("31 bytes"));
+    }
+
+    #[test]
+    fn signed_data_for_address() {
+        let result = SignedData::from_bytes(COSE_KEY, COSE_SIG).unwrap();
+        let address = Address::from_bytes(&result.address).unwrap();
+
+        assert_eq!(result.verify_address(&address), Ok(()));
+
+        let other: Address = ShelleyAddress::new(
+            Network::Mainnet,
+            ShelleyPaymentPart::key_hash(Hash::from([1; 28])),
+            ShelleyDelegationPart::Null,
+        )
+        .into();
+        assert_eq!(
+            result.verify_address(&other),
+            Err(VerifyDataError::AddressMismatch)
+        );
+
+        let mut tampered = SignedData::from_bytes(COSE_KEY, COSE_SIG).unwrap();
+        tampered.address = other.to_vec();
+        assert_eq!(
+            tampered.verify_address(&other),
+            Err(VerifyDataError::KeyMismatch)
+        );
+        tampered.signed_data.push(0);
+        assert_eq!(
+            tampered.verify_address(&other),
+            Err(VerifyDataError::InvalidSignature)
+        );
     }
 }
@@ -0,0 +1,608 @@
+/*!
+Connection management across page loads.
+
+The [`Connector`] remembers the user's [`Preferences`] (which wallet they
+connected with and the extensions it granted, whether to reconnect
+automatically, on which network) in a [`Storage`] so the dApp can
+reconnect on the next visit with [`Connector::connect_default`] (or
+[`Connector::reconnect`], which ignores `auto_connect`) without asking
+again:
+
+```no_run
+# use cardano_connector::{connector::Connector, storage::LocalStorage};
+# async fn test() -> anyhow::Result<()> {
+let mut connector = Connector::new(LocalStorage);
+// on page load, never prompts the user
+if connector.connect_default().await?.is_none() {
+    // let the user pick a wallet
+    connector.connect("lace").await?;
+    let mut preferences = connector.preferences();
+    preferences.auto_connect = true;
+    connector.set_preferences(&preferences)?;
+}
+# Ok(()) }
+```
+
+The [`ConnectorBuilder`] configures the connector in one place, the
+wallets it connects follow its [`Settings`] (strictness, telemetry
+reporter and quirks):
+
+```no_run
+# use cardano_connector::{NetworkId, connector::ConnectorBuilder, backend::BackendConfig, storage::LocalStorage, strictness::Strictness};
+# use std::time::Duration;
+# async fn test() -> anyhow::Result<()> {
+let mut connector = ConnectorBuilder::new(LocalStorage)
+    .expect_network(NetworkId::Mainnet)
+    .with_enable_timeout(Duration::from_secs(60))
+    .with_backend(BackendConfig::new("https://cardano-mainnet.blockfrost.io/api/v0").with_max_retries(5))
+    .with_strictness(Strictness::Paranoid)
+    .with_reporter(|report| { let _ = report; })
+    .build();
+let wallet = connector.connect("lace").await?;
+# Ok(()) }
+```
+
+The other subsystems are given the connector's state: the
+[`data_source`](Connector::data_source) reads from the connected wallet,
+the [`transaction_builder`](Connector::transaction_builder) sends the
+change to it and the [`envelope_verifier`](Connector::envelope_verifier)
+keeps the used nonces in the connector's storage.
+*/
+
+use crate::{
+    ConnectedWallet, NetworkId, Wallet, WalletKind, WalletQuirks,
+    audit::AuditLog,
+    auth::EnvelopeVerifier,
+    backend::{BackendConfig, ChainProvider, HttpClient},
+    cardano::{TransactionBuilder, fees::ProtocolParams},
+    data_source::DataSource,
+    error::APIError,
+    events::{Event, EventBus},
+    ffi::{self, Extension},
+    interaction::{Interactions, WalletAction},
+    permission::PermissionTracker,
+    storage::{Storage, StorageError},
+    strictness::Strictness,
+    telemetry::{self, QuirkReport},
+    wallet,
+    watch::WatchWallet,
+};
+use futures::future::{self, Either};
+use std::{collections::HashMap, fmt, pin::pin, rc::Rc, time::Duration};
+
+/// the permission of the connected wallet is considered stale after an
+/// hour
+const PERMISSION_TTL: Duration = Duration::from_secs(60 * 60);
+
+const PREFERENCES_KEY: &str = "cardano-connector.preferences";
+
+/// The connection preferences of the user.
+#[derive(Debug, Clone, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Preferences {
+    /// name of the wallet the user connected with last
+    pub default_wallet: Option<String>,
+    /// the extensions the `default_wallet` granted, requested again
+    /// when reconnecting
+    pub extensions: Vec<Extension>,
+    /// reconnect to the `default_wallet` when the dApp loads
+    pub auto_connect: bool,
+    /// refuse to connect to a wallet on another network
+    pub preferred_network: Option<NetworkId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ConnectorError {
+    #[error(transparent)]
+    Api(#[from] APIError),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error("The wallet `{0}' is not available")]
+    WalletNotFound(String),
+    #[error("The wallet is on {found}, expected {expected}")]
+    WrongNetwork {
+        expected: NetworkId,
+        found: NetworkId,
+    },
+    #[error("The wallet did not answer within {0:?}")]
+    Timeout(Duration),
+    #[error("No wallet is connected")]
+    NotConnected,
+}
+
+/// How the wallets connected by a [`Connector`] are handled, see
+/// [`ConnectorBuilder`].
+#[derive(Clone, Default)]
+pub struct Settings {
+    strictness: Strictness,
+    reporter: Option<telemetry::Reporter>,
+    quirks: HashMap<WalletKind, WalletQuirks>,
+}
+
+impl Settings {
+    pub fn strictness(&self) -> Strictness {
+        self.strictness
+    }
+
+    /// the quirks of the `kind` of wallet, the ones of the
+    /// [`registry`](crate::registry) unless overridden
+    pub fn quirks(&self, kind: &WalletKind) -> WalletQuirks {
+        self.quirks
+            .get(kind)
+            .copied()
+            .unwrap_or_else(|| kind.quirks())
+    }
+
+    pub(crate) fn reporter(&self) -> Option<&telemetry::Reporter> {
+        self.reporter.as_ref()
+    }
+}
+
+impl PartialEq for Settings {
+    fn eq(&self, other: &Self) -> bool {
+        let reporter = match (&self.reporter, &other.reporter) {
+            (Some(reporter), Some(other)) => Rc::ptr_eq(reporter, other),
+            (reporter, other) => reporter.is_none() && other.is_none(),
+        };
+        reporter && self.strictness == other.strictness && self.quirks == other.quirks
+    }
+}
+
+impl fmt::Debug for Settings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Settings")
+            .field("strictness", &self.strictness)
+            .field("reporter", &self.reporter.is_some())
+            .field("quirks", &self.quirks)
+            .finish()
+    }
+}
+
+/// Configuration of a [`Connector`], see the [module documentation](self).
+///
+/// The strictness and the telemetry reporter only apply to the wallets
+/// connected by the built connector, the crate wide defaults
+/// ([`strictness::set_strictness`](crate::strictness::set_strictness) and
+/// [`telemetry::set_reporter`]) are left untouched.
+pub struct ConnectorBuilder<S> {
+    storage: S,
+    permission_ttl: Duration,
+    expected_network: Option<NetworkId>,
+    enable_timeout: Option<Duration>,
+    backend: Option<BackendConfig>,
+    audit: Option<AuditLog>,
+    settings: Settings,
+}
+
+impl<S: Storage> ConnectorBuilder<S> {
+    pub fn new(storage: S) -> Self {
+        Self {
+            storage,
+            permission_ttl: PERMISSION_TTL,
+            expected_network: None,
+            enable_timeout: None,
+            backend: None,
+            audit: None,
+            settings: Settings::default(),
+        }
+    }
+
+    /// see [`Connector::with_permission_ttl`]
+    pub fn with_permission_ttl(mut self, ttl: Duration) -> Self {
+        self.permission_ttl = ttl;
+        self
+    }
+
+    /// refuse to connect to a wallet on another network, whatever the
+    /// [`preferred_network`](Preferences::preferred_network) of the user
+    pub fn expect_network(mut self, network_id: NetworkId) -> Self {
+        self.expected_network = Some(network_id);
+        self
+    }
+
+    /// give up connecting if the user does not answer the wallet's
+    /// prompt within `timeout`
+    pub fn with_enable_timeout(mut self, timeout: Duration) -> Self {
+        self.enable_timeout = Some(timeout);
+        self
+    }
+
+    /// the chain indexer, with its rate limit and retries, see
+    /// [`Connector::backend`]
+    pub fn with_backend(mut self, config: BackendConfig) -> Self {
+        self.backend = Some(config);
+        self
+    }
+
+    /// record the signatures of the connected wallets, see
+    /// [`ConnectedWallet::with_audit_log`]
+    pub fn with_audit_log(mut self, audit: AuditLog) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// the [`Strictness`] of the connected wallets, `Strict` by default
+    pub fn with_strictness(mut self, strictness: Strictness) -> Self {
+        self.settings.strictness = strictness;
+        self
+    }
+
+    /// give the [`QuirkReport`]s of the connected wallets to `reporter`,
+    /// see [`telemetry`]
+    pub fn with_reporter(mut self, reporter: impl Fn(&QuirkReport) + 'static) -> Self {
+        self.settings.reporter = Some(Rc::new(reporter));
+        self
+    }
+
+    /// replace the [`WalletQuirks`] known for the `kind` of wallet, e.g.
+    /// once a new version of the wallet fixed one
+    pub fn with_quirks(mut self, kind: WalletKind, quirks: WalletQuirks) -> Self {
+        self.settings.quirks.insert(kind, quirks);
+        self
+    }
+
+    pub fn build(self) -> Connector<S> {
+        let events = EventBus::new();
+        Connector {
+            storage: self.storage,
+            interactions: Interactions::new(events.clone()),
+            permissions: PermissionTracker::new(events.clone(), self.permission_ttl),
+            backend: self
+                .backend
+                .map(|config| Rc::new(HttpClient::new(config).with_events(events.clone()))),
+            events,
+            expected_network: self.expected_network,
+            enable_timeout: self.enable_timeout,
+            audit: self.audit,
+            settings: Rc::new(self.settings),
+            wallet: None,
+        }
+    }
+}
+
+/// Connects to the wallets and remembers the user's [`Preferences`].
+pub struct Connector<S> {
+    storage: S,
+    events: EventBus,
+    interactions: Interactions,
+    permissions: PermissionTracker,
+    backend: Option<Rc<HttpClient>>,
+    expected_network: Option<NetworkId>,
+    enable_timeout: Option<Duration>,
+    audit: Option<AuditLog>,
+    settings: Rc<Settings>,
+    wallet: Option<ConnectedWallet>,
+}
+
+impl<S: Storage> Connector<S> {
+    /// the permission of the connected wallet is considered stale after
+    /// an hour, see [`with_permission_ttl`](Self::with_permission_ttl)
+    pub fn new(storage: S) -> Self {
+        ConnectorBuilder::new(storage).build()
+    }
+
+    pub fn builder(storage: S) -> ConnectorBuilder<S> {
+        ConnectorBuilder::new(storage)
+    }
+
+    /// emit [`Event::PermissionStale`] once the wallet was enabled for
+    /// longer than `ttl`
+    pub fn with_permission_ttl(mut self, ttl: Duration) -> Self {
+        self.permissions = PermissionTracker::new(self.events.clone(), ttl);
+        self
+    }
+
+    /// the bus on which the connection events are emitted
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// the actions of the connected wallet waiting for the user
+    pub fn interactions(&self) -> &Interactions {
+        &self.interactions
+    }
+
+    /// when the connected wallet was enabled, see
+    /// [`PermissionTracker::run`] to be notified when it becomes stale
+    pub fn permissions(&self) -> &PermissionTracker {
+        &self.permissions
+    }
+
+    /// the client of the chain indexer configured with
+    /// [`ConnectorBuilder::with_backend`]
+    ///
+    /// Cloning the `Rc` shares the rate limit and the quota with the
+    /// other users of the backend.
+    pub fn backend(&self) -> Option<&Rc<HttpClient>> {
+        self.backend.as_ref()
+    }
+
+    /// the settings given to the connected wallets
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// the quirks of the `wallet`, with the overrides of the
+    /// [`ConnectorBuilder::with_quirks`]
+    pub fn quirks(&self, wallet: &Wallet) -> WalletQuirks {
+        self.settings.quirks(&wallet.kind())
+    }
+
+    /// the network the wallets must be on, if any: the one set with
+    /// [`ConnectorBuilder::expect_network`], or else the
+    /// [`preferred_network`](Preferences::preferred_network) of the user
+    pub fn expected_network(&self) -> Option<NetworkId> {
+        self.expected_network
+            .or_else(|| self.preferences().preferred_network)
+    }
+
+    /// the stored preferences, the default ones if none were stored
+    pub fn preferences(&self) -> Preferences {
+        self.storage
+            .get(PREFERENCES_KEY)
+            .and_then(|preferences| serde_json::from_str(&preferences).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn set_preferences(&self, preferences: &Preferences) -> Result<(), StorageError> {
+        let preferences = serde_json::to_string(preferences)
+            .map_err(|error| StorageError::Write(error.to_string()))?;
+        self.storage.set(PREFERENCES_KEY, &preferences)
+    }
+
+    /// the currently connected wallet
+    pub fn wallet(&self) -> Option<&ConnectedWallet> {
+        self.wallet.as_ref()
+    }
+
+    /// connect to the wallet with the given name, prompting the user if
+    /// needed, and remember it as the default wallet
+    pub async fn connect(&mut self, name: &str) -> Result<&ConnectedWallet, ConnectorError> {
+        self.connect_with_extensions(name, &[]).await
+    }
+
+    /// same as [`connect`](Self::connect), requesting the CIP-30
+    /// `extensions` (e.g. `Extension { cip: 95 }`)
+    ///
+    /// The extensions the wallet granted are remembered with it.
+    pub async fn connect_with_extensions(
+        &mut self,
+        name: &str,
+        extensions: &[Extension],
+    ) -> Result<&ConnectedWallet, ConnectorError> {
+        let wallet = wallet(name).ok_or_else(|| ConnectorError::WalletNotFound(name.to_owned()))?;
+        let mut connected = {
+            let _interaction = self.interactions.begin(WalletAction::Enable);
+            self.enable(&wallet, extensions).await?
+        }
+        .with_interactions(self.interactions.clone())
+        .with_settings(Rc::clone(&self.settings));
+        if let Some(audit) = &self.audit {
+            connected = connected.with_audit_log(audit.clone());
+        }
+
+        if let Some(expected) = self.expected_network() {
+            let found = connected.network_id().await?;
+            if u8::from(found) != u8::from(expected) {
+                return Err(ConnectorError::WrongNetwork { expected, found });
+            }
+        }
+
+        let mut preferences = self.preferences();
+        preferences.default_wallet = Some(name.to_owned());
+        preferences.extensions = connected.granted_extensions().to_vec();
+        self.set_preferences(&preferences)?;
+        self.permissions.granted(name, js_sys::Date::now() as u64);
+        Ok(self.wallet.insert(connected))
+    }
+
+    async fn enable(
+        &self,
+        wallet: &Wallet,
+        extensions: &[Extension],
+    ) -> Result<ConnectedWallet, ConnectorError> {
+        let enable = async {
+            if extensions.is_empty() {
+                wallet.enable().await
+            } else {
+                wallet.enable_with_extensions(extensions).await
+            }
+        };
+        let Some(timeout) = self.enable_timeout else {
+            return Ok(enable.await?);
+        };
+        match future::select(pin!(enable), pin!(ffi::timer::sleep(timeout))).await {
+            Either::Left((connected, _)) => Ok(connected?),
+            Either::Right(_) => Err(ConnectorError::Timeout(timeout)),
+        }
+    }
+
+    /// reconnect to the default wallet if the user enabled
+    /// `auto_connect`, see [`Connector::reconnect`]
+    pub async fn connect_default(&mut self) -> Result<Option<&ConnectedWallet>, ConnectorError> {
+        if !self.preferences().auto_connect {
+            return Ok(None);
+        }
+        self.reconnect().await
+    }
+
+    /// silently re-enable the wallet the user connected with last,
+    /// requesting the extensions it granted
+    ///
+    /// The user is never prompted: `None` is returned if there is no
+    /// such wallet, if it is not available anymore (it may be injected
+    /// later) or if it no longer authorises the dApp, `auto_connect` is
+    /// then disabled.
+    pub async fn reconnect(&mut self) -> Result<Option<&ConnectedWallet>, ConnectorError> {
+        let preferences = self.preferences();
+        let Some(name) = preferences.default_wallet.clone() else {
+            return Ok(None);
+        };
+        let Some(wallet) = wallet(&name) else {
+            return Ok(None);
+        };
+        if !wallet.enabled().await? {
+            self.set_preferences(&Preferences {
+                auto_connect: false,
+                ..preferences
+            })?;
+            return Ok(None);
+        }
+
+        self.connect_with_extensions(&name, &preferences.extensions)
+            .await
+            .map(Some)
+    }
+
+    /// connect to another wallet, replacing the current one
+    ///
+    /// The current wallet stays connected if the new one can't be
+    /// enabled. On success a single [`Event::WalletSwitched`] is emitted;
+    /// the subscribers of the [`events`](Self::events) are kept so the
+    /// application keeps listening to the new wallet without
+    /// re-subscribing.
+    pub async fn switch_to(&mut self, name: &str) -> Result<&ConnectedWallet, ConnectorError> {
+        let from = self.wallet.as_ref().map(ConnectedWallet::name);
+        if from.as_deref() == Some(name) {
+            return Ok(self.wallet.as_ref().expect("the wallet is connected"));
+        }
+
+        let previous = self.wallet.take();
+        if let Err(error) = self.connect(name).await {
+            self.wallet = previous;
+            return Err(error);
+        }
+
+        self.events.emit(Event::WalletSwitched {
+            from,
+            to: name.to_owned(),
+        });
+        Ok(self.wallet.as_ref().expect("the wallet was just connected"))
+    }
+
+    /// check the connected wallet still authorises the dApp (`isEnabled()`)
+    ///
+    /// The wallet is dropped if it doesn't, otherwise its permission is
+    /// fresh again. Returns whether a wallet is still connected.
+    pub async fn reverify(&mut self) -> Result<bool, APIError> {
+        let Some(name) = self.wallet.as_ref().map(ConnectedWallet::name) else {
+            return Ok(false);
+        };
+        let enabled = match wallet(&name) {
+            Some(wallet) => wallet.enabled().await?,
+            None => false,
+        };
+
+        if enabled {
+            self.permissions.granted(&name, js_sys::Date::now() as u64);
+        } else {
+            self.wallet = None;
+            self.permissions.revoke();
+        }
+        Ok(enabled)
+    }
+
+    /// read from the connected wallet, or else from the `watch`ed
+    /// address
+    pub fn data_source<P: ChainProvider>(
+        &self,
+        watch: WatchWallet<P>,
+    ) -> DataSource<&ConnectedWallet, P> {
+        DataSource::new(self.wallet.as_ref(), watch)
+    }
+
+    /// a [`TransactionBuilder`] sending the change to the connected
+    /// wallet
+    pub async fn transaction_builder(
+        &self,
+        params: &ProtocolParams,
+    ) -> Result<TransactionBuilder, ConnectorError> {
+        let wallet = self.wallet.as_ref().ok_or(ConnectorError::NotConnected)?;
+        let change_address = wallet.change_address().await?;
+        Ok(TransactionBuilder::new(params.build_params(change_address)))
+    }
+
+    /// an [`EnvelopeVerifier`] of the envelopes signed for `origin`,
+    /// keeping the used nonces in the connector's storage
+    pub fn envelope_verifier(&self, origin: impl Into<String>) -> EnvelopeVerifier<&S> {
+        EnvelopeVerifier::new(origin, &self.storage)
+    }
+
+    /// forget the connected wallet and stop reconnecting automatically
+    pub fn disconnect(&mut self) -> Result<(), StorageError> {
+        self.wallet = None;
+        self.permissions.revoke();
+        let preferences = Preferences {
+            auto_connect: false,
+            ..self.preferences()
+        };
+        self.set_preferences(&preferences)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    #[test]
+    fn preferences_are_persisted() {
+        let storage = MemoryStorage::new();
+        let connector = Connector::new(&storage);
+        assert_eq!(connector.preferences(), Preferences::default());
+
+        let preferences = Preferences {
+            default_wallet: Some("lace".to_owned()),
+            extensions: vec![Extension { cip: 95 }],
+            auto_connect: true,
+            preferred_network: Some(NetworkId::Preview),
+        };
+        connector.set_preferences(&preferences).unwrap();
+
+        let mut reloaded = Connector::new(&storage);
+        assert_eq!(reloaded.preferences(), preferences);
+
+        reloaded.disconnect().unwrap();
+        assert!(!reloaded.preferences().auto_connect);
+        assert_eq!(
+            reloaded.preferences().default_wallet.as_deref(),
+            Some("lace")
+        );
+    }
+
+    #[test]
+    fn builder_configuration() {
+        let storage = MemoryStorage::new();
+        let connector = Connector::new(&storage);
+        assert_eq!(connector.expected_network(), None);
+        assert!(connector.backend().is_none());
+
+        connector
+            .set_preferences(&Preferences {
+                preferred_network: Some(NetworkId::Preview),
+                ..Preferences::default()
+            })
+            .unwrap();
+        let fixed = WalletQuirks {
+            experimental_collateral: false,
+            ..WalletKind::Nami.quirks()
+        };
+        let connector = Connector::builder(&storage)
+            .expect_network(NetworkId::Mainnet)
+            .with_backend(BackendConfig::new("https://koios.rest/api/v1").with_max_retries(5))
+            .with_strictness(Strictness::Paranoid)
+            .with_quirks(WalletKind::Nami, fixed)
+            .build();
+        assert_eq!(connector.expected_network(), Some(NetworkId::Mainnet));
+        assert_eq!(connector.backend().unwrap().config().max_retries, 5);
+        assert_eq!(connector.settings().strictness(), Strictness::Paranoid);
+        assert_eq!(connector.settings().quirks(&WalletKind::Nami), fixed);
+        assert!(connector.settings().quirks(&WalletKind::Lace).cip95);
+        // the crate wide default is left untouched
+        assert_eq!(crate::strictness::strictness(), Strictness::Strict);
+
+        let connector = Connector::new(&storage);
+        assert_eq!(connector.settings().strictness(), Strictness::Strict);
+        assert_eq!(connector.expected_network(), Some(NetworkId::Preview));
+    }
+}
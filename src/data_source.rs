@@ -0,0 +1,152 @@
+/*!
+Reads answered by the wallet when connected, by a backend otherwise.
+
+Before the user connects a wallet the dApp may still show a portfolio,
+from an address the user pasted (see [`WatchWallet`]). [`DataSource`]
+answers the same queries either way, so the rendering code does not
+branch on the connection state.
+
+```no_run
+# use cardano_connector::{ConnectedWallet, backend::ChainProvider, data_source::DataSource, watch::WatchWallet};
+# async fn test(provider: impl ChainProvider, pasted: &str, wallet: Option<ConnectedWallet>) -> anyhow::Result<()> {
+let watched = WatchWallet::parse(provider, pasted)?;
+let source = DataSource::new(wallet, watched);
+let balance = source.balance().await?;
+# Ok(()) }
+```
+*/
+
+use crate::{
+    Address, AssetId, Coin, UtxoSet, Value,
+    backend::{BackendError, ChainProvider},
+    cardano::{assets_of, lovelace_of},
+    error::APIError,
+    wallet_api::WalletApi,
+    watch::WatchWallet,
+};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DataSourceError {
+    #[error(transparent)]
+    Wallet(#[from] APIError),
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+}
+
+/// see the [module documentation](self)
+pub enum DataSource<W, P> {
+    /// the connected wallet
+    Wallet(W),
+    /// a watched address, until a wallet is connected
+    Watch(WatchWallet<P>),
+}
+
+impl<W: WalletApi, P: ChainProvider> DataSource<W, P> {
+    /// the wallet if connected, the watched address otherwise
+    pub fn new(wallet: Option<W>, watch: WatchWallet<P>) -> Self {
+        match wallet {
+            Some(wallet) => Self::Wallet(wallet),
+            None => Self::Watch(watch),
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        matches!(self, Self::Wallet(_))
+    }
+
+    pub async fn balance(&self) -> Result<Value, DataSourceError> {
+        match self {
+            Self::Wallet(wallet) => Ok(wallet.balance().await?),
+            Self::Watch(watch) => Ok(watch.balance().await?),
+        }
+    }
+
+    pub async fn lovelace(&self) -> Result<Coin, DataSourceError> {
+        Ok(lovelace_of(&self.balance().await?))
+    }
+
+    pub async fn assets(&self) -> Result<BTreeMap<AssetId, u64>, DataSourceError> {
+        Ok(assets_of(&self.balance().await?))
+    }
+
+    pub async fn utxos(&self) -> Result<UtxoSet, DataSourceError> {
+        match self {
+            Self::Wallet(wallet) => Ok(UtxoSet::from(wallet.utxos(None).await?)),
+            Self::Watch(watch) => Ok(watch.utxos().await?),
+        }
+    }
+
+    /// the used addresses of the wallet, or the watched address
+    pub async fn addresses(&self) -> Result<Vec<Address>, DataSourceError> {
+        match self {
+            Self::Wallet(wallet) => Ok(wallet.used_addresses(None).await?),
+            Self::Watch(watch) => Ok(vec![watch.address().clone()]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ConnectedWallet, Hash, Utxo,
+        cardano::{TransactionInput, TransactionOutput},
+    };
+    use futures::executor::block_on;
+    use pallas_primitives::babbage::PseudoPostAlonzoTransactionOutput;
+
+    struct Provider;
+
+    impl ChainProvider for Provider {
+        async fn resolve_input(
+            &self,
+            _input: &TransactionInput,
+        ) -> Result<Option<Utxo>, BackendError> {
+            Ok(None)
+        }
+
+        async fn address_utxos(&self, _address: &Address) -> Result<UtxoSet, BackendError> {
+            Ok(UtxoSet::from(vec![Utxo {
+                input: TransactionInput {
+                    transaction_id: Hash::from([1; 32]),
+                    index: 0,
+                },
+                output: TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+                    address: vec![0x61; 29].into(),
+                    value: Value::Coin(4_000_000),
+                    datum_option: None,
+                    script_ref: None,
+                }),
+            }]))
+        }
+    }
+
+    #[test]
+    fn watched_until_connected() {
+        let address = Address::from_bytes(&[0x61; 29]).unwrap();
+        let source =
+            DataSource::<ConnectedWallet, _>::new(None, WatchWallet::new(Provider, address));
+
+        assert!(!source.is_connected());
+        assert_eq!(block_on(source.lovelace()).unwrap(), 4_000_000);
+        assert_eq!(block_on(source.utxos()).unwrap().len(), 1);
+        assert_eq!(block_on(source.addresses()).unwrap().len(), 1);
+    }
+
+    #[cfg(feature = "mock")]
+    #[test]
+    fn answered_by_the_wallet() {
+        use crate::{NetworkId, mock::MockWallet};
+
+        let mock = MockWallet::new("mock", [7; 32], NetworkId::Preview);
+        mock.add_utxo(Value::Coin(10_000_000));
+        let connected = block_on(mock.enable()).unwrap();
+        let address = Address::from_bytes(&[0x61; 29]).unwrap();
+        let source = DataSource::new(Some(connected), WatchWallet::new(Provider, address));
+
+        assert!(source.is_connected());
+        assert_eq!(block_on(source.lovelace()).unwrap(), 10_000_000);
+        assert_eq!(block_on(source.addresses()).unwrap(), vec![mock.address()]);
+    }
+}
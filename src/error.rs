@@ -1,3 +1,9 @@
+use crate::{
+    TxHash,
+    cardano::{AddrKeyhash, BuildError, PolicyId},
+};
+use std::collections::BTreeSet;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, thiserror::Error)]
 pub enum APIErrorCode {
     #[error("Invalid inputs.")]
@@ -14,7 +20,16 @@ pub enum APIErrorCode {
 }
 
 #[derive(
-    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, thiserror::Error, serde::Deserialize,
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    thiserror::Error,
+    serde::Deserialize,
+    serde::Serialize,
 )]
 #[error("{code}. {info}.")]
 pub struct APIError {
@@ -36,15 +51,337 @@ pub enum DataSignErrorCode {
     Unknown(u64),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, thiserror::Error)]
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, thiserror::Error, serde::Serialize,
+)]
 #[error("{code}. {info}.")]
 pub struct DataSignError {
     pub code: DataSignErrorCode,
     pub info: String,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, thiserror::Error)]
+pub enum TxSendErrorCode {
+    #[error("Wallet refuses to send the tx (could be rate limiting)")]
+    Refused,
+    #[error("Wallet could not send the tx")]
+    Failure,
+    #[error("Unknown error code `{0}'")]
+    Unknown(u64),
+}
+
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    thiserror::Error,
+    serde::Deserialize,
+    serde::Serialize,
+)]
+#[error("{code}. {info}.")]
+pub struct TxSendError {
+    pub code: TxSendErrorCode,
+    pub info: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, thiserror::Error)]
+pub enum TxSignErrorCode {
+    #[error(
+        "User has accepted the transaction sign, but the wallet was unable to sign the transaction (e.g. not having some of the private keys)"
+    )]
+    ProofGeneration,
+    #[error("User declined to sign the transaction")]
+    UserDeclined,
+    #[error("Unknown error code `{0}'")]
+    Unknown(u64),
+}
+
 #[derive(
-    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, thiserror::Error, serde::Deserialize,
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    thiserror::Error,
+    serde::Deserialize,
+    serde::Serialize,
+)]
+#[error("{code}. {info}.")]
+pub struct TxSignError {
+    pub code: TxSignErrorCode,
+    pub info: String,
+}
+
+/// Failure to sign a transaction with the wallet.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SignTxError {
+    #[error(transparent)]
+    Api(#[from] APIError),
+    #[error(transparent)]
+    Sign(#[from] TxSignError),
+}
+
+impl SignTxError {
+    /// CIP-30 `signTx` rejects either with an `APIError` (negative
+    /// codes) or a `TxSignError` (positive codes)
+    pub fn from_code(code: i64, info: String) -> Self {
+        if code < 0 {
+            Self::Api(APIError {
+                code: APIErrorCode::from_code(code),
+                info,
+            })
+        } else {
+            Self::Sign(TxSignError {
+                code: TxSignErrorCode::from_code(code as u64),
+                info,
+            })
+        }
+    }
+
+    /// the user rejected the request, as opposed to the wallet failing
+    /// to sign
+    pub fn is_user_declined(&self) -> bool {
+        matches!(
+            self,
+            Self::Sign(TxSignError {
+                code: TxSignErrorCode::UserDeclined,
+                ..
+            })
+        )
+    }
+}
+
+/// Failure to submit a transaction through the wallet.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SubmitTxError {
+    #[error(transparent)]
+    Api(#[from] APIError),
+    #[error(transparent)]
+    Send(#[from] TxSendError),
+    /// the wallet reported another transaction id than the one of the
+    /// submitted transaction
+    #[error("The wallet returned the transaction id {returned}, expected {expected}")]
+    HashMismatch { expected: TxHash, returned: TxHash },
+}
+
+impl SubmitTxError {
+    /// CIP-30 `submitTx` rejects either with an `APIError` (negative
+    /// codes) or a `TxSendError` (positive codes)
+    pub fn from_code(code: i64, info: String) -> Self {
+        if code < 0 {
+            Self::Api(APIError {
+                code: APIErrorCode::from_code(code),
+                info,
+            })
+        } else {
+            Self::Send(TxSendError {
+                code: TxSendErrorCode::from_code(code as u64),
+                info,
+            })
+        }
+    }
+}
+
+/// Failure of [`ConnectedWallet::send`].
+///
+/// [`ConnectedWallet::send`]: crate::ConnectedWallet::send
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SendError {
+    #[error(transparent)]
+    Api(#[from] APIError),
+    #[error(transparent)]
+    Build(#[from] BuildError),
+    #[error(transparent)]
+    Sign(#[from] SignTxError),
+    #[error(transparent)]
+    Submit(#[from] SubmitTxError),
+}
+
+/// Failure of [`ConnectedWallet::burn_all`].
+///
+/// [`ConnectedWallet::burn_all`]: crate::ConnectedWallet::burn_all
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BurnError {
+    #[error(transparent)]
+    Api(#[from] APIError),
+    #[error("The wallet holds no asset of the policy {0}")]
+    NothingToBurn(PolicyId),
+    #[error(transparent)]
+    Build(#[from] BuildError),
+}
+
+/// Failure of [`SignedData::verify_address`].
+///
+/// [`SignedData::verify_address`]: crate::SignedData::verify_address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum VerifyDataError {
+    #[error("The signature is not valid")]
+    InvalidSignature,
+    #[error("The data was signed for another address")]
+    AddressMismatch,
+    #[error("The address is not controlled by a key")]
+    NoKeyCredential,
+    #[error("The signing key doesn't match the credential of the address")]
+    KeyMismatch,
+}
+
+/// The vkey witnesses of a transaction that would make it rejected, see
+/// [`verify_witnesses`](crate::cardano::verify_witnesses).
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "{} invalid and {} missing vkey witness(es)",
+    self.invalid.len(),
+    self.missing.len()
+)]
+pub struct VerifyError {
+    /// the keys whose signature is not for the transaction's body
+    pub invalid: Vec<AddrKeyhash>,
+    /// the required signers without a vkey witness
+    pub missing: BTreeSet<AddrKeyhash>,
+}
+
+/// The class of a wallet error, whatever the CIP-30 call that failed.
+///
+/// Unlike the `info` of the errors (free text chosen by the wallet), the
+/// kinds are stable: see [`ErrorKind::key`] and the
+/// [`error_keys`](crate::error_keys) table to show the users messages of
+/// the dApp's own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ErrorKind {
+    InvalidRequest,
+    InternalError,
+    Refused,
+    AccountChange,
+    /// the wallet could not sign (e.g. a key it does not have)
+    ProofGeneration,
+    AddressNotPK,
+    UserDeclined,
+    /// the wallet refused to send the transaction
+    SendRefused,
+    SendFailure,
+    Pagination,
+    Unknown,
+}
+
+impl ErrorKind {
+    /// a short, stable identifier of the kind, e.g. `user_declined`
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::InvalidRequest => "invalid_request",
+            Self::InternalError => "internal_error",
+            Self::Refused => "refused",
+            Self::AccountChange => "account_change",
+            Self::ProofGeneration => "proof_generation",
+            Self::AddressNotPK => "address_not_pk",
+            Self::UserDeclined => "user_declined",
+            Self::SendRefused => "send_refused",
+            Self::SendFailure => "send_failure",
+            Self::Pagination => "pagination",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.key())
+    }
+}
+
+impl APIError {
+    pub fn kind(&self) -> ErrorKind {
+        match self.code {
+            APIErrorCode::InvalidRequest => ErrorKind::InvalidRequest,
+            APIErrorCode::InternalError => ErrorKind::InternalError,
+            APIErrorCode::Refused => ErrorKind::Refused,
+            APIErrorCode::AccountChange => ErrorKind::AccountChange,
+            APIErrorCode::Unknown(_) => ErrorKind::Unknown,
+        }
+    }
+}
+
+impl DataSignError {
+    pub fn kind(&self) -> ErrorKind {
+        match self.code {
+            DataSignErrorCode::ProofGeneration => ErrorKind::ProofGeneration,
+            DataSignErrorCode::AddressNotPK => ErrorKind::AddressNotPK,
+            DataSignErrorCode::UserDeclined => ErrorKind::UserDeclined,
+            DataSignErrorCode::Unknown(_) => ErrorKind::Unknown,
+        }
+    }
+}
+
+impl TxSignError {
+    pub fn kind(&self) -> ErrorKind {
+        match self.code {
+            TxSignErrorCode::ProofGeneration => ErrorKind::ProofGeneration,
+            TxSignErrorCode::UserDeclined => ErrorKind::UserDeclined,
+            TxSignErrorCode::Unknown(_) => ErrorKind::Unknown,
+        }
+    }
+}
+
+impl TxSendError {
+    pub fn kind(&self) -> ErrorKind {
+        match self.code {
+            TxSendErrorCode::Refused => ErrorKind::SendRefused,
+            TxSendErrorCode::Failure => ErrorKind::SendFailure,
+            TxSendErrorCode::Unknown(_) => ErrorKind::Unknown,
+        }
+    }
+}
+
+impl PaginateError {
+    pub fn kind(&self) -> ErrorKind {
+        ErrorKind::Pagination
+    }
+}
+
+impl SignTxError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Api(error) => error.kind(),
+            Self::Sign(error) => error.kind(),
+        }
+    }
+}
+
+impl SubmitTxError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Api(error) => error.kind(),
+            Self::Send(error) => error.kind(),
+            Self::HashMismatch { .. } => ErrorKind::InternalError,
+        }
+    }
+}
+
+/// the `{ code, info }` object wallets reject with, before knowing which
+/// of the CIP-30 error types it is
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct RawError {
+    pub code: i64,
+    pub info: String,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    thiserror::Error,
+    serde::Deserialize,
+    serde::Serialize,
 )]
 #[serde(rename_all = "camelCase")]
 #[error("Pagination error")]
@@ -52,6 +389,133 @@ pub struct PaginateError {
     pub max_size: usize,
 }
 
+impl APIErrorCode {
+    pub fn from_code(code: i64) -> Self {
+        match code {
+            -1 => Self::InvalidRequest,
+            -2 => Self::InternalError,
+            -3 => Self::Refused,
+            -4 => Self::AccountChange,
+            unknown => Self::Unknown(unknown),
+        }
+    }
+
+    /// the numerical code as defined by CIP-30
+    pub fn code(&self) -> i64 {
+        match self {
+            Self::InvalidRequest => -1,
+            Self::InternalError => -2,
+            Self::Refused => -3,
+            Self::AccountChange => -4,
+            Self::Unknown(code) => *code,
+        }
+    }
+}
+
+impl DataSignErrorCode {
+    /// the numerical code as defined by CIP-30
+    pub fn code(&self) -> u64 {
+        match self {
+            Self::ProofGeneration => 1,
+            Self::AddressNotPK => 2,
+            Self::UserDeclined => 3,
+            Self::Unknown(code) => *code,
+        }
+    }
+}
+
+impl TxSignErrorCode {
+    pub fn from_code(code: u64) -> Self {
+        match code {
+            1 => Self::ProofGeneration,
+            2 => Self::UserDeclined,
+            unknown => Self::Unknown(unknown),
+        }
+    }
+
+    /// the numerical code as defined by CIP-30
+    pub fn code(&self) -> u64 {
+        match self {
+            Self::ProofGeneration => 1,
+            Self::UserDeclined => 2,
+            Self::Unknown(code) => *code,
+        }
+    }
+}
+
+impl serde::Serialize for TxSignErrorCode {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u64(self.code())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for TxSignErrorCode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        u64::deserialize(deserializer).map(Self::from_code)
+    }
+}
+
+impl TxSendErrorCode {
+    pub fn from_code(code: u64) -> Self {
+        match code {
+            1 => Self::Refused,
+            2 => Self::Failure,
+            unknown => Self::Unknown(unknown),
+        }
+    }
+
+    /// the numerical code as defined by CIP-30
+    pub fn code(&self) -> u64 {
+        match self {
+            Self::Refused => 1,
+            Self::Failure => 2,
+            Self::Unknown(code) => *code,
+        }
+    }
+}
+
+impl serde::Serialize for TxSendErrorCode {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u64(self.code())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for TxSendErrorCode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        u64::deserialize(deserializer).map(Self::from_code)
+    }
+}
+
+impl serde::Serialize for APIErrorCode {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_i64(self.code())
+    }
+}
+
+impl serde::Serialize for DataSignErrorCode {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u64(self.code())
+    }
+}
+
 impl<'de> serde::Deserialize<'de> for APIErrorCode {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -69,13 +533,7 @@ impl<'de> serde::Deserialize<'de> for APIErrorCode {
             where
                 E: serde::de::Error,
             {
-                match v {
-                    -1 => Ok(APIErrorCode::InvalidRequest),
-                    -2 => Ok(APIErrorCode::InternalError),
-                    -3 => Ok(APIErrorCode::Refused),
-                    -4 => Ok(APIErrorCode::AccountChange),
-                    unknown => Ok(APIErrorCode::Unknown(unknown)),
-                }
+                Ok(APIErrorCode::from_code(v))
             }
         }
 
@@ -113,6 +571,49 @@ impl<'de> serde::Deserialize<'de> for DataSignErrorCode {
     }
 }
 
+/// convert the error into a CIP-30 shaped `{ code, info }` JS object
+/// so it can be thrown back to JS callers as they would expect it from
+/// a wallet.
+#[cfg(feature = "js-api")]
+fn to_js_value<T: serde::Serialize>(error: &T) -> wasm_bindgen::JsValue {
+    serde_wasm_bindgen::to_value(error).unwrap_or_else(wasm_bindgen::JsValue::from)
+}
+
+#[cfg(feature = "js-api")]
+impl From<APIError> for wasm_bindgen::JsValue {
+    fn from(error: APIError) -> Self {
+        to_js_value(&error)
+    }
+}
+
+#[cfg(feature = "js-api")]
+impl From<DataSignError> for wasm_bindgen::JsValue {
+    fn from(error: DataSignError) -> Self {
+        to_js_value(&error)
+    }
+}
+
+#[cfg(feature = "js-api")]
+impl From<TxSignError> for wasm_bindgen::JsValue {
+    fn from(error: TxSignError) -> Self {
+        to_js_value(&error)
+    }
+}
+
+#[cfg(feature = "js-api")]
+impl From<TxSendError> for wasm_bindgen::JsValue {
+    fn from(error: TxSendError) -> Self {
+        to_js_value(&error)
+    }
+}
+
+#[cfg(feature = "js-api")]
+impl From<PaginateError> for wasm_bindgen::JsValue {
+    fn from(error: PaginateError) -> Self {
+        to_js_value(&error)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::json;
@@ -213,4 +714,95 @@ mod tests {
             DataSignErrorCode::Unknown(42)
         );
     }
+
+    #[test]
+    fn submit_tx_error_codes() {
+        assert_eq!(
+            SubmitTxError::from_code(-3, "Access Denied.".to_owned()),
+            SubmitTxError::Api(APIError {
+                code: APIErrorCode::Refused,
+                info: "Access Denied.".to_owned()
+            })
+        );
+        assert_eq!(
+            SubmitTxError::from_code(2, "Bad signature.".to_owned()),
+            SubmitTxError::Send(TxSendError {
+                code: TxSendErrorCode::Failure,
+                info: "Bad signature.".to_owned()
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<TxSendErrorCode>(json! { 1 }).unwrap(),
+            TxSendErrorCode::Refused
+        );
+    }
+
+    #[test]
+    fn sign_tx_error_codes() {
+        let declined = SignTxError::from_code(2, "Declined.".to_owned());
+        assert_eq!(
+            declined,
+            SignTxError::Sign(TxSignError {
+                code: TxSignErrorCode::UserDeclined,
+                info: "Declined.".to_owned()
+            })
+        );
+        assert!(declined.is_user_declined());
+
+        let proof = SignTxError::from_code(1, "Missing key.".to_owned());
+        assert!(!proof.is_user_declined());
+        assert_eq!(
+            SignTxError::from_code(-2, "Oops.".to_owned()),
+            SignTxError::Api(APIError {
+                code: APIErrorCode::InternalError,
+                info: "Oops.".to_owned()
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<TxSignErrorCode>(json! { 1 }).unwrap(),
+            TxSignErrorCode::ProofGeneration
+        );
+    }
+
+    #[test]
+    fn api_error_to_json() {
+        assert_eq!(
+            serde_json::to_value(APIError {
+                code: APIErrorCode::Refused,
+                info: "Access Denied.".to_owned()
+            })
+            .unwrap(),
+            json! { {
+                "code": -3,
+                "info": "Access Denied.",
+            }}
+        );
+        assert_eq!(
+            serde_json::to_value(APIErrorCode::Unknown(-42)).unwrap(),
+            json! { -42 }
+        );
+    }
+
+    #[test]
+    fn sign_data_error_to_json() {
+        assert_eq!(
+            serde_json::to_value(DataSignError {
+                code: DataSignErrorCode::UserDeclined,
+                info: "User declined.".to_owned()
+            })
+            .unwrap(),
+            json! { {
+                "code": 3,
+                "info": "User declined.",
+            }}
+        );
+    }
+
+    #[test]
+    fn paginate_error_to_json() {
+        assert_eq!(
+            serde_json::to_value(PaginateError { max_size: 42 }).unwrap(),
+            json! { { "maxSize": 42 } }
+        );
+    }
 }
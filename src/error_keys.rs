@@ -0,0 +1,100 @@
+/*!
+Stable identifiers of the wallet errors, for the dApp's translations.
+
+The `info` of the CIP-30 errors is free text written by the wallet, in
+English or not at all: it is not fit to be shown to the users. The
+[`ErrorKeys`] table gives instead a short identifier per [`ErrorKind`]
+(e.g. `user_declined`) the dApp looks up in its own i18n catalog.
+
+The wallets do not all report the same situation the same way, e.g. one
+rejects a declined signature with [`ErrorKind::Refused`]: the table can
+give another key for a given wallet.
+
+```
+# use cardano_connector::{error::{APIError, APIErrorCode, ErrorKind}, error_keys::ErrorKeys};
+let keys = ErrorKeys::new()
+    .with_prefix("wallet.error.")
+    .with_wallet_key("typhon", ErrorKind::Refused, "user_declined");
+
+let error = APIError { code: APIErrorCode::Refused, info: "Denied".to_owned() };
+assert_eq!(keys.key("eternl", error.kind()), "wallet.error.refused");
+assert_eq!(keys.key("typhon", error.kind()), "wallet.error.user_declined");
+```
+*/
+
+use crate::error::ErrorKind;
+use std::collections::BTreeMap;
+
+/// see the [module documentation](self)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorKeys {
+    prefix: String,
+    keys: BTreeMap<ErrorKind, String>,
+    wallet_keys: BTreeMap<(String, ErrorKind), String>,
+}
+
+impl ErrorKeys {
+    /// the keys of [`ErrorKind::key`], without prefix
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// prepended to every key, e.g. the namespace of the catalog
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// the key of `kind`, for every wallet
+    pub fn with_key(mut self, kind: ErrorKind, key: impl Into<String>) -> Self {
+        self.keys.insert(kind, key.into());
+        self
+    }
+
+    /// the key of `kind` for the wallet of the given name, over the one
+    /// for every wallet
+    pub fn with_wallet_key(
+        mut self,
+        wallet: impl Into<String>,
+        kind: ErrorKind,
+        key: impl Into<String>,
+    ) -> Self {
+        self.wallet_keys.insert((wallet.into(), kind), key.into());
+        self
+    }
+
+    /// the key of an error of `kind` reported by `wallet`
+    pub fn key(&self, wallet: &str, kind: ErrorKind) -> String {
+        let key = self
+            .wallet_keys
+            .get(&(wallet.to_owned(), kind))
+            .or_else(|| self.keys.get(&kind))
+            .map_or(kind.key(), String::as_str);
+        format!("{}{key}", self.prefix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{SignTxError, TxSendError, TxSendErrorCode};
+
+    #[test]
+    fn keys_by_kind_and_wallet() {
+        let keys = ErrorKeys::new()
+            .with_key(ErrorKind::SendFailure, "submit_failed")
+            .with_wallet_key("nami", ErrorKind::InternalError, "user_declined");
+
+        let declined = SignTxError::from_code(2, "Declined".to_owned());
+        assert_eq!(keys.key("lace", declined.kind()), "user_declined");
+        let internal = SignTxError::from_code(-2, "Oops".to_owned());
+        assert_eq!(keys.key("lace", internal.kind()), "internal_error");
+        assert_eq!(keys.key("nami", internal.kind()), "user_declined");
+
+        let failure = TxSendError {
+            code: TxSendErrorCode::Failure,
+            info: "Bad".to_owned(),
+        };
+        assert_eq!(keys.key("lace", failure.kind()), "submit_failed");
+    }
+}
@@ -0,0 +1,172 @@
+/*!
+Notifications emitted by the connector.
+
+Subscribe to an [`EventBus`] to be notified of what happens in the
+background (e.g. a queued transaction eventually being submitted).
+
+With the `js-api` feature, the host page can push its own events (e.g.
+from an existing account change detection) with the
+`JsEventBridge` of the bus:
+
+```js
+// `bridge` given to the page by the dApp, see `EventBus::js_bridge`
+bridge.push("accountChange", { addresses: ["addr1..."] });
+```
+
+They are received as [`Event::Custom`].
+*/
+
+use crate::{
+    Address, TxHash,
+    backend::Quota,
+    interaction::{InteractionId, WalletAction},
+};
+use std::{cell::RefCell, rc::Rc};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Event {
+    /// the transaction couldn't be submitted right away and was queued
+    TxQueued { hash: TxHash },
+    /// the transaction has been accepted by the wallet or the backend
+    TxSubmitted { hash: TxHash },
+    /// the transaction's TTL passed before it could be submitted
+    TxExpired { hash: TxHash },
+    /// the queued transaction was refused when retried
+    TxRejected { hash: TxHash, reason: String },
+    /// the dApp is now connected to another wallet, see
+    /// [`Connector::switch_to`](crate::Connector::switch_to)
+    WalletSwitched { from: Option<String>, to: String },
+    /// the wallet is waiting for the user, see [`Interactions`]
+    ///
+    /// [`Interactions`]: crate::interaction::Interactions
+    InteractionStarted {
+        id: InteractionId,
+        action: WalletAction,
+    },
+    /// the user answered (or the action was abandoned)
+    InteractionEnded {
+        id: InteractionId,
+        action: WalletAction,
+    },
+    /// few requests remain in the quota of the backend, see
+    /// [`HttpClient`](crate::backend::HttpClient)
+    QuotaLow { base_url: String, quota: Quota },
+    /// a new transaction involves a watched address, see
+    /// [`PushWatcher`](crate::backend::PushWatcher)
+    AddressTransaction { address: Address, hash: TxHash },
+    /// a watched transaction is on chain
+    TxOnChain { hash: TxHash },
+    /// the wallet was enabled long ago, the dApp should check it is
+    /// still enabled, see [`PermissionTracker`]
+    ///
+    /// [`PermissionTracker`]: crate::permission::PermissionTracker
+    PermissionStale { wallet: String, granted_at: u64 },
+    /// an event pushed by the application, e.g. from JS with the
+    /// `JsEventBridge` (`js-api` feature)
+    Custom {
+        name: String,
+        payload: serde_json::Value,
+    },
+}
+
+/// identifies a subscription, to [`EventBus::unsubscribe`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SubscriptionId(u64);
+
+type Callback = Rc<dyn Fn(&Event)>;
+
+/// Dispatch [`Event`]s to the subscribers.
+///
+/// Cloning the bus gives another handle to the same subscribers.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    inner: Rc<RefCell<Subscribers>>,
+}
+
+#[derive(Default)]
+struct Subscribers {
+    next_id: u64,
+    callbacks: Vec<(SubscriptionId, Callback)>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe(&self, callback: impl Fn(&Event) + 'static) -> SubscriptionId {
+        let mut inner = self.inner.borrow_mut();
+        let id = SubscriptionId(inner.next_id);
+        inner.next_id += 1;
+        inner.callbacks.push((id, Rc::new(callback)));
+        id
+    }
+
+    pub fn unsubscribe(&self, id: SubscriptionId) {
+        self.inner
+            .borrow_mut()
+            .callbacks
+            .retain(|(subscription, _)| *subscription != id);
+    }
+
+    pub fn emit(&self, event: Event) {
+        // release the borrow before calling the callbacks so they can
+        // (un)subscribe
+        let callbacks: Vec<_> = self
+            .inner
+            .borrow()
+            .callbacks
+            .iter()
+            .map(|(_, callback)| Rc::clone(callback))
+            .collect();
+
+        for callback in callbacks {
+            callback(&event);
+        }
+    }
+}
+
+/// Handle given to the JS code to push [`Event::Custom`] on an
+/// [`EventBus`].
+#[cfg(feature = "js-api")]
+#[wasm_bindgen::prelude::wasm_bindgen]
+pub struct JsEventBridge {
+    bus: EventBus,
+}
+
+#[cfg(feature = "js-api")]
+#[wasm_bindgen::prelude::wasm_bindgen]
+impl JsEventBridge {
+    /// emit an [`Event::Custom`], the payload must be representable as
+    /// JSON (`undefined` is `null`)
+    pub fn push(
+        &self,
+        name: String,
+        payload: wasm_bindgen::JsValue,
+    ) -> Result<(), wasm_bindgen::JsValue> {
+        let payload = if payload.is_undefined() {
+            serde_json::Value::Null
+        } else {
+            serde_wasm_bindgen::from_value(payload)?
+        };
+        self.bus.emit(Event::Custom { name, payload });
+        Ok(())
+    }
+}
+
+#[cfg(feature = "js-api")]
+impl EventBus {
+    /// a handle for the JS code to push events on this bus
+    pub fn js_bridge(&self) -> JsEventBridge {
+        JsEventBridge { bus: self.clone() }
+    }
+}
+
+impl std::fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventBus")
+            .field("subscribers", &self.inner.borrow().callbacks.len())
+            .finish()
+    }
+}
@@ -0,0 +1,85 @@
+//! CIP-103 bulk transaction signing (`api.cip103.signTxs`)
+//!
+//! Signs several transactions with a single prompt to the user. See
+//! [`ConnectedWallet::sign_txs`].
+//!
+//! [`ConnectedWallet::sign_txs`]: crate::ConnectedWallet::sign_txs
+
+use crate::{
+    cardano::WitnessSet,
+    error::{APIError, APIErrorCode, RawError, SignTxError},
+};
+
+crate::define_extension_api! {
+    /// The CIP-103 namespace of the connected wallet.
+    pub struct Cip103Api: 103 {
+        /// sign all the transactions, one witness set (or error) per
+        /// transaction in the same order
+        fn sign_txs(requests: Vec<SignTxRequest>) -> Vec<serde_json::Value> = "signTxs";
+    }
+}
+
+/// One transaction of a `signTxs` request.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignTxRequest {
+    /// hex encoded CBOR of the transaction
+    pub cbor: String,
+    pub partial_sign: bool,
+}
+
+/// decode the entry of the `signTxs` response for one transaction
+///
+/// Wallets report the transactions they could not sign with an error
+/// object (or `null`) in place of the witness set.
+pub(crate) fn decode_entry(entry: serde_json::Value) -> Result<WitnessSet, SignTxError> {
+    match entry {
+        serde_json::Value::String(hex) => Ok(crate::connected_wallet::decode_witness_set(&hex)?),
+        serde_json::Value::Null => Err(SignTxError::Api(APIError {
+            code: APIErrorCode::InternalError,
+            info: "The wallet did not sign the transaction".to_owned(),
+        })),
+        error => match serde_json::from_value::<RawError>(error.clone()) {
+            Ok(RawError { code, info }) => Err(SignTxError::from_code(code, info)),
+            Err(decode_error) => Err(SignTxError::Api(APIError {
+                code: APIErrorCode::InternalError,
+                info: format!("Couldn't decode the error content: {decode_error} ({error})"),
+            })),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pallas_codec::minicbor;
+
+    #[test]
+    fn decode_entries() {
+        let witness_set = WitnessSet {
+            vkeywitness: None,
+            native_script: None,
+            bootstrap_witness: None,
+            plutus_v1_script: None,
+            plutus_data: None,
+            redeemer: None,
+            plutus_v2_script: None,
+            plutus_v3_script: None,
+        };
+        let hex = hex::encode(minicbor::to_vec(&witness_set).unwrap());
+
+        let signed = decode_entry(serde_json::Value::String(hex)).unwrap();
+        let declined = decode_entry(serde_json::json!({ "code": -3, "info": "declined" }));
+        let missing = decode_entry(serde_json::Value::Null);
+
+        assert_eq!(signed, witness_set);
+        assert_eq!(
+            declined,
+            Err(SignTxError::Api(APIError {
+                code: APIErrorCode::Refused,
+                info: "declined".to_owned()
+            }))
+        );
+        assert!(missing.is_err());
+    }
+}
@@ -0,0 +1,181 @@
+//! CIP-106 multisig wallets (`api.cip106`)
+//!
+//! A multisig wallet controls funds locked by a native script. The dApp
+//! builds the transaction against the [`script`] and its
+//! [`script_requirements`], then hands the unsigned transaction to the
+//! wallet which collects the signatures of the co-signers. See
+//! [`ConnectedWallet::multisig`].
+//!
+//! [`script`]: MultisigWallet::script
+//! [`script_requirements`]: MultisigWallet::script_requirements
+//! [`ConnectedWallet::multisig`]: crate::ConnectedWallet::multisig
+
+use crate::{
+    cardano::{AddrKeyhash, HashExt as _, Tx, TxHash, WitnessSet},
+    error::{APIError, APIErrorCode},
+};
+use pallas_codec::minicbor;
+use pallas_primitives::conway::NativeScript;
+
+crate::define_extension_api! {
+    /// The raw CIP-106 namespace of the connected wallet, see
+    /// [`MultisigWallet`] for the typed interface.
+    pub struct Cip106Api: 106 {
+        fn script() -> String = "getScript";
+        fn script_requirements() -> Vec<RawScriptRequirement> = "getScriptRequirements";
+        fn submit_unsigned_tx(tx: String) -> String = "submitUnsignedTx";
+        fn completed_tx(tx_hash: String) -> (String, String) = "getCompletedTx";
+    }
+}
+
+/// A requirement of the wallet's script as returned by
+/// `getScriptRequirements`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+pub struct RawScriptRequirement {
+    pub code: u8,
+    pub value: serde_json::Value,
+}
+
+/// What the transaction must include to satisfy the wallet's script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScriptRequirement {
+    /// the key must be in the transaction's `required_signers`
+    Signer(AddrKeyhash),
+    /// the transaction's TTL must be set before this slot
+    Before(u64),
+    /// the transaction's validity start must be set after this slot
+    After(u64),
+}
+
+impl TryFrom<RawScriptRequirement> for ScriptRequirement {
+    type Error = APIError;
+
+    fn try_from(raw: RawScriptRequirement) -> Result<Self, Self::Error> {
+        let invalid = || APIError {
+            code: APIErrorCode::InternalError,
+            info: format!("Invalid script requirement {}: {}", raw.code, raw.value),
+        };
+        let slot = || match &raw.value {
+            serde_json::Value::Number(slot) => slot.as_u64(),
+            serde_json::Value::String(slot) => slot.parse().ok(),
+            _ => None,
+        };
+
+        match raw.code {
+            1 => raw
+                .value
+                .as_str()
+                .and_then(|hash| AddrKeyhash::from_hex(hash).ok())
+                .map(Self::Signer)
+                .ok_or_else(invalid),
+            2 => slot().map(Self::Before).ok_or_else(invalid),
+            3 => slot().map(Self::After).ok_or_else(invalid),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// The typed interface of a CIP-106 multisig wallet.
+#[derive(Clone)]
+pub struct MultisigWallet {
+    api: Cip106Api,
+}
+
+impl MultisigWallet {
+    pub(crate) fn new(api: Cip106Api) -> Self {
+        Self { api }
+    }
+
+    /// the native script controlling the funds of the wallet
+    pub async fn script(&self) -> Result<NativeScript, APIError> {
+        decode_cbor(&self.api.script().await?, "native script")
+    }
+
+    /// what the transactions spending the wallet's funds must include
+    pub async fn script_requirements(&self) -> Result<Vec<ScriptRequirement>, APIError> {
+        self.api
+            .script_requirements()
+            .await?
+            .into_iter()
+            .map(ScriptRequirement::try_from)
+            .collect()
+    }
+
+    /// hand the unsigned transaction to the wallet so it collects the
+    /// signatures of the co-signers, returns the transaction's hash to
+    /// query the [`completed_tx`](Self::completed_tx) later
+    pub async fn submit_unsigned_tx(&self, tx: &Tx) -> Result<TxHash, APIError> {
+        let tx = hex::encode(minicbor::to_vec(tx).unwrap());
+        let hash = self.api.submit_unsigned_tx(tx).await?;
+        TxHash::from_hex(&hash).map_err(|error| APIError {
+            code: APIErrorCode::InternalError,
+            info: format!("Invalid transaction hash `{hash}': {error}"),
+        })
+    }
+
+    /// the transaction once all the co-signers signed it, with the
+    /// collected witnesses
+    pub async fn completed_tx(&self, hash: &TxHash) -> Result<(Tx, WitnessSet), APIError> {
+        let (tx, witness_set) = self.api.completed_tx(hash.to_string()).await?;
+        Ok((
+            decode_cbor(&tx, "transaction")?,
+            crate::connected_wallet::decode_witness_set(&witness_set)?,
+        ))
+    }
+}
+
+fn decode_cbor<T>(value: &str, what: &str) -> Result<T, APIError>
+where
+    T: for<'b> minicbor::Decode<'b, ()>,
+{
+    let bytes = hex::decode(value).map_err(|error| APIError {
+        code: APIErrorCode::InternalError,
+        info: format!("Couldn't decode the {what}: {error}"),
+    })?;
+    minicbor::decode(&bytes).map_err(|error| APIError {
+        code: APIErrorCode::InternalError,
+        info: format!("Couldn't decode the {what}: {error}"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Hash;
+
+    fn requirement(value: serde_json::Value) -> Result<ScriptRequirement, APIError> {
+        serde_json::from_value::<RawScriptRequirement>(value)
+            .unwrap()
+            .try_into()
+    }
+
+    #[test]
+    fn decode_script_requirements() {
+        let signer = Hash::from([1; 28]);
+
+        assert_eq!(
+            requirement(serde_json::json!({ "code": 1, "value": signer.to_string() })),
+            Ok(ScriptRequirement::Signer(signer))
+        );
+        assert_eq!(
+            requirement(serde_json::json!({ "code": 2, "value": 42 })),
+            Ok(ScriptRequirement::Before(42))
+        );
+        assert_eq!(
+            requirement(serde_json::json!({ "code": 3, "value": "43" })),
+            Ok(ScriptRequirement::After(43))
+        );
+        assert!(requirement(serde_json::json!({ "code": 4, "value": 0 })).is_err());
+    }
+
+    #[test]
+    fn decode_native_script() {
+        let script = NativeScript::ScriptPubkey(Hash::from([2; 28]));
+        let hex = hex::encode(minicbor::to_vec(&script).unwrap());
+
+        assert_eq!(
+            decode_cbor::<NativeScript>(&hex, "native script"),
+            Ok(script)
+        );
+    }
+}
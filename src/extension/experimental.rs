@@ -0,0 +1,106 @@
+//! The non standard `api.experimental` namespace.
+//!
+//! CIP-30 leaves the wallets free to put their own endpoints under
+//! `api.experimental`: Nami's `getCollateral`, the `on` and `off` event
+//! listeners of Eternl, Lace and Nami (see
+//! [`wallet_events`](crate::wallet_events)), `getAddressInfo`... The
+//! [`ExperimentalApi`] calls any of them by name, with bindings for the
+//! known ones:
+//!
+//! ```no_run
+//! # async fn test(wallet: cardano_connector::ConnectedWallet) -> anyhow::Result<()> {
+//! let experimental = wallet.experimental();
+//! if experimental.has("getCollateral") {
+//!     let collateral = experimental.collateral().await?;
+//! }
+//! let version = experimental.call("appVersion", &[]).await?;
+//! # Ok(()) }
+//! ```
+
+use super::{call, decode, endpoint};
+use crate::{
+    ConnectedWallet, Utxo,
+    error::{APIError, APIErrorCode},
+};
+use wasm_bindgen::JsValue;
+
+/// The `api.experimental` namespace of a [`ConnectedWallet`], see the
+/// [module documentation](self).
+#[derive(Clone)]
+pub struct ExperimentalApi<'a> {
+    wallet: &'a ConnectedWallet,
+    namespace: JsValue,
+}
+
+impl<'a> ExperimentalApi<'a> {
+    pub(crate) fn new(wallet: &'a ConnectedWallet, namespace: JsValue) -> Self {
+        Self { wallet, namespace }
+    }
+
+    /// the wallet has an `experimental` namespace
+    pub fn is_available(&self) -> bool {
+        self.namespace.is_object()
+    }
+
+    /// the namespace has the endpoint `name`
+    pub fn has(&self, name: &str) -> bool {
+        self.is_available() && endpoint(&self.namespace, name).is_some()
+    }
+
+    /// the names of the endpoints of the namespace
+    pub fn endpoints(&self) -> Vec<String> {
+        if !self.is_available() {
+            return Vec::new();
+        }
+        js_sys::Object::keys(&js_sys::Object::from(self.namespace.clone()))
+            .iter()
+            .filter_map(|key| key.as_string())
+            .filter(|name| self.has(name))
+            .collect()
+    }
+
+    /// the `experimental` object itself, for what the bindings don't
+    /// cover (e.g. its non function properties)
+    pub fn as_js(&self) -> &JsValue {
+        &self.namespace
+    }
+
+    /// call the endpoint `name` with the `args` and await its result
+    ///
+    /// Fails with [`APIErrorCode::InternalError`] if the wallet has no
+    /// such endpoint.
+    pub async fn call(&self, name: &str, args: &[JsValue]) -> Result<JsValue, APIError> {
+        if !self.is_available() {
+            return Err(APIError {
+                code: APIErrorCode::InternalError,
+                info: "The wallet has no experimental namespace".to_owned(),
+            });
+        }
+        call(&self.namespace, name, args).await
+    }
+
+    /// call the endpoint `name` and decode its result from its serde
+    /// representation
+    pub async fn call_as<T: serde::de::DeserializeOwned>(
+        &self,
+        name: &str,
+        args: &[JsValue],
+    ) -> Result<T, APIError> {
+        decode(self.call(name, args).await?)
+    }
+
+    /// Nami's `getCollateral`, the collateral UTxOs before it was
+    /// standardised in CIP-30 (see
+    /// [`WalletQuirks::experimental_collateral`](crate::WalletQuirks::experimental_collateral))
+    ///
+    /// [`ConnectedWallet::collateral`] falls back to it for the wallets
+    /// with this quirk.
+    pub async fn collateral(&self) -> Result<Vec<Utxo>, APIError> {
+        let utxos = self.call("getCollateral", &[]).await?;
+        if utxos.is_null() || utxos.is_undefined() {
+            return Ok(Vec::new());
+        }
+        self.wallet
+            .decode_utxos("experimental.getCollateral", js_sys::Array::from(&utxos))
+    }
+}
@@ -0,0 +1,166 @@
+/*!
+Typed bindings for the CIP-30 extensions.
+
+Once enabled, the endpoints of an extension are namespaced under
+`api.cipXXXX` (no leading zeros). The [`define_extension_api!`] macro
+declares the typed bindings of such a namespace from any crate, and
+[`ConnectedWallet::extension_api`] returns them if the wallet exposes
+the extension:
+
+```no_run
+use cardano_connector::define_extension_api;
+
+define_extension_api! {
+    /// CIP-95 governance extension
+    pub struct Cip95Api: 95 {
+        /// the DRep public key of the wallet, hex encoded
+        fn pub_drep_key() -> String = "getPubDRepKey";
+        fn registered_pub_stake_keys() -> Vec<String> = "getRegisteredPubStakeKeys";
+    }
+}
+
+# async fn test(wallet: cardano_connector::ConnectedWallet) -> anyhow::Result<()> {
+if let Some(cip95) = wallet.extension_api::<Cip95Api>() {
+    let drep_key = cip95.pub_drep_key().await?;
+}
+# Ok(()) }
+```
+
+Arguments are passed to the endpoint with their serde representation
+and the result is decoded the same way.
+
+[`ConnectedWallet::extension_api`]: crate::ConnectedWallet::extension_api
+*/
+
+pub mod cip103;
+pub mod cip106;
+pub mod experimental;
+
+use crate::error::{APIError, APIErrorCode};
+use wasm_bindgen::{JsCast as _, JsValue};
+
+/// The typed bindings of a CIP-30 extension namespace, see
+/// [`define_extension_api!`].
+pub trait ExtensionApi: Sized {
+    /// the CIP number of the extension
+    const CIP: u64;
+
+    /// wrap the `api.cipXXXX` object of the connected wallet
+    fn from_namespace(namespace: JsValue) -> Self;
+}
+
+/// the name of the namespace of the extension (`cipXXXX`)
+pub fn namespace_name(cip: u64) -> String {
+    format!("cip{cip}")
+}
+
+/// the `api.cipXXXX` object, `None` if the wallet does not expose it
+pub(crate) fn namespace(api: &JsValue, cip: u64) -> Option<JsValue> {
+    js_sys::Reflect::get(api, &JsValue::from_str(&namespace_name(cip)))
+        .ok()
+        .filter(JsValue::is_object)
+}
+
+/// the function `name` of the object, if any
+pub(crate) fn endpoint(object: &JsValue, name: &str) -> Option<js_sys::Function> {
+    js_sys::Reflect::get(object, &JsValue::from_str(name))
+        .ok()
+        .and_then(|function| function.dyn_into::<js_sys::Function>().ok())
+}
+
+/// call the endpoint `name` of the namespace and await its result
+///
+/// Fails with [`APIErrorCode::InternalError`] if the namespace has no
+/// such endpoint.
+pub async fn call(namespace: &JsValue, name: &str, args: &[JsValue]) -> Result<JsValue, APIError> {
+    let Some(function) = endpoint(namespace, name) else {
+        return Err(APIError {
+            code: APIErrorCode::InternalError,
+            info: format!("The wallet does not expose the `{name}' endpoint"),
+        });
+    };
+
+    let args = args.iter().collect::<js_sys::Array>();
+    let result = function
+        .apply(namespace, &args)
+        .map(|value| js_sys::Promise::resolve(&value))
+        .map(wasm_bindgen_futures::JsFuture::from);
+    let result = match result {
+        Ok(future) => future.await,
+        Err(error) => Err(error),
+    };
+
+    match result {
+        Ok(value) => Ok(value),
+        Err(error) => serde_wasm_bindgen::from_value(error)
+            .map_err(|decode_error| APIError {
+                code: APIErrorCode::InternalError,
+                info: format!("Couldn't decode the error content: {decode_error}"),
+            })
+            .and_then(Err),
+    }
+}
+
+/// encode an argument of an extension endpoint
+pub fn encode<T: serde::Serialize + ?Sized>(value: &T) -> Result<JsValue, APIError> {
+    serde_wasm_bindgen::to_value(value).map_err(|error| APIError {
+        code: APIErrorCode::InvalidRequest,
+        info: format!("Couldn't encode the argument: {error}"),
+    })
+}
+
+/// decode the result of an extension endpoint
+pub fn decode<T: serde::de::DeserializeOwned>(value: JsValue) -> Result<T, APIError> {
+    serde_wasm_bindgen::from_value(value).map_err(|error| APIError {
+        code: APIErrorCode::InternalError,
+        info: format!("Couldn't decode the result: {error}"),
+    })
+}
+
+/// Declare the typed bindings of a CIP-30 extension.
+///
+/// Every method maps to the JavaScript endpoint named after the `=`, its
+/// arguments must implement `serde::Serialize` and its return type
+/// `serde::de::DeserializeOwned`. The methods are `async` and return a
+/// `Result<_, APIError>`. See the [module documentation](crate::extension)
+/// for an example.
+#[macro_export]
+macro_rules! define_extension_api {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident: $cip:literal {
+            $(
+                $(#[$method_meta:meta])*
+                fn $method:ident($($arg:ident: $arg_ty:ty),* $(,)?) -> $ret:ty = $endpoint:literal;
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone)]
+        $vis struct $name {
+            namespace: ::wasm_bindgen::JsValue,
+        }
+
+        impl $crate::extension::ExtensionApi for $name {
+            const CIP: u64 = $cip;
+
+            fn from_namespace(namespace: ::wasm_bindgen::JsValue) -> Self {
+                Self { namespace }
+            }
+        }
+
+        impl $name {
+            $(
+                $(#[$method_meta])*
+                pub async fn $method(
+                    &self,
+                    $($arg: $arg_ty),*
+                ) -> ::std::result::Result<$ret, $crate::error::APIError> {
+                    let args = [$($crate::extension::encode(&$arg)?),*];
+                    let value = $crate::extension::call(&self.namespace, $endpoint, &args).await?;
+                    $crate::extension::decode(value)
+                }
+            )*
+        }
+    };
+}
@@ -7,14 +7,71 @@ use wasm_bindgen::prelude::*;
 /// modified between paginated calls that this will change the pagination, e.g.
 /// some results skipped or showing up multiple times but otherwise the wallet
 /// must respect the pagination order.
+///
+/// The wallets read it as the CIP-30 `{ page, limit }` object.
+///
+/// ```
+/// # use cardano_connector::ffi::cip30_api::Paginate;
+/// let second = Paginate::new(0, 20).next();
+/// assert_eq!((second.page, second.limit()), (1, 20));
+/// ```
 #[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
 pub struct Paginate {
     /// the page index
     pub page: usize,
     /// the limit of elements per pages
+    #[deprecated(note = "use `Paginate::new` and `Paginate::limit`")]
+    #[wasm_bindgen(skip)]
+    #[serde(rename = "limit")]
     pub limite: usize,
 }
 
+#[allow(deprecated)]
+impl Paginate {
+    /// the page size of [`Paginate::default`]
+    pub const DEFAULT_LIMIT: usize = 50;
+
+    pub const fn new(page: usize, limit: usize) -> Self {
+        Self {
+            page,
+            limite: limit,
+        }
+    }
+
+    pub const fn with_page(mut self, page: usize) -> Self {
+        self.page = page;
+        self
+    }
+
+    pub const fn with_limit(mut self, limit: usize) -> Self {
+        self.limite = limit;
+        self
+    }
+
+    /// the following page, of the same size
+    pub const fn next(&self) -> Self {
+        Self::new(self.page + 1, self.limite)
+    }
+}
+
+#[wasm_bindgen]
+#[allow(deprecated)]
+impl Paginate {
+    /// the limit of elements per pages
+    #[wasm_bindgen(getter)]
+    pub fn limit(&self) -> usize {
+        self.limite
+    }
+}
+
+/// the first page, of [`Paginate::DEFAULT_LIMIT`] elements
+impl Default for Paginate {
+    fn default() -> Self {
+        Self::new(0, Self::DEFAULT_LIMIT)
+    }
+}
+
 #[wasm_bindgen]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub enum NetworkId {
@@ -40,6 +97,11 @@ extern "C" {
     #[derive(Clone, PartialEq)]
     pub type Cip30Api;
 
+    /// Non standard namespace some wallets use to expose endpoints that
+    /// are not (yet) part of CIP-30. `undefined` if the wallet has none.
+    #[wasm_bindgen(method, getter)]
+    pub fn experimental(this: &Cip30Api) -> JsValue;
+
     #[wasm_bindgen(method, catch, js_name = "getExtensions")]
     pub async fn get_extensions(this: &Cip30Api) -> Result<JsValue, JsValue>;
 
@@ -63,6 +125,12 @@ extern "C" {
         amount: Option<String>,
         pagination: Option<Paginate>,
     ) -> Result<Array, JsValue>;
+    /// Returns the UTxOs the wallet reserved to be used as collateral
+    /// inputs of Plutus transactions (CIP-40), enough to cover the
+    /// `amount` of the `params` (`{ amount: cbor<Coin> }`). `null` if the
+    /// wallet cannot provide enough collateral.
+    #[wasm_bindgen(method, catch, js_name = "getCollateral")]
+    pub async fn get_collateral(this: &Cip30Api, params: &JsValue) -> Result<JsValue, JsValue>;
     /// Returns an address owned by the wallet that should be used as a change
     /// address to return leftover assets during transaction creation back to
     /// the connected wallet. This can be used as a generic receive address as
@@ -132,3 +200,17 @@ extern "C" {
     pub async fn submit_tx(this: &Cip30Api, transaction: &str) -> Result<JsString, JsValue>;
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paginate_json() {
+        assert_eq!(
+            serde_json::to_value(Paginate::new(2, 10)).unwrap(),
+            serde_json::json!({ "page": 2, "limit": 10 })
+        );
+        assert_eq!(Paginate::default().with_limit(5), Paginate::new(0, 5));
+    }
+}
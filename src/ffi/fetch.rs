@@ -0,0 +1,87 @@
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(catch, js_name = "fetch")]
+    async fn fetch_with_init(url: &str, init: &JsValue) -> Result<JsValue, JsValue>;
+
+    pub type Response;
+
+    #[wasm_bindgen(method, getter)]
+    pub fn status(this: &Response) -> u16;
+
+    #[wasm_bindgen(method, getter)]
+    pub fn headers(this: &Response) -> Headers;
+
+    #[wasm_bindgen(method, catch)]
+    pub async fn text(this: &Response) -> Result<JsValue, JsValue>;
+
+    pub type Headers;
+
+    #[wasm_bindgen(method)]
+    pub fn get(this: &Headers, name: &str) -> Option<String>;
+}
+
+impl Headers {
+    /// the headers of the response, the names are in lower case
+    pub fn to_vec(&self) -> Vec<(String, String)> {
+        let Ok(Some(entries)) = js_sys::try_iter(self) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let entry = js_sys::Array::from(&entry);
+                Some((entry.get(0).as_string()?, entry.get(1).as_string()?))
+            })
+            .collect()
+    }
+}
+
+/// The parts of an HTTP response the backends are interested in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    /// the `Retry-After` header, if any
+    pub retry_after: Option<String>,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl HttpResponse {
+    /// the value of the header, `name` in lower case
+    pub fn header(&self, name: &str) -> Option<String> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.clone())
+    }
+}
+
+/// send an HTTP request with the global `fetch`
+pub async fn fetch(
+    method: &str,
+    url: &str,
+    headers: &[(&str, String)],
+    body: Option<JsValue>,
+) -> Result<HttpResponse, JsValue> {
+    let init = js_sys::Object::new();
+    let js_headers = js_sys::Object::new();
+    for (name, value) in headers {
+        js_sys::Reflect::set(&js_headers, &(*name).into(), &value.into())?;
+    }
+    js_sys::Reflect::set(&init, &"method".into(), &method.into())?;
+    js_sys::Reflect::set(&init, &"headers".into(), &js_headers)?;
+    if let Some(body) = body {
+        js_sys::Reflect::set(&init, &"body".into(), &body)?;
+    }
+
+    let response: Response = fetch_with_init(url, &init).await?.unchecked_into();
+    let body = response.text().await?.as_string().unwrap_or_default();
+    Ok(HttpResponse {
+        status: response.status(),
+        retry_after: response.headers().get("Retry-After"),
+        headers: response.headers().to_vec(),
+        body,
+    })
+}
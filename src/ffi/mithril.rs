@@ -0,0 +1,45 @@
+//! bindings to the `MithrilClient` of the `@mithril-dev/mithril-client-wasm`
+//! package
+
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(module = "@mithril-dev/mithril-client-wasm")]
+extern "C" {
+    #[derive(Clone)]
+    pub type MithrilClient;
+
+    #[wasm_bindgen(constructor)]
+    pub fn new(aggregator_endpoint: &str, genesis_verification_key: &str) -> MithrilClient;
+
+    /// the proofs of membership of the transactions in the latest
+    /// certified set of Cardano transactions
+    #[wasm_bindgen(method, catch)]
+    pub async fn get_cardano_transaction_proofs(
+        this: &MithrilClient,
+        transactions_hashes: Box<[JsValue]>,
+    ) -> Result<JsValue, JsValue>;
+
+    /// verify the chain of certificates up to the genesis certificate,
+    /// returns the certificate
+    #[wasm_bindgen(method, catch)]
+    pub async fn verify_certificate_chain(
+        this: &MithrilClient,
+        certificate_hash: &str,
+    ) -> Result<JsValue, JsValue>;
+
+    /// verify the Merkle proofs of the transactions, returns the message
+    /// the certificate must have signed
+    #[wasm_bindgen(method, catch)]
+    pub async fn verify_cardano_transaction_proof_then_compute_message(
+        this: &MithrilClient,
+        proofs: &JsValue,
+        certificate: &JsValue,
+    ) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(method, catch)]
+    pub async fn verify_message_match_certificate(
+        this: &MithrilClient,
+        message: &JsValue,
+        certificate: &JsValue,
+    ) -> Result<JsValue, JsValue>;
+}
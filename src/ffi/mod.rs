@@ -1,5 +1,13 @@
 pub mod cip30;
 pub mod cip30_api;
+pub mod fetch;
+#[cfg(feature = "mithril")]
+pub mod mithril;
+#[cfg(feature = "cip45")]
+pub mod peer_connect;
+pub mod storage;
+pub mod timer;
+pub mod websocket;
 
 pub use self::{cip30::Cip30Wallet, cip30_api::Cip30Api};
 use wasm_bindgen::prelude::*;
@@ -0,0 +1,28 @@
+//! bindings to the `DAppPeerConnect` of the
+//! `@fabianbormann/cardano-peer-connect` package (CIP-45)
+
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(module = "@fabianbormann/cardano-peer-connect")]
+extern "C" {
+    pub type DAppPeerConnect;
+
+    /// `config` holds the `dAppInfo`, the `announce` trackers and the
+    /// `onApiInject`, `onApiEject` and `verifyConnection` callbacks
+    #[wasm_bindgen(constructor, catch)]
+    pub fn new(config: &JsValue) -> Result<DAppPeerConnect, JsValue>;
+
+    /// the identifier of the dApp the wallet connects to, shown in the
+    /// QR code
+    #[wasm_bindgen(method, js_name = getAddress)]
+    pub fn get_address(this: &DAppPeerConnect) -> String;
+
+    /// draw the QR code of the address in the canvas
+    #[wasm_bindgen(method, js_name = generateQRCode)]
+    pub fn generate_qr_code(this: &DAppPeerConnect, canvas: &JsValue);
+
+    /// a picture (data URL) derived from the address, for the user to
+    /// compare with the one shown by the wallet
+    #[wasm_bindgen(method, js_name = getIdenticon)]
+    pub fn get_identicon(this: &DAppPeerConnect) -> String;
+}
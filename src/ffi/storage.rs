@@ -0,0 +1,20 @@
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    /// the browser's `window.localStorage`
+    #[wasm_bindgen(thread_local_v2, js_namespace = ["window"], js_name = "localStorage")]
+    pub static LOCAL_STORAGE: Option<WebStorage>;
+
+    #[derive(Clone)]
+    pub type WebStorage;
+
+    #[wasm_bindgen(method, catch, js_name = "getItem")]
+    pub fn get_item(this: &WebStorage, key: &str) -> Result<Option<String>, JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = "setItem")]
+    pub fn set_item(this: &WebStorage, key: &str, value: &str) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = "removeItem")]
+    pub fn remove_item(this: &WebStorage, key: &str) -> Result<(), JsValue>;
+}
@@ -0,0 +1,17 @@
+use std::time::Duration;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = "setTimeout")]
+    fn set_timeout(handler: &js_sys::Function, timeout: i32) -> JsValue;
+}
+
+/// wait for the given duration without blocking the browser's event loop
+pub async fn sleep(duration: Duration) {
+    let timeout = duration.as_millis().min(i32::MAX as u128) as i32;
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        set_timeout(&resolve, timeout);
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
@@ -0,0 +1,33 @@
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    /// The browser's `WebSocket`.
+    #[derive(Clone)]
+    pub type WebSocket;
+
+    #[wasm_bindgen(constructor, catch)]
+    pub fn new(url: &str) -> Result<WebSocket, JsValue>;
+
+    #[wasm_bindgen(method, catch)]
+    pub fn send(this: &WebSocket, data: &str) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method)]
+    pub fn close(this: &WebSocket);
+
+    #[wasm_bindgen(method, setter)]
+    pub fn set_onopen(this: &WebSocket, handler: Option<&js_sys::Function>);
+
+    #[wasm_bindgen(method, setter)]
+    pub fn set_onmessage(this: &WebSocket, handler: Option<&js_sys::Function>);
+
+    /// also called after an error: the socket is closed
+    #[wasm_bindgen(method, setter)]
+    pub fn set_onclose(this: &WebSocket, handler: Option<&js_sys::Function>);
+
+    pub type MessageEvent;
+
+    /// the content of the message, a string for text messages
+    #[wasm_bindgen(method, getter)]
+    pub fn data(this: &MessageEvent) -> JsValue;
+}
@@ -0,0 +1,145 @@
+/*!
+Idempotency keys for the actions moving funds.
+
+A dApp action (e.g. "pay invoice #42") may be retried because the
+network is flaky, the user clicked twice or the page was reloaded. By
+running the action through [`IdempotencyKeys::run`] with a key
+identifying it, the action is executed at most once: later runs with the
+same key return the hash of the transaction of the first run.
+
+The keys are persisted in a [`Storage`] so they survive reloads.
+*/
+
+use crate::{
+    TxHash,
+    storage::{Storage, StorageError},
+};
+use std::future::Future;
+
+const KEY_PREFIX: &str = "cardano-connector.idempotency.";
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum IdempotencyError<E> {
+    /// The action was started and did not complete, possibly because the
+    /// page was closed in the middle of it. It is not known whether the
+    /// transaction was submitted: check on chain and call
+    /// [`IdempotencyKeys::forget`] before trying again.
+    #[error("The action `{0}` was already started and did not complete")]
+    Pending(String),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    Action(E),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "state", rename_all = "camelCase")]
+enum Record {
+    Pending,
+    Done { hash: TxHash },
+}
+
+/// Persisted record of the actions already executed.
+pub struct IdempotencyKeys<S> {
+    storage: S,
+}
+
+impl<S: Storage> IdempotencyKeys<S> {
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    fn record(&self, key: &str) -> Option<Record> {
+        self.storage
+            .get(&format!("{KEY_PREFIX}{key}"))
+            .and_then(|record| serde_json::from_str(&record).ok())
+    }
+
+    fn save(&self, key: &str, record: &Record) -> Result<(), StorageError> {
+        let record = serde_json::to_string(record)
+            .map_err(|error| StorageError::Write(error.to_string()))?;
+        self.storage.set(&format!("{KEY_PREFIX}{key}"), &record)
+    }
+
+    /// the transaction produced by the action with the given key, if it
+    /// completed
+    pub fn get(&self, key: &str) -> Option<TxHash> {
+        match self.record(key)? {
+            Record::Done { hash } => Some(hash),
+            Record::Pending => None,
+        }
+    }
+
+    /// forget about the given key, the next [`run`] with this key will
+    /// execute the action
+    ///
+    /// [`run`]: Self::run
+    pub fn forget(&self, key: &str) -> Result<(), StorageError> {
+        self.storage.remove(&format!("{KEY_PREFIX}{key}"))
+    }
+
+    /// Execute `action` unless an action with the same `key` already ran.
+    ///
+    /// If the action fails the key is released so it can be retried.
+    pub async fn run<F, Fut, E>(&self, key: &str, action: F) -> Result<TxHash, IdempotencyError<E>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<TxHash, E>>,
+    {
+        match self.record(key) {
+            Some(Record::Done { hash }) => return Ok(hash),
+            Some(Record::Pending) => return Err(IdempotencyError::Pending(key.to_owned())),
+            None => {}
+        }
+
+        self.save(key, &Record::Pending)?;
+        match action().await {
+            Ok(hash) => {
+                self.save(key, &Record::Done { hash })?;
+                Ok(hash)
+            }
+            Err(error) => {
+                self.forget(key)?;
+                Err(IdempotencyError::Action(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Hash, storage::MemoryStorage};
+    use futures::executor::block_on;
+    use std::cell::Cell;
+
+    #[test]
+    fn run_at_most_once() {
+        let keys = IdempotencyKeys::new(MemoryStorage::new());
+        let calls = Cell::new(0);
+        let pay = || async {
+            calls.set(calls.get() + 1);
+            Ok::<_, ()>(Hash::from([1; 32]))
+        };
+
+        let first = block_on(keys.run("invoice-42", pay)).unwrap();
+        let second = block_on(keys.run("invoice-42", pay)).unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(calls.get(), 1);
+        assert_eq!(keys.get("invoice-42"), Some(first));
+    }
+
+    #[test]
+    fn failed_action_can_be_retried() {
+        let keys = IdempotencyKeys::new(MemoryStorage::new());
+
+        let failed = block_on(keys.run("invoice-43", || async { Err("offline") }));
+        assert_eq!(failed, Err(IdempotencyError::Action("offline")));
+
+        let hash = block_on(keys.run("invoice-43", || async {
+            Ok::<_, &str>(Hash::from([2; 32]))
+        }));
+        assert_eq!(hash, Ok(Hash::from([2; 32])));
+    }
+}
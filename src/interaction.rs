@@ -0,0 +1,162 @@
+/*!
+Tracking of the actions waiting for the user in the wallet.
+
+Enabling the wallet, signing a transaction or some data opens the
+wallet's popup and only completes once the user answered. [`Interactions`]
+keeps the list of these pending actions and emits
+[`Event::InteractionStarted`] and [`Event::InteractionEnded`] so the dApp
+can render a "waiting for wallet…" overlay without managing its own
+flags.
+
+The [`Connector`] tracks the interactions of the wallets it connects, a
+[`ConnectedWallet`] created otherwise can be given an [`Interactions`]
+with [`ConnectedWallet::with_interactions`].
+
+[`Connector`]: crate::Connector
+[`ConnectedWallet`]: crate::ConnectedWallet
+[`ConnectedWallet::with_interactions`]: crate::ConnectedWallet::with_interactions
+*/
+
+use crate::events::{Event, EventBus};
+use std::{cell::RefCell, rc::Rc};
+
+/// An action that requires the user's attention in the wallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WalletAction {
+    Enable,
+    SignTx,
+    SignTxs,
+    SignData,
+}
+
+/// identifies an interaction from its start to its end
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InteractionId(u64);
+
+#[derive(Default)]
+struct Pending {
+    next_id: u64,
+    actions: Vec<(InteractionId, WalletAction)>,
+}
+
+/// The interactions currently pending, see the [module documentation](self).
+///
+/// Cloning gives another handle to the same list.
+#[derive(Clone)]
+pub struct Interactions {
+    pending: Rc<RefCell<Pending>>,
+    events: EventBus,
+}
+
+impl Interactions {
+    /// track the interactions, emitting the notifications on `events`
+    pub fn new(events: EventBus) -> Self {
+        Self {
+            pending: Rc::default(),
+            events,
+        }
+    }
+
+    /// the actions waiting for the user, oldest first
+    pub fn pending(&self) -> Vec<(InteractionId, WalletAction)> {
+        self.pending.borrow().actions.clone()
+    }
+
+    pub fn is_waiting(&self) -> bool {
+        !self.pending.borrow().actions.is_empty()
+    }
+
+    /// start tracking the action, it ends when the returned guard is
+    /// dropped (including if the future awaiting the wallet is dropped)
+    pub fn begin(&self, action: WalletAction) -> InteractionGuard {
+        let id = {
+            let mut pending = self.pending.borrow_mut();
+            let id = InteractionId(pending.next_id);
+            pending.next_id += 1;
+            pending.actions.push((id, action));
+            id
+        };
+        self.events.emit(Event::InteractionStarted { id, action });
+
+        InteractionGuard {
+            interactions: self.clone(),
+            id,
+            action,
+        }
+    }
+}
+
+impl PartialEq for Interactions {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.pending, &other.pending)
+    }
+}
+
+impl std::fmt::Debug for Interactions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Interactions")
+            .field("pending", &self.pending.borrow().actions)
+            .finish()
+    }
+}
+
+/// see [`Interactions::begin`]
+#[must_use = "the interaction ends when the guard is dropped"]
+pub struct InteractionGuard {
+    interactions: Interactions,
+    id: InteractionId,
+    action: WalletAction,
+}
+
+impl Drop for InteractionGuard {
+    fn drop(&mut self) {
+        self.interactions
+            .pending
+            .borrow_mut()
+            .actions
+            .retain(|(id, _)| *id != self.id);
+        self.interactions.events.emit(Event::InteractionEnded {
+            id: self.id,
+            action: self.action,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn begin_and_end() {
+        let events = EventBus::new();
+        let received = Rc::new(RefCell::new(Vec::new()));
+        events.subscribe({
+            let received = Rc::clone(&received);
+            move |event| received.borrow_mut().push(event.clone())
+        });
+        let interactions = Interactions::new(events);
+
+        let guard = interactions.begin(WalletAction::SignTx);
+        assert!(interactions.is_waiting());
+        assert_eq!(
+            interactions.pending(),
+            vec![(InteractionId(0), WalletAction::SignTx)]
+        );
+
+        drop(guard);
+        assert!(!interactions.is_waiting());
+        assert_eq!(
+            *received.borrow(),
+            vec![
+                Event::InteractionStarted {
+                    id: InteractionId(0),
+                    action: WalletAction::SignTx
+                },
+                Event::InteractionEnded {
+                    id: InteractionId(0),
+                    action: WalletAction::SignTx
+                },
+            ]
+        );
+    }
+}
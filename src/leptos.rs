@@ -0,0 +1,212 @@
+/*!
+[Leptos](https://leptos.dev) signals of the connected wallet.
+
+[`provide_wallet_context`] puts a [`WalletContext`] in the context of the
+component: the [`WalletSignal`] of the connected wallet and the
+[`BalanceSignal`] of its balance and used addresses. They are refreshed
+when the wallet is connected and, for the wallets emitting the events
+(see [`wallet_events`](crate::wallet_events)), when the user switches
+account or network.
+
+```no_run
+use cardano_connector::{leptos::{provide_wallet_context, use_wallet_context}, lovelace_of};
+use leptos::prelude::*;
+
+#[component]
+fn App() -> impl IntoView {
+    provide_wallet_context();
+    view! { <Balance /> }
+}
+
+#[component]
+fn Balance() -> impl IntoView {
+    let context = use_wallet_context();
+    move || match context.balance.get() {
+        Some(balance) => format!("{} lovelace", lovelace_of(&balance)),
+        None => "Not connected".to_owned(),
+    }
+}
+```
+
+This needs the `leptos` feature.
+*/
+
+use crate::{
+    Address, ConnectedWallet, Value, Wallet, error::APIError, wallet_events::WalletSubscription,
+};
+use ::leptos::prelude::*;
+
+/// The connected wallet, `None` until a wallet is connected.
+#[derive(Clone, Copy)]
+pub struct WalletSignal {
+    wallet: RwSignal<Option<ConnectedWallet>, LocalStorage>,
+    /// the account and network listeners of the connected wallet,
+    /// removed when replaced
+    subscriptions: StoredValue<Vec<WalletSubscription>, LocalStorage>,
+    /// bumped by every refresh and disconnection, a refresh only sets
+    /// the signals if none happened since it started
+    generation: StoredValue<u64>,
+    error: RwSignal<Option<APIError>>,
+}
+
+impl WalletSignal {
+    fn new() -> Self {
+        Self {
+            wallet: RwSignal::new_local(None),
+            subscriptions: StoredValue::new_local(Vec::new()),
+            generation: StoredValue::new(0),
+            error: RwSignal::new(None),
+        }
+    }
+
+    /// the connected wallet, tracked
+    pub fn get(&self) -> Option<ConnectedWallet> {
+        self.wallet.get()
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.wallet.with(Option::is_some)
+    }
+
+    /// the last error of the wallet: the user refused to connect, or a
+    /// refresh failed
+    pub fn error(&self) -> Option<APIError> {
+        self.error.get()
+    }
+
+    /// start a new generation, outdating the refreshes in progress
+    fn next_generation(&self) -> u64 {
+        self.generation.update_value(|generation| *generation += 1);
+        self.generation.get_value()
+    }
+
+    fn is_current(&self, generation: u64) -> bool {
+        self.generation.get_value() == generation
+    }
+}
+
+/// The balance and used addresses of the connected wallet.
+#[derive(Clone, Copy)]
+pub struct BalanceSignal {
+    balance: RwSignal<Option<Value>>,
+    addresses: RwSignal<Vec<Address>>,
+}
+
+impl BalanceSignal {
+    fn new() -> Self {
+        Self {
+            balance: RwSignal::new(None),
+            addresses: RwSignal::new(Vec::new()),
+        }
+    }
+
+    /// the balance, `None` until known, tracked
+    pub fn get(&self) -> Option<Value> {
+        self.balance.get()
+    }
+
+    /// the used addresses (the first page of `getUsedAddresses`), tracked
+    pub fn addresses(&self) -> Vec<Address> {
+        self.addresses.get()
+    }
+}
+
+/// The signals of the wallet, see the [module documentation](self).
+#[derive(Clone, Copy)]
+pub struct WalletContext {
+    pub wallet: WalletSignal,
+    pub balance: BalanceSignal,
+}
+
+impl WalletContext {
+    /// enable the `wallet`, prompting the user unless the dApp is
+    /// already authorised, and follow it
+    pub fn connect(self, wallet: Wallet) {
+        wasm_bindgen_futures::spawn_local(async move {
+            match wallet.enable().await {
+                Ok(connected) => self.follow(connected),
+                Err(error) => self.wallet.error.set(Some(error)),
+            }
+        });
+    }
+
+    /// forget the connected wallet and its balance
+    pub fn disconnect(self) {
+        self.wallet.next_generation();
+        self.wallet.subscriptions.set_value(Vec::new());
+        self.wallet.wallet.set(None);
+        self.wallet.error.set(None);
+        self.balance.balance.set(None);
+        self.balance.addresses.set(Vec::new());
+    }
+
+    /// fetch the balance and the used addresses of the connected wallet
+    /// again
+    ///
+    /// The outcome is dropped if the wallet was disconnected, replaced or
+    /// refreshed again in the meantime.
+    pub fn refresh(self) {
+        let Some(wallet) = self.wallet.wallet.get_untracked() else {
+            return;
+        };
+        let generation = self.wallet.next_generation();
+        wasm_bindgen_futures::spawn_local(async move {
+            let refreshed = async {
+                let balance = wallet.balance().await?;
+                let addresses = wallet.used_addresses(None).await?;
+                Ok::<_, APIError>((balance, addresses))
+            };
+            let refreshed = refreshed.await;
+            if !self.wallet.is_current(generation) {
+                return;
+            }
+            match refreshed {
+                Ok((balance, addresses)) => {
+                    self.balance.balance.set(Some(balance));
+                    self.balance.addresses.set(addresses);
+                }
+                Err(error) => self.wallet.error.set(Some(error)),
+            }
+        });
+    }
+
+    /// make `wallet` the connected wallet, listening to its account and
+    /// network changes
+    ///
+    /// The wallets without events keep the balance of the connection,
+    /// until [`refresh`](Self::refresh)ed.
+    fn follow(self, wallet: ConnectedWallet) {
+        let account = wallet.on_account_change(move |wallet| match wallet {
+            Ok(wallet) => self.follow(wallet),
+            Err(error) => self.wallet.error.set(Some(error)),
+        });
+        let network = wallet.on_network_change(move |_| self.refresh());
+        self.wallet
+            .subscriptions
+            .set_value(account.into_iter().chain(network).collect());
+
+        self.wallet.error.set(None);
+        self.wallet.wallet.set(Some(wallet));
+        self.refresh();
+    }
+}
+
+/// create the [`WalletContext`] and provide it to the children of the
+/// component
+pub fn provide_wallet_context() -> WalletContext {
+    let context = WalletContext {
+        wallet: WalletSignal::new(),
+        balance: BalanceSignal::new(),
+    };
+    provide_context(context);
+    context
+}
+
+/// the [`WalletContext`] of a parent component
+///
+/// # Panics
+///
+/// If no parent called [`provide_wallet_context`].
+pub fn use_wallet_context() -> WalletContext {
+    expect_context()
+}
@@ -42,17 +42,56 @@ let connected_wallet = wallet.enable().await?;
 
 */
 
+pub mod address;
+pub mod audit;
+pub mod auth;
+pub mod backend;
 pub mod cardano;
+#[cfg(feature = "cip45")]
+pub mod cip45;
 mod connected_wallet;
+pub mod connector;
+pub mod data_source;
 pub mod error;
+pub mod error_keys;
+pub mod events;
+pub mod extension;
 pub mod ffi;
+pub mod idempotency;
+pub mod interaction;
+#[cfg(feature = "leptos")]
+pub mod leptos;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod network;
+mod pagination;
+pub mod permission;
+pub mod redact;
+pub mod registry;
+pub mod signing_session;
+pub mod storage;
+pub mod strictness;
+pub mod submission;
+pub mod telemetry;
 mod wallet;
+pub mod wallet_api;
+pub mod wallet_events;
+pub mod watch;
+#[cfg(feature = "yew")]
+pub mod yew;
 
 pub use self::{
+    address::{
+        AddressExt, AddressInfo, AddressKind, AddressPurpose, DerivationPath, DerivationPathError,
+    },
     cardano::{
-        AssetName, Coin, Hash, Multiasset, PolicyId, PositiveCoin, TxHash, Utxo, Value, lovelace_of,
+        AssetId, AssetName, Coin, Hash, Multiasset, PolicyId, PositiveCoin, TxHash, Utxo, UtxoSet,
+        Value, assets_of, lovelace_of,
     },
-    connected_wallet::{ConnectedWallet, NetworkId},
-    wallet::{Wallet, lace, wallet, wallets},
+    connected_wallet::{ConnectedWallet, NetworkId, SignedData},
+    connector::{Connector, ConnectorBuilder, Preferences},
+    registry::{WalletKind, WalletQuirks},
+    wallet::{Wallet, lace, wallet, wallets, watch_wallets},
+    wallet_api::WalletApi,
 };
 pub use pallas_addresses::Address;
@@ -0,0 +1,607 @@
+/*!
+A programmable CIP-30 wallet, to test a dApp without a browser extension.
+
+[`MockWallet`] stands for a [`Wallet`](crate::Wallet) and
+[`MockCip30Api`] for the [`ConnectedWallet`](crate::ConnectedWallet) it
+gives once enabled, with the same methods and the same
+[`WalletApi`] implementation. The wallet holds an Ed25519
+key: the addresses are derived from it and the transactions and data are
+really signed. The UTxOs, the addresses and the failures of the calls
+are programmed on the [`MockWallet`], also after enabling.
+
+```
+# use cardano_connector::{NetworkId, Value, mock::{MockCall, MockWallet}};
+# async fn test() -> anyhow::Result<()> {
+let wallet = MockWallet::new("mock", [7; 32], NetworkId::Preview);
+wallet.add_utxo(Value::Coin(10_000_000));
+// the user declines the next signature (CIP-30 `UserDeclined` code)
+wallet.fail_next(MockCall::SignTx, 2, "declined");
+
+let connected = wallet.enable().await?;
+assert_eq!(connected.lovelace().await?, 10_000_000);
+# Ok(()) }
+```
+*/
+
+use crate::{
+    Address, AssetId, Coin, NetworkId, TxHash, Utxo, UtxoSet, Value,
+    cardano::{
+        Hash, TransactionBody, TransactionInput, TransactionOutput, Tx, ValueExt as _, WitnessSet,
+        assets_of, coin_selection, inspect, lovelace_of, witness,
+    },
+    connected_wallet::SignedData,
+    error::{APIError, APIErrorCode, SignTxError, SubmitTxError},
+    ffi::cip30_api::Paginate,
+    wallet_api::WalletApi,
+};
+use pallas_addresses::{
+    Network, ShelleyAddress, ShelleyDelegationPart, ShelleyPaymentPart, StakeAddress,
+};
+use pallas_crypto::{hash::Hasher, key::ed25519::SecretKey};
+use pallas_primitives::babbage::PseudoPostAlonzoTransactionOutput;
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+/// The calls that can be made to fail, see [`MockWallet::fail_next`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MockCall {
+    Enable,
+    NetworkId,
+    Balance,
+    Utxos,
+    Collateral,
+    Addresses,
+    SignData,
+    SignTx,
+    SubmitTx,
+}
+
+struct State {
+    secret_key: [u8; 32],
+    network_id: NetworkId,
+    enabled: bool,
+    utxos: Vec<Utxo>,
+    collateral: Vec<Utxo>,
+    unused_addresses: Vec<Address>,
+    /// the CIP-30 error code and info of the next call
+    failures: BTreeMap<MockCall, (i64, String)>,
+    submitted: Vec<Tx>,
+    next_id: u8,
+}
+
+impl State {
+    fn fail(&mut self, call: MockCall) -> Result<(), (i64, String)> {
+        match self.failures.remove(&call) {
+            Some(failure) => Err(failure),
+            None => Ok(()),
+        }
+    }
+
+    fn key_hash(&self) -> Hash<28> {
+        Hasher::<224>::hash(SecretKey::from(self.secret_key).public_key().as_ref())
+    }
+
+    fn network(&self) -> Network {
+        match self.network_id {
+            NetworkId::Mainnet => Network::Mainnet,
+            _ => Network::Testnet,
+        }
+    }
+
+    fn shelley_address(&self) -> ShelleyAddress {
+        ShelleyAddress::new(
+            self.network(),
+            ShelleyPaymentPart::key_hash(self.key_hash()),
+            ShelleyDelegationPart::key_hash(self.key_hash()),
+        )
+    }
+
+    fn address(&self) -> Address {
+        self.shelley_address().into()
+    }
+}
+
+fn api_error((code, info): (i64, String)) -> APIError {
+    APIError {
+        code: APIErrorCode::from_code(code),
+        info,
+    }
+}
+
+/// see the [module documentation](self)
+#[derive(Clone)]
+pub struct MockWallet {
+    name: String,
+    state: Rc<RefCell<State>>,
+}
+
+impl MockWallet {
+    /// a wallet with no UTxO, whose key is `secret_key`
+    pub fn new(name: impl Into<String>, secret_key: [u8; 32], network_id: NetworkId) -> Self {
+        Self {
+            name: name.into(),
+            state: Rc::new(RefCell::new(State {
+                secret_key,
+                network_id,
+                enabled: false,
+                utxos: Vec::new(),
+                collateral: Vec::new(),
+                unused_addresses: Vec::new(),
+                failures: BTreeMap::new(),
+                submitted: Vec::new(),
+                next_id: 0,
+            })),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn version(&self) -> String {
+        "1.0.0".to_owned()
+    }
+
+    pub fn icon(&self) -> String {
+        String::new()
+    }
+
+    /// the base address of the wallet's key
+    pub fn address(&self) -> Address {
+        self.state.borrow().address()
+    }
+
+    /// add a UTxO holding `value` at the wallet's address
+    pub fn add_utxo(&self, value: Value) -> Utxo {
+        let mut state = self.state.borrow_mut();
+        state.next_id = state.next_id.wrapping_add(1);
+        let utxo = Utxo {
+            input: TransactionInput {
+                transaction_id: Hash::from([state.next_id; 32]),
+                index: 0,
+            },
+            output: TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+                address: state.address().to_vec().into(),
+                value,
+                datum_option: None,
+                script_ref: None,
+            }),
+        };
+        state.utxos.push(utxo.clone());
+        utxo
+    }
+
+    pub fn set_utxos(&self, utxos: Vec<Utxo>) {
+        self.state.borrow_mut().utxos = utxos;
+    }
+
+    pub fn set_collateral(&self, collateral: Vec<Utxo>) {
+        self.state.borrow_mut().collateral = collateral;
+    }
+
+    pub fn set_unused_addresses(&self, addresses: Vec<Address>) {
+        self.state.borrow_mut().unused_addresses = addresses;
+    }
+
+    /// switch network, as the user would in the wallet
+    pub fn set_network_id(&self, network_id: NetworkId) {
+        self.state.borrow_mut().network_id = network_id;
+    }
+
+    /// Make the next `call` fail with the CIP-30 error `code`.
+    ///
+    /// The negative codes are `APIError`s, the positive ones the errors
+    /// of the call (e.g. `2` for `UserDeclined` on [`MockCall::SignTx`]).
+    pub fn fail_next(&self, call: MockCall, code: i64, info: impl Into<String>) {
+        self.state
+            .borrow_mut()
+            .failures
+            .insert(call, (code, info.into()));
+    }
+
+    /// the transactions submitted so far
+    pub fn submitted(&self) -> Vec<Tx> {
+        self.state.borrow().submitted.clone()
+    }
+
+    pub async fn enabled(&self) -> Result<bool, APIError> {
+        Ok(self.state.borrow().enabled)
+    }
+
+    pub async fn enable(&self) -> Result<MockCip30Api, APIError> {
+        let mut state = self.state.borrow_mut();
+        state.fail(MockCall::Enable).map_err(api_error)?;
+        state.enabled = true;
+        Ok(MockCip30Api {
+            wallet: self.clone(),
+        })
+    }
+}
+
+/// The enabled [`MockWallet`], see the [module documentation](self).
+#[derive(Clone)]
+pub struct MockCip30Api {
+    wallet: MockWallet,
+}
+
+impl MockCip30Api {
+    fn call(&self, call: MockCall) -> Result<std::cell::RefMut<'_, State>, (i64, String)> {
+        let mut state = self.wallet.state.borrow_mut();
+        state.fail(call)?;
+        Ok(state)
+    }
+
+    pub fn name(&self) -> String {
+        self.wallet.name()
+    }
+
+    pub fn version(&self) -> String {
+        self.wallet.version()
+    }
+
+    pub fn icon(&self) -> String {
+        self.wallet.icon()
+    }
+
+    pub async fn network_id(&self) -> Result<NetworkId, APIError> {
+        Ok(self
+            .call(MockCall::NetworkId)
+            .map_err(api_error)?
+            .network_id)
+    }
+
+    pub async fn balance(&self) -> Result<Value, APIError> {
+        let state = self.call(MockCall::Balance).map_err(api_error)?;
+        Ok(UtxoSet::from(state.utxos.clone()).total())
+    }
+
+    pub async fn lovelace(&self) -> Result<Coin, APIError> {
+        self.balance().await.map(|balance| lovelace_of(&balance))
+    }
+
+    pub async fn assets(&self) -> Result<BTreeMap<AssetId, u64>, APIError> {
+        self.balance().await.map(|balance| assets_of(&balance))
+    }
+
+    pub async fn can_cover(&self, required: &Value) -> Result<bool, APIError> {
+        Ok(self.balance().await?.meets_or_exceeds(required))
+    }
+
+    pub async fn used_addresses(
+        &self,
+        pagination: Option<Paginate>,
+    ) -> Result<Vec<Address>, APIError> {
+        let state = self.call(MockCall::Addresses).map_err(api_error)?;
+        Ok(paginate(vec![state.address()], pagination))
+    }
+
+    pub async fn unused_addresses(&self) -> Result<Vec<Address>, APIError> {
+        let state = self.call(MockCall::Addresses).map_err(api_error)?;
+        Ok(state.unused_addresses.clone())
+    }
+
+    pub async fn change_address(&self) -> Result<Address, APIError> {
+        let state = self.call(MockCall::Addresses).map_err(api_error)?;
+        Ok(state.address())
+    }
+
+    pub async fn reward_addresses(&self) -> Result<Vec<Address>, APIError> {
+        let state = self.call(MockCall::Addresses).map_err(api_error)?;
+        Ok(StakeAddress::try_from(state.shelley_address())
+            .map(|address| vec![address.into()])
+            .unwrap_or_default())
+    }
+
+    /// the UTxOs selected with [`coin_selection::largest_first`], empty
+    /// if the wallet can't cover `value`
+    pub async fn select_utxos(
+        &self,
+        value: &Value,
+        pagination: Option<Paginate>,
+    ) -> Result<Vec<Utxo>, APIError> {
+        let state = self.call(MockCall::Utxos).map_err(api_error)?;
+        let selected = coin_selection::largest_first(&state.utxos, value)
+            .map(|selection| selection.inputs)
+            .unwrap_or_default();
+        Ok(paginate(selected, pagination))
+    }
+
+    pub async fn all_utxos(&self, pagination: Option<Paginate>) -> Result<Vec<Utxo>, APIError> {
+        let state = self.call(MockCall::Utxos).map_err(api_error)?;
+        Ok(paginate(state.utxos.clone(), pagination))
+    }
+
+    pub async fn collateral(&self, amount: Coin) -> Result<Vec<Utxo>, APIError> {
+        let state = self.call(MockCall::Collateral).map_err(api_error)?;
+        let total: Coin = state
+            .collateral
+            .iter()
+            .map(|utxo| lovelace_of(&utxo.value()))
+            .sum();
+        if total < amount {
+            return Ok(Vec::new());
+        }
+        Ok(state.collateral.clone())
+    }
+
+    /// sign the payload with the wallet's key (CIP-8 COSE_Sign1)
+    pub async fn sign_data(
+        &self,
+        address: &Address,
+        payload: impl AsRef<[u8]>,
+    ) -> Result<SignedData, APIError> {
+        let state = self.call(MockCall::SignData).map_err(api_error)?;
+        let secret_key = SecretKey::from(state.secret_key);
+        let payload = payload.as_ref().to_vec();
+        let address = address.to_vec();
+
+        let protected_header = cose_protected_header(&address);
+        let signed_data = cose_sig_structure(&protected_header, &payload);
+        let signature = secret_key.sign(&signed_data);
+        Ok(SignedData {
+            key: secret_key
+                .public_key()
+                .as_ref()
+                .try_into()
+                .unwrap_or([0; 32]),
+            signature: signature.as_ref().try_into().unwrap_or([0; 64]),
+            signed_data,
+            address,
+            payload,
+            hashed: false,
+        })
+    }
+
+    /// the vkey witness of the wallet's key
+    pub async fn sign_tx(
+        &self,
+        transaction: &TransactionBody,
+        partial_sign: bool,
+    ) -> Result<WitnessSet, SignTxError> {
+        let _ = partial_sign;
+        let state = self
+            .call(MockCall::SignTx)
+            .map_err(|(code, info)| SignTxError::from_code(code, info))?;
+        Ok(witness::sign_body_with_key(transaction, state.secret_key))
+    }
+
+    /// record the transaction and apply it to the UTxOs of the wallet:
+    /// the inputs are spent and the outputs to the wallet's address added
+    pub async fn submit_tx(&self, transaction: &Tx) -> Result<TxHash, SubmitTxError> {
+        let mut state = self
+            .call(MockCall::SubmitTx)
+            .map_err(|(code, info)| SubmitTxError::from_code(code, info))?;
+        let cbor = pallas_codec::minicbor::to_vec(transaction).unwrap_or_default();
+        let hash = inspect::tx_hash(&cbor).map_err(|error| APIError {
+            code: APIErrorCode::InternalError,
+            info: format!("Couldn't hash the transaction: {error}"),
+        })?;
+
+        let body = &transaction.transaction_body;
+        state
+            .utxos
+            .retain(|utxo| !body.inputs.contains(&utxo.input));
+        let own = state.address().to_vec();
+        for (index, output) in body.outputs.iter().enumerate() {
+            let utxo = Utxo {
+                input: TransactionInput {
+                    transaction_id: hash,
+                    index: index as u64,
+                },
+                output: output.clone(),
+            };
+            if utxo.address().is_ok_and(|address| address.to_vec() == own) {
+                state.utxos.push(utxo);
+            }
+        }
+        state.submitted.push(transaction.clone());
+        Ok(hash)
+    }
+}
+
+impl WalletApi for MockCip30Api {
+    async fn network_id(&self) -> Result<NetworkId, APIError> {
+        MockCip30Api::network_id(self).await
+    }
+
+    async fn balance(&self) -> Result<Value, APIError> {
+        MockCip30Api::balance(self).await
+    }
+
+    async fn used_addresses(&self, pagination: Option<Paginate>) -> Result<Vec<Address>, APIError> {
+        MockCip30Api::used_addresses(self, pagination).await
+    }
+
+    async fn unused_addresses(&self) -> Result<Vec<Address>, APIError> {
+        MockCip30Api::unused_addresses(self).await
+    }
+
+    async fn change_address(&self) -> Result<Address, APIError> {
+        MockCip30Api::change_address(self).await
+    }
+
+    async fn reward_addresses(&self) -> Result<Vec<Address>, APIError> {
+        MockCip30Api::reward_addresses(self).await
+    }
+
+    async fn utxos(&self, pagination: Option<Paginate>) -> Result<Vec<Utxo>, APIError> {
+        MockCip30Api::all_utxos(self, pagination).await
+    }
+
+    async fn select_utxos(
+        &self,
+        value: &Value,
+        pagination: Option<Paginate>,
+    ) -> Result<Vec<Utxo>, APIError> {
+        MockCip30Api::select_utxos(self, value, pagination).await
+    }
+
+    async fn collateral(&self, amount: Coin) -> Result<Vec<Utxo>, APIError> {
+        MockCip30Api::collateral(self, amount).await
+    }
+
+    async fn sign_data(&self, address: &Address, payload: &[u8]) -> Result<SignedData, APIError> {
+        MockCip30Api::sign_data(self, address, payload).await
+    }
+
+    async fn sign_tx(
+        &self,
+        transaction: &TransactionBody,
+        partial_sign: bool,
+    ) -> Result<WitnessSet, SignTxError> {
+        MockCip30Api::sign_tx(self, transaction, partial_sign).await
+    }
+
+    async fn submit_tx(&self, transaction: &Tx) -> Result<TxHash, SubmitTxError> {
+        MockCip30Api::submit_tx(self, transaction).await
+    }
+}
+
+fn paginate<T>(items: Vec<T>, pagination: Option<Paginate>) -> Vec<T> {
+    match pagination {
+        Some(pagination) => items
+            .into_iter()
+            .skip(pagination.page.saturating_mul(pagination.limit()))
+            .take(pagination.limit())
+            .collect(),
+        None => items,
+    }
+}
+
+/// `{ 1: -8 (EdDSA), "address": address }`
+fn cose_protected_header(address: &[u8]) -> Vec<u8> {
+    use cbor_event::{Len, se::Serializer};
+
+    let mut header = Serializer::new_vec();
+    let _ = header.write_map(Len::Len(2));
+    let _ = header.write_unsigned_integer(1);
+    let _ = header.write_negative_integer(-8);
+    let _ = header.write_text("address");
+    let _ = header.write_bytes(address);
+    header.finalize()
+}
+
+/// the `Sig_structure` of the COSE_Sign1, without external data
+fn cose_sig_structure(protected_header: &[u8], payload: &[u8]) -> Vec<u8> {
+    use cbor_event::{Len, se::Serializer};
+
+    let mut structure = Serializer::new_vec();
+    let _ = structure.write_array(Len::Len(4));
+    let _ = structure.write_text("Signature1");
+    let _ = structure.write_bytes(protected_header);
+    let _ = structure.write_bytes([]);
+    let _ = structure.write_bytes(payload);
+    structure.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cardano::{BuildParams, TxIntent};
+    use futures::executor::block_on;
+
+    #[test]
+    fn send_from_mock_wallet() {
+        let wallet = MockWallet::new("mock", [7; 32], NetworkId::Preview);
+        wallet.add_utxo(Value::Coin(10_000_000));
+        let connected = block_on(wallet.enable()).unwrap();
+
+        let change = block_on(connected.change_address()).unwrap();
+        let utxos = UtxoSet::from(block_on(connected.all_utxos(None)).unwrap());
+        let mut tx = TxIntent::new()
+            .pay(&wallet.address(), Value::Coin(2_000_000))
+            .build(&utxos, &BuildParams::defaults(change))
+            .unwrap();
+        let witnesses = block_on(connected.sign_tx(&tx.transaction_body, false)).unwrap();
+        tx.transaction_witness_set = witnesses;
+
+        let hash = block_on(connected.submit_tx(&tx)).unwrap();
+        assert_eq!(hash, Hasher::<256>::hash_cbor(&tx.transaction_body));
+        assert_eq!(wallet.submitted().len(), 1);
+        // the payment and the change went back to the wallet
+        let balance = block_on(connected.lovelace()).unwrap();
+        assert_eq!(balance, 10_000_000 - tx.transaction_body.fee);
+    }
+
+    /// a dApp function written against the trait
+    async fn pay(wallet: &impl WalletApi, to: &Address) -> Result<TxHash, crate::error::SendError> {
+        let change = wallet.change_address().await?;
+        wallet
+            .send_with(to, 2_000_000, &BuildParams::defaults(change))
+            .await
+    }
+
+    #[test]
+    fn generic_over_wallet_api() {
+        let wallet = MockWallet::new("mock", [7; 32], NetworkId::Preview);
+        wallet.add_utxo(Value::Coin(10_000_000));
+        let connected = block_on(wallet.enable()).unwrap();
+
+        let hash = block_on(pay(&connected, &wallet.address())).unwrap();
+        let submitted = wallet.submitted();
+        assert_eq!(submitted.len(), 1);
+        assert_eq!(
+            hash,
+            Hasher::<256>::hash_cbor(&submitted[0].transaction_body)
+        );
+        assert_eq!(
+            submitted[0]
+                .transaction_witness_set
+                .vkeywitness
+                .iter()
+                .count(),
+            1
+        );
+
+        wallet.fail_next(MockCall::SignTx, 2, "declined");
+        assert!(block_on(pay(&connected, &wallet.address())).is_err());
+    }
+
+    #[test]
+    fn injected_failures() {
+        let wallet = MockWallet::new("mock", [7; 32], NetworkId::Preview);
+        let connected = block_on(wallet.enable()).unwrap();
+
+        wallet.fail_next(MockCall::SignTx, 2, "declined");
+        let body = crate::cardano::testing::empty_body();
+        assert!(
+            block_on(connected.sign_tx(&body, false))
+                .unwrap_err()
+                .is_user_declined()
+        );
+        assert!(block_on(connected.sign_tx(&body, false)).is_ok());
+
+        wallet.fail_next(MockCall::Balance, -4, "account changed");
+        assert_eq!(
+            block_on(connected.balance()).unwrap_err().code,
+            APIErrorCode::AccountChange
+        );
+    }
+
+    #[test]
+    fn sign_the_whole_transaction() {
+        let wallet = MockWallet::new("mock", [7; 32], NetworkId::Preview);
+        let connected = block_on(wallet.enable()).unwrap();
+        let body = crate::cardano::testing::empty_body();
+        let other = witness::sign_body_with_key(&body, [8; 32]);
+        let tx = crate::cardano::assemble_tx(body, &[other], true, None);
+
+        let signed = block_on(connected.sign_transaction(&tx, true)).unwrap();
+
+        assert_eq!(
+            witness::vkey_hashes(&signed.transaction_witness_set).len(),
+            2
+        );
+        assert!(crate::cardano::verify_witnesses(&signed).is_ok());
+    }
+
+    #[test]
+    fn signed_data_verifies() {
+        let wallet = MockWallet::new("mock", [7; 32], NetworkId::Preview);
+        let connected = block_on(wallet.enable()).unwrap();
+
+        let signed = block_on(connected.sign_data(&wallet.address(), b"login")).unwrap();
+
+        assert_eq!(signed.verify_address(&wallet.address()), Ok(()));
+        assert!(signed.signs(b"login"));
+    }
+}
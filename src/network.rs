@@ -0,0 +1,196 @@
+/*!
+Bech32 prefixes (HRPs) of the Cardano networks.
+
+Every bech32 string produced or parsed by this crate goes through the
+[`Prefixes`] of the network: `addr`/`addr_test` for the payment
+addresses, `stake`/`stake_test` for the reward addresses and so on.
+Custom networks (e.g. a private devnet) can [`register`] their own.
+*/
+
+use crate::{Address, NetworkId};
+use bech32::{FromBase32 as _, ToBase32 as _, Variant};
+use std::{cell::RefCell, collections::HashMap};
+
+#[derive(Debug, thiserror::Error)]
+pub enum PrefixError {
+    #[error("Invalid bech32 string: {0}")]
+    Bech32(#[from] bech32::Error),
+    #[error("Unexpected prefix `{found}', expected `{expected}'")]
+    UnexpectedPrefix { found: String, expected: String },
+    #[error("Invalid address: {0}")]
+    Address(#[from] pallas_addresses::Error),
+}
+
+/// The HRPs used on a network.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Prefixes {
+    pub address: String,
+    pub stake: String,
+    pub pool: String,
+    pub drep: String,
+    pub asset: String,
+}
+
+thread_local! {
+    static CUSTOM: RefCell<HashMap<NetworkId, Prefixes>> = RefCell::new(HashMap::new());
+}
+
+/// register the prefixes of a custom network, overriding the default
+/// ones if the network is already known
+pub fn register(network: NetworkId, prefixes: Prefixes) {
+    CUSTOM.with(|custom| custom.borrow_mut().insert(network, prefixes));
+}
+
+impl Prefixes {
+    pub fn mainnet() -> Self {
+        Self {
+            address: "addr".to_owned(),
+            stake: "stake".to_owned(),
+            pool: "pool".to_owned(),
+            drep: "drep".to_owned(),
+            asset: "asset".to_owned(),
+        }
+    }
+
+    /// the prefixes shared by the public test networks
+    pub fn testnet() -> Self {
+        Self {
+            address: "addr_test".to_owned(),
+            stake: "stake_test".to_owned(),
+            ..Self::mainnet()
+        }
+    }
+
+    /// the prefixes of the given network
+    ///
+    /// Unknown networks that were not [`register`]ed use the test network
+    /// prefixes.
+    pub fn of(network: NetworkId) -> Self {
+        if let Some(prefixes) = CUSTOM.with(|custom| custom.borrow().get(&network).cloned()) {
+            return prefixes;
+        }
+
+        match network {
+            NetworkId::Mainnet => Self::mainnet(),
+            NetworkId::PreProduction | NetworkId::Preview | NetworkId::Unknown(_) => {
+                Self::testnet()
+            }
+        }
+    }
+
+    /// encode the address with the prefixes of this network
+    ///
+    /// Byron addresses have no bech32 representation and are encoded in
+    /// base58.
+    pub fn encode_address(&self, address: &Address) -> Result<String, PrefixError> {
+        match address {
+            Address::Byron(address) => Ok(address.to_base58()),
+            Address::Shelley(address) => encode(&self.address, &address.to_vec()),
+            Address::Stake(address) => encode(&self.stake, &address.to_vec()),
+        }
+    }
+
+    /// parse a bech32 (or base58 for Byron) address, checking the prefix
+    pub fn decode_address(&self, address: &str) -> Result<Address, PrefixError> {
+        let Ok((hrp, data, _)) = bech32::decode(address) else {
+            return Ok(pallas_addresses::ByronAddress::from_base58(address)?.into());
+        };
+
+        if hrp != self.address && hrp != self.stake {
+            return Err(PrefixError::UnexpectedPrefix {
+                found: hrp,
+                expected: format!("{}' or `{}", self.address, self.stake),
+            });
+        }
+
+        let bytes = Vec::<u8>::from_base32(&data)?;
+        Ok(Address::from_bytes(&bytes)?)
+    }
+
+    pub fn encode_pool(&self, pool_id: &[u8]) -> Result<String, PrefixError> {
+        encode(&self.pool, pool_id)
+    }
+
+    pub fn encode_drep(&self, drep_id: &[u8]) -> Result<String, PrefixError> {
+        encode(&self.drep, drep_id)
+    }
+
+    /// encode a CIP-14 asset fingerprint
+    pub fn encode_asset(&self, fingerprint: &[u8]) -> Result<String, PrefixError> {
+        encode(&self.asset, fingerprint)
+    }
+
+    /// decode a bech32 string expecting the given prefix (one of the
+    /// fields of this table)
+    pub fn decode(&self, expected: &str, value: &str) -> Result<Vec<u8>, PrefixError> {
+        let (hrp, data, _) = bech32::decode(value)?;
+        if hrp != expected {
+            return Err(PrefixError::UnexpectedPrefix {
+                found: hrp,
+                expected: expected.to_owned(),
+            });
+        }
+        Ok(Vec::<u8>::from_base32(&data)?)
+    }
+}
+
+fn encode(hrp: &str, bytes: &[u8]) -> Result<String, PrefixError> {
+    Ok(bech32::encode(hrp, bytes.to_base32(), Variant::Bech32)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Hash;
+    use pallas_addresses::{Network, ShelleyAddress, ShelleyDelegationPart, ShelleyPaymentPart};
+
+    fn address(network: Network) -> Address {
+        ShelleyAddress::new(
+            network,
+            ShelleyPaymentPart::key_hash(Hash::from([1; 28])),
+            ShelleyDelegationPart::Null,
+        )
+        .into()
+    }
+
+    #[test]
+    fn encode_per_network() {
+        let mainnet = Prefixes::of(NetworkId::Mainnet)
+            .encode_address(&address(Network::Mainnet))
+            .unwrap();
+        let preview = Prefixes::of(NetworkId::Preview)
+            .encode_address(&address(Network::Testnet))
+            .unwrap();
+
+        assert!(mainnet.starts_with("addr1"));
+        assert!(preview.starts_with("addr_test1"));
+        assert!(Prefixes::mainnet().decode_address(&preview).is_err());
+        assert_eq!(
+            Prefixes::testnet().decode_address(&preview).unwrap(),
+            address(Network::Testnet)
+        );
+    }
+
+    #[test]
+    fn custom_network() {
+        let devnet = NetworkId::Unknown(3);
+        register(
+            devnet,
+            Prefixes {
+                address: "addr_dev".to_owned(),
+                stake: "stake_dev".to_owned(),
+                ..Prefixes::testnet()
+            },
+        );
+
+        let encoded = Prefixes::of(devnet)
+            .encode_address(&address(Network::Other(3)))
+            .unwrap();
+
+        assert!(encoded.starts_with("addr_dev1"));
+        assert_eq!(
+            Prefixes::of(devnet).decode_address(&encoded).unwrap(),
+            address(Network::Other(3))
+        );
+    }
+}
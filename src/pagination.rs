@@ -0,0 +1,113 @@
+//! Walk the pages of the paginated CIP-30 endpoints.
+//!
+//! The wallets answer the pages beyond the last one with a
+//! [`PaginateError`]; some also reject a page size greater than what they
+//! serve, with this size as the `max_size` hint. [`pages`] retries the
+//! first page with the hinted size and stops at the first short, empty or
+//! rejected page.
+
+use crate::{error::APIError, error::PaginateError, ffi::cip30_api::Paginate};
+use futures::stream::{self, Stream, StreamExt as _};
+
+/// one page, or the wallet's pagination boundary
+pub(crate) type PageResult<T> = Result<Result<Vec<T>, PaginateError>, APIError>;
+
+/// every item of every page `fetch` returns, starting with pages of
+/// `limit` items
+///
+/// The stream ends after the first error.
+pub(crate) fn pages<T, F, Fut>(limit: usize, fetch: F) -> impl Stream<Item = Result<T, APIError>>
+where
+    F: Fn(Paginate) -> Fut,
+    Fut: Future<Output = PageResult<T>>,
+{
+    stream::unfold(
+        (fetch, Some((0, limit.max(1)))),
+        |(fetch, next)| async move {
+            let (page, limit) = next?;
+            let (items, next) = match fetch(Paginate::new(page, limit)).await {
+                Ok(Ok(items)) => {
+                    let next = (items.len() >= limit).then_some((page + 1, limit));
+                    (Ok(items), next)
+                }
+                Ok(Err(PaginateError { max_size }))
+                    if page == 0 && (1..limit).contains(&max_size) =>
+                {
+                    (Ok(Vec::new()), Some((0, max_size)))
+                }
+                Ok(Err(PaginateError { .. })) => (Ok(Vec::new()), None),
+                Err(error) => (Err(error), None),
+            };
+            Some((items, (fetch, next)))
+        },
+    )
+    .flat_map(|items| {
+        let items: Vec<_> = match items {
+            Ok(items) => items.into_iter().map(Ok).collect(),
+            Err(error) => vec![Err(error)],
+        };
+        stream::iter(items)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::APIErrorCode;
+    use futures::executor::block_on;
+
+    /// a wallet of `len` items, serving pages of at most `max_size`
+    fn wallet(
+        len: usize,
+        max_size: usize,
+    ) -> impl Fn(Paginate) -> std::future::Ready<PageResult<usize>> {
+        move |pagination: Paginate| {
+            let (page, limit) = (pagination.page, pagination.limit());
+            let result = if limit > max_size {
+                Ok(Err(PaginateError { max_size }))
+            } else if page * limit >= len && page > 0 {
+                Ok(Err(PaginateError {
+                    max_size: len.div_ceil(limit),
+                }))
+            } else {
+                Ok(Ok((page * limit..len.min((page + 1) * limit)).collect()))
+            };
+            std::future::ready(result)
+        }
+    }
+
+    #[test]
+    fn every_page() {
+        let items: Vec<_> = block_on(pages(10, wallet(25, 100)).collect());
+        assert_eq!(items, (0..25).map(Ok).collect::<Vec<_>>());
+
+        let items: Vec<_> = block_on(pages(5, wallet(20, 100)).collect());
+        assert_eq!(items.len(), 20, "stops at the rejected page");
+
+        let items: Vec<_> = block_on(pages(10, wallet(0, 100)).collect());
+        assert!(items.is_empty());
+    }
+
+    #[test]
+    fn page_size_hint() {
+        let items: Vec<_> = block_on(pages(50, wallet(12, 5)).collect());
+        assert_eq!(items, (0..12).map(Ok).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn stops_on_error() {
+        let fetch = |pagination: Paginate| {
+            std::future::ready(if pagination.page == 0 {
+                Ok(Ok(vec![1, 2]))
+            } else {
+                Err(APIError {
+                    code: APIErrorCode::Refused,
+                    info: "Disconnected".to_owned(),
+                })
+            })
+        };
+        let items: Vec<_> = block_on(pages(2, fetch).collect());
+        assert_eq!(items.len(), 3);
+        assert!(items[2].is_err());
+    }
+}
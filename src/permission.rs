@@ -0,0 +1,150 @@
+/*!
+Reminder to re-verify long lived connections.
+
+A single page application may keep a [`ConnectedWallet`] for hours while
+the user locked the wallet or revoked the dApp in the meantime: the next
+call then fails. The [`PermissionTracker`] records when the wallet was
+enabled and emits [`Event::PermissionStale`] once this is older than
+the configured period, for the dApp to check `isEnabled()` again (see
+[`Connector::reverify`]) before the user acts.
+
+The [`Connector`] tracks the wallets it connects.
+
+[`ConnectedWallet`]: crate::ConnectedWallet
+[`Connector`]: crate::Connector
+[`Connector::reverify`]: crate::Connector::reverify
+*/
+
+use crate::{
+    events::{Event, EventBus},
+    ffi,
+};
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+#[derive(Debug, Clone)]
+struct Grant {
+    wallet: String,
+    at: u64,
+    notified: bool,
+}
+
+/// see the [module documentation](self)
+///
+/// Cloning gives another handle to the same tracker.
+#[derive(Debug, Clone)]
+pub struct PermissionTracker {
+    events: EventBus,
+    stale_after: Duration,
+    grant: Rc<RefCell<Option<Grant>>>,
+}
+
+impl PermissionTracker {
+    pub fn new(events: EventBus, stale_after: Duration) -> Self {
+        Self {
+            events,
+            stale_after,
+            grant: Rc::default(),
+        }
+    }
+
+    pub fn stale_after(&self) -> Duration {
+        self.stale_after
+    }
+
+    /// the wallet was enabled (or re-verified) at `now`, POSIX time in
+    /// milliseconds
+    pub fn granted(&self, wallet: &str, now: u64) {
+        self.grant.replace(Some(Grant {
+            wallet: wallet.to_owned(),
+            at: now,
+            notified: false,
+        }));
+    }
+
+    /// the wallet was disconnected
+    pub fn revoke(&self) {
+        self.grant.replace(None);
+    }
+
+    /// when the permission was granted, POSIX time in milliseconds
+    pub fn granted_at(&self) -> Option<u64> {
+        self.grant.borrow().as_ref().map(|grant| grant.at)
+    }
+
+    pub fn is_stale(&self, now: u64) -> bool {
+        self.granted_at()
+            .is_some_and(|at| u128::from(now.saturating_sub(at)) > self.stale_after.as_millis())
+    }
+
+    /// emit [`Event::PermissionStale`] if the permission became stale,
+    /// once per grant
+    ///
+    /// Returns whether the permission is stale.
+    pub fn check(&self, now: u64) -> bool {
+        if !self.is_stale(now) {
+            return false;
+        }
+
+        let event = {
+            let mut grant = self.grant.borrow_mut();
+            let grant = grant.as_mut().expect("a stale permission was granted");
+            (!grant.notified).then(|| {
+                grant.notified = true;
+                Event::PermissionStale {
+                    wallet: grant.wallet.clone(),
+                    granted_at: grant.at,
+                }
+            })
+        };
+        if let Some(event) = event {
+            self.events.emit(event);
+        }
+        true
+    }
+
+    /// [`check`](Self::check) every `interval`, as long as a permission
+    /// is tracked
+    pub async fn run(&self, interval: Duration) {
+        while self.granted_at().is_some() {
+            ffi::timer::sleep(interval).await;
+            self.check(js_sys::Date::now() as u64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: u64 = 60_000;
+
+    #[test]
+    fn stale_once_per_grant() {
+        let events = EventBus::new();
+        let received = Rc::new(RefCell::new(Vec::new()));
+        events.subscribe({
+            let received = Rc::clone(&received);
+            move |event| received.borrow_mut().push(event.clone())
+        });
+        let tracker = PermissionTracker::new(events, Duration::from_secs(30 * 60));
+
+        assert!(!tracker.check(0));
+        tracker.granted("lace", 0);
+        assert!(!tracker.check(30 * MINUTE));
+        assert!(tracker.check(31 * MINUTE));
+        assert!(tracker.check(40 * MINUTE));
+        assert_eq!(
+            *received.borrow(),
+            [Event::PermissionStale {
+                wallet: "lace".to_owned(),
+                granted_at: 0
+            }]
+        );
+
+        tracker.granted("lace", 40 * MINUTE);
+        assert!(!tracker.check(41 * MINUTE));
+        tracker.revoke();
+        assert!(!tracker.check(100 * MINUTE));
+        assert_eq!(received.borrow().len(), 1);
+    }
+}
@@ -0,0 +1,96 @@
+/*!
+Redaction of the sensitive values in the `Debug` output.
+
+With the `redact` feature enabled, the `Debug` implementation of the
+types holding addresses, keys or signatures (e.g. [`SignedData`])
+prints truncated forms (`61a1…e3f4`) so they don't leak through a
+stray `dbg!` or a log line in a production bundle. The full form is
+still available explicitly with [`Redact::unredacted`].
+
+[`SignedData`]: crate::SignedData
+*/
+
+use std::fmt;
+
+/// number of hex characters kept at each end of a redacted value
+const KEPT: usize = 4;
+
+/// types whose `Debug` output is redacted with the `redact` feature
+pub trait Redact {
+    /// format the value, truncating the sensitive fields if `redact`
+    fn fmt_debug(&self, f: &mut fmt::Formatter<'_>, redact: bool) -> fmt::Result;
+
+    /// a `Debug` view of the value that is never redacted
+    fn unredacted(&self) -> Unredacted<'_, Self> {
+        Unredacted(self)
+    }
+}
+
+/// see [`Redact::unredacted`]
+pub struct Unredacted<'a, T: ?Sized>(&'a T);
+
+impl<T: Redact + ?Sized> fmt::Debug for Unredacted<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_debug(f, false)
+    }
+}
+
+/// whether the `Debug` output is redacted by default
+pub(crate) const fn enabled() -> bool {
+    cfg!(feature = "redact")
+}
+
+/// `Debug` of sensitive bytes: hex encoded, truncated if `redact`
+pub(crate) struct Bytes<'a> {
+    pub(crate) bytes: &'a [u8],
+    pub(crate) redact: bool,
+}
+
+impl fmt::Debug for Bytes<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = hex::encode(self.bytes);
+        if self.redact && hex.len() > 2 * KEPT {
+            write!(f, "{}…{}", &hex[..KEPT], &hex[hex.len() - KEPT..])
+        } else {
+            f.write_str(&hex)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_bytes() {
+        let bytes = [0xab; 32];
+
+        let redacted = format!(
+            "{:?}",
+            Bytes {
+                bytes: &bytes,
+                redact: true
+            }
+        );
+        let full = format!(
+            "{:?}",
+            Bytes {
+                bytes: &bytes,
+                redact: false
+            }
+        );
+
+        assert_eq!(redacted, "abab…abab");
+        assert_eq!(full, "ab".repeat(32));
+        assert_eq!(
+            format!(
+                "{:?}",
+                Bytes {
+                    bytes: &bytes[..2],
+                    redact: true
+                }
+            ),
+            "abab"
+        );
+    }
+}
@@ -0,0 +1,178 @@
+/*!
+The wallets this crate knows about and how they depart from CIP-30.
+
+The extensions inject their CIP-30 API under a key of `window.cardano`;
+[`WalletKind::from_key`] recognizes the known wallets from this key
+(the `name` of the API is free text and changes with the versions). The
+[`WalletQuirks`] of a kind tell the dApps what to expect from it:
+
+```no_run
+# use cardano_connector::{WalletKind, wallets};
+for wallet in wallets() {
+    if wallet.kind() == WalletKind::Nami || !wallet.quirks().cip95 {
+        // hide the governance features
+    }
+}
+```
+*/
+
+use std::fmt;
+
+/// A wallet, recognized from the key it is injected under.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WalletKind {
+    Lace,
+    Eternl,
+    Flint,
+    Nami,
+    Yoroi,
+    Typhon,
+    Vespr,
+    Gero,
+    NuFi,
+    Begin,
+    /// a wallet this crate doesn't know about, with its key
+    Unknown(String),
+}
+
+/// What a wallet does differently, as known when this crate was
+/// released.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WalletQuirks {
+    /// the CIP-95 (governance) extension can be requested
+    pub cip95: bool,
+    /// `signTx` returns the whole signed transaction instead of its
+    /// witness set, [`ConnectedWallet::sign_transaction`](crate::ConnectedWallet::sign_transaction)
+    /// takes the witness set out of it
+    pub sign_tx_returns_tx: bool,
+    /// the collateral is only given by `experimental.getCollateral`
+    pub experimental_collateral: bool,
+    /// the wallet is no longer maintained, its users are asked to move
+    /// to another one
+    pub deprecated: bool,
+}
+
+impl WalletKind {
+    /// the wallet injected under the `key` of `window.cardano`
+    ///
+    /// The former keys of the wallets are recognized too (`ccvault` for
+    /// Eternl, `gerowallet` for Gero...).
+    pub fn from_key(key: &str) -> Self {
+        match key {
+            "lace" => Self::Lace,
+            "eternl" | "ccvault" => Self::Eternl,
+            "flint" => Self::Flint,
+            "nami" => Self::Nami,
+            "yoroi" => Self::Yoroi,
+            "typhoncip30" | "typhon" => Self::Typhon,
+            "vespr" => Self::Vespr,
+            "gerowallet" | "gero" => Self::Gero,
+            "nufi" => Self::NuFi,
+            "begin" => Self::Begin,
+            key => Self::Unknown(key.to_owned()),
+        }
+    }
+
+    /// the key the wallet is injected under, the current one for the
+    /// known wallets
+    pub fn key(&self) -> &str {
+        match self {
+            Self::Lace => "lace",
+            Self::Eternl => "eternl",
+            Self::Flint => "flint",
+            Self::Nami => "nami",
+            Self::Yoroi => "yoroi",
+            Self::Typhon => "typhoncip30",
+            Self::Vespr => "vespr",
+            Self::Gero => "gerowallet",
+            Self::NuFi => "nufi",
+            Self::Begin => "begin",
+            Self::Unknown(key) => key,
+        }
+    }
+
+    /// the quirks of the wallet, none for an unknown wallet
+    pub fn quirks(&self) -> WalletQuirks {
+        let cip95 = WalletQuirks {
+            cip95: true,
+            ..WalletQuirks::default()
+        };
+        match self {
+            Self::Lace | Self::Eternl | Self::Yoroi | Self::Typhon | Self::Vespr | Self::NuFi => {
+                cip95
+            }
+            Self::Nami => WalletQuirks {
+                experimental_collateral: true,
+                deprecated: true,
+                ..WalletQuirks::default()
+            },
+            Self::Flint | Self::Gero | Self::Begin | Self::Unknown(_) => WalletQuirks::default(),
+        }
+    }
+
+    pub fn is_known(&self) -> bool {
+        !matches!(self, Self::Unknown(_))
+    }
+}
+
+impl fmt::Display for WalletKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Lace => "Lace",
+            Self::Eternl => "Eternl",
+            Self::Flint => "Flint",
+            Self::Nami => "Nami",
+            Self::Yoroi => "Yoroi",
+            Self::Typhon => "Typhon",
+            Self::Vespr => "VESPR",
+            Self::Gero => "GeroWallet",
+            Self::NuFi => "NuFi",
+            Self::Begin => "Begin",
+            Self::Unknown(key) => key,
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds_from_keys() {
+        assert_eq!(WalletKind::from_key("eternl"), WalletKind::Eternl);
+        assert_eq!(WalletKind::from_key("ccvault"), WalletKind::Eternl);
+        assert_eq!(WalletKind::from_key("typhoncip30"), WalletKind::Typhon);
+        assert_eq!(
+            WalletKind::from_key("mywallet"),
+            WalletKind::Unknown("mywallet".to_owned())
+        );
+
+        for key in [
+            "lace",
+            "eternl",
+            "flint",
+            "nami",
+            "yoroi",
+            "typhoncip30",
+            "vespr",
+            "gerowallet",
+            "nufi",
+            "begin",
+            "mywallet",
+        ] {
+            assert_eq!(WalletKind::from_key(key).key(), key);
+        }
+    }
+
+    #[test]
+    fn quirks() {
+        assert!(WalletKind::Lace.quirks().cip95);
+        assert!(WalletKind::Nami.quirks().experimental_collateral);
+        assert!(WalletKind::Nami.quirks().deprecated);
+        assert_eq!(
+            WalletKind::Unknown("mywallet".to_owned()).quirks(),
+            WalletQuirks::default()
+        );
+    }
+}
@@ -0,0 +1,275 @@
+/*!
+Sequences of related transactions signed one after the other.
+
+Marketplace flows chain transactions quickly: list an NFT, delist it,
+buy another one... Each transaction must not spend the UTxOs of the
+previous ones while they are still pending, but it may spend the outputs
+they create (e.g. their change). The [`SigningSession`] keeps this shared
+state across the steps and computes the [`TxSummary`] of each step so
+the user can confirm it before the wallet is asked to sign.
+
+```no_run
+# use cardano_connector::{ConnectedWallet, UtxoSet, cardano::{DepositParams, Tx}};
+# use cardano_connector::signing_session::SigningSession;
+# async fn test(
+#     wallet: &ConnectedWallet,
+#     params: DepositParams,
+#     utxos: UtxoSet,
+#     list: Tx,
+# ) -> anyhow::Result<()> {
+let mut session = SigningSession::new(params);
+
+let step = session.prepare("list", list, &utxos)?;
+// display `step.summary()` and wait for the user's confirmation
+let hash = session.submit(wallet, step).await?;
+# Ok(()) }
+```
+*/
+
+use crate::{
+    ConnectedWallet, TxHash, Utxo, UtxoSet,
+    cardano::{
+        DepositParams, PendingUtxos, SummaryError, TransactionInput, Tx, TxSummary, tx_hash,
+    },
+    error::{SignTxError, SubmitTxError},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    #[error(transparent)]
+    Summary(#[from] SummaryError),
+    /// the input is already spent by a pending step of the session
+    #[error("Input {}#{} is already spent by the transaction {spent_by}", .input.transaction_id, .input.index)]
+    Conflict {
+        input: TransactionInput,
+        spent_by: TxHash,
+    },
+    #[error(transparent)]
+    Sign(#[from] SignTxError),
+    #[error(transparent)]
+    Submit(#[from] SubmitTxError),
+}
+
+/// A transaction of the session waiting for the user's confirmation.
+#[derive(Debug, Clone)]
+pub struct PreparedStep {
+    label: String,
+    tx: Tx,
+    summary: TxSummary,
+}
+
+impl PreparedStep {
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn tx(&self) -> &Tx {
+        &self.tx
+    }
+
+    /// what the transaction does with the funds, to display before
+    /// submitting the step
+    pub fn summary(&self) -> &TxSummary {
+        &self.summary
+    }
+
+    pub fn hash(&self) -> TxHash {
+        tx_hash(&self.tx.transaction_body)
+    }
+}
+
+/// A step of the session that was submitted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionStep {
+    pub label: String,
+    pub hash: TxHash,
+}
+
+/// see the [module documentation](self)
+#[derive(Debug, Clone)]
+pub struct SigningSession {
+    params: DepositParams,
+    pending: PendingUtxos,
+    /// the outputs of the submitted steps
+    produced: UtxoSet,
+    steps: Vec<SessionStep>,
+}
+
+impl SigningSession {
+    pub fn new(params: DepositParams) -> Self {
+        Self {
+            params,
+            pending: PendingUtxos::new(),
+            produced: UtxoSet::new(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// the submitted steps, in order
+    pub fn steps(&self) -> &[SessionStep] {
+        &self.steps
+    }
+
+    /// the UTxOs spent by the pending steps
+    pub fn pending(&self) -> &PendingUtxos {
+        &self.pending
+    }
+
+    /// the `utxos` (e.g. from the wallet) not spent by a pending step
+    pub fn available(&self, utxos: &UtxoSet) -> UtxoSet {
+        self.pending.available(utxos)
+    }
+
+    /// the outputs of the pending steps that are not spent yet
+    ///
+    /// They can be spent by the next steps before they are on chain.
+    pub fn chained(&self) -> UtxoSet {
+        self.pending.available(&self.produced)
+    }
+
+    /// check the transaction against the pending steps and compute its
+    /// summary
+    ///
+    /// `inputs` holds the UTxOs the transaction spends, the outputs of the
+    /// pending steps are looked up too.
+    pub fn prepare(
+        &self,
+        label: impl Into<String>,
+        tx: Tx,
+        inputs: &UtxoSet,
+    ) -> Result<PreparedStep, SessionError> {
+        self.check_conflicts(&tx)?;
+
+        let resolved: UtxoSet = inputs
+            .iter()
+            .chain(self.chained().iter())
+            .cloned()
+            .collect();
+        let summary = TxSummary::new(&tx.transaction_body, &resolved, &self.params)?;
+
+        Ok(PreparedStep {
+            label: label.into(),
+            tx,
+            summary,
+        })
+    }
+
+    /// ask the wallet to sign the (confirmed) step and submit it
+    ///
+    /// The inputs are checked again as another step may have been
+    /// submitted since this one was prepared.
+    pub async fn submit(
+        &mut self,
+        wallet: &ConnectedWallet,
+        step: PreparedStep,
+    ) -> Result<TxHash, SessionError> {
+        self.check_conflicts(&step.tx)?;
+
+        let tx = wallet.sign_transaction(&step.tx, false).await?;
+        wallet.submit_tx(&tx).await?;
+
+        Ok(self.record(step.label, &tx))
+    }
+
+    /// the transaction of a step is on chain (or is known to be dropped)
+    ///
+    /// Its outputs are now returned by the wallet, they are no longer
+    /// part of the [`chained`](Self::chained) UTxOs.
+    pub fn confirm(&mut self, hash: &TxHash) {
+        self.pending.confirm(hash);
+        self.produced = self
+            .produced
+            .iter()
+            .filter(|utxo| &utxo.transaction_id() != hash)
+            .cloned()
+            .collect();
+    }
+
+    fn check_conflicts(&self, tx: &Tx) -> Result<(), SessionError> {
+        for input in tx.transaction_body.inputs.iter() {
+            if let Some(pending) = self
+                .pending
+                .iter()
+                .find(|pending| pending.inputs.contains(input))
+            {
+                return Err(SessionError::Conflict {
+                    input: input.clone(),
+                    spent_by: pending.hash,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, label: String, tx: &Tx) -> TxHash {
+        let hash = self.pending.track(tx);
+        for (index, output) in tx.transaction_body.outputs.iter().enumerate() {
+            self.produced.push(Utxo {
+                input: TransactionInput {
+                    transaction_id: hash,
+                    index: index as u64,
+                },
+                output: output.clone(),
+            });
+        }
+        self.steps.push(SessionStep { label, hash });
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Value,
+        cardano::{
+            TxIntent,
+            testing::{address, params, utxo},
+        },
+    };
+
+    fn pay(utxos: &UtxoSet, coin: u64) -> Tx {
+        TxIntent::new()
+            .pay(&address(2), Value::Coin(coin))
+            .build(utxos, &params())
+            .unwrap()
+    }
+
+    #[test]
+    fn chain_steps() {
+        let mut session = SigningSession::new(params().deposits);
+        let utxos: UtxoSet = vec![utxo(0, &address(1), Value::Coin(10_000_000))]
+            .into_iter()
+            .collect();
+
+        let list = session
+            .prepare("list", pay(&utxos, 2_000_000), &utxos)
+            .unwrap();
+        assert_eq!(list.summary().net_change.len(), 2);
+        let hash = session.record(list.label, &list.tx);
+
+        // the wallet still returns the spent UTxO
+        assert!(matches!(
+            session.prepare("buy", pay(&utxos, 3_000_000), &utxos),
+            Err(SessionError::Conflict { spent_by, .. }) if spent_by == hash
+        ));
+        assert!(session.available(&utxos).is_empty());
+
+        // the change of the first step can be spent
+        let chained = session.chained();
+        assert_eq!(chained.len(), 2);
+        let buy = session
+            .prepare("buy", pay(&chained, 3_000_000), &UtxoSet::new())
+            .unwrap();
+        session.record(buy.label, &buy.tx);
+        assert_eq!(session.steps().len(), 2);
+
+        session.confirm(&hash);
+        assert!(
+            session
+                .chained()
+                .iter()
+                .all(|utxo| utxo.transaction_id() != hash)
+        );
+    }
+}
@@ -0,0 +1,133 @@
+/*!
+Persistence of the connector's state across page reloads.
+
+The [`Storage`] trait abstracts a simple key/value store. In the browser
+[`LocalStorage`] is backed by `window.localStorage`, [`MemoryStorage`]
+keeps everything in memory (tests, or when persistence is not wanted).
+*/
+
+use crate::ffi::storage::LOCAL_STORAGE;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum StorageError {
+    #[error("The storage is not available")]
+    Unavailable,
+    #[error("Couldn't write to the storage: {0}")]
+    Write(String),
+    /// the stored value can't be parsed, it is left untouched
+    #[error("The stored {key} can't be read: {reason}")]
+    Corrupt { key: String, reason: String },
+}
+
+pub trait Storage {
+    fn get(&self, key: &str) -> Option<String>;
+
+    fn set(&self, key: &str, value: &str) -> Result<(), StorageError>;
+
+    fn remove(&self, key: &str) -> Result<(), StorageError>;
+}
+
+impl<S: Storage> Storage for &S {
+    fn get(&self, key: &str) -> Option<String> {
+        (*self).get(key)
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), StorageError> {
+        (*self).set(key, value)
+    }
+
+    fn remove(&self, key: &str) -> Result<(), StorageError> {
+        (*self).remove(key)
+    }
+}
+
+impl<S: Storage + ?Sized> Storage for Rc<S> {
+    fn get(&self, key: &str) -> Option<String> {
+        (**self).get(key)
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), StorageError> {
+        (**self).set(key, value)
+    }
+
+    fn remove(&self, key: &str) -> Result<(), StorageError> {
+        (**self).remove(key)
+    }
+}
+
+/// the JSON value stored under `key`, `None` if there is none
+pub(crate) fn read_json<T: serde::de::DeserializeOwned>(
+    storage: &(impl Storage + ?Sized),
+    key: &str,
+) -> Result<Option<T>, StorageError> {
+    storage
+        .get(key)
+        .map(|value| serde_json::from_str(&value))
+        .transpose()
+        .map_err(|error| StorageError::Corrupt {
+            key: key.to_owned(),
+            reason: error.to_string(),
+        })
+}
+
+/// Storage kept in memory, lost when dropped.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    entries: RefCell<HashMap<String, String>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        self.entries.borrow().get(key).cloned()
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), StorageError> {
+        self.entries
+            .borrow_mut()
+            .insert(key.to_owned(), value.to_owned());
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> Result<(), StorageError> {
+        self.entries.borrow_mut().remove(key);
+        Ok(())
+    }
+}
+
+/// Storage backed by the browser's `window.localStorage`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LocalStorage;
+
+impl Storage for LocalStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        LOCAL_STORAGE.with(|storage| storage.as_ref()?.get_item(key).ok().flatten())
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), StorageError> {
+        LOCAL_STORAGE.with(|storage| {
+            storage
+                .as_ref()
+                .ok_or(StorageError::Unavailable)?
+                .set_item(key, value)
+                // typically the quota is exceeded
+                .map_err(|error| StorageError::Write(format!("{error:?}")))
+        })
+    }
+
+    fn remove(&self, key: &str) -> Result<(), StorageError> {
+        LOCAL_STORAGE.with(|storage| {
+            storage
+                .as_ref()
+                .ok_or(StorageError::Unavailable)?
+                .remove_item(key)
+                .map_err(|error| StorageError::Write(format!("{error:?}")))
+        })
+    }
+}
@@ -0,0 +1,121 @@
+/*!
+How much the crate trusts the responses of the wallets.
+
+Not every wallet follows CIP-30 to the letter. The [`Strictness`] of the
+[`Connector`] the wallet was connected with (see
+[`ConnectorBuilder::with_strictness`]), or else the one set with
+[`set_strictness`], decides what happens when a response is off:
+
+* [`Lenient`](Strictness::Lenient): the items of the UTxO and address
+  lists (`getUtxos`, `getCollateral`, `getUsedAddresses`,
+  `getUnusedAddresses`, `getRewardAddresses`) that cannot be decoded are
+  skipped, the rest of the list is used;
+* [`Strict`](Strictness::Strict), the default: such a list fails the
+  call;
+* [`Paranoid`](Strictness::Paranoid): as strict, and the signatures
+  returned by the wallet are verified before being handed to the dApp
+  (the `signData` signature against the address, the vkey witnesses of
+  `signTx` and of the CIP-103 `signTxs` against the transaction ids), at
+  the cost of the verification.
+
+The other responses (the balance, a signed transaction...) fail the
+call when they cannot be decoded, whatever the strictness. Unknown
+fields are ignored at every level: the strictness is about the
+malformed data, not the extra data.
+
+The non conforming responses are reported either way, see
+[`telemetry`](crate::telemetry).
+
+[`Connector`]: crate::connector::Connector
+[`ConnectorBuilder::with_strictness`]: crate::connector::ConnectorBuilder::with_strictness
+*/
+
+use std::cell::Cell;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Strictness {
+    Lenient,
+    #[default]
+    Strict,
+    Paranoid,
+}
+
+impl Strictness {
+    /// the malformed items of a list are skipped
+    pub fn skips_malformed(&self) -> bool {
+        *self == Self::Lenient
+    }
+
+    /// the signatures of the wallet are verified
+    pub fn verifies_signatures(&self) -> bool {
+        *self == Self::Paranoid
+    }
+}
+
+thread_local! {
+    static STRICTNESS: Cell<Strictness> = const { Cell::new(Strictness::Strict) };
+}
+
+/// the strictness of the following calls to the wallets not connected
+/// through a [`Connector`](crate::connector::Connector)
+pub fn set_strictness(strictness: Strictness) {
+    STRICTNESS.with(|current| current.set(strictness));
+}
+
+pub fn strictness() -> Strictness {
+    STRICTNESS.with(Cell::get)
+}
+
+/// decode every item of a list, following the `strictness`
+///
+/// `report` is called for every item `decode` rejects; the first one is
+/// returned as the error unless the malformed items are skipped.
+pub(crate) fn decode_all<I, T, E>(
+    strictness: Strictness,
+    items: impl IntoIterator<Item = I>,
+    mut decode: impl FnMut(&I) -> Result<T, E>,
+    mut report: impl FnMut(&I, &E),
+) -> Result<Vec<T>, E> {
+    let skip = strictness.skips_malformed();
+    let mut decoded = Vec::new();
+    for item in items {
+        match decode(&item) {
+            Ok(value) => decoded.push(value),
+            Err(error) => {
+                report(&item, &error);
+                if !skip {
+                    return Err(error);
+                }
+            }
+        }
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed_items() {
+        let decode = |item: &&str| item.parse::<u8>().map_err(|_| item.to_string());
+        let mut reported = Vec::new();
+
+        assert_eq!(strictness(), Strictness::Strict);
+        assert_eq!(
+            decode_all(Strictness::Strict, ["1", "x", "y"], decode, |item, _| {
+                reported.push(*item)
+            }),
+            Err("x".to_owned())
+        );
+        assert_eq!(reported, ["x"]);
+
+        assert_eq!(
+            decode_all(Strictness::Lenient, ["1", "x", "2"], decode, |item, _| {
+                reported.push(*item)
+            }),
+            Ok(vec![1, 2])
+        );
+        assert_eq!(reported, ["x", "x"]);
+    }
+}
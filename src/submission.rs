@@ -0,0 +1,517 @@
+/*!
+Queued submission of signed transactions.
+
+When the user is offline, or the backend is down, a signed transaction
+would be lost if the page was closed before it could be submitted. The
+[`SubmissionQueue`] persists such transactions in a [`Storage`] and
+retries them until they are accepted or their TTL passes.
+
+Progress is reported on the [`EventBus`]: [`Event::TxQueued`],
+[`Event::TxSubmitted`], [`Event::TxExpired`] and [`Event::TxRejected`].
+*/
+
+use crate::{
+    TxHash,
+    backend::{BackendError, TxSubmitter},
+    cardano::{ValidityError, check_validity, inspect::decode_tx},
+    events::{Event, EventBus},
+    storage::{Storage, StorageError, read_json},
+};
+use futures::{FutureExt as _, channel::oneshot, future::Shared};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
+
+const QUEUE_KEY: &str = "cardano-connector.submission-queue";
+const SUBMITTED_KEY: &str = "cardano-connector.submitted";
+/// how many submitted hashes are remembered across sessions
+const MAX_SUBMITTED: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SubmissionError {
+    #[error("Invalid transaction: {0}")]
+    InvalidTx(String),
+    /// see [`with_fresh_ttl`] to rebuild the transaction
+    ///
+    /// [`with_fresh_ttl`]: crate::cardano::with_fresh_ttl
+    #[error(transparent)]
+    Validity(#[from] ValidityError),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+}
+
+/// outcome of [`SubmissionQueue::submit`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Submission {
+    /// the transaction was accepted straight away
+    Submitted(TxHash),
+    /// the transaction will be retried later
+    Queued(TxHash),
+}
+
+impl Submission {
+    pub fn hash(&self) -> TxHash {
+        match self {
+            Self::Submitted(hash) | Self::Queued(hash) => *hash,
+        }
+    }
+}
+
+/// A signed transaction waiting to be submitted.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct QueuedTx {
+    pub hash: TxHash,
+    /// the slot from which the transaction is no longer valid
+    pub ttl: Option<u64>,
+    /// hex encoded CBOR of the transaction
+    cbor: String,
+}
+
+impl QueuedTx {
+    pub fn new(tx: &[u8]) -> Result<Self, SubmissionError> {
+        let decoded =
+            decode_tx(tx).map_err(|error| SubmissionError::InvalidTx(error.to_string()))?;
+        let hash = decoded.hash;
+
+        Ok(Self {
+            hash,
+            ttl: decoded.tx.transaction_body.ttl,
+            cbor: hex::encode(tx),
+        })
+    }
+
+    /// the signed transaction, failing if the stored entry was altered
+    pub fn tx(&self) -> Result<Vec<u8>, SubmissionError> {
+        hex::decode(&self.cbor).map_err(|error| SubmissionError::InvalidTx(error.to_string()))
+    }
+
+    /// the transaction can no longer be included in a block
+    pub fn is_expired(&self, current_slot: u64) -> bool {
+        self.ttl.is_some_and(|ttl| ttl <= current_slot)
+    }
+}
+
+/// the outcome of a submission in progress, for the concurrent
+/// submissions of the same transaction
+type InFlight = Shared<oneshot::Receiver<Result<Submission, SubmissionError>>>;
+
+/// removes the submission from the ones in progress, even if its future
+/// is dropped before completing
+struct InFlightGuard<'a> {
+    in_flight: &'a RefCell<BTreeMap<TxHash, InFlight>>,
+    hash: TxHash,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.in_flight.borrow_mut().remove(&self.hash);
+    }
+}
+
+/// Persisted queue of transactions to submit.
+///
+/// The hashes of the submitted transactions are remembered so submitting
+/// the same transaction twice (e.g. the user clicking twice on a button)
+/// does not reach the submitter again.
+pub struct SubmissionQueue<S> {
+    storage: S,
+    events: EventBus,
+    in_flight: RefCell<BTreeMap<TxHash, InFlight>>,
+}
+
+impl<S: Storage> SubmissionQueue<S> {
+    pub fn new(storage: S, events: EventBus) -> Self {
+        Self {
+            storage,
+            events,
+            in_flight: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// the transactions waiting to be submitted
+    ///
+    /// Fails with [`StorageError::Corrupt`] if the stored queue can't be
+    /// parsed, the queue is then never overwritten.
+    pub fn pending(&self) -> Result<Vec<QueuedTx>, StorageError> {
+        Ok(read_json(&self.storage, QUEUE_KEY)?.unwrap_or_default())
+    }
+
+    fn save(&self, queue: &[QueuedTx]) -> Result<(), StorageError> {
+        if queue.is_empty() {
+            return self.storage.remove(QUEUE_KEY);
+        }
+        let queue =
+            serde_json::to_string(queue).map_err(|error| StorageError::Write(error.to_string()))?;
+        self.storage.set(QUEUE_KEY, &queue)
+    }
+
+    /// the hashes of the transactions already submitted, oldest first
+    pub fn submitted(&self) -> Result<Vec<TxHash>, StorageError> {
+        Ok(read_json(&self.storage, SUBMITTED_KEY)?.unwrap_or_default())
+    }
+
+    fn record_submitted(&self, hash: TxHash) -> Result<(), StorageError> {
+        let mut submitted = self.submitted()?;
+        if submitted.contains(&hash) {
+            return Ok(());
+        }
+        submitted.push(hash);
+        let excess = submitted.len().saturating_sub(MAX_SUBMITTED);
+        submitted.drain(..excess);
+
+        let submitted = serde_json::to_string(&submitted)
+            .map_err(|error| StorageError::Write(error.to_string()))?;
+        self.storage.set(SUBMITTED_KEY, &submitted)
+    }
+
+    /// the outcome of a previous submission of the same transaction
+    fn previous(&self, hash: TxHash) -> Result<Option<Submission>, StorageError> {
+        Ok(if self.submitted()?.contains(&hash) {
+            Some(Submission::Submitted(hash))
+        } else if self.pending()?.iter().any(|queued| queued.hash == hash) {
+            Some(Submission::Queued(hash))
+        } else {
+            None
+        })
+    }
+
+    /// the submission of the transaction in progress
+    fn in_flight(&self, hash: TxHash) -> Option<InFlight> {
+        self.in_flight.borrow().get(&hash).cloned()
+    }
+
+    /// add the signed transaction to the queue without trying to submit it
+    pub fn enqueue(&self, tx: &[u8]) -> Result<TxHash, SubmissionError> {
+        let queued = QueuedTx::new(tx)?;
+        let hash = queued.hash;
+
+        let mut queue = self.pending()?;
+        if !queue.iter().any(|pending| pending.hash == hash) {
+            queue.push(queued);
+            self.save(&queue)?;
+        }
+        self.events.emit(Event::TxQueued { hash });
+
+        Ok(hash)
+    }
+
+    /// Submit the signed transaction, queuing it if the submitter is
+    /// not reachable.
+    ///
+    /// The validity interval is checked first: an expired transaction is
+    /// never sent. Errors that won't be fixed by retrying (e.g. the
+    /// transaction is rejected) are returned as is.
+    ///
+    /// If the same transaction was already submitted (or queued) the
+    /// previous outcome is returned without calling the submitter. If it
+    /// is being submitted, the outcome of this submission is awaited.
+    pub async fn submit<P: TxSubmitter>(
+        &self,
+        submitter: &P,
+        tx: &[u8],
+        current_slot: u64,
+    ) -> Result<Submission, SubmissionError> {
+        let decoded =
+            decode_tx(tx).map_err(|error| SubmissionError::InvalidTx(error.to_string()))?;
+        let hash = decoded.hash;
+        // a submission dropped before completing is taken over
+        while let Some(in_flight) = self.in_flight(hash) {
+            if let Ok(outcome) = in_flight.await {
+                return outcome;
+            }
+        }
+        if let Some(previous) = self.previous(hash)? {
+            return Ok(previous);
+        }
+        check_validity(&decoded, current_slot)?;
+
+        let (sender, receiver) = oneshot::channel();
+        self.in_flight.borrow_mut().insert(hash, receiver.shared());
+        let _guard = InFlightGuard {
+            in_flight: &self.in_flight,
+            hash,
+        };
+
+        let outcome = match submitter.submit_tx(tx).await {
+            Ok(hash) => self.record_submitted(hash).map_err(Into::into).map(|()| {
+                self.events.emit(Event::TxSubmitted { hash });
+                Submission::Submitted(hash)
+            }),
+            Err(error) if error.is_transient() => self.enqueue(tx).map(Submission::Queued),
+            Err(error) => Err(error.into()),
+        };
+        // no one may be waiting
+        let _ = sender.send(outcome.clone());
+        outcome
+    }
+
+    /// Try to submit every queued transaction once.
+    ///
+    /// Expired transactions are dropped (emitting [`Event::TxExpired`]),
+    /// so are the ones rejected by the submitter. The transactions
+    /// enqueued while flushing stay in the queue.
+    pub async fn flush<P: TxSubmitter>(
+        &self,
+        submitter: &P,
+        current_slot: u64,
+    ) -> Result<(), StorageError> {
+        let mut done = BTreeSet::new();
+
+        for queued in self.pending()? {
+            let hash = queued.hash;
+            if queued.is_expired(current_slot) {
+                self.events.emit(Event::TxExpired { hash });
+                done.insert(hash);
+                continue;
+            }
+
+            let result = match queued.tx() {
+                Ok(tx) => submitter
+                    .submit_tx(&tx)
+                    .await
+                    .map_err(SubmissionError::Backend),
+                Err(error) => Err(error),
+            };
+            match result {
+                Ok(_) => {
+                    self.record_submitted(hash)?;
+                    self.events.emit(Event::TxSubmitted { hash });
+                    done.insert(hash);
+                }
+                Err(SubmissionError::Backend(error)) if error.is_transient() => {}
+                Err(error) => {
+                    self.events.emit(Event::TxRejected {
+                        hash,
+                        reason: error.to_string(),
+                    });
+                    done.insert(hash);
+                }
+            }
+        }
+
+        // the queue is read again, it may have changed during the awaits
+        let mut queue = self.pending()?;
+        queue.retain(|queued| !done.contains(&queued.hash));
+        self.save(&queue)
+    }
+
+    /// Keep flushing the queue every `interval` until it is empty.
+    ///
+    /// `current_slot` is called before every attempt to detect the
+    /// expired transactions.
+    pub async fn run<P: TxSubmitter>(
+        &self,
+        submitter: &P,
+        current_slot: impl Fn() -> u64,
+        interval: Duration,
+    ) -> Result<(), StorageError> {
+        while !self.pending()?.is_empty() {
+            self.flush(submitter, current_slot()).await?;
+            if self.pending()?.is_empty() {
+                break;
+            }
+            crate::ffi::timer::sleep(interval).await;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cardano::{
+            Tx,
+            inspect::tx_hash,
+            testing::{empty_body, empty_witness_set},
+        },
+        storage::MemoryStorage,
+    };
+    use futures::executor::block_on;
+    use pallas_codec::{minicbor, utils::Nullable};
+    use std::rc::Rc;
+
+    /// accepts the transactions once back online
+    struct Submitter {
+        online: RefCell<bool>,
+        calls: RefCell<usize>,
+    }
+
+    impl TxSubmitter for Submitter {
+        async fn submit_tx(&self, tx: &[u8]) -> Result<TxHash, BackendError> {
+            *self.calls.borrow_mut() += 1;
+            if *self.online.borrow() {
+                Ok(tx_hash(tx).unwrap())
+            } else {
+                Err(BackendError::Unavailable("offline".to_owned()))
+            }
+        }
+    }
+
+    fn signed_tx(ttl: u64) -> Vec<u8> {
+        let mut body = empty_body();
+        body.ttl = Some(ttl);
+        minicbor::to_vec(Tx {
+            transaction_body: body,
+            transaction_witness_set: empty_witness_set(),
+            success: true,
+            auxiliary_data: Nullable::Null,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn retry_when_back_online() {
+        let events = EventBus::new();
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&received);
+        events.subscribe(move |event| sink.borrow_mut().push(event.clone()));
+        let queue = SubmissionQueue::new(MemoryStorage::new(), events);
+        let submitter = Submitter {
+            online: RefCell::new(false),
+            calls: RefCell::new(0),
+        };
+        let valid = signed_tx(100);
+        let expiring = signed_tx(10);
+
+        let submission = block_on(queue.submit(&submitter, &valid, 0)).unwrap();
+        let valid_hash = submission.hash();
+        assert!(matches!(submission, Submission::Queued(_)));
+        let expiring_hash = block_on(queue.submit(&submitter, &expiring, 0))
+            .unwrap()
+            .hash();
+        assert_eq!(queue.pending().unwrap().len(), 2);
+        assert!(matches!(
+            block_on(queue.submit(&submitter, &signed_tx(5), 10)),
+            Err(SubmissionError::Validity(ValidityError::Expired { .. }))
+        ));
+
+        block_on(queue.flush(&submitter, 5)).unwrap();
+        assert_eq!(queue.pending().unwrap().len(), 2);
+
+        *submitter.online.borrow_mut() = true;
+        block_on(queue.flush(&submitter, 50)).unwrap();
+
+        assert!(queue.pending().unwrap().is_empty());
+        assert_eq!(
+            received.borrow().as_slice(),
+            &[
+                Event::TxQueued { hash: valid_hash },
+                Event::TxQueued {
+                    hash: expiring_hash
+                },
+                Event::TxSubmitted { hash: valid_hash },
+                Event::TxExpired {
+                    hash: expiring_hash
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn submit_once() {
+        let queue = SubmissionQueue::new(MemoryStorage::new(), EventBus::new());
+        let submitter = Submitter {
+            online: RefCell::new(true),
+            calls: RefCell::new(0),
+        };
+        let tx = signed_tx(100);
+
+        let first = block_on(queue.submit(&submitter, &tx, 0)).unwrap();
+        let second = block_on(queue.submit(&submitter, &tx, 0)).unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(*submitter.calls.borrow(), 1);
+        assert_eq!(queue.submitted().unwrap(), vec![first.hash()]);
+    }
+
+    #[test]
+    fn concurrent_submissions_share_the_outcome() {
+        /// answers on the second poll, letting the other submission start
+        struct Slow(Submitter);
+
+        impl TxSubmitter for Slow {
+            async fn submit_tx(&self, tx: &[u8]) -> Result<TxHash, BackendError> {
+                let mut yielded = false;
+                std::future::poll_fn(|cx| {
+                    if std::mem::replace(&mut yielded, true) {
+                        std::task::Poll::Ready(())
+                    } else {
+                        cx.waker().wake_by_ref();
+                        std::task::Poll::Pending
+                    }
+                })
+                .await;
+                self.0.submit_tx(tx).await
+            }
+        }
+
+        let queue = SubmissionQueue::new(MemoryStorage::new(), EventBus::new());
+        let submitter = Slow(Submitter {
+            online: RefCell::new(true),
+            calls: RefCell::new(0),
+        });
+        let tx = signed_tx(100);
+
+        let (first, second) = block_on(futures::future::join(
+            queue.submit(&submitter, &tx, 0),
+            queue.submit(&submitter, &tx, 0),
+        ));
+
+        assert_eq!(first.unwrap(), second.unwrap());
+        assert_eq!(*submitter.0.calls.borrow(), 1);
+        assert!(queue.in_flight.borrow().is_empty());
+    }
+
+    #[test]
+    fn keep_what_is_enqueued_while_flushing() {
+        /// enqueues another transaction while submitting
+        struct Enqueuing<'a> {
+            queue: &'a SubmissionQueue<MemoryStorage>,
+            tx: Vec<u8>,
+        }
+
+        impl TxSubmitter for Enqueuing<'_> {
+            async fn submit_tx(&self, tx: &[u8]) -> Result<TxHash, BackendError> {
+                self.queue.enqueue(&self.tx).unwrap();
+                Ok(tx_hash(tx).unwrap())
+            }
+        }
+
+        let queue = SubmissionQueue::new(MemoryStorage::new(), EventBus::new());
+        let submitted = queue.enqueue(&signed_tx(100)).unwrap();
+        let submitter = Enqueuing {
+            queue: &queue,
+            tx: signed_tx(200),
+        };
+
+        block_on(queue.flush(&submitter, 0)).unwrap();
+
+        let pending = queue.pending().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].tx().unwrap(), signed_tx(200));
+        assert_eq!(queue.submitted().unwrap(), vec![submitted]);
+    }
+
+    #[test]
+    fn corrupt_queue_is_not_overwritten() {
+        let storage = MemoryStorage::new();
+        storage.set(QUEUE_KEY, "[{\"hash\":").unwrap();
+        let queue = SubmissionQueue::new(&storage, EventBus::new());
+
+        assert!(matches!(queue.pending(), Err(StorageError::Corrupt { .. })));
+        assert!(matches!(
+            queue.enqueue(&signed_tx(100)),
+            Err(SubmissionError::Storage(StorageError::Corrupt { .. }))
+        ));
+        assert_eq!(storage.get(QUEUE_KEY).as_deref(), Some("[{\"hash\":"));
+    }
+}
@@ -0,0 +1,275 @@
+/*!
+Reports of the wallets answering out of the CIP-30 specification.
+
+When a wallet returns something the crate cannot decode (an error that is
+not a CIP-30 error object, a malformed UTxO...) the call fails or the
+value is skipped, and a [`QuirkReport`] is given to the reporter of the
+[`Connector`] the wallet was connected with (see
+[`ConnectorBuilder::with_reporter`]), or else to the one set with
+[`set_reporter`], if any. The report names the wallet, its version and the
+endpoint, and describes the *shape* of the response, never its content
+(see [`shape`]): the dApp may forward it to gather compatibility data,
+nothing is sent by the crate itself.
+
+```
+# use cardano_connector::telemetry;
+telemetry::set_reporter(|report| {
+    // e.g. send it to the dApp's own analytics
+    let _ = (report.wallet.as_str(), report.endpoint.as_str(), report.shape.as_str());
+});
+```
+
+[`Connector`]: crate::connector::Connector
+[`ConnectorBuilder::with_reporter`]: crate::connector::ConnectorBuilder::with_reporter
+*/
+
+use serde_json::Value;
+use std::{cell::RefCell, rc::Rc};
+use wasm_bindgen::JsValue;
+
+/// the arrays and objects nested deeper are not described
+const MAX_SHAPE_DEPTH: usize = 4;
+
+/// A response the crate could not decode.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuirkReport {
+    /// the name of the wallet
+    pub wallet: String,
+    /// the version of the CIP-30 API the wallet announces
+    pub version: String,
+    /// the CIP-30 endpoint, e.g. `getUtxos`
+    pub endpoint: String,
+    /// the anonymized shape of the response, see [`shape`]
+    pub shape: String,
+    /// why it could not be decoded
+    pub reason: QuirkReason,
+}
+
+/// Why a response could not be decoded.
+///
+/// A fixed category rather than the decoder's message, which may quote
+/// the response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum QuirkReason {
+    /// not the expected type, e.g. a number instead of a string
+    UnexpectedType,
+    /// a string that is not hexadecimal
+    InvalidHex,
+    /// hexadecimal that is not the expected CBOR
+    InvalidCbor,
+    /// bytes that are not an address
+    InvalidAddress,
+    /// a rejection that is not a CIP-30 error object
+    NotAnApiError,
+}
+
+impl QuirkReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::UnexpectedType => "unexpected-type",
+            Self::InvalidHex => "invalid-hex",
+            Self::InvalidCbor => "invalid-cbor",
+            Self::InvalidAddress => "invalid-address",
+            Self::NotAnApiError => "not-an-api-error",
+        }
+    }
+
+    /// the reason a hex encoded response failed to decode: `decoding` if
+    /// it is hexadecimal
+    pub(crate) fn of_hex(response: &JsValue, decoding: Self) -> Self {
+        match response.as_string() {
+            None => Self::UnexpectedType,
+            Some(string) if hex::decode(&string).is_err() => Self::InvalidHex,
+            Some(_) => decoding,
+        }
+    }
+}
+
+impl std::fmt::Display for QuirkReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+pub(crate) type Reporter = Rc<dyn Fn(&QuirkReport)>;
+
+thread_local! {
+    static REPORTER: RefCell<Option<Reporter>> = const { RefCell::new(None) };
+}
+
+/// give the following reports to `reporter`, replacing the previous one
+pub fn set_reporter(reporter: impl Fn(&QuirkReport) + 'static) {
+    REPORTER.with(|current| current.replace(Some(Rc::new(reporter))));
+}
+
+/// stop reporting
+pub fn clear_reporter() {
+    REPORTER.with(|current| current.replace(None));
+}
+
+/// report to `reporter`, or else to the one set with [`set_reporter`]
+pub(crate) fn report(
+    reporter: Option<&Reporter>,
+    wallet: String,
+    version: String,
+    endpoint: &str,
+    response: &JsValue,
+    reason: QuirkReason,
+) {
+    report_with(
+        reporter.cloned(),
+        wallet,
+        version,
+        endpoint,
+        || shape_of(response),
+        reason,
+    )
+}
+
+/// the shape is only computed if there is a reporter
+fn report_with(
+    reporter: Option<Reporter>,
+    wallet: String,
+    version: String,
+    endpoint: &str,
+    shape: impl FnOnce() -> String,
+    reason: QuirkReason,
+) {
+    // the reporter may set another one, don't keep the cell borrowed
+    let Some(reporter) = reporter.or_else(|| REPORTER.with(|current| current.borrow().clone()))
+    else {
+        return;
+    };
+    reporter(&QuirkReport {
+        wallet,
+        version,
+        endpoint: endpoint.to_owned(),
+        shape: shape(),
+        reason,
+    });
+}
+
+fn shape_of(response: &JsValue) -> String {
+    if response.is_undefined() {
+        return "undefined".to_owned();
+    }
+    match serde_wasm_bindgen::from_value::<Value>(response.clone()) {
+        Ok(value) => shape(&value),
+        Err(_) => response
+            .js_typeof()
+            .as_string()
+            .unwrap_or_else(|| "unknown".to_owned()),
+    }
+}
+
+/// describe the structure of `value` without its content: the keys of
+/// the objects and the types of the values, the strings by their kind
+/// (hexadecimal or not) and length
+///
+/// ```
+/// # use cardano_connector::telemetry::shape;
+/// let value = serde_json::json!({ "code": -3, "info": "Refused", "data": ["a1b2"] });
+/// assert_eq!(shape(&value), "{code: number, data: [hex(4)], info: string(7)}");
+/// ```
+pub fn shape(value: &Value) -> String {
+    shape_at(value, 0)
+}
+
+fn shape_at(value: &Value, depth: usize) -> String {
+    match value {
+        Value::Null => "null".to_owned(),
+        Value::Bool(_) => "boolean".to_owned(),
+        Value::Number(_) => "number".to_owned(),
+        Value::String(string) => {
+            let hex = !string.is_empty() && string.chars().all(|c| c.is_ascii_hexdigit());
+            if hex {
+                format!("hex({})", string.len())
+            } else {
+                format!("string({})", string.chars().count())
+            }
+        }
+        Value::Array(_) | Value::Object(_) if depth >= MAX_SHAPE_DEPTH => "…".to_owned(),
+        Value::Array(items) => {
+            let mut shapes: Vec<_> = items.iter().map(|item| shape_at(item, depth + 1)).collect();
+            shapes.dedup();
+            match shapes.as_slice() {
+                [] => "[]".to_owned(),
+                [shape] => format!("[{shape}]"),
+                shapes => format!("[{}]", shapes.join(" | ")),
+            }
+        }
+        Value::Object(fields) => {
+            let fields: Vec<_> = fields
+                .iter()
+                .map(|(key, value)| format!("{key}: {}", shape_at(value, depth + 1)))
+                .collect();
+            format!("{{{}}}", fields.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn shapes_hide_the_content() {
+        assert_eq!(shape(&json!(null)), "null");
+        assert_eq!(shape(&json!("Access denied")), "string(13)");
+        assert_eq!(shape(&json!(["82a1", "82a2", 7])), "[hex(4) | number]");
+        assert_eq!(
+            shape(&json!({ "error": { "code": "x" } })),
+            "{error: {code: string(1)}}"
+        );
+        assert_eq!(shape(&json!([[[[[1]]]]])), "[[[[…]]]]");
+    }
+
+    #[test]
+    fn reports_only_when_opted_in() {
+        let reports = Rc::new(RefCell::new(Vec::new()));
+        report_with(
+            None,
+            "lace".to_owned(),
+            "1.0.0".to_owned(),
+            "getUtxos",
+            || "undefined".to_owned(),
+            QuirkReason::UnexpectedType,
+        );
+
+        set_reporter({
+            let reports = Rc::clone(&reports);
+            move |report| reports.borrow_mut().push(report.clone())
+        });
+        report_with(
+            None,
+            "lace".to_owned(),
+            "1.0.0".to_owned(),
+            "getUtxos",
+            || "undefined".to_owned(),
+            QuirkReason::UnexpectedType,
+        );
+        clear_reporter();
+        report_with(
+            None,
+            "lace".to_owned(),
+            "1.0.0".to_owned(),
+            "getUtxos",
+            || "undefined".to_owned(),
+            QuirkReason::UnexpectedType,
+        );
+
+        assert_eq!(
+            *reports.borrow(),
+            [QuirkReport {
+                wallet: "lace".to_owned(),
+                version: "1.0.0".to_owned(),
+                endpoint: "getUtxos".to_owned(),
+                shape: "undefined".to_owned(),
+                reason: QuirkReason::UnexpectedType,
+            }]
+        );
+    }
+}
@@ -1,13 +1,18 @@
 use crate::{
     ConnectedWallet,
     error::{APIError, APIErrorCode},
-    ffi,
+    extension, ffi,
+    registry::{WalletKind, WalletQuirks},
 };
+use futures::stream::{self, Stream};
+use std::time::Duration;
 use wasm_bindgen::JsValue;
 
 #[derive(Clone, PartialEq)]
 pub struct Wallet {
     cip30_wallet: ffi::Cip30Wallet,
+    /// the key of `window.cardano` the wallet is injected under
+    key: String,
 }
 
 /// attempt to find the wallet by name
@@ -23,7 +28,10 @@ pub fn wallet(name: &str) -> Option<Wallet> {
 }
 
 pub fn lace() -> Option<Wallet> {
-    ffi::cip30::LACE.with(|opt| opt.clone().map(Wallet::new))
+    ffi::cip30::LACE.with(|opt| {
+        opt.clone()
+            .map(|cip30_wallet| Wallet::new(cip30_wallet, WalletKind::Lace.key()))
+    })
 }
 
 /// List the wallets that may be available.
@@ -35,7 +43,7 @@ pub fn lace() -> Option<Wallet> {
 ///
 /// Alternatively, it is also possible the wallets were not injected yet by the
 /// extensions. You should make sure the page is loaded fully before calling this
-/// function (or do refresh the value from time to time).
+/// function (or follow the injections with [`watch_wallets`]).
 ///
 pub fn wallets() -> Vec<Wallet> {
     ffi::cip30::WALLETS.with(|wallets| {
@@ -54,12 +62,14 @@ pub fn wallets() -> Vec<Wallet> {
 
         if fresh_wallets.is_object() {
             let fresh_wallets_obj: js_sys::Object = fresh_wallets.into();
-            for element in js_sys::Object::values(&fresh_wallets_obj) {
+            for entry in js_sys::Object::entries(&fresh_wallets_obj) {
+                let entry = js_sys::Array::from(&entry);
+                let element = entry.get(1);
                 if !looks_like_cip30_wallet(&element) {
                     continue;
                 }
-                let cip30_wallet = ffi::Cip30Wallet::from(element);
-                let wallet = Wallet { cip30_wallet };
+                let key = entry.get(0).as_string().unwrap_or_default();
+                let wallet = Wallet::new(ffi::Cip30Wallet::from(element), &key);
 
                 vec.push(wallet)
             }
@@ -69,6 +79,52 @@ pub fn wallets() -> Vec<Wallet> {
     })
 }
 
+/// The [`wallets`] now, then each time a wallet is injected or removed.
+///
+/// The extensions inject themselves in `window.cardano` when they are
+/// ready, which may be after the page is loaded: the list is refreshed
+/// every `interval` (`window.cardano` can't be observed) and a new one
+/// is yielded when it changed. The stream never ends, it stops polling
+/// once dropped.
+///
+/// ```no_run
+/// # use cardano_connector::watch_wallets;
+/// # use futures::StreamExt as _;
+/// # use std::time::Duration;
+/// # async fn test() {
+/// let mut updates = std::pin::pin!(watch_wallets(Duration::from_millis(500)));
+/// while let Some(wallets) = updates.next().await {
+///     println!("{} wallets available", wallets.len());
+/// }
+/// # }
+/// ```
+pub fn watch_wallets(interval: Duration) -> impl Stream<Item = Vec<Wallet>> {
+    changes(|| Some(wallets()), move || ffi::timer::sleep(interval))
+}
+
+/// the value of `poll` now, then each time it differs from the previous
+/// one, polled after each `wait`
+///
+/// The stream ends when `poll` returns `None`.
+pub(crate) fn changes<T, W, Fut>(poll: impl FnMut() -> Option<T>, wait: W) -> impl Stream<Item = T>
+where
+    T: Clone + PartialEq,
+    W: Fn() -> Fut,
+    Fut: Future<Output = ()>,
+{
+    stream::unfold((poll, wait, None), |(mut poll, wait, last)| async move {
+        loop {
+            if last.is_some() {
+                wait().await;
+            }
+            let current = poll()?;
+            if last.as_ref() != Some(&current) {
+                return Some((current.clone(), (poll, wait, Some(current))));
+            }
+        }
+    })
+}
+
 fn looks_like_cip30_wallet(value: &JsValue) -> bool {
     if !value.is_object() {
         return false;
@@ -96,8 +152,27 @@ fn looks_like_cip30_wallet(value: &JsValue) -> bool {
 }
 
 impl Wallet {
-    fn new(cip30_wallet: ffi::Cip30Wallet) -> Self {
-        Wallet { cip30_wallet }
+    fn new(cip30_wallet: ffi::Cip30Wallet, key: &str) -> Self {
+        Wallet {
+            cip30_wallet,
+            key: key.to_owned(),
+        }
+    }
+
+    /// the key of `window.cardano` the wallet is injected under, e.g.
+    /// `"eternl"`
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// the wallet, recognized from its [`key`](Self::key)
+    pub fn kind(&self) -> WalletKind {
+        WalletKind::from_key(&self.key)
+    }
+
+    /// what the wallet does differently, see [`WalletQuirks`]
+    pub fn quirks(&self) -> WalletQuirks {
+        self.kind().quirks()
     }
 
     /// get the name of the wallet connector application
@@ -159,12 +234,87 @@ impl Wallet {
     pub async fn enable(&self) -> Result<ConnectedWallet, APIError> {
         match self.cip30_wallet.enable(JsValue::undefined()).await {
             Ok(cip30_api) => Ok(ConnectedWallet::new(self.clone(), cip30_api)),
-            Err(error) => serde_wasm_bindgen::from_value(error)
-                .map_err(|decode_error| APIError {
-                    code: APIErrorCode::InternalError,
-                    info: format!("Couldn't decode the error content: {decode_error}"),
-                })
-                .and_then(Err),
+            Err(error) => decode_error(error),
         }
     }
+
+    /// Same as [`Wallet::enable`] but requesting the given CIP-30
+    /// extensions (e.g. `Extension { cip: 95 }`).
+    ///
+    /// The wallet may not grant all of them (unsupported or conflicting
+    /// extensions): the granted ones are available with
+    /// [`ConnectedWallet::granted_extensions`]. The wallets failing to
+    /// list them (`getExtensions`) are assumed to have granted all the
+    /// requested extensions.
+    pub async fn enable_with_extensions(
+        &self,
+        extensions: &[ffi::Extension],
+    ) -> Result<ConnectedWallet, APIError> {
+        let request = extension::encode(&EnableRequest { extensions })?;
+        let cip30_api = match self.cip30_wallet.enable(request).await {
+            Ok(cip30_api) => cip30_api,
+            Err(error) => return decode_error(error),
+        };
+        let wallet = ConnectedWallet::new(self.clone(), cip30_api);
+        // the wallet is enabled, failing here would lose the connection
+        let granted = wallet
+            .enabled_extensions()
+            .await
+            .unwrap_or_else(|_| extensions.to_vec());
+        Ok(wallet.with_granted_extensions(granted))
+    }
+}
+
+/// the argument of `enable`: `{ extensions: [{ cip: 95 }] }`
+#[derive(serde::Serialize)]
+struct EnableRequest<'a> {
+    extensions: &'a [ffi::Extension],
+}
+
+fn decode_error<T>(error: JsValue) -> Result<T, APIError> {
+    serde_wasm_bindgen::from_value(error)
+        .map_err(|decode_error| APIError {
+            code: APIErrorCode::InternalError,
+            info: format!("Couldn't decode the error content: {decode_error}"),
+        })
+        .and_then(Err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{StreamExt as _, executor::block_on};
+    use std::cell::Cell;
+
+    #[test]
+    fn only_the_changes() {
+        let lists = [
+            vec![],
+            vec![],
+            vec!["lace"],
+            vec!["lace"],
+            vec!["lace", "eternl"],
+        ];
+        let polls = Cell::new(0);
+        let waits = Cell::new(0);
+        // the stream ends at the 7th poll
+        let stop_after = 6;
+        let poll = || {
+            let poll = polls.get();
+            polls.set(poll + 1);
+            (poll < stop_after).then(|| lists[poll.min(lists.len() - 1)].clone())
+        };
+        let wait = || {
+            waits.set(waits.get() + 1);
+            futures::future::ready(())
+        };
+
+        let updates: Vec<_> = block_on(changes(poll, wait).collect());
+
+        assert_eq!(updates, [vec![], vec!["lace"], vec!["lace", "eternl"]]);
+        // the stream ends with the first `None`
+        assert_eq!(polls.get(), stop_after + 1);
+        // no wait before the first poll
+        assert_eq!(waits.get(), stop_after);
+    }
 }
@@ -0,0 +1,262 @@
+/*!
+The CIP-30 calls, abstracted over the wallet.
+
+[`WalletApi`] is implemented by the [`ConnectedWallet`] and by the
+[`MockCip30Api`](crate::mock::MockCip30Api) of the `mock` feature. Code
+written against the trait runs in the browser with a real wallet and in
+the tests with a mock:
+
+```
+# use cardano_connector::{Address, TxHash, cardano::BuildParams, error::SendError, wallet_api::WalletApi};
+async fn pay_invoice(
+    wallet: &impl WalletApi,
+    merchant: &Address,
+    amount: u64,
+) -> Result<TxHash, SendError> {
+    let change_address = wallet.change_address().await?;
+    wallet
+        .send_with(merchant, amount, &BuildParams::defaults(change_address))
+        .await
+}
+```
+*/
+
+use crate::{
+    Address, AssetId, Coin, ConnectedWallet, NetworkId, SignedData, TxHash, Utxo, UtxoSet, Value,
+    auth::Envelope,
+    cardano::{
+        BuildParams, TransactionBody, Tx, TxIntent, ValueExt as _, WitnessSet, WitnessSetExt as _,
+        assets_of, lovelace_of,
+    },
+    error::{APIError, SendError, SignTxError, SubmitTxError},
+    ffi::cip30_api::Paginate,
+};
+use std::collections::BTreeMap;
+
+/// The calls of an enabled wallet.
+///
+/// The futures are not required to be `Send` as this crate is meant to
+/// run in the browser's single threaded environment.
+#[allow(async_fn_in_trait)]
+pub trait WalletApi {
+    async fn network_id(&self) -> Result<NetworkId, APIError>;
+
+    /// the total balance of the wallet
+    async fn balance(&self) -> Result<Value, APIError>;
+
+    async fn used_addresses(&self, pagination: Option<Paginate>) -> Result<Vec<Address>, APIError>;
+
+    async fn unused_addresses(&self) -> Result<Vec<Address>, APIError>;
+
+    async fn change_address(&self) -> Result<Address, APIError>;
+
+    async fn reward_addresses(&self) -> Result<Vec<Address>, APIError>;
+
+    /// all the UTxOs of the wallet
+    async fn utxos(&self, pagination: Option<Paginate>) -> Result<Vec<Utxo>, APIError>;
+
+    /// UTxOs of the wallet covering `value`, empty if the wallet can't
+    async fn select_utxos(
+        &self,
+        value: &Value,
+        pagination: Option<Paginate>,
+    ) -> Result<Vec<Utxo>, APIError>;
+
+    /// the UTxOs set aside as collateral, covering at least `amount`
+    /// lovelace, empty if the wallet can't
+    async fn collateral(&self, amount: Coin) -> Result<Vec<Utxo>, APIError>;
+
+    /// sign the payload with the key of `address` (CIP-8)
+    async fn sign_data(&self, address: &Address, payload: &[u8]) -> Result<SignedData, APIError>;
+
+    async fn sign_tx(
+        &self,
+        transaction: &TransactionBody,
+        partial_sign: bool,
+    ) -> Result<WitnessSet, SignTxError>;
+
+    async fn submit_tx(&self, transaction: &Tx) -> Result<TxHash, SubmitTxError>;
+
+    /// sign the transaction and add the returned witnesses to it
+    ///
+    /// By default only the body is given to [`sign_tx`](Self::sign_tx).
+    async fn sign_transaction(&self, tx: &Tx, partial_sign: bool) -> Result<Tx, SignTxError> {
+        let witness_set = self.sign_tx(&tx.transaction_body, partial_sign).await?;
+        let mut tx = tx.clone();
+        tx.transaction_witness_set.merge(witness_set);
+        Ok(tx)
+    }
+
+    /// the lovelace part of the [`balance`](Self::balance)
+    async fn lovelace(&self) -> Result<Coin, APIError> {
+        self.balance().await.map(|balance| lovelace_of(&balance))
+    }
+
+    /// the native assets part of the [`balance`](Self::balance)
+    async fn assets(&self) -> Result<BTreeMap<AssetId, u64>, APIError> {
+        self.balance().await.map(|balance| assets_of(&balance))
+    }
+
+    /// sign the payload of the `envelope`, see [`auth`](crate::auth)
+    async fn sign_envelope(
+        &self,
+        address: &Address,
+        envelope: &Envelope,
+    ) -> Result<SignedData, APIError> {
+        self.sign_data(address, &envelope.to_payload()).await
+    }
+
+    /// check whether the wallet holds at least `required`
+    async fn can_cover(&self, required: &Value) -> Result<bool, APIError> {
+        Ok(self.balance().await?.meets_or_exceeds(required))
+    }
+
+    /// send `amount` lovelace to `to`: build the transaction from the
+    /// UTxOs of the wallet, sign it and submit it through the wallet
+    ///
+    /// The change goes back to the wallet's change address, whatever the
+    /// `change_address` of the `params`.
+    async fn send_with(
+        &self,
+        to: &Address,
+        amount: Coin,
+        params: &BuildParams,
+    ) -> Result<TxHash, SendError> {
+        let utxos = UtxoSet::from(self.utxos(None).await?);
+        let params = BuildParams {
+            change_address: self.change_address().await?,
+            ..params.clone()
+        };
+        let tx = TxIntent::new()
+            .pay(to, Value::Coin(amount))
+            .build(&utxos, &params)?;
+
+        let tx = self.sign_transaction(&tx, false).await?;
+
+        Ok(self.submit_tx(&tx).await?)
+    }
+}
+
+impl<W: WalletApi> WalletApi for &W {
+    async fn network_id(&self) -> Result<NetworkId, APIError> {
+        (*self).network_id().await
+    }
+
+    async fn balance(&self) -> Result<Value, APIError> {
+        (*self).balance().await
+    }
+
+    async fn used_addresses(&self, pagination: Option<Paginate>) -> Result<Vec<Address>, APIError> {
+        (*self).used_addresses(pagination).await
+    }
+
+    async fn unused_addresses(&self) -> Result<Vec<Address>, APIError> {
+        (*self).unused_addresses().await
+    }
+
+    async fn change_address(&self) -> Result<Address, APIError> {
+        (*self).change_address().await
+    }
+
+    async fn reward_addresses(&self) -> Result<Vec<Address>, APIError> {
+        (*self).reward_addresses().await
+    }
+
+    async fn utxos(&self, pagination: Option<Paginate>) -> Result<Vec<Utxo>, APIError> {
+        (*self).utxos(pagination).await
+    }
+
+    async fn select_utxos(
+        &self,
+        value: &Value,
+        pagination: Option<Paginate>,
+    ) -> Result<Vec<Utxo>, APIError> {
+        (*self).select_utxos(value, pagination).await
+    }
+
+    async fn collateral(&self, amount: Coin) -> Result<Vec<Utxo>, APIError> {
+        (*self).collateral(amount).await
+    }
+
+    async fn sign_data(&self, address: &Address, payload: &[u8]) -> Result<SignedData, APIError> {
+        (*self).sign_data(address, payload).await
+    }
+
+    async fn sign_tx(
+        &self,
+        transaction: &TransactionBody,
+        partial_sign: bool,
+    ) -> Result<WitnessSet, SignTxError> {
+        (*self).sign_tx(transaction, partial_sign).await
+    }
+
+    async fn submit_tx(&self, transaction: &Tx) -> Result<TxHash, SubmitTxError> {
+        (*self).submit_tx(transaction).await
+    }
+
+    async fn sign_transaction(&self, tx: &Tx, partial_sign: bool) -> Result<Tx, SignTxError> {
+        (*self).sign_transaction(tx, partial_sign).await
+    }
+}
+
+impl WalletApi for ConnectedWallet {
+    async fn network_id(&self) -> Result<NetworkId, APIError> {
+        ConnectedWallet::network_id(self).await
+    }
+
+    async fn balance(&self) -> Result<Value, APIError> {
+        ConnectedWallet::balance(self).await
+    }
+
+    async fn used_addresses(&self, pagination: Option<Paginate>) -> Result<Vec<Address>, APIError> {
+        ConnectedWallet::used_addresses(self, pagination).await
+    }
+
+    async fn unused_addresses(&self) -> Result<Vec<Address>, APIError> {
+        ConnectedWallet::unused_addresses(self).await
+    }
+
+    async fn change_address(&self) -> Result<Address, APIError> {
+        ConnectedWallet::change_address(self).await
+    }
+
+    async fn reward_addresses(&self) -> Result<Vec<Address>, APIError> {
+        ConnectedWallet::reward_addresses(self).await
+    }
+
+    async fn utxos(&self, pagination: Option<Paginate>) -> Result<Vec<Utxo>, APIError> {
+        ConnectedWallet::all_utxos(self, pagination).await
+    }
+
+    async fn select_utxos(
+        &self,
+        value: &Value,
+        pagination: Option<Paginate>,
+    ) -> Result<Vec<Utxo>, APIError> {
+        ConnectedWallet::select_utxos(self, value, pagination).await
+    }
+
+    async fn collateral(&self, amount: Coin) -> Result<Vec<Utxo>, APIError> {
+        ConnectedWallet::collateral(self, amount).await
+    }
+
+    async fn sign_data(&self, address: &Address, payload: &[u8]) -> Result<SignedData, APIError> {
+        ConnectedWallet::sign_data(self, address, payload).await
+    }
+
+    async fn sign_tx(
+        &self,
+        transaction: &TransactionBody,
+        partial_sign: bool,
+    ) -> Result<WitnessSet, SignTxError> {
+        ConnectedWallet::sign_tx(self, transaction, partial_sign).await
+    }
+
+    async fn submit_tx(&self, transaction: &Tx) -> Result<TxHash, SubmitTxError> {
+        ConnectedWallet::submit_tx(self, transaction).await
+    }
+
+    async fn sign_transaction(&self, tx: &Tx, partial_sign: bool) -> Result<Tx, SignTxError> {
+        ConnectedWallet::sign_transaction(self, tx, partial_sign).await
+    }
+}
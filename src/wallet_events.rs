@@ -0,0 +1,213 @@
+/*!
+Account and network changes, as notified by the wallet.
+
+Some wallets (e.g. Eternl, Lace, Nami) emit events through their non
+standard `experimental.on` and `experimental.off` endpoints when the user
+switches account or network. Once the account changed, the
+[`ConnectedWallet`] fails with [`APIErrorCode::AccountChange`]:
+[`ConnectedWallet::on_account_change`] enables the wallet again and gives
+the new [`ConnectedWallet`] to the callback.
+
+```no_run
+# use cardano_connector::ConnectedWallet;
+# fn test(wallet: &ConnectedWallet) -> anyhow::Result<()> {
+// the subscription lasts as long as the returned value is kept
+let subscription = wallet.on_network_change(|network_id| {
+    println!("now on {network_id}");
+})?;
+# Ok(()) }
+```
+
+[`APIErrorCode::AccountChange`]: crate::error::APIErrorCode::AccountChange
+*/
+
+use crate::{
+    Address, ConnectedWallet, NetworkId,
+    error::{APIError, APIErrorCode},
+    extension,
+};
+use wasm_bindgen::{JsCast as _, JsValue, closure::Closure};
+
+/// The events a wallet may emit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WalletEventKind {
+    AccountChange,
+    NetworkChange,
+}
+
+impl WalletEventKind {
+    /// the name of the event for `experimental.on`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::AccountChange => "accountChange",
+            Self::NetworkChange => "networkChange",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalletEvent {
+    /// the user switched account, with the addresses of the new account
+    /// when the wallet gives them
+    AccountChange { addresses: Vec<Address> },
+    /// the user switched network
+    NetworkChange { network_id: NetworkId },
+}
+
+impl WalletEvent {
+    pub fn kind(&self) -> WalletEventKind {
+        match self {
+            Self::AccountChange { .. } => WalletEventKind::AccountChange,
+            Self::NetworkChange { .. } => WalletEventKind::NetworkChange,
+        }
+    }
+
+    /// decode the argument the wallet gives to the event listener
+    ///
+    /// The wallets don't agree on the payload of the account changes:
+    /// what can't be decoded is left out.
+    fn decode(kind: WalletEventKind, payload: &JsValue) -> Result<Self, APIError> {
+        match kind {
+            WalletEventKind::AccountChange => {
+                let addresses = match payload.dyn_ref::<js_sys::Array>() {
+                    Some(array) => array
+                        .iter()
+                        .filter_map(|address| address.as_string())
+                        .filter_map(|address| Address::from_hex(&address).ok())
+                        .collect(),
+                    None => Vec::new(),
+                };
+                Ok(Self::AccountChange { addresses })
+            }
+            WalletEventKind::NetworkChange => {
+                let Some(number) = payload.as_f64() else {
+                    return Err(APIError {
+                        code: APIErrorCode::InternalError,
+                        info: format!("Unknown network id: {payload:?}"),
+                    });
+                };
+                Ok(Self::NetworkChange {
+                    network_id: NetworkId::from(number as u8),
+                })
+            }
+        }
+    }
+}
+
+/// A listener registered with the wallet.
+///
+/// The listener is removed (`experimental.off`) when dropped.
+pub struct WalletSubscription {
+    experimental: JsValue,
+    kind: WalletEventKind,
+    listener: Closure<dyn Fn(JsValue)>,
+}
+
+impl WalletSubscription {
+    /// register `callback` for the events of the given kind
+    ///
+    /// Fails with [`APIErrorCode::InvalidRequest`] if the wallet does not
+    /// emit events.
+    pub(crate) fn new(
+        experimental: JsValue,
+        kind: WalletEventKind,
+        callback: impl Fn(Result<WalletEvent, APIError>) + 'static,
+    ) -> Result<Self, APIError> {
+        let on = experimental
+            .is_object()
+            .then(|| extension::endpoint(&experimental, "on"))
+            .flatten()
+            .ok_or_else(|| APIError {
+                code: APIErrorCode::InvalidRequest,
+                info: format!("The wallet does not emit {} events", kind.name()),
+            })?;
+
+        let listener = Closure::<dyn Fn(JsValue)>::new(move |payload: JsValue| {
+            callback(WalletEvent::decode(kind, &payload));
+        });
+        on.call2(
+            &experimental,
+            &JsValue::from_str(kind.name()),
+            listener.as_ref(),
+        )
+        .map_err(|error| APIError {
+            code: APIErrorCode::InternalError,
+            info: format!("Couldn't register the {} listener: {error:?}", kind.name()),
+        })?;
+
+        Ok(Self {
+            experimental,
+            kind,
+            listener,
+        })
+    }
+
+    pub fn kind(&self) -> WalletEventKind {
+        self.kind
+    }
+}
+
+impl Drop for WalletSubscription {
+    fn drop(&mut self) {
+        if let Some(off) = extension::endpoint(&self.experimental, "off") {
+            let _ = off.call2(
+                &self.experimental,
+                &JsValue::from_str(self.kind.name()),
+                self.listener.as_ref(),
+            );
+        }
+    }
+}
+
+impl std::fmt::Debug for WalletSubscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WalletSubscription")
+            .field("kind", &self.kind)
+            .finish()
+    }
+}
+
+impl ConnectedWallet {
+    /// call `callback` with the events of the given kind, see the
+    /// [module documentation](crate::wallet_events)
+    pub fn on_event(
+        &self,
+        kind: WalletEventKind,
+        callback: impl Fn(Result<WalletEvent, APIError>) + 'static,
+    ) -> Result<WalletSubscription, APIError> {
+        WalletSubscription::new(self.experimental().as_js().clone(), kind, callback)
+    }
+
+    /// call `callback` with the wallet enabled again (see
+    /// [`ConnectedWallet::reenable`]) each time the user switches account
+    pub fn on_account_change(
+        &self,
+        callback: impl Fn(Result<ConnectedWallet, APIError>) + 'static,
+    ) -> Result<WalletSubscription, APIError> {
+        let wallet = self.clone();
+        let callback = std::rc::Rc::new(callback);
+        self.on_event(WalletEventKind::AccountChange, move |event| {
+            let wallet = wallet.clone();
+            let callback = std::rc::Rc::clone(&callback);
+            wasm_bindgen_futures::spawn_local(async move {
+                match event {
+                    Ok(_) => callback(wallet.reenable().await),
+                    Err(error) => callback(Err(error)),
+                }
+            });
+        })
+    }
+
+    /// call `callback` with the new network each time the user switches
+    /// network
+    pub fn on_network_change(
+        &self,
+        callback: impl Fn(NetworkId) + 'static,
+    ) -> Result<WalletSubscription, APIError> {
+        self.on_event(WalletEventKind::NetworkChange, move |event| {
+            if let Ok(WalletEvent::NetworkChange { network_id }) = event {
+                callback(network_id);
+            }
+        })
+    }
+}
@@ -0,0 +1,138 @@
+/*!
+Read-only wallets.
+
+A [`WatchWallet`] follows an address (or all the addresses of a stake
+key) the user pasted, using a [`ChainProvider`] instead of a CIP-30
+wallet. This lets the dApp show the portfolio before the user connects a
+wallet able to sign.
+
+```no_run
+# use cardano_connector::{backend::ChainProvider, watch::WatchWallet};
+# async fn test(provider: impl ChainProvider, pasted: &str) -> anyhow::Result<()> {
+// e.g. `stake1...` or `addr1...`
+let wallet = WatchWallet::parse(provider, pasted)?;
+let balance = wallet.balance().await?;
+# Ok(()) }
+```
+*/
+
+use crate::{
+    Address, AssetId, TxHash, UtxoSet, Value,
+    backend::{BackendError, ChainProvider},
+    cardano::assets_of,
+};
+use std::collections::BTreeMap;
+
+/// A wallet that can only be read, see the [module documentation](self).
+pub struct WatchWallet<P> {
+    provider: P,
+    address: Address,
+}
+
+impl<P: ChainProvider> WatchWallet<P> {
+    /// follow the given payment or stake address
+    pub fn new(provider: P, address: Address) -> Self {
+        Self { provider, address }
+    }
+
+    /// follow the address given in bech32, base58 (Byron) or hex
+    pub fn parse(provider: P, address: &str) -> Result<Self, pallas_addresses::Error> {
+        Ok(Self::new(provider, address.parse()?))
+    }
+
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// the UTxOs of the watched address
+    pub async fn utxos(&self) -> Result<UtxoSet, BackendError> {
+        self.provider.address_utxos(&self.address).await
+    }
+
+    /// the total [`Value`] held by the watched address
+    pub async fn balance(&self) -> Result<Value, BackendError> {
+        Ok(self.utxos().await?.total())
+    }
+
+    /// the native assets held by the watched address
+    pub async fn assets(&self) -> Result<BTreeMap<AssetId, u64>, BackendError> {
+        Ok(assets_of(&self.balance().await?))
+    }
+
+    /// the transactions involving the watched address, most recent first
+    pub async fn history(&self) -> Result<Vec<TxHash>, BackendError> {
+        self.provider.address_transactions(&self.address).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Hash, Utxo,
+        cardano::{Multiasset, NonEmptyKeyValuePairs, TransactionInput, TransactionOutput},
+    };
+    use futures::executor::block_on;
+    use pallas_primitives::babbage::PseudoPostAlonzoTransactionOutput;
+
+    struct Provider;
+
+    fn utxo(index: u64, value: Value) -> Utxo {
+        Utxo {
+            input: TransactionInput {
+                transaction_id: Hash::from([1; 32]),
+                index,
+            },
+            output: TransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+                address: vec![0x61; 29].into(),
+                value,
+                datum_option: None,
+                script_ref: None,
+            }),
+        }
+    }
+
+    impl ChainProvider for Provider {
+        async fn resolve_input(
+            &self,
+            _input: &TransactionInput,
+        ) -> Result<Option<Utxo>, BackendError> {
+            Ok(None)
+        }
+
+        async fn address_utxos(&self, _address: &Address) -> Result<UtxoSet, BackendError> {
+            let token = NonEmptyKeyValuePairs::from_vec(vec![(
+                b"token".to_vec().into(),
+                5.try_into().unwrap(),
+            )])
+            .unwrap();
+            let assets = Multiasset::from_vec(vec![(Hash::from([2; 28]), token)]).unwrap();
+            Ok(UtxoSet::from(vec![
+                utxo(0, Value::Coin(1_000_000)),
+                utxo(1, Value::Multiasset(2_000_000, assets)),
+            ]))
+        }
+    }
+
+    #[test]
+    fn read_portfolio() {
+        let address = Address::from_bytes(&[0x61; 29]).unwrap();
+        let wallet = WatchWallet::new(Provider, address);
+
+        let balance = block_on(wallet.balance()).unwrap();
+        let assets = block_on(wallet.assets()).unwrap();
+
+        assert_eq!(crate::lovelace_of(&balance), 3_000_000);
+        assert_eq!(
+            assets.into_iter().collect::<Vec<_>>(),
+            vec![(
+                AssetId::new(Hash::from([2; 28]), b"token".to_vec().into()),
+                5
+            )]
+        );
+        assert!(matches!(
+            block_on(wallet.history()),
+            Err(BackendError::Unsupported(_))
+        ));
+    }
+}
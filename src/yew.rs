@@ -0,0 +1,190 @@
+/*!
+[Yew](https://yew.rs) hooks and components.
+
+* [`use_wallets`]: the wallets injected in the page, updated as the
+  extensions inject themselves (see [`watch_wallets`](crate::watch_wallets));
+* [`use_connected_wallet`]: the enable flow of a wallet, with its
+  [`Connection`] state;
+* [`WalletPicker`]: the list of the wallets, with their icons, enabling
+  the one the user picks.
+
+```no_run
+use cardano_connector::yew::{WalletPicker, use_connected_wallet};
+use yew::prelude::*;
+
+#[function_component]
+fn App() -> Html {
+    let connection = use_connected_wallet();
+    match connection.wallet() {
+        Some(wallet) => html! { <p>{ format!("Connected to {}", wallet.name()) }</p> },
+        None => html! { <WalletPicker on_connect={connection.on_connect()} /> },
+    }
+}
+```
+
+This needs the `yew` feature.
+*/
+
+use crate::{ConnectedWallet, Wallet, error::APIError, ffi, wallet::changes, wallets};
+use ::yew::prelude::*;
+use futures::StreamExt as _;
+use std::{cell::Cell, rc::Rc, time::Duration};
+
+/// how often [`use_wallets`] looks for new wallets
+pub const WALLETS_REFRESH: Duration = Duration::from_millis(500);
+
+/// the wallets of the page, refreshed every [`WALLETS_REFRESH`] while
+/// the component is mounted
+#[hook]
+pub fn use_wallets() -> Vec<Wallet> {
+    let state = use_state(wallets);
+    {
+        let state = state.clone();
+        use_effect_with((), move |()| {
+            let mounted = Rc::new(Cell::new(true));
+            let poll = {
+                let mounted = Rc::clone(&mounted);
+                move || mounted.get().then(wallets)
+            };
+            wasm_bindgen_futures::spawn_local(async move {
+                let mut updates =
+                    std::pin::pin!(changes(poll, || ffi::timer::sleep(WALLETS_REFRESH)));
+                while let Some(wallets) = updates.next().await {
+                    state.set(wallets);
+                }
+            });
+            move || mounted.set(false)
+        });
+    }
+    (*state).clone()
+}
+
+/// Where the enable flow of [`use_connected_wallet`] is.
+#[derive(Clone, PartialEq, Default)]
+pub enum Connection {
+    #[default]
+    Disconnected,
+    /// waiting for the user to authorise the dApp in the wallet
+    Connecting(Wallet),
+    Connected(ConnectedWallet),
+    /// the user refused or the wallet failed
+    Failed(APIError),
+}
+
+/// The state of [`use_connected_wallet`] and its actions.
+#[derive(Clone, PartialEq)]
+pub struct ConnectionHandle {
+    state: UseStateHandle<Connection>,
+}
+
+impl ConnectionHandle {
+    pub fn connection(&self) -> &Connection {
+        &self.state
+    }
+
+    pub fn wallet(&self) -> Option<&ConnectedWallet> {
+        match &*self.state {
+            Connection::Connected(wallet) => Some(wallet),
+            _ => None,
+        }
+    }
+
+    pub fn is_connecting(&self) -> bool {
+        matches!(*self.state, Connection::Connecting(_))
+    }
+
+    /// enable the `wallet`, prompting the user unless the dApp is
+    /// already authorised
+    pub fn connect(&self, wallet: Wallet) {
+        let state = self.state.clone();
+        state.set(Connection::Connecting(wallet.clone()));
+        wasm_bindgen_futures::spawn_local(async move {
+            state.set(match wallet.enable().await {
+                Ok(connected) => Connection::Connected(connected),
+                Err(error) => Connection::Failed(error),
+            });
+        });
+    }
+
+    /// the callback for [`WalletPicker::on_connect`](WalletPickerProps::on_connect)
+    pub fn on_connect(&self) -> Callback<ConnectedWallet> {
+        let state = self.state.clone();
+        Callback::from(move |wallet| state.set(Connection::Connected(wallet)))
+    }
+
+    /// forget the connected wallet
+    ///
+    /// CIP-30 has no way to revoke the authorisation: the next
+    /// [`connect`](Self::connect) to the same wallet won't prompt the
+    /// user.
+    pub fn disconnect(&self) {
+        self.state.set(Connection::Disconnected);
+    }
+}
+
+/// the enable flow of a wallet, see [`ConnectionHandle`]
+#[hook]
+pub fn use_connected_wallet() -> ConnectionHandle {
+    ConnectionHandle {
+        state: use_state(Connection::default),
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct WalletPickerProps {
+    /// called with the wallet the user picked, once enabled
+    pub on_connect: Callback<ConnectedWallet>,
+    /// called when the user refused or the wallet failed
+    #[prop_or_default]
+    pub on_error: Callback<APIError>,
+    /// the text shown when no wallet is injected
+    #[prop_or(AttrValue::Static("No Cardano wallet found"))]
+    pub empty: AttrValue,
+}
+
+/// The wallets of the page as a list of buttons, with their icons.
+///
+/// Picking a wallet enables it; the buttons are disabled in the
+/// meantime. The list has the `wallet-picker` class for styling.
+#[function_component]
+pub fn WalletPicker(props: &WalletPickerProps) -> Html {
+    let wallets = use_wallets();
+    let connection = use_connected_wallet();
+    {
+        let props = (props.on_connect.clone(), props.on_error.clone());
+        use_effect_with(connection.connection().clone(), move |connection| {
+            let (on_connect, on_error) = props;
+            match connection {
+                Connection::Connected(wallet) => on_connect.emit(wallet.clone()),
+                Connection::Failed(error) => on_error.emit(error.clone()),
+                Connection::Disconnected | Connection::Connecting(_) => {}
+            }
+        });
+    }
+
+    if wallets.is_empty() {
+        return html! { <p class="wallet-picker">{ props.empty.clone() }</p> };
+    }
+
+    let connecting = connection.is_connecting();
+    html! {
+        <ul class="wallet-picker">
+            { for wallets.into_iter().map(|wallet| {
+                let name = wallet.name();
+                let icon = wallet.icon();
+                let onclick = {
+                    let connection = connection.clone();
+                    Callback::from(move |_: MouseEvent| connection.connect(wallet.clone()))
+                };
+                html! {
+                    <li>
+                        <button disabled={connecting} {onclick}>
+                            <img src={icon} alt="" width="24" height="24" />
+                            { name }
+                        </button>
+                    </li>
+                }
+            }) }
+        </ul>
+    }
+}
@@ -0,0 +1,174 @@
+//! The common dApp flows, end to end against the mock wallet.
+//!
+//! Every flow is written against [`WalletApi`], as a dApp would: the same
+//! functions run in the browser with a [`ConnectedWallet`]. Run them with
+//! `cargo test --features mock`.
+//!
+//! [`ConnectedWallet`]: cardano_connector::ConnectedWallet
+
+use cardano_connector::{
+    Address, AddressExt as _, AssetId, NetworkId, TxHash, Value, WalletApi,
+    auth::{Envelope, EnvelopeVerifier},
+    cardano::{
+        BuildParams, Certificate, PoolId, StakeCredential, TxIntent, assemble_tx,
+        mint::MintBuilder, verify_witnesses, witness,
+    },
+    mock::{MockCall, MockWallet},
+    storage::MemoryStorage,
+};
+use futures::executor::block_on;
+use pallas_primitives::conway::NativeScript;
+
+const ADA: u64 = 1_000_000;
+
+/// a wallet on preview holding `lovelace` in one UTxO
+fn funded(lovelace: u64) -> MockWallet {
+    let wallet = MockWallet::new("mock", [7; 32], NetworkId::Preview);
+    wallet.add_utxo(Value::Coin(lovelace));
+    wallet
+}
+
+fn merchant() -> Address {
+    MockWallet::new("merchant", [9; 32], NetworkId::Preview).address()
+}
+
+/// connect → pay: check the network and the funds, then send
+async fn pay(wallet: &impl WalletApi, to: &Address, amount: u64) -> anyhow::Result<TxHash> {
+    anyhow::ensure!(wallet.network_id().await? == NetworkId::Preview);
+    anyhow::ensure!(wallet.lovelace().await? > amount, "not enough funds");
+
+    let change = wallet.change_address().await?;
+    Ok(wallet
+        .send_with(to, amount, &BuildParams::defaults(change))
+        .await?)
+}
+
+/// connect → delegate: register the stake key of the wallet and delegate
+/// it to `pool`
+async fn delegate(wallet: &impl WalletApi, pool: PoolId) -> anyhow::Result<TxHash> {
+    let reward_address = wallet.reward_addresses().await?;
+    let credential = reward_address
+        .first()
+        .and_then(|address| address.stake_credential())
+        .ok_or_else(|| anyhow::anyhow!("the wallet has no stake key"))?;
+
+    let utxos = wallet.utxos(None).await?.into();
+    let params = BuildParams::defaults(wallet.change_address().await?);
+    let tx = TxIntent::new()
+        .with_certificate(Certificate::StakeRegistration(credential.clone()))
+        .with_certificate(Certificate::StakeDelegation(credential, pool.0))
+        .build(&utxos, &params)?;
+
+    let witness_set = wallet.sign_tx(&tx.transaction_body, false).await?;
+    let tx = assemble_tx(tx.transaction_body, &[witness_set], true, None);
+    // a wallet bug is caught here rather than by the node
+    verify_witnesses(&tx)?;
+    Ok(wallet.submit_tx(&tx).await?)
+}
+
+/// connect → sign in: the wallet signs an envelope, the backend verifies
+/// it once
+async fn sign_in(
+    wallet: &impl WalletApi,
+    verifier: &EnvelopeVerifier<MemoryStorage>,
+    nonce: &str,
+    now: u64,
+) -> anyhow::Result<Address> {
+    let address = wallet.change_address().await?;
+    let body = address.to_vec();
+    let envelope = Envelope::new("https://my-dapp.io", "login", nonce, now + 60_000, &body)?;
+
+    let signed = wallet.sign_envelope(&address, &envelope).await?;
+    verifier.verify(&signed, &address, &body, now)?;
+    Ok(address)
+}
+
+/// mint an NFT under a policy of the wallet's key
+async fn mint_nft(wallet: &impl WalletApi, name: &[u8]) -> anyhow::Result<AssetId> {
+    let change = wallet.change_address().await?;
+    let Some(StakeCredential::AddrKeyhash(key)) = change.payment_credential() else {
+        anyhow::bail!("the change address is not a key address");
+    };
+    let mint = MintBuilder::new(NativeScript::ScriptPubkey(key)).mint(name.to_vec(), 1);
+
+    let utxos = wallet.utxos(None).await?.into();
+    let tx = mint.build(&utxos, &BuildParams::defaults(change))?;
+    // the policy script is already in the witness set
+    let tx = wallet.sign_transaction(&tx, false).await?;
+    wallet.submit_tx(&tx).await?;
+    Ok(AssetId::new(mint.policy_id(), name.to_vec().into()))
+}
+
+#[test]
+fn connect_and_pay() {
+    let wallet = funded(10 * ADA);
+    let connected = block_on(wallet.enable()).unwrap();
+
+    block_on(pay(&connected, &merchant(), 2 * ADA)).unwrap();
+
+    let tx = &wallet.submitted()[0];
+    // the payment and the change
+    assert_eq!(tx.transaction_body.outputs.len(), 2);
+    let balance = block_on(connected.lovelace()).unwrap();
+    assert_eq!(balance, 8 * ADA - tx.transaction_body.fee);
+
+    assert!(block_on(pay(&connected, &merchant(), 20 * ADA)).is_err());
+}
+
+#[test]
+fn connect_and_delegate() {
+    let wallet = funded(10 * ADA);
+    let connected = block_on(wallet.enable()).unwrap();
+
+    block_on(delegate(&connected, PoolId([3; 28].into()))).unwrap();
+
+    let tx = &wallet.submitted()[0];
+    assert_eq!(tx.transaction_body.certificates.iter().flatten().count(), 2);
+    let Some(StakeCredential::AddrKeyhash(stake_key)) = wallet.address().stake_credential() else {
+        panic!("the mock wallet has a stake key");
+    };
+    assert_eq!(
+        witness::vkey_hashes(&tx.transaction_witness_set),
+        [stake_key]
+    );
+    // the key deposit is spent
+    let balance = block_on(connected.lovelace()).unwrap();
+    assert_eq!(balance, 8 * ADA - tx.transaction_body.fee);
+}
+
+#[test]
+fn connect_and_sign_in() {
+    let wallet = funded(ADA);
+    let connected = block_on(wallet.enable()).unwrap();
+    let verifier =
+        EnvelopeVerifier::new("https://my-dapp.io", MemoryStorage::new()).with_purpose("login");
+
+    let address = block_on(sign_in(&connected, &verifier, "5f1c9a2e", 1_000)).unwrap();
+    assert_eq!(address, wallet.address());
+
+    // the nonce can only be used once
+    assert!(block_on(sign_in(&connected, &verifier, "5f1c9a2e", 2_000)).is_err());
+    // nor can the user decline and still be signed in
+    wallet.fail_next(MockCall::SignData, 3, "declined");
+    assert!(block_on(sign_in(&connected, &verifier, "0b7d4e11", 2_000)).is_err());
+}
+
+#[test]
+fn mint_an_nft() {
+    let wallet = funded(10 * ADA);
+    let connected = block_on(wallet.enable()).unwrap();
+
+    let nft = block_on(mint_nft(&connected, b"Artwork #1")).unwrap();
+
+    let assets = block_on(connected.assets()).unwrap();
+    assert_eq!(assets.get(&nft), Some(&1));
+    assert_eq!(
+        wallet.submitted()[0]
+            .transaction_witness_set
+            .native_script
+            .iter()
+            .flatten()
+            .count(),
+        1
+    );
+}